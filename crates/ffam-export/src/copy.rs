@@ -0,0 +1,35 @@
+use std::path::{Component, Path};
+
+use ffam_fs::{Error, Result};
+
+/// Copies `files`, paths relative to `source`, to the same paths below `target`, so the copy holds just what a run needed.
+/// Files that don't exist in the source (anymore) are skipped, they were e.g. added to the mount at runtime, and so
+/// are symlinks, which could point anywhere.
+/// Returns how many files and bytes were copied.
+pub fn copy_files<'a>(source : &Path, files : impl Iterator<Item = &'a str>, target : &Path) -> Result<(usize, u64)> {
+    let (mut count, mut bytes) = (0, 0);
+    for file in files {
+        let file = relative_path(file.as_ref())?;
+        let from = source.join(file);
+        match std::fs::symlink_metadata(&from) {
+            Ok(metadata) if metadata.is_file() => {}
+            _ => continue
+        }
+        let to = target.join(file);
+        if let Some(parent) = to.parent() {
+            std::fs::create_dir_all(parent).map_err(|err| Error::io(parent, err))?;
+        }
+        bytes += std::fs::copy(&from, &to).map_err(|err| Error::io(&from, err))?;
+        count += 1;
+    }
+    Ok((count, bytes))
+}
+
+/// `path` if it only consists of names, so joining it can't leave the directory it is joined to. The paths come from
+/// session files, which could have been edited.
+pub(crate) fn relative_path(path : &Path) -> Result<&Path> {
+    match path.components().all(|component| matches!(component, Component::Normal(_))) {
+        true => Ok(path),
+        false => Err(Error::InvalidPath(path.to_string_lossy().into_owned()))
+    }
+}
@@ -0,0 +1,143 @@
+use std::collections::BTreeMap;
+use std::ffi::OsStr;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::sync::Arc;
+
+use ffam_fs::fs::{Event, EventType};
+use ffam_fs::{Error, Result};
+
+use crate::copy::relative_path;
+
+/// Ranges are filled and written in chunks of this size, so reading a whole big archive doesn't take its size in memory.
+const CHUNK_SIZE : u64 = 1024 * 1024;
+
+/// How the bytes that were read end up in the target.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExtractMode {
+    Sparse, // a file of the original size with the bytes that were read at their offsets and holes everywhere else
+    Concatenated // `<file>.reads` with just the bytes that were read, `<file>.ranges` says where they came from
+}
+
+#[derive(Debug, Default)]
+struct FileReads {
+    size : u64,
+    ranges : Vec<(u64, u64)>,
+    payloads : Vec<(u64, Arc<Vec<u8>>)> // captured data by offset
+}
+
+/// Reconstructs what a program actually consumed of every file it read, from the payloads captured with the events
+/// and, where none were captured, by reading the same ranges from `source` again. Captured payloads win, they are
+/// what was really returned, even if the source changed since. Returns how many files and bytes were written.
+pub fn extract_reads<'a>(events : impl Iterator<Item = &'a Event>, source : &Path, target : &Path, mode : ExtractMode) -> Result<(usize, u64)> {
+    let mut files : BTreeMap<&OsStr, FileReads> = BTreeMap::new();
+    for event in events {
+        if let EventType::Read(read) = &event.event {
+            let file = files.entry(read.file.as_os_str()).or_default();
+            file.size = read.file_size;
+            let start = (read.offset as u64).min(read.file_size);
+            let end = (read.offset as u64 + read.size as u64).min(read.file_size);
+            if start < end {
+                file.ranges.push((start, end));
+            }
+            if let Some(data) = &read.data {
+                file.payloads.push((read.offset as u64, data.clone()));
+            }
+        }
+    }
+    let (mut count, mut bytes) = (0, 0);
+    for (path, mut file) in files {
+        file.ranges = merge(file.ranges);
+        if file.ranges.is_empty() {
+            continue;
+        }
+        // of payloads at the same offset the later read wins, sorting is stable
+        file.payloads.sort_by_key(|(offset, _)| *offset);
+        let path = relative_path(path.as_ref())?;
+        bytes += extract_file(&file, &source.join(path), &target.join(path), mode)?;
+        count += 1;
+    }
+    Ok((count, bytes))
+}
+
+fn merge(mut ranges : Vec<(u64, u64)>) -> Vec<(u64, u64)> {
+    ranges.sort();
+    let mut merged : Vec<(u64, u64)> = Vec::new();
+    for (start, end) in ranges {
+        match merged.last_mut() {
+            Some((_, last_end)) if start <= *last_end => *last_end = (*last_end).max(end),
+            _ => merged.push((start, end))
+        }
+    }
+    merged
+}
+
+fn extract_file(file : &FileReads, from : &Path, to : &Path, mode : ExtractMode) -> Result<u64> {
+    if let Some(parent) = to.parent() {
+        std::fs::create_dir_all(parent).map_err(|err| Error::io(parent, err))?;
+    }
+    // without the source only the captured payloads are left, the rest stays zero
+    let mut source = File::open(from).ok();
+    let dump_path = match mode {
+        ExtractMode::Sparse => to.to_path_buf(),
+        ExtractMode::Concatenated => with_suffix(to, "reads")
+    };
+    let mut dump = File::create(&dump_path).map_err(|err| Error::io(&dump_path, err))?;
+    if mode == ExtractMode::Sparse {
+        dump.set_len(file.size).map_err(|err| Error::io(&dump_path, err))?;
+    }
+    let longest_payload = file.payloads.iter().map(|(_, data)| data.len() as u64).max().unwrap_or_default();
+    let mut index = String::from("# offset, length, position in the .reads file\n");
+    let mut written = 0;
+    for &(start, end) in &file.ranges {
+        index.push_str(&format!("{start}\t{}\t{written}\n", end - start));
+        let mut chunk_start = start;
+        while chunk_start < end {
+            let chunk_end = (chunk_start + CHUNK_SIZE).min(end);
+            let mut chunk = vec![0; (chunk_end - chunk_start) as usize];
+            if let Some(source) = &mut source {
+                // a source that got shorter leaves the rest of the chunk zeroed
+                let _ = source.seek(SeekFrom::Start(chunk_start)).and_then(|_| read_full(source, &mut chunk));
+            }
+            let first = file.payloads.partition_point(|(offset, _)| offset + longest_payload <= chunk_start);
+            for (offset, data) in file.payloads[first..].iter().take_while(|(offset, _)| *offset < chunk_end) {
+                let from = chunk_start.max(*offset);
+                let until = chunk_end.min(offset + data.len() as u64);
+                if from < until {
+                    chunk[(from - chunk_start) as usize..(until - chunk_start) as usize]
+                        .copy_from_slice(&data[(from - offset) as usize..(until - offset) as usize]);
+                }
+            }
+            if mode == ExtractMode::Sparse {
+                dump.seek(SeekFrom::Start(chunk_start)).map_err(|err| Error::io(&dump_path, err))?;
+            }
+            dump.write_all(&chunk).map_err(|err| Error::io(&dump_path, err))?;
+            written += chunk.len() as u64;
+            chunk_start = chunk_end;
+        }
+    }
+    if mode == ExtractMode::Concatenated {
+        let index_path = with_suffix(to, "ranges");
+        std::fs::write(&index_path, index).map_err(|err| Error::io(&index_path, err))?;
+    }
+    Ok(written)
+}
+
+fn read_full(file : &mut File, buffer : &mut [u8]) -> std::io::Result<()> {
+    let mut read = 0;
+    while read < buffer.len() {
+        match file.read(&mut buffer[read..])? {
+            0 => break,
+            count => read += count
+        }
+    }
+    Ok(())
+}
+
+fn with_suffix(path : &Path, suffix : &str) -> std::path::PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(".");
+    name.push(suffix);
+    name.into()
+}
@@ -0,0 +1,131 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::io::Write;
+
+use ffam_fs::fs::EventType;
+
+use crate::session::Session;
+
+/// Sorts a table by the clicked column, numeric columns sort by their `data-sort` value, a second click reverses.
+const SCRIPT : &str = r#"
+for (const header of document.querySelectorAll("th")) {
+  header.addEventListener("click", () => {
+    const table = header.closest("table");
+    const column = [...header.parentNode.children].indexOf(header);
+    const descending = header.dataset.order !== "desc";
+    header.dataset.order = descending ? "desc" : "asc";
+    const key = row => {
+      const cell = row.children[column];
+      return cell.dataset.sort !== undefined ? Number(cell.dataset.sort) : cell.textContent;
+    };
+    const rows = [...table.tBodies[0].rows];
+    rows.sort((a, b) => (key(a) < key(b) ? -1 : key(a) > key(b) ? 1 : 0) * (descending ? -1 : 1));
+    table.tBodies[0].append(...rows);
+  });
+}
+"#;
+
+const STYLE : &str = r#"
+body { font-family: sans-serif; margin: 2em; }
+table { border-collapse: collapse; margin-bottom: 2em; }
+th, td { padding: 2px 8px; text-align: right; }
+th { cursor: pointer; background: #ddd; }
+td:first-child, th:first-child { text-align: left; }
+tr:nth-child(even) { background: #f4f4f4; }
+.bar { width: 120px; height: 10px; background: #ddd; display: inline-block; }
+.bar div { height: 100%; background: #3a7; }
+"#;
+
+/// Reads and coverage of one file or directory in the report.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Coverage {
+    pub files : u64,
+    pub reads : u64,
+    pub bytes : u64,
+    pub size : u64,
+    pub covered : u64
+}
+
+impl Coverage {
+    fn add(&mut self, other : &Coverage) {
+        self.files += other.files;
+        self.reads += other.reads;
+        self.bytes += other.bytes;
+        self.size += other.size;
+        self.covered += other.covered;
+    }
+
+    fn percentage(&self) -> f64 {
+        if self.size == 0 { 0.0 } else { self.covered as f64 * 100.0 / self.size as f64 }
+    }
+}
+
+/// Coverage of every file read in a session and of every directory above them, "" is the mounted root.
+pub fn coverage(session : &Session) -> (BTreeMap<String, Coverage>, BTreeMap<String, Coverage>) {
+    let mut ranges : BTreeMap<Cow<str>, (Coverage, Vec<(u64, u64)>)> = BTreeMap::new();
+    for event in &session.events {
+        if let EventType::Read(read) = &event.event {
+            let (file, file_ranges) = ranges.entry(read.file.to_string_lossy()).or_default();
+            file.files = 1;
+            file.reads += 1;
+            file.bytes += read.size as u64;
+            file.size = read.file_size;
+            // reads past the end of the file are clamped, like the coverage of the GUI
+            let start = (read.offset as u64).min(read.file_size);
+            let end = (read.offset as u64 + read.size as u64).min(read.file_size);
+            file_ranges.push((start, end));
+        }
+    }
+    let mut files = BTreeMap::new();
+    let mut directories : BTreeMap<String, Coverage> = BTreeMap::new();
+    for (path, (mut file, mut file_ranges)) in ranges {
+        file_ranges.sort();
+        let mut end_covered = 0;
+        for (start, end) in file_ranges {
+            let start = start.max(end_covered);
+            if end > start {
+                file.covered += end - start;
+                end_covered = end;
+            }
+        }
+        directories.entry(String::new()).or_default().add(&file);
+        for (index, _) in path.match_indices('/') {
+            directories.entry(path[..index].to_string()).or_default().add(&file);
+        }
+        files.insert(path.to_string(), file);
+    }
+    (files, directories)
+}
+
+fn escape(text : &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+fn write_table(out : &mut impl Write, title : &str, first_column : &str, rows : &BTreeMap<String, Coverage>) -> std::io::Result<()> {
+    writeln!(out, "<h2>{title}</h2>\n<table>\n<thead><tr><th>{first_column}</th><th>Files</th><th>Reads</th><th>Bytes read</th><th>Size</th><th>Covered</th><th>Coverage</th></tr></thead>\n<tbody>")?;
+    for (path, coverage) in rows {
+        let path = if path.is_empty() { "/" } else { path };
+        let percentage = coverage.percentage();
+        writeln!(out, "<tr><td>{}</td><td data-sort=\"{}\">{}</td><td data-sort=\"{}\">{}</td><td data-sort=\"{}\">{}</td><td data-sort=\"{}\">{}</td><td data-sort=\"{}\">{}</td>\
+            <td data-sort=\"{percentage}\"><span class=\"bar\"><div style=\"width: {percentage:.1}%\"></div></span> {percentage:.1} %</td></tr>",
+            escape(path), coverage.files, coverage.files, coverage.reads, coverage.reads, coverage.bytes, coverage.bytes,
+            coverage.size, coverage.size, coverage.covered, coverage.covered)?;
+    }
+    writeln!(out, "</tbody>\n</table>")
+}
+
+/// Writes a standalone html page with the coverage per file and per directory of a session, with tables that sort
+/// by clicking their headers. Everything is inline, so the page can be attached to a forum post as it is.
+pub fn write_report(out : &mut impl Write, session : &Session) -> std::io::Result<()> {
+    let (files, directories) = coverage(session);
+    let total = directories.get("").cloned().unwrap_or_default();
+    writeln!(out, "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>{STYLE}</style>\n</head>\n<body>", escape(&session.name))?;
+    writeln!(out, "<h1>{}</h1>", escape(&session.name))?;
+    writeln!(out, "<p>Source {}, mounted at {} on {}, saved on {}.</p>", escape(&session.source), escape(&session.mountpoint),
+        session.mounted_at.format("%Y-%m-%d %H:%M:%S UTC"), session.saved_at.format("%Y-%m-%d %H:%M:%S UTC"))?;
+    writeln!(out, "<p>{} files read with {} reads, {} of {} bytes covered ({:.1} %).</p>",
+        total.files, total.reads, total.covered, total.size, total.percentage())?;
+    write_table(out, "Directories", "Directory", &directories)?;
+    write_table(out, "Files", "File", &files)?;
+    writeln!(out, "<script>{SCRIPT}</script>\n</body>\n</html>")
+}
@@ -0,0 +1,5 @@
+//! Saved sessions of the monitoring filesystem, comparing them, copying or extracting what they read and html reports.
+pub mod copy;
+pub mod extract;
+pub mod html;
+pub mod session;
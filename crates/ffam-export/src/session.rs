@@ -0,0 +1,133 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use ffam_fs::fs::{AccessPattern, Event, EventType};
+use ffam_fs::schema::{self, Header, SCHEMA_VERSION, SESSION_EXTENSION};
+use ffam_fs::{Error, Result};
+
+/// A named recording of all events of a mount, saved as json so runs can be compared later.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Session {
+    #[serde(default = "Session::first_version")]
+    pub schema_version : u32, // see `Header`, the field is missing in sessions saved before it was added
+    pub name : String,
+    pub source : String,
+    pub mountpoint : String,
+    pub mounted_at : chrono::DateTime<chrono::Utc>,
+    pub saved_at : chrono::DateTime<chrono::Utc>,
+    pub events : Vec<Event>
+}
+
+impl Session {
+    /// A new session, in the current format.
+    pub fn new(name : String, source : String, mountpoint : String, mounted_at : chrono::DateTime<chrono::Utc>, events : Vec<Event>) -> Self {
+        Self { schema_version: SCHEMA_VERSION, name, source, mountpoint, mounted_at, saved_at: chrono::Utc::now(), events }
+    }
+
+    fn first_version() -> u32 {
+        1
+    }
+
+    fn path(dir : &Path, name : &str) -> PathBuf {
+        // the name becomes the file name, so path separators are not allowed
+        let file_name : String = name.chars().map(|c| if c == '/' || c == '\0' { '_' } else { c }).collect();
+        dir.join(format!("{file_name}.{SESSION_EXTENSION}"))
+    }
+
+    /// Saves the session as `<name>.json` in `dir`, replacing an older session of that name.
+    pub fn save(&self, dir : &Path) -> Result<()> {
+        std::fs::create_dir_all(dir).map_err(|err| Error::io(dir, err))?;
+        let path = Self::path(dir, &self.name);
+        std::fs::write(&path, serde_json::to_string(self)?).map_err(|err| Error::io(&path, err))
+    }
+
+    pub fn load(dir : &Path, name : &str) -> Result<Self> {
+        let path = Self::path(dir, name);
+        let content = std::fs::read_to_string(&path).map_err(|err| Error::io(&path, err))?;
+        // the version first, events of a newer format would only fail with a confusing error
+        let header : Header = serde_json::from_str(&content)?;
+        if !header.is_supported() {
+            return Err(Error::UnsupportedVersion(header.schema_version));
+        }
+        let session = serde_json::from_str(&content)?;
+        // the strings of sessions loaded before are only kept if still used
+        schema::forget_unused();
+        Ok(session)
+    }
+
+    /// Names of all sessions saved in `dir`, sorted.
+    pub fn list(dir : &Path) -> Vec<String> {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return Vec::new();
+        };
+        let mut names : Vec<String> = entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| entry.file_name().to_str()?.strip_suffix(SESSION_EXTENSION)?.strip_suffix('.').map(String::from))
+            .collect();
+        names.sort();
+        names
+    }
+
+    /// Reads and bytes read per file.
+    pub fn file_totals(&self) -> BTreeMap<String, (u64, u64)> {
+        let mut totals : BTreeMap<String, (u64, u64)> = BTreeMap::new();
+        for event in &self.events {
+            if let EventType::Read(read) = &event.event {
+                // only allocating the key the first time a file shows up
+                let file = read.file.to_string_lossy();
+                let total = match totals.get_mut(file.as_ref()) {
+                    Some(total) => total,
+                    None => totals.entry(file.into_owned()).or_default()
+                };
+                total.0 += 1;
+                total.1 += read.size as u64;
+            }
+        }
+        totals
+    }
+
+    /// How each file was read, in the order of the events.
+    pub fn file_patterns(&self) -> BTreeMap<String, AccessPattern> {
+        let mut patterns : BTreeMap<String, AccessPattern> = BTreeMap::new();
+        for event in &self.events {
+            if let EventType::Read(read) = &event.event {
+                let file = read.file.to_string_lossy();
+                let pattern = match patterns.get_mut(file.as_ref()) {
+                    Some(pattern) => pattern,
+                    None => patterns.entry(file.into_owned()).or_default()
+                };
+                pattern.record(read.offset as u64, read.size as u64);
+            }
+        }
+        patterns
+    }
+}
+
+/// How the reads of one file differ between two sessions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileComparison {
+    pub file : String,
+    pub reads : (u64, u64),
+    pub bytes : (u64, u64)
+}
+
+/// Compares the files read by two sessions, files whose reads differ the most come first.
+pub fn compare(a : &Session, b : &Session) -> Vec<FileComparison> {
+    let totals_a = a.file_totals();
+    let totals_b = b.file_totals();
+    let mut files : Vec<&String> = totals_a.keys().chain(totals_b.keys()).collect();
+    files.sort();
+    files.dedup();
+    let mut comparison : Vec<FileComparison> = files.into_iter().map(|file| {
+        let (reads_a, bytes_a) = totals_a.get(file).copied().unwrap_or_default();
+        let (reads_b, bytes_b) = totals_b.get(file).copied().unwrap_or_default();
+        FileComparison {
+            file: file.clone(),
+            reads: (reads_a, reads_b),
+            bytes: (bytes_a, bytes_b)
+        }
+    }).collect();
+    comparison.sort_by_key(|file| std::cmp::Reverse(file.bytes.0.abs_diff(file.bytes.1)));
+    comparison
+}
@@ -0,0 +1,45 @@
+/*
+ * Mounts a directory, prints every event until Enter is pressed, then prints the statistics and unmounts.
+ *
+ *     cargo build --release -p ffam-ffi
+ *     cc -I crates/ffam-ffi/include crates/ffam-ffi/examples/monitor.c -L target/release -lffam -o monitor
+ *     LD_LIBRARY_PATH=target/release ./monitor <source> <mountpoint>
+ */
+#include <stdio.h>
+
+#include "ffam.h"
+
+static void print_event(const char *event_json, void *user_data) {
+    unsigned long *count = user_data;
+    ++*count;
+    printf("%s\n", event_json);
+}
+
+int main(int argc, char **argv) {
+    if (argc != 3) {
+        fprintf(stderr, "Usage: %s <source> <mountpoint>\n", argv[0]);
+        return 2;
+    }
+    unsigned long count = 0;
+    FfamMount *mount = ffam_mount(argv[1], argv[2], "{\"read_only\": true}", print_event, &count);
+    if (!mount) {
+        fprintf(stderr, "Failed to mount: %s\n", ffam_last_error());
+        return 1;
+    }
+    fprintf(stderr, "Mounted %s at %s, press Enter to unmount\n", argv[1], argv[2]);
+    getchar();
+
+    /* no more events are printed, count isn't written anymore once this returns */
+    ffam_set_callback(mount, NULL, NULL);
+    char *stats = ffam_stats(mount);
+    if (stats) {
+        printf("%s\n", stats);
+        ffam_string_free(stats);
+    }
+    fprintf(stderr, "%lu events\n", count);
+    if (ffam_unmount(mount) != 0) {
+        fprintf(stderr, "Failed to unmount: %s\n", ffam_last_error());
+        return 1;
+    }
+    return 0;
+}
@@ -0,0 +1,55 @@
+/*
+ * C interface of fuse_file_access_monitor, for embedding the monitor in C, C++ and C# tools.
+ * Build with `cargo build --release -p ffam-ffi`, which makes libffam.so and libffam.a in target/release.
+ *
+ * Options, events and statistics are json. The options are the fields of the saved mount options, e.g.
+ * {"read_only": true, "filter": {"include": [".dat"], "exclude": []}}, missing fields keep their defaults.
+ * Events have the format of json lines recordings.
+ *
+ * Calls that fail return NULL or -1, ffam_last_error() then returns the reason.
+ *
+ * examples/monitor.c mounts a directory, prints its events and statistics and unmounts it again.
+ */
+#ifndef FFAM_H
+#define FFAM_H
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct FfamMount FfamMount;
+
+/*
+ * Called with every event as json, on a thread of the filesystem, which waits for it to return, so it should be
+ * quick. The string is only valid during the call. user_data is passed through and must be usable from that thread.
+ * The events of a mount are delivered one at a time and in order, even when several threads serve requests.
+ * The callback may call ffam_set_callback and ffam_stats. It must not call ffam_unmount, which waits for the
+ * filesystem threads and so for the callback itself.
+ */
+typedef void (*ffam_event_callback)(const char *event_json, void *user_data);
+
+/* Scans source and mounts it at mountpoint, blocks until the scan is done. options_json and callback may be NULL. */
+FfamMount *ffam_mount(const char *source, const char *mountpoint, const char *options_json, ffam_event_callback callback, void *user_data);
+
+/*
+ * Replaces the callback, NULL stops the calls. Returns once no call of the previous callback is running, apart from
+ * the one it is called from when the callback replaces itself.
+ */
+int ffam_set_callback(FfamMount *mount, ffam_event_callback callback, void *user_data);
+
+/* The current statistics as json, free it with ffam_string_free. */
+char *ffam_stats(const FfamMount *mount);
+
+/* Unmounts, waits for the last event and frees the mount. */
+int ffam_unmount(FfamMount *mount);
+
+void ffam_string_free(char *string);
+
+/* The reason the last call on this thread failed, NULL if none did. Valid until the next call fails. */
+const char *ffam_last_error(void);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
@@ -0,0 +1,308 @@
+//! C ABI of the monitoring engine, for embedding it in the mod tools of the community written in C, C++ or C#.
+//! The declarations are in include/ffam.h.
+//!
+//! Events, options and statistics cross the boundary as json, in the same format as recordings and `StatsSnapshot`,
+//! so callers don't have to mirror the Rust types. Functions that fail return NULL or -1, `ffam_last_error` tells why.
+use std::cell::RefCell;
+use std::ffi::{c_char, c_int, c_void, CStr, CString};
+use std::panic::AssertUnwindSafe;
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::ThreadId;
+
+use ffam_fs::fs::Event;
+use ffam_fs::{MountBuilder, MountOptions, MountSession};
+
+/// Called with every event as a json object, on the thread serving the read, which waits for it to return.
+/// The string is only valid during the call.
+pub type EventCallback = Option<unsafe extern "C" fn(event_json : *const c_char, user_data : *mut c_void)>;
+
+#[derive(Clone, Copy)]
+struct Callback {
+    function : EventCallback,
+    user_data : *mut c_void
+}
+
+// SAFETY: the caller promises that the user data may be used from the threads of the filesystem, see ffam.h
+unsafe impl Send for Callback {}
+
+/// The callback of a mount and its calls that are running, so replacing it can wait for them. The lock is not held
+/// during a call, the callback may replace itself.
+struct Callbacks {
+    state : Mutex<CallbackState>,
+    finished : Condvar
+}
+
+struct CallbackState {
+    callback : Callback,
+    generation : u64, // increased by every replacement
+    running : Vec<(u64, ThreadId)> // the generation of the callback of each running call and its thread
+}
+
+impl Callbacks {
+    fn new(callback : Callback) -> Self {
+        Self { state: Mutex::new(CallbackState { callback, generation: 0, running: Vec::new() }), finished: Condvar::new() }
+    }
+
+    fn call(&self, event : &Event) {
+        let (call, callback) = {
+            let mut state = self.state.lock().unwrap();
+            let call = (state.generation, std::thread::current().id());
+            state.running.push(call);
+            (call, state.callback)
+        };
+        let json = callback.function.and_then(|_| CString::new(serde_json::to_string(event).ok()?).ok());
+        if let (Some(function), Some(json)) = (callback.function, json) {
+            // SAFETY: the caller registered the function for this signature, the string lives until it returns
+            unsafe { function(json.as_ptr(), callback.user_data) };
+        }
+        let mut state = self.state.lock().unwrap();
+        if let Some(position) = state.running.iter().position(|running| *running == call) {
+            state.running.swap_remove(position);
+        }
+        drop(state);
+        self.finished.notify_all();
+    }
+
+    /// Replaces the callback and waits until no call of a previous one is running, except the one on this thread
+    /// if the callback replaces itself.
+    fn replace(&self, callback : Callback) {
+        let thread = std::thread::current().id();
+        let mut state = self.state.lock().unwrap();
+        state.callback = callback;
+        state.generation += 1;
+        let generation = state.generation;
+        drop(self.finished.wait_while(state, |state| {
+            state.running.iter().any(|(running, running_thread)| *running < generation && *running_thread != thread)
+        }).unwrap());
+    }
+}
+
+/// A mount, opaque to C.
+pub struct FfamMount {
+    session : MountSession,
+    callbacks : Arc<Callbacks> // shared with the filesystem, which calls it for every event
+}
+
+thread_local! {
+    static LAST_ERROR : RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_error(message : impl ToString) {
+    let message = CString::new(message.to_string().replace('\0', " ")).unwrap_or_default();
+    LAST_ERROR.with(|error| *error.borrow_mut() = Some(message));
+}
+
+/// Runs `f`, a panic must not unwind into the caller.
+fn guard<T>(failed : T, f : impl FnOnce() -> Option<T>) -> T {
+    match std::panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Some(result)) => result,
+        Ok(None) => failed,
+        Err(_) => {
+            set_error("The monitor panicked");
+            failed
+        }
+    }
+}
+
+/// # Safety
+/// `pointer` is NULL or a null terminated string.
+unsafe fn string(pointer : *const c_char, name : &str) -> Option<String> {
+    if pointer.is_null() {
+        set_error(format!("{name} is NULL"));
+        return None;
+    }
+    // SAFETY: see above
+    match unsafe { CStr::from_ptr(pointer) }.to_str() {
+        Ok(string) => Some(string.to_string()),
+        Err(_) => {
+            set_error(format!("{name} is not UTF-8"));
+            None
+        }
+    }
+}
+
+/// The message of the last failed call on this thread, NULL if none failed yet. Valid until the next failing call.
+#[unsafe(no_mangle)]
+pub extern "C" fn ffam_last_error() -> *const c_char {
+    LAST_ERROR.with(|error| error.borrow().as_ref().map_or(std::ptr::null(), |error| error.as_ptr()))
+}
+
+/// Scans `source` and mounts it at `mountpoint`, blocks until the scan is done.
+///
+/// # Safety
+/// The strings are null terminated, `options_json` may be NULL for the default options. See ffam.h for the callback.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ffam_mount(source : *const c_char, mountpoint : *const c_char, options_json : *const c_char, callback : EventCallback, user_data : *mut c_void) -> *mut FfamMount {
+    guard(std::ptr::null_mut(), || {
+        // SAFETY: passed on from the caller
+        let source = unsafe { string(source, "source") }?;
+        let mountpoint = unsafe { string(mountpoint, "mountpoint") }?;
+        let options = match options_json.is_null() {
+            true => MountOptions::default(),
+            // SAFETY: as above
+            false => match serde_json::from_str(&unsafe { string(options_json, "options") }?) {
+                Ok(options) => options,
+                Err(err) => {
+                    set_error(format!("Invalid options: {err}"));
+                    return None;
+                }
+            }
+        };
+        let callbacks = Arc::new(Callbacks::new(Callback { function: callback, user_data }));
+        let events = callbacks.clone();
+        let session = MountBuilder::new(source, mountpoint)
+            .options(options)
+            .on_event(move |event| events.call(&event))
+            .mount();
+        match session {
+            Ok(session) => Some(Box::into_raw(Box::new(FfamMount { session, callbacks }))),
+            Err(err) => {
+                set_error(err);
+                None
+            }
+        }
+    })
+}
+
+/// Replaces the callback of a mount, NULL stops the calls. Returns once no call of the previous one is running, apart
+/// from the one calling this.
+///
+/// # Safety
+/// `mount` was returned by `ffam_mount` and not unmounted yet.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ffam_set_callback(mount : *mut FfamMount, callback : EventCallback, user_data : *mut c_void) -> c_int {
+    // SAFETY: see above
+    let Some(mount) = (unsafe { mount.as_ref() }) else {
+        set_error("mount is NULL");
+        return -1;
+    };
+    guard(-1, || {
+        mount.callbacks.replace(Callback { function: callback, user_data });
+        Some(0)
+    })
+}
+
+/// The current statistics as json, see `StatsSnapshot`. Has to be freed with `ffam_string_free`.
+///
+/// # Safety
+/// `mount` was returned by `ffam_mount` and not unmounted yet.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ffam_stats(mount : *const FfamMount) -> *mut c_char {
+    // SAFETY: see above
+    let Some(mount) = (unsafe { mount.as_ref() }) else {
+        set_error("mount is NULL");
+        return std::ptr::null_mut();
+    };
+    guard(std::ptr::null_mut(), || {
+        let json = serde_json::to_string(&mount.session.stats()).ok()?;
+        CString::new(json).ok().map(CString::into_raw)
+    })
+}
+
+/// Unmounts, waits for the last event and frees the mount, which must not be used afterwards.
+///
+/// # Safety
+/// `mount` was returned by `ffam_mount` and not unmounted yet.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ffam_unmount(mount : *mut FfamMount) -> c_int {
+    if mount.is_null() {
+        set_error("mount is NULL");
+        return -1;
+    }
+    // SAFETY: see above, ownership goes back to Rust
+    let mount = unsafe { Box::from_raw(mount) };
+    guard(-1, move || {
+        mount.session.join();
+        Some(0)
+    })
+}
+
+/// Frees a string returned by this library.
+///
+/// # Safety
+/// `string` is NULL or was returned by this library and not freed yet.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ffam_string_free(string : *mut c_char) {
+    if !string.is_null() {
+        // SAFETY: see above, it was made with `CString::into_raw`
+        drop(unsafe { CString::from_raw(string) });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    fn event() -> Event {
+        serde_json::from_str(r#"{"time":"2023-11-14T22:13:20Z","process":{"pid":1,"name":"test"},"event":{"Marker":"start"}}"#).unwrap()
+    }
+
+    fn none() -> Callback {
+        Callback { function: None, user_data: std::ptr::null_mut() }
+    }
+
+    struct Counter {
+        started : AtomicBool,
+        finished : AtomicUsize
+    }
+
+    /// Counts its calls in the `Counter` behind `user_data`, slowly.
+    unsafe extern "C" fn count(_event_json : *const c_char, user_data : *mut c_void) {
+        // SAFETY: the tests pass a static Counter
+        let counter = unsafe { &*(user_data as *const Counter) };
+        counter.started.store(true, Ordering::SeqCst);
+        std::thread::sleep(Duration::from_millis(100));
+        counter.finished.fetch_add(1, Ordering::SeqCst);
+    }
+
+    unsafe extern "C" fn replace_itself(_event_json : *const c_char, user_data : *mut c_void) {
+        // SAFETY: the test passes its Callbacks, which outlive the call
+        unsafe { &*(user_data as *const Callbacks) }.replace(none());
+    }
+
+    #[test]
+    fn replacing_the_callback_waits_for_its_calls() {
+        static COUNTER : Counter = Counter { started: AtomicBool::new(false), finished: AtomicUsize::new(0) };
+        let callbacks = Arc::new(Callbacks::new(Callback { function: Some(count), user_data: &COUNTER as *const Counter as *mut c_void }));
+        let caller = {
+            let callbacks = callbacks.clone();
+            std::thread::spawn(move || callbacks.call(&event()))
+        };
+        while !COUNTER.started.load(Ordering::SeqCst) {
+            std::thread::yield_now();
+        }
+        callbacks.replace(none());
+        assert_eq!(COUNTER.finished.load(Ordering::SeqCst), 1);
+        callbacks.call(&event());
+        caller.join().unwrap();
+        assert_eq!(COUNTER.finished.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn the_callback_can_replace_itself() {
+        let callbacks = Arc::new(Callbacks::new(none()));
+        callbacks.replace(Callback { function: Some(replace_itself), user_data: Arc::as_ptr(&callbacks) as *mut c_void });
+        callbacks.call(&event());
+        assert!(callbacks.state.lock().unwrap().callback.function.is_none());
+    }
+
+    #[test]
+    fn failing_calls_tell_why() {
+        // SAFETY: only called after a call failed, the error isn't NULL then
+        let last_error = || unsafe { CStr::from_ptr(ffam_last_error()) }.to_string_lossy().into_owned();
+        // SAFETY: the strings are null terminated
+        let mount = unsafe { ffam_mount(std::ptr::null(), c"/mnt".as_ptr(), std::ptr::null(), None, std::ptr::null_mut()) };
+        assert!(mount.is_null());
+        assert_eq!(last_error(), "source is NULL");
+        // SAFETY: as above
+        let mount = unsafe { ffam_mount(c"/srv".as_ptr(), c"/mnt".as_ptr(), c"{".as_ptr(), None, std::ptr::null_mut()) };
+        assert!(mount.is_null());
+        assert!(last_error().starts_with("Invalid options"));
+        // SAFETY: NULL is checked
+        assert!(unsafe { ffam_stats(std::ptr::null()) }.is_null());
+        assert_eq!(unsafe { ffam_unmount(std::ptr::null_mut()) }, -1);
+        assert_eq!(last_error(), "mount is NULL");
+    }
+}
@@ -0,0 +1,68 @@
+//! The drivers that can serve a `FileAccessTrackingFs`.
+//!
+//! The filesystem handles every access without a driver in between (`lookup_entry`, `attributes`, `open_handle`,
+//! `read_file`, `release_handle`, `directory_entries`), a backend only translates the requests of its driver to
+//! these calls and their results back. So every backend feeds the same event pipeline, stats and rules.
+//!
+//! Nothing of a driver shows in these traits: the filesystem answers with its own `Attributes` and `EntryKind`, and a
+//! mount is a `BackendSession`, so a backend for another driver only needs to implement them. This module doesn't
+//! use fuser, the FUSE backend lives in its own module. There is none for Windows yet, WinFsp or Dokan would be one
+//! more `Backend` next to `FuseBackend` and `NfsBackend`.
+use serde::{Deserialize, Serialize};
+use std::os::fd::OwnedFd;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::fs::{Change, FileAccessTrackingFs};
+use crate::nfs::{NfsBackend, NfsOptions};
+use crate::MountOptions;
+
+pub use crate::fuse::FuseBackend;
+
+/// Mounts a filesystem with a driver, see `MountBuilder::backend`.
+pub trait Backend : Send + std::fmt::Debug {
+    fn name(&self) -> &'static str;
+
+    /// Mounts `fs` at `mountpoint` and serves it on a background thread until the session is joined or dropped.
+    fn mount(&self, fs : FileAccessTrackingFs, mountpoint : &Path, options : &MountOptions) -> std::io::Result<Box<dyn BackendSession>>;
+}
+
+/// A mounted filesystem of a backend, dropping it unmounts.
+pub trait BackendSession : Send + std::fmt::Debug {
+    /// Makes the driver forget what it cached about a changed entry, failing only means nothing was cached.
+    fn invalidate(&self, change : &Change);
+
+    /// Unmounts and waits for the thread serving the filesystem to finish.
+    fn join(self : Box<Self>);
+}
+
+/// Mounts and unmounts for a process that can't do it itself, e.g. one that dropped root and asks a helper process
+/// that kept it, see `MountBuilder::mounter`. Without one the backends mount themselves.
+pub trait Mounter : Send + Sync + std::fmt::Debug {
+    /// Mounts FUSE at `mountpoint` and returns the opened `/dev/fuse` the filesystem is then served through.
+    fn mount_fuse(&self, mountpoint : &Path, options : &MountOptions) -> std::io::Result<OwnedFd>;
+
+    /// Mounts the NFS server listening on the local `port` at `mountpoint`, see `nfs::client_options`.
+    fn mount_nfs(&self, port : u16, mountpoint : &Path, options : &MountOptions) -> std::io::Result<()>;
+
+    /// Unmounts a mount of this mounter, lazily if `force`, like `platform::force_unmount`.
+    fn unmount(&self, mountpoint : &Path, force : bool) -> std::io::Result<()>;
+}
+
+/// The backends that can be chosen in the options, others can only be given to `MountBuilder::backend`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BackendKind {
+    #[default]
+    Fuse,
+    Nfs(NfsOptions) // where FUSE isn't available, see `nfs`
+}
+
+impl BackendKind {
+    pub fn backend(&self, mounter : Option<Arc<dyn Mounter>>) -> Box<dyn Backend> {
+        match self {
+            Self::Fuse => Box::new(FuseBackend { mounter }),
+            Self::Nfs(options) => Box::new(NfsBackend { options: *options, mounter })
+        }
+    }
+}
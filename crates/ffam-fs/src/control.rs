@@ -0,0 +1,105 @@
+//! The `.ffam` directory in the root of the mount: files generated by the monitor instead of read from the source,
+//! so scripts can follow a session through the mount itself, also on another machine through a share of it.
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::fs::{AccessStats, StatsSnapshot};
+
+pub const DIRECTORY : &str = ".ffam";
+/// Live statistics as json, see `stats`.
+pub const STATS : &str = ".ffam/stats";
+/// Takes commands, one per line, see `Command`. Reading it shows the state they changed.
+pub const CONTROL : &str = ".ffam/ctl";
+
+/// A generated file, its content is made anew whenever it is read from the start.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Generated {
+    Stats,
+    Control
+}
+
+#[derive(Serialize)]
+struct Stats<'a> {
+    time : DateTime<Utc>,
+    coverage : f64,
+    #[serde(flatten)]
+    snapshot : &'a StatsSnapshot
+}
+
+/// The content of `.ffam/stats`: the totals and the counters of every file read so far.
+pub(crate) fn stats(stats : &AccessStats) -> Vec<u8> {
+    let snapshot = stats.snapshot();
+    let stats = Stats { time: Utc::now(), coverage: snapshot.coverage(), snapshot: &snapshot };
+    let mut json = serde_json::to_vec_pretty(&stats).unwrap_or_default();
+    json.push(b'\n');
+    json
+}
+
+/// A command written to `.ffam/ctl`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Pause, // reads are neither reported nor counted until `resume`
+    Resume,
+    Marker(String), // "marker <label>", the label may contain spaces
+    Filter(bool), // "filter on" or "filter off", off reports the reads of all paths
+    Flush // writes what the recording buffered to its file
+}
+
+impl std::str::FromStr for Command {
+    type Err = String;
+
+    fn from_str(line : &str) -> Result<Self, Self::Err> {
+        let (command, argument) = match line.trim().split_once(char::is_whitespace) {
+            Some((command, argument)) => (command, argument.trim()),
+            None => (line.trim(), "")
+        };
+        match (command, argument) {
+            ("pause", "") => Ok(Self::Pause),
+            ("resume", "") => Ok(Self::Resume),
+            ("marker", "") => Ok(Self::Marker(String::from("marker"))),
+            ("marker", label) => Ok(Self::Marker(label.to_string())),
+            ("filter", "on") => Ok(Self::Filter(true)),
+            ("filter", "off") => Ok(Self::Filter(false)),
+            ("flush", "") => Ok(Self::Flush),
+            _ => Err(format!("Unknown command: {}", line.trim()))
+        }
+    }
+}
+
+/// The content of `.ffam/ctl`: what the commands changed and which there are.
+pub(crate) fn state(paused : bool, filter : bool, recording : bool) -> Vec<u8> {
+    let on = |on : bool| if on { "on" } else { "off" };
+    format!(
+        "paused {}\nfilter {}\nrecording {}\n# commands: pause, resume, marker <label>, filter on|off, flush\n",
+        on(paused), on(filter), on(recording)
+    ).into_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commands_parse() {
+        assert_eq!("pause".parse(), Ok(Command::Pause));
+        assert_eq!(" resume \n".parse(), Ok(Command::Resume));
+        assert_eq!("marker level 2 loaded".parse(), Ok(Command::Marker(String::from("level 2 loaded"))));
+        assert_eq!("filter on".parse(), Ok(Command::Filter(true)));
+        assert_eq!("filter  off".parse(), Ok(Command::Filter(false)));
+        assert_eq!("flush".parse(), Ok(Command::Flush));
+    }
+
+    #[test]
+    fn markers_without_a_label_are_called_marker() {
+        assert_eq!("marker".parse(), Ok(Command::Marker(String::from("marker"))));
+        assert_eq!("marker   ".parse(), Ok(Command::Marker(String::from("marker"))));
+    }
+
+    #[test]
+    fn unknown_commands_and_arguments_are_refused() {
+        assert_eq!("stop".parse::<Command>(), Err(String::from("Unknown command: stop")));
+        assert!("pause now".parse::<Command>().is_err());
+        assert!("filter maybe".parse::<Command>().is_err());
+        assert!("Pause".parse::<Command>().is_err());
+    }
+}
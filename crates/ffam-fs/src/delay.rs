@@ -0,0 +1,91 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::sync::mpsc::{Receiver, RecvTimeoutError, Sender};
+use std::time::{Duration, Instant};
+
+use crate::trace::log;
+
+/// A reply that is sent once it is due.
+pub(crate) type Reply = Box<dyn FnOnce() + Send>;
+
+struct Pending {
+    due : Instant,
+    order : u64, // replies due at the same time are sent in the order they were held back
+    reply : Reply
+}
+
+impl PartialEq for Pending {
+    fn eq(&self, other : &Self) -> bool {
+        (self.due, self.order) == (other.due, other.order)
+    }
+}
+
+impl Eq for Pending {}
+
+impl PartialOrd for Pending {
+    fn partial_cmp(&self, other : &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Pending {
+    // reversed, the heap has the reply that is due first on top
+    fn cmp(&self, other : &Self) -> Ordering {
+        (other.due, other.order).cmp(&(self.due, self.order))
+    }
+}
+
+/// Holds back replies delayed by `RuleAction::Delay` on a thread of its own, so the thread serving the filesystem
+/// goes on with other requests meanwhile, like a slow disk only slows down the accesses that wait for it.
+#[derive(Debug)]
+pub(crate) struct Delayer {
+    sender : Sender<(Instant, Reply)>
+}
+
+impl Delayer {
+    pub fn new() -> Self {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        if let Err(err) = std::thread::Builder::new().name(String::from("delay")).spawn(move || run(receiver)) {
+            log!(error, "Failed to start the thread delaying replies, they are sent right away: {err}");
+        }
+        Self { sender }
+    }
+
+    /// Sends `reply` after `delay`.
+    pub fn send(&self, delay : Duration, reply : Reply) {
+        if let Err(err) = self.sender.send((Instant::now() + delay, reply)) {
+            // there is no thread, better late than never
+            (err.0.1)();
+        }
+    }
+}
+
+/// Sends the replies when they are due, those still held back when the filesystem is gone are sent in time as well.
+fn run(receiver : Receiver<(Instant, Reply)>) {
+    let mut pending = BinaryHeap::new();
+    let mut order = 0u64;
+    let mut open = true;
+    while open || !pending.is_empty() {
+        let next = pending.peek().map(|next : &Pending| next.due.saturating_duration_since(Instant::now()));
+        let received = match (next, open) {
+            (Some(timeout), true) => receiver.recv_timeout(timeout),
+            (None, true) => receiver.recv().map_err(|_| RecvTimeoutError::Disconnected),
+            (Some(timeout), false) => {
+                std::thread::sleep(timeout);
+                Err(RecvTimeoutError::Timeout)
+            }
+            (None, false) => break
+        };
+        match received {
+            Ok((due, reply)) => {
+                pending.push(Pending { due, order, reply });
+                order += 1;
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => open = false
+        }
+        while pending.peek().is_some_and(|next| next.due <= Instant::now()) {
+            (pending.pop().unwrap().reply)();
+        }
+    }
+}
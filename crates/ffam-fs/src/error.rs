@@ -0,0 +1,81 @@
+use std::path::{Path, PathBuf};
+
+/// Everything that can fail in the library, so embedders can react to the cause instead of parsing messages.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("Failed to scan {}: {source}", path.display())]
+    Scan {
+        path : PathBuf,
+        source : std::io::Error
+    },
+    #[error("Scan cancelled")]
+    ScanCancelled,
+    #[error("Failed to mount at {mountpoint}: {source}")]
+    Mount {
+        mountpoint : String,
+        source : std::io::Error
+    },
+    #[error("{mountpoint} is already mounted by the monitor with pid {pid}")]
+    AlreadyMounted {
+        mountpoint : String,
+        pid : u32
+    },
+    #[error("No monitor has a mount at {0}")]
+    NotMounted(String),
+    #[error("The session thread failed: {0}")]
+    SessionThread(String), // it panicked or the runtime shut down while waiting for it
+    #[error("No config directory, neither XDG_CONFIG_HOME nor HOME is set")]
+    NoConfigDir,
+    #[error("Failed to access {}: {source}", path.display())]
+    Io {
+        path : PathBuf,
+        source : std::io::Error
+    },
+    #[error("No such file or directory: {0}")]
+    NoSuchEntry(String),
+    #[error("Not a directory: {0}")]
+    NotADirectory(String),
+    #[error("Invalid path: {0:?}")]
+    InvalidPath(String),
+    #[error("Failed to load plugin {}: {message}", path.display())]
+    Plugin {
+        path : PathBuf,
+        message : String
+    },
+    #[error("Invalid script: {0}")]
+    Script(String),
+    #[error("Invalid archive index {}: {message}", path.display())]
+    ArchiveIndex {
+        path : PathBuf,
+        message : String
+    },
+    #[error("Invalid manifest {}: {message}", path.display())]
+    Manifest {
+        path : PathBuf,
+        message : String
+    },
+    #[error("Invalid session file: {0}")]
+    SessionFormat(#[from] serde_json::Error),
+    #[error("The file was written in format version {0}, which is not supported by this version")]
+    UnsupportedVersion(u32)
+}
+
+impl Error {
+    pub(crate) fn scan(path : impl AsRef<Path>, source : std::io::Error) -> Self {
+        Self::Scan { path: path.as_ref().to_path_buf(), source }
+    }
+
+    pub fn io(path : impl AsRef<Path>, source : std::io::Error) -> Self {
+        Self::Io { path: path.as_ref().to_path_buf(), source }
+    }
+
+    /// The underlying OS error, if there is one.
+    pub fn io_error(&self) -> Option<&std::io::Error> {
+        match self {
+            Self::Scan { source, .. } | Self::Mount { source, .. } | Self::Io { source, .. } => Some(source),
+            _ => None
+        }
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
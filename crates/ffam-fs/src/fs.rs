@@ -0,0 +1,2993 @@
+
+use chrono::Utc;
+use fuser::{
+    consts, Filesystem, KernelConfig, ReplyAttr, ReplyData, ReplyDirectory, ReplyEmpty, ReplyEntry, ReplyOpen, ReplyXattr,
+    Request
+};
+use libc::{ENOENT, EROFS};
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::ffi::{c_int, OsStr, OsString};
+use std::fs::File;
+use std::os::unix::ffi::{OsStrExt, OsStringExt};
+use std::os::unix::fs::FileExt;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, SystemTime};
+
+use crate::control::{self, Command, Generated};
+use crate::delay::Delayer;
+use crate::platform;
+use crate::plugin::Plugins;
+use crate::prefetch::Prefetcher;
+use crate::recorder::Recorder;
+use crate::rules::{self, Operation, Rule};
+use crate::source::{DiskSource, Source, SourceMetadata};
+use crate::trace::log;
+#[cfg(feature = "io-uring")]
+use crate::uring::{UringRead, UringReader};
+
+pub use crate::schema::{ChangeEvent, ChangeOperation, Event, EventType, HandleEvent, LinkEvent, MetadataEvent, MetadataOperation, Process, ReadEvent, WriteEvent, XattrEvent, XattrOperation};
+
+/// Decides which paths are monitored. Patterns match anywhere in the path relative to the mounted root, ignoring case.
+#[derive(Eq, PartialEq, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PathFilter {
+    pub include: Vec<String>, // if empty, everything is included
+    pub exclude: Vec<String>
+}
+
+impl PathFilter {
+    pub fn matches(&self, path : &str) -> bool {
+        let path = path.to_lowercase();
+        let contains = |pattern : &String| !pattern.is_empty() && path.contains(&pattern.to_lowercase());
+        (self.include.is_empty() || self.include.iter().any(contains)) && !self.exclude.iter().any(contains)
+    }
+}
+
+/// How names are compared when programs look up entries, chosen when mounting.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CaseSensitivity {
+    Sensitive, // names have to match exactly, like on Linux
+    Insensitive, // the case is ignored and new entries get lowercase names
+    #[default]
+    InsensitivePreserving // the case is ignored and new entries keep theirs, like on Windows, which the games expect
+}
+
+/// Shared between the scan of the source directory and the UI, so the UI can show progress and cancel it.
+#[derive(Debug, Default)]
+pub struct ScanProgress {
+    pub directories : AtomicU64,
+    pub files : AtomicU64,
+    cancelled : AtomicBool
+}
+
+impl ScanProgress {
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct Directory {
+    root : Entry,
+    inode_ctr: u64,
+    source : Arc<dyn Source>, // for the metadata of the entries, which is only looked up when needed
+    case : CaseSensitivity,
+    detached : HashMap<u64, Entry>, // files removed or replaced while still open, by inode, until their last handle is closed
+    open : HashMap<u64, usize>, // number of handles open per inode
+    uid : u32, // the owner of every entry, the user who mounted
+    gid : u32
+}
+
+impl Directory {
+    pub fn new(source : Arc<dyn Source>, case : CaseSensitivity, progress : &ScanProgress) -> crate::Result<Self> {
+        let inode_ctr = AtomicU64::new(2); // 1 is the root
+        Ok(Self {
+            root: Entry::new(source.as_ref(), &inode_ctr, progress)?,
+            inode_ctr: inode_ctr.into_inner(),
+            source,
+            case,
+            detached: HashMap::new(),
+            open: HashMap::new(),
+            // SAFETY: getuid and getgid can't fail
+            uid: unsafe { libc::getuid() },
+            gid: unsafe { libc::getgid() }
+        })
+    }
+
+    /// Attributes of an entry, stat'ing it in the source the first time they are needed.
+    pub fn attr(&self, entry : &Entry) -> Attributes {
+        entry.get_fileattr(self.source.as_ref(), self.uid, self.gid)
+    }
+
+    /// The entry with the inode `ino`, also one that was removed from the tree but is still open.
+    pub fn entry(&self, ino : u64) -> Option<&Entry> {
+        self.root.find_ino(ino).or_else(|| self.detached.get(&ino))
+    }
+
+    /// Keeps a file that was removed from the tree readable through the handles still open on it, like unlinked files
+    /// stay readable on disk. A file of the source gets the `content` it had, the source is about to lose it.
+    fn keep_detached(&mut self, mut entry : Entry, content : Option<(SourceMetadata, Data)>) {
+        if let Some((metadata, data)) = content {
+            entry.metadata = Stat::known(metadata);
+            entry.data = data;
+        }
+        self.discard(entry);
+    }
+
+    /// Drops an entry taken out of the tree, it and the entries below it that are still open are kept in `detached`.
+    fn discard(&mut self, mut entry : Entry) {
+        if let EntryInfo::Directory(entries) = &mut entry.info {
+            for child in std::mem::take(entries) {
+                self.discard(child);
+            }
+        }
+        if self.is_open(entry.inode) {
+            self.detached.insert(entry.inode, entry);
+        }
+    }
+
+    /// Whether a handle is open on the inode `ino`.
+    fn is_open(&self, ino : u64) -> bool {
+        self.open.contains_key(&ino)
+    }
+
+    /// Counts a handle opened on `ino`.
+    fn opened(&mut self, ino : u64) {
+        *self.open.entry(ino).or_default() += 1;
+    }
+
+    /// Counts a handle of `ino` as closed, a detached entry is dropped with its last one.
+    fn closed(&mut self, ino : u64) {
+        let Some(count) = self.open.get_mut(&ino) else {
+            return;
+        };
+        *count -= 1;
+        if *count == 0 {
+            self.open.remove(&ino);
+            self.detached.remove(&ino);
+        }
+    }
+
+    /// The entry `name` of the directory `parent`.
+    pub fn find_child(&self, parent : u64, name : &OsStr) -> Option<&Entry> {
+        self.root.find_ino(parent)?.find_name(name, self.case)
+    }
+
+    /// The name and the path a new entry `name` of the directory `parent` gets, see `CaseSensitivity`.
+    pub fn new_path(&self, parent : u64, name : &OsStr) -> Result<(OsString, OsString), c_int> {
+        let parent = self.root.find_ino(parent).ok_or(ENOENT)?;
+        let name = self.case.new_name(name);
+        let path = Entry::join_path(&parent.path, &name);
+        Ok((name, path))
+    }
+
+    /// Adds an empty file, one that was created in the source as well if `in_source`, otherwise it only exists in memory.
+    /// Returns its attributes.
+    pub fn create_file(&mut self, parent : u64, name : &OsStr, in_source : bool) -> Result<Attributes, c_int> {
+        let data = if in_source { Data::Source } else { Data::Memory(Arc::new(Vec::new())) };
+        self.create(parent, name, data, EntryInfo::File)
+    }
+
+    /// Adds an empty directory like `create_file`, one only in memory counts as virtual.
+    pub fn create_directory(&mut self, parent : u64, name : &OsStr, in_source : bool) -> Result<Attributes, c_int> {
+        let data = if in_source { Data::Source } else { Data::Virtual };
+        self.create(parent, name, data, EntryInfo::Directory(Vec::new()))
+    }
+
+    fn create(&mut self, parent : u64, name : &OsStr, data : Data, info : EntryInfo) -> Result<Attributes, c_int> {
+        // entries in the source are stat'ed like the scanned ones, the others are created now
+        let metadata = match data {
+            Data::Source => Stat::default(),
+            _ => Stat::known(SourceMetadata::new(info.is_dir(), matches!(info, EntryInfo::Symlink(_)), 0))
+        };
+        let case = self.case;
+        match self.root.find_ino_mut(parent) {
+            Some(parent) => {
+                match &mut parent.info {
+                    EntryInfo::File | EntryInfo::Symlink(_) => {
+                        Err(libc::ENOTDIR)
+                    }
+                    EntryInfo::Directory(entries) => {
+                        if case.find(entries, name).is_some() {
+                            return Err(libc::EEXIST);
+                        }
+                        entries.push(Entry {
+                            name: Arc::new(name.to_os_string()),
+                            path: Arc::new(Entry::join_path(&parent.path, name)),
+                            data,
+                            inode: self.inode_ctr,
+                            info,
+                            metadata
+                        });
+                        self.inode_ctr += 1;
+                        Ok(entries.last().unwrap().get_fileattr(self.source.as_ref(), self.uid, self.gid))
+                    }
+                }
+            }
+            None => {
+                Err(ENOENT)
+            }
+        }
+    }
+
+    /// Adds a symlink to `target` like `create_file`.
+    pub fn create_symlink(&mut self, parent : u64, name : &OsStr, target : &OsStr, in_source : bool) -> Result<Attributes, c_int> {
+        let data = if in_source { Data::Source } else { Data::Memory(Arc::new(Vec::new())) };
+        self.create(parent, name, data, EntryInfo::Symlink(target.to_os_string()))
+    }
+
+    /// Moves the entry `name` of `parent` to `new_name` in `new_parent`, replacing an entry of that name the way
+    /// rename(2) does. The entry keeps its inode, so open handles stay valid, the paths below it move along.
+    /// Returns the replaced entry.
+    pub fn rename(&mut self, parent : u64, name : &OsStr, new_parent : u64, new_name : &OsStr) -> Result<Option<Entry>, c_int> {
+        let moved = self.find_child(parent, name).ok_or(ENOENT)?;
+        let (inode, is_dir, path) = (moved.inode, moved.info.is_dir(), moved.path.clone());
+        let new_parent_entry = self.root.find_ino(new_parent).ok_or(ENOENT)?;
+        if !new_parent_entry.info.is_dir() {
+            return Err(libc::ENOTDIR);
+        }
+        // a directory can't be moved below itself
+        if is_dir && (*new_parent_entry.path == *path || new_parent_entry.path.as_bytes().strip_prefix(path.as_bytes()).is_some_and(|rest| rest.starts_with(b"/"))) {
+            return Err(libc::EINVAL);
+        }
+        let new_path = Entry::join_path(&new_parent_entry.path, new_name);
+        // renaming to another case of the same name finds the entry itself
+        let replaced = match new_parent_entry.find_name(new_name, self.case).filter(|target| target.inode != inode) {
+            Some(target) => match (&target.info, is_dir) {
+                (EntryInfo::Directory(entries), true) if entries.is_empty() => Some(target.inode),
+                (EntryInfo::Directory(_), true) => return Err(libc::ENOTEMPTY),
+                (EntryInfo::Directory(_), false) => return Err(libc::EISDIR),
+                (EntryInfo::File | EntryInfo::Symlink(_), true) => return Err(libc::ENOTDIR),
+                (EntryInfo::File | EntryInfo::Symlink(_), false) => Some(target.inode)
+            },
+            None => None
+        };
+        let replaced = replaced.and_then(|replaced| self.detach(new_parent, replaced));
+        let mut entry = self.detach(parent, inode).ok_or(ENOENT)?;
+        entry.name = Arc::new(new_name.to_os_string());
+        entry.relocate(new_path);
+        if let Some(EntryInfo::Directory(entries)) = self.root.find_ino_mut(new_parent).map(|parent| &mut parent.info) {
+            entries.push(entry);
+        }
+        Ok(replaced)
+    }
+
+    /// Takes the child with the inode `ino` out of the directory `parent`.
+    fn detach(&mut self, parent : u64, ino : u64) -> Option<Entry> {
+        let EntryInfo::Directory(entries) = &mut self.root.find_ino_mut(parent)?.info else {
+            return None;
+        };
+        let position = entries.iter().position(|entry| entry.inode == ino)?;
+        Some(entries.remove(position))
+    }
+
+    /// Forgets the metadata of an entry after it changed in the source, it is stat'ed again when it is needed next.
+    pub fn restat(&mut self, ino : u64) {
+        if let Some(entry) = self.root.find_ino_mut(ino).filter(|entry| matches!(entry.data, Data::Source)) {
+            entry.metadata = Stat::default();
+        }
+    }
+
+    pub fn find_path(&self, path : &OsStr) -> Option<&Entry> {
+        let mut entry = &self.root;
+        for name in components(path) {
+            entry = entry.find_name(name, self.case)?;
+        }
+        Some(entry)
+    }
+
+    fn find_path_mut(&mut self, path : &OsStr) -> Option<&mut Entry> {
+        let mut entry = &mut self.root;
+        for name in components(path) {
+            entry = entry.find_name_mut(name, self.case)?;
+        }
+        Some(entry)
+    }
+
+    /// Adds a virtual file (with content) or directory (without), an existing entry of that name gets replaced. Adding a
+    /// directory where there is one already keeps it with its entries.
+    pub fn put(&mut self, path : &OsStr, content : Option<Vec<u8>>) -> crate::Result<Change> {
+        let (data, info, metadata) = match content {
+            Some(content) => {
+                let size = content.len() as u64;
+                (Data::Memory(Arc::new(content)), EntryInfo::File, SourceMetadata::new(false, false, size))
+            }
+            None => (Data::Virtual, EntryInfo::Directory(Vec::new()), SourceMetadata::new(true, false, 0))
+        };
+        self.insert(path, data, info, metadata)
+    }
+
+    /// Adds a file whose content the filesystem generates whenever it is read, see `control`.
+    pub fn put_generated(&mut self, path : &OsStr, generated : Generated) -> crate::Result<Change> {
+        self.insert(path, Data::Generated(generated), EntryInfo::File, SourceMetadata::new(false, false, 0))
+    }
+
+    fn insert(&mut self, path : &OsStr, data : Data, info : EntryInfo, metadata : SourceMetadata) -> crate::Result<Change> {
+        let (parent_path, name) = split_path(path);
+        if name.is_empty() {
+            return Err(crate::Error::InvalidPath(path.to_string_lossy().into_owned()));
+        }
+        let (case, inode_ctr) = (self.case, &mut self.inode_ctr);
+        let mut entry = &mut self.root;
+        for parent_name in components(parent_path) {
+            entry = entry.find_name_mut(parent_name, case).ok_or_else(|| crate::Error::NoSuchEntry(parent_path.to_string_lossy().into_owned()))?;
+        }
+        let parent = entry.inode;
+        let parent_relative = entry.path.clone();
+        let EntryInfo::Directory(entries) = &mut entry.info else {
+            return Err(crate::Error::NotADirectory(parent_path.to_string_lossy().into_owned()));
+        };
+        let replaced = match case.find(entries, name).map(|position| &mut entries[position]) {
+            Some(existing) if existing.info.is_dir() && info.is_dir() => {
+                return Ok(Change { parent, name: existing.name.to_os_string(), inode: None });
+            }
+            Some(existing) if !existing.info.is_dir() && !info.is_dir() => {
+                // a file replaced by a file keeps its inode, so open handles read the new content
+                existing.data = data;
+                existing.metadata = Stat::known(metadata);
+                return Ok(Change { parent, name: existing.name.to_os_string(), inode: Some(existing.inode) });
+            }
+            Some(existing) => {
+                // a file replacing a directory or the other way round is a new entry, handles on the old one keep it
+                let entry = Entry {
+                    name: existing.name.clone(),
+                    path: existing.path.clone(),
+                    data,
+                    inode: *inode_ctr,
+                    info,
+                    metadata: Stat::known(metadata)
+                };
+                *inode_ctr += 1;
+                std::mem::replace(existing, entry)
+            }
+            None => {
+                let name = case.new_name(name);
+                entries.push(Entry {
+                    path: Arc::new(Entry::join_path(&parent_relative, &name)),
+                    name: Arc::new(name.clone()),
+                    data,
+                    inode: *inode_ctr,
+                    info,
+                    metadata: Stat::known(metadata)
+                });
+                *inode_ctr += 1;
+                return Ok(Change { parent, name, inode: None });
+            }
+        };
+        let change = Change { parent, name: replaced.name.to_os_string(), inode: Some(replaced.inode) };
+        self.discard(replaced);
+        Ok(change)
+    }
+
+    /// Removes a file or a directory with everything below it, what is still open stays readable like after unlinking it.
+    pub fn remove(&mut self, path : &OsStr) -> crate::Result<Change> {
+        let (parent_path, name) = split_path(path);
+        if name.is_empty() {
+            return Err(crate::Error::InvalidPath(path.to_string_lossy().into_owned()));
+        }
+        let no_such_entry = || crate::Error::NoSuchEntry(path.to_string_lossy().into_owned());
+        let case = self.case;
+        let parent = self.find_path_mut(parent_path).ok_or_else(no_such_entry)?;
+        let parent_inode = parent.inode;
+        let EntryInfo::Directory(entries) = &mut parent.info else {
+            return Err(no_such_entry());
+        };
+        let position = case.find(entries, name).ok_or_else(no_such_entry)?;
+        let removed = entries.remove(position);
+        let change = Change { parent: parent_inode, name: removed.name.to_os_string(), inode: Some(removed.inode) };
+        self.discard(removed);
+        Ok(change)
+    }
+}
+
+/// An entry of the mounted tree with its access counters, see `MountSession::tree`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TreeEntry {
+    pub path : String,
+    pub inode : u64,
+    pub is_dir : bool,
+    pub size : u64,
+    pub is_virtual : bool, // added at runtime or created through the mount, not backed by the source
+    pub reads : u64,
+    pub bytes : u64
+}
+
+impl Directory {
+    /// All entries below the root, depth first.
+    pub fn list(&self, stats : &AccessStats) -> Vec<TreeEntry> {
+        let mut list = Vec::new();
+        self.list_internal(&self.root, stats, &mut list);
+        list
+    }
+
+    fn list_internal(&self, directory : &Entry, stats : &AccessStats, list : &mut Vec<TreeEntry>) {
+        let EntryInfo::Directory(entries) = &directory.info else {
+            return;
+        };
+        for entry in entries {
+            list.push(self.tree_entry(entry, stats));
+            self.list_internal(entry, stats, list);
+        }
+    }
+
+    pub fn tree_entry(&self, entry : &Entry, stats : &AccessStats) -> TreeEntry {
+        let counters = stats.files.get(&entry.path);
+        TreeEntry {
+            path: entry.path.to_string_lossy().into_owned(),
+            inode: entry.inode,
+            is_dir: entry.info.is_dir(),
+            size: self.attr(entry).size,
+            is_virtual: !matches!(entry.data, Data::Source),
+            reads: counters.map_or(0, |file| file.reads),
+            bytes: counters.map_or(0, |file| file.bytes)
+        }
+    }
+}
+
+/// What changed in the tree, so the kernel's caches of it can be invalidated, see `BackendSession::invalidate`.
+#[derive(Debug)]
+pub struct Change {
+    pub parent : u64,
+    pub name : OsString,
+    pub inode : Option<u64> // the previous inode, if an entry got replaced or removed
+}
+
+impl Entry {
+    pub fn new(source : &dyn Source, inode_ctr : &AtomicU64, progress : &ScanProgress) -> crate::Result<Self> {
+        Ok(Self {
+            data: Data::Source,
+            name: Arc::new(OsString::new()),
+            path: Arc::new(OsString::new()),
+            info: EntryInfo::Directory(Self::build_directory(source, OsStr::new(""), inode_ctr, progress)?),
+            inode: 1,
+            metadata: Stat::default()
+        })
+    }
+
+    fn find_ino(&self, ino: u64) -> Option<&Entry> {
+        if ino==1 {
+            Some(self)
+        } else {
+            match &self.info {
+                EntryInfo::Directory(contents) => {
+                    Self::find_ino_internal(contents, ino)
+                }
+                EntryInfo::File | EntryInfo::Symlink(_) => None
+            }
+        }
+    }
+
+    fn find_ino_mut(&mut self, ino: u64) -> Option<&mut Entry> {
+        if ino==self.inode {
+            Some(self)
+        } else {
+            match &mut self.info {
+                EntryInfo::Directory(contents) => {
+                    Self::find_ino_mut_internal(contents, ino)
+                }
+                EntryInfo::File | EntryInfo::Symlink(_) => None
+            }
+        }
+    }
+
+    fn find_name(&self, name : &OsStr, case : CaseSensitivity) -> Option<&Self> {
+        match &self.info {
+            EntryInfo::Directory(entries) => {
+                case.find(entries, name).map(|position| &entries[position])
+            }
+            EntryInfo::File | EntryInfo::Symlink(_) => None
+        }
+    }
+
+    fn find_name_mut(&mut self, name : &OsStr, case : CaseSensitivity) -> Option<&mut Self> {
+        match &mut self.info {
+            EntryInfo::Directory(entries) => {
+                case.find(entries, name).map(|position| &mut entries[position])
+            }
+            EntryInfo::File | EntryInfo::Symlink(_) => None
+        }
+    }
+
+    fn find_ino_internal(directory : &Vec<Entry>, ino : u64) -> Option<&Entry> {
+        match directory.iter().filter(|e| e.inode==ino).next() {
+            Some(result) => Some(result),
+            None => {
+                directory.iter().filter_map(|e| {
+                    match &e.info {
+                        EntryInfo::File | EntryInfo::Symlink(_) => None,
+                        EntryInfo::Directory(entries) => Self::find_ino_internal(&entries, ino)
+                    }
+                }).next()
+            }
+        }
+    }
+
+    fn find_ino_mut_internal(directory : &mut Vec<Entry>, ino : u64) -> Option<&mut Entry> {
+        for entry in directory.iter_mut().filter(|e| e.inode==ino || e.info.is_dir()) {
+            if entry.inode == ino {
+                return Some(entry);
+            } else {
+                match &mut entry.info {
+                    EntryInfo::Directory(contents) => {
+                        if let Some(entry) = Self::find_ino_mut_internal(contents, ino) {
+                            return Some(entry);
+                        }
+                    }
+                    EntryInfo::File | EntryInfo::Symlink(_) => {}
+                }
+            }
+        }
+        None
+    }
+    
+    /// Changes the path of an entry and of everything below it.
+    fn relocate(&mut self, path : OsString) {
+        if let EntryInfo::Directory(entries) = &mut self.info {
+            for entry in entries {
+                entry.relocate(Self::join_path(&path, &entry.name));
+            }
+        }
+        self.path = Arc::new(path);
+    }
+
+    /// Path of a child relative to the mounted root, using '/' as separator.
+    fn join_path(parent : &OsStr, name : &OsStr) -> OsString {
+        let mut path = parent.to_os_string();
+        if !parent.is_empty() {
+            path.push("/");
+        }
+        path.push(name);
+        path
+    }
+
+    /// Scans a directory of the source recursively, fails with `ScanCancelled` if the scan gets cancelled.
+    /// Only entries whose kind the source can't tell from the directory listing are stat'ed, the metadata of the others is
+    /// looked up once the kernel asks for it. Subdirectories are scanned in parallel, so large trees don't wait on one disk access after another.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(source, inode_ctr, progress)))]
+    fn build_directory(source : &dyn Source, prefix: &OsStr, inode_ctr: &AtomicU64, progress : &ScanProgress) -> crate::Result<Vec<Entry>> {
+        if progress.is_cancelled() {
+            log!(debug, "Scan cancelled at {}", prefix.display());
+            return Err(crate::Error::ScanCancelled);
+        }
+        progress.directories.fetch_add(1, Ordering::Relaxed);
+
+        let names = source.read_dir_kinds(prefix).map_err(|err| crate::Error::scan(Path::new("/").join(prefix), err))?;
+        names.into_par_iter()
+            // Skip . and ..
+            .filter(|(name, _)| name != "." && name != "..")
+            .map(|(name, is_dir)| {
+                let relative_path = Self::join_path(prefix, &name);
+                let (is_dir, is_symlink, metadata) = match is_dir {
+                    Some(is_dir) => (is_dir, false, Stat::default()),
+                    None => {
+                        let meta = source.stat(&relative_path).map_err(|err| crate::Error::scan(Path::new("/").join(&relative_path), err))?;
+                        (meta.is_dir, meta.is_symlink, Stat::known(meta))
+                    }
+                };
+                let info = if is_dir {
+                    EntryInfo::Directory(Self::build_directory(source, &relative_path, inode_ctr, progress)?)
+                } else if is_symlink {
+                    // kept as a link, the kernel resolves it through `readlink`, also if the target doesn't exist
+                    EntryInfo::Symlink(source.read_link(&relative_path).map_err(|err| crate::Error::scan(Path::new("/").join(&relative_path), err))?)
+                } else {
+                    progress.files.fetch_add(1, Ordering::Relaxed);
+                    EntryInfo::File
+                };
+                Ok(Entry {
+                    name: Arc::new(name),
+                    path: Arc::new(relative_path),
+                    data: Data::Source,
+                    // the order of the inodes depends on which thread gets there first, they only have to be unique
+                    inode: inode_ctr.fetch_add(1, Ordering::Relaxed),
+                    info,
+                    metadata
+                })
+            })
+            .collect()
+    }
+
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub(crate) enum Data {
+    Source, // read from the source at the path of the entry
+    Memory(Arc<Vec<u8>>), // shared, so a read can take it out of the tree and copy from it without holding the lock
+    Generated(Generated), // made by the filesystem, see `control`
+    Open(OpenFile), // a file of the source that is gone from it, read through the descriptor its handles keep
+    Virtual // a directory added at runtime or created through the mount, it has no content of its own
+}
+
+/// A file opened before it was removed from the source or replaced there, see `FileAccessTrackingFs::keep_open`.
+#[derive(Clone, Debug)]
+pub(crate) struct OpenFile(Arc<File>);
+
+impl PartialEq for OpenFile {
+    fn eq(&self, other : &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for OpenFile {}
+
+impl Data {
+    fn generated(&self) -> Option<Generated> {
+        match self {
+            Data::Generated(generated) => Some(*generated),
+            _ => None
+        }
+    }
+
+    fn read(&self, source : &dyn Source, path : &OsStr, buffer : &mut [u8], offset : usize) -> std::io::Result<usize> {
+        match self {
+            Data::Source => source.read(path, buffer, offset as u64),
+            Data::Memory(data) => {
+                let start = offset.min(data.len());
+                let end = (start + buffer.len()).min(data.len());
+                buffer[..end - start].copy_from_slice(&data[start..end]);
+                Ok(end - start)
+            }
+            Data::Generated(_) => Ok(0), // read by the filesystem from the content generated for the handle
+            Data::Open(file) => file.0.read_at(buffer, offset as u64),
+            Data::Virtual => Ok(0)
+        }
+    }
+}
+
+#[derive(Eq, PartialEq, Debug)]
+pub(crate) struct Entry {
+    pub name: std::sync::Arc<OsString>,
+    pub path: std::sync::Arc<OsString>, // relative to the mounted root
+    pub data: Data,
+    pub inode : u64,
+    pub info: EntryInfo,
+    pub metadata : Stat
+}
+
+#[derive(Eq, PartialEq, Debug)]
+pub(crate) enum EntryInfo {
+    Directory(Vec<Entry>),
+    File,
+    Symlink(OsString) // the target, as it is stored in the link
+}
+
+/// Metadata of an entry, stat'ed in the source when it is first needed instead of during the scan.
+#[derive(Eq, PartialEq, Debug, Default)]
+pub(crate) struct Stat(std::sync::OnceLock<SourceMetadata>);
+
+impl Stat {
+    pub fn known(metadata : SourceMetadata) -> Self {
+        Self(std::sync::OnceLock::from(metadata))
+    }
+
+    /// The metadata, that of an empty entry created now if it can't be stat'ed. A failed stat is tried again the next time.
+    pub fn get(&self, source : &dyn Source, path : &OsStr, info : &EntryInfo) -> SourceMetadata {
+        if let Some(metadata) = self.0.get() {
+            return *metadata;
+        }
+        match source.stat(path) {
+            Ok(metadata) => *self.0.get_or_init(|| metadata),
+            Err(err) => {
+                log!(warn, "Failed to stat {}: {err}", path.display());
+                SourceMetadata::new(info.is_dir(), matches!(info, EntryInfo::Symlink(_)), 0)
+            }
+        }
+    }
+}
+
+/// The kind of an entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EntryKind {
+    RegularFile,
+    Directory,
+    Symlink
+}
+
+/// The attributes of an entry, the backends translate them to the ones of their driver.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Attributes {
+    pub ino : u64,
+    pub size : u64,
+    pub blocks : u64, // of 512 bytes
+    pub atime : SystemTime,
+    pub mtime : SystemTime,
+    pub ctime : SystemTime,
+    pub kind : EntryKind,
+    pub perm : u16,
+    pub nlink : u32,
+    pub uid : u32,
+    pub gid : u32,
+    pub blksize : u32
+}
+
+impl From<EntryKind> for fuser::FileType {
+    fn from(kind : EntryKind) -> Self {
+        match kind {
+            EntryKind::RegularFile => Self::RegularFile,
+            EntryKind::Directory => Self::Directory,
+            EntryKind::Symlink => Self::Symlink
+        }
+    }
+}
+
+impl From<Attributes> for fuser::FileAttr {
+    fn from(attr : Attributes) -> Self {
+        Self {
+            ino: attr.ino,
+            size: attr.size,
+            blocks: attr.blocks,
+            atime: attr.atime,
+            mtime: attr.mtime,
+            ctime: attr.ctime,
+            crtime: attr.mtime, // only used on macOS, the source doesn't tell the creation time everywhere
+            kind: attr.kind.into(),
+            perm: attr.perm,
+            nlink: attr.nlink,
+            uid: attr.uid,
+            gid: attr.gid,
+            rdev: 0,
+            flags: 0,
+            blksize: attr.blksize
+        }
+    }
+}
+
+impl Entry {
+    /// The attributes of the entry in the source, owned by `uid` and `gid`.
+    pub fn get_fileattr(&self, source : &dyn Source, uid : u32, gid : u32) -> Attributes {
+        let metadata = self.metadata.get(source, &self.path, &self.info);
+        let (kind, size, nlink) = match &self.info {
+            EntryInfo::File => (EntryKind::RegularFile, metadata.size, 1),
+            EntryInfo::Symlink(target) => (EntryKind::Symlink, target.len() as u64, 1),
+            EntryInfo::Directory(_) => (EntryKind::Directory, 0, 2)
+        };
+        Attributes {
+            ino: self.inode,
+            size,
+            blocks: size.div_ceil(512),
+            atime: metadata.accessed,
+            mtime: metadata.modified,
+            ctime: metadata.changed,
+            kind,
+            perm: metadata.permissions,
+            nlink,
+            uid,
+            gid,
+            blksize: 512
+        }
+    }
+}
+
+impl CaseSensitivity {
+    /// Whether two names are the same. Of names that aren't UTF-8 only the case of ASCII letters is ignored.
+    fn same_name(self, a : &OsStr, b : &OsStr) -> bool {
+        match (self, a.to_str(), b.to_str()) {
+            (Self::Sensitive, _, _) => a == b,
+            (_, Some(a), Some(b)) => a.to_lowercase() == b.to_lowercase(),
+            _ => a.as_bytes().eq_ignore_ascii_case(b.as_bytes())
+        }
+    }
+
+    /// The name a new entry called `name` gets.
+    fn new_name(self, name : &OsStr) -> OsString {
+        match (self, name.to_str()) {
+            (Self::Insensitive, Some(name)) => OsString::from(name.to_lowercase()),
+            (Self::Insensitive, None) => OsString::from_vec(name.as_bytes().to_ascii_lowercase()),
+            (Self::Sensitive | Self::InsensitivePreserving, _) => name.to_os_string()
+        }
+    }
+
+    /// The position of the entry `name` refers to. Of entries whose names only differ in case the one with exactly
+    /// this name wins, otherwise the first of them in byte order, so it doesn't depend on the order of the scan.
+    fn find(self, entries : &[Entry], name : &OsStr) -> Option<usize> {
+        entries.iter().position(|entry| entry.name.as_os_str() == name).or_else(|| {
+            entries.iter().enumerate()
+                .filter(|(_, entry)| self.same_name(&entry.name, name))
+                .min_by(|(_, a), (_, b)| a.name.cmp(&b.name))
+                .map(|(position, _)| position)
+        })
+    }
+}
+
+/// The names of a path relative to the mounted root.
+fn components(path : &OsStr) -> impl Iterator<Item = &OsStr> {
+    path.as_bytes().split(|byte| *byte == b'/').filter(|name| !name.is_empty()).map(OsStr::from_bytes)
+}
+
+/// The path of the parent and the name of a path, the parent of the entries of the root is "".
+fn split_path(path : &OsStr) -> (&OsStr, &OsStr) {
+    let mut path = path.as_bytes();
+    while let [rest @ .., b'/'] | [b'/', rest @ ..] = path {
+        path = rest;
+    }
+    match path.iter().rposition(|byte| *byte == b'/') {
+        Some(slash) => (OsStr::from_bytes(&path[..slash]), OsStr::from_bytes(&path[slash + 1..])),
+        None => (OsStr::new(""), OsStr::from_bytes(path))
+    }
+}
+
+impl EntryInfo {
+    pub fn is_dir(&self) -> bool {
+        match self {
+            Self::Directory(_) => true,
+            Self::File | Self::Symlink(_) => false
+        }
+    }
+}
+
+/// Where the filesystem delivers its events. The channels are only used from the FUSE threads, so none of them needs a tokio runtime.
+pub enum EventSink {
+    #[cfg(feature = "tokio")]
+    Channel(tokio::sync::mpsc::Sender<Event>),
+    StdChannel(std::sync::mpsc::Sender<Event>),
+    StdSyncChannel(std::sync::mpsc::SyncSender<Event>), // bounded, blocks the read while full like the tokio channel
+    Callback(Box<dyn FnMut(Event) + Send>), // called inline from the FUSE handler, the read waits for it to return
+    #[cfg(feature = "ring")]
+    Ring(rtrb::Producer<Event>), // lock-free, never blocks the read, events are dropped while the ring is full
+    Discard
+}
+
+impl EventSink {
+    /// Returns false if the event got lost because the receiver is gone or the ring is full.
+    fn send(&mut self, event : Event) -> bool {
+        // a closed receiver just means nobody is interested anymore
+        let delivered = match self {
+            #[cfg(feature = "tokio")]
+            Self::Channel(sender) => sender.blocking_send(event).is_ok(),
+            Self::StdChannel(sender) => sender.send(event).is_ok(),
+            Self::StdSyncChannel(sender) => sender.send(event).is_ok(),
+            Self::Callback(callback) => {
+                callback(event);
+                true
+            }
+            #[cfg(feature = "ring")]
+            Self::Ring(producer) => {
+                let pushed = producer.push(event).is_ok();
+                if !pushed {
+                    log!(trace, "Event ring is full, dropping event");
+                }
+                return pushed;
+            }
+            Self::Discard => true
+        };
+        if !delivered {
+            log!(trace, "Event receiver closed, dropping event");
+        }
+        delivered
+    }
+}
+
+impl std::fmt::Debug for EventSink {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            #[cfg(feature = "tokio")]
+            Self::Channel(sender) => f.debug_tuple("Channel").field(sender).finish(),
+            Self::StdChannel(sender) => f.debug_tuple("StdChannel").field(sender).finish(),
+            Self::StdSyncChannel(sender) => f.debug_tuple("StdSyncChannel").field(sender).finish(),
+            Self::Callback(_) => write!(f, "Callback"),
+            #[cfg(feature = "ring")]
+            Self::Ring(producer) => f.debug_tuple("Ring").field(producer).finish(),
+            Self::Discard => write!(f, "Discard")
+        }
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl From<tokio::sync::mpsc::Sender<Event>> for EventSink {
+    fn from(sender : tokio::sync::mpsc::Sender<Event>) -> Self {
+        Self::Channel(sender)
+    }
+}
+
+impl From<std::sync::mpsc::Sender<Event>> for EventSink {
+    fn from(sender : std::sync::mpsc::Sender<Event>) -> Self {
+        Self::StdChannel(sender)
+    }
+}
+
+impl From<std::sync::mpsc::SyncSender<Event>> for EventSink {
+    fn from(sender : std::sync::mpsc::SyncSender<Event>) -> Self {
+        Self::StdSyncChannel(sender)
+    }
+}
+
+/// Counters of one file, see `StatsSnapshot`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileCounters {
+    pub reads : u64,
+    pub bytes : u64, // bytes actually returned, reads past the end count less than requested
+    pub size : u64,
+    pub covered : u64, // distinct bytes of the file that were read at least once
+    #[serde(default)]
+    pub pattern : AccessPattern,
+    #[serde(default)]
+    pub opens : u64
+}
+
+/// Share of the reads that have to follow a pattern for a file to count as read that way.
+const PATTERN_SHARE : f64 = 0.9;
+
+/// How a file was read, see `AccessPattern::kind`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AccessKind {
+    Sequential, // every read starts where the previous one ended
+    Strided, // reads skip the same distance every time, e.g. one record out of a table
+    Random
+}
+
+impl std::fmt::Display for AccessKind {
+    fn fmt(&self, f : &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Sequential => "sequential",
+            Self::Strided => "strided",
+            Self::Random => "random"
+        })
+    }
+}
+
+/// Where the reads of a file started compared to where the previous read ended, updated read by read.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccessPattern {
+    pub reads : u64,
+    pub sequential : u64, // reads that started where the previous one ended
+    pub strided : u64, // reads that skipped as far as the previous skip
+    pub seek_bytes : u64, // distance skipped by all reads that didn't continue the previous one
+    #[serde(skip)]
+    next_offset : u64,
+    #[serde(skip)]
+    stride : Option<i64>
+}
+
+impl AccessPattern {
+    pub fn record(&mut self, offset : u64, size : u64) {
+        if self.reads > 0 {
+            let skipped = offset as i64 - self.next_offset as i64;
+            if skipped == 0 {
+                self.sequential += 1;
+            } else {
+                self.seek_bytes += skipped.unsigned_abs();
+                if self.stride == Some(skipped) {
+                    self.strided += 1;
+                }
+                self.stride = Some(skipped);
+            }
+        }
+        self.reads += 1;
+        self.next_offset = offset + size;
+    }
+
+    /// Sequential if nearly all reads continue the previous one, strided if the others nearly all skip the same distance.
+    /// A single read counts as sequential.
+    pub fn kind(&self) -> AccessKind {
+        let following = self.reads.saturating_sub(1) as f64;
+        if self.sequential as f64 >= following * PATTERN_SHARE {
+            AccessKind::Sequential
+        } else if self.strided > 0 && (self.sequential + self.strided + 1) as f64 >= following * PATTERN_SHARE {
+            // the first skip of the stride can't match a previous one, a single skip isn't a stride yet
+            AccessKind::Strided
+        } else {
+            AccessKind::Random
+        }
+    }
+
+    /// Share of the reads after the first that continued the previous one, between 0 and 1.
+    pub fn sequential_share(&self) -> f64 {
+        match self.reads {
+            0 | 1 => 1.0,
+            reads => self.sequential as f64 / (reads - 1) as f64
+        }
+    }
+
+    /// Average distance skipped by the reads that didn't continue the previous one.
+    pub fn mean_seek(&self) -> u64 {
+        let seeks = self.reads.saturating_sub(1) - self.sequential;
+        if seeks == 0 { 0 } else { self.seek_bytes / seeks }
+    }
+}
+
+/// Counters of a mount at one point in time, only reads that pass the path filter are counted.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StatsSnapshot {
+    pub files : BTreeMap<String, FileCounters>,
+    pub reads : u64,
+    pub bytes : u64,
+    pub covered : u64,
+    pub dropped : u64, // events lost because the receiver was closed or the ring was full
+    #[serde(default)]
+    pub opens : u64
+}
+
+impl StatsSnapshot {
+    /// Share of the bytes of all read files that were read at least once, between 0 and 1.
+    pub fn coverage(&self) -> f64 {
+        let size : u64 = self.files.values().map(|file| file.size).sum();
+        if size == 0 {
+            0.0
+        } else {
+            self.covered as f64 / size as f64
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct FileAccess {
+    reads : u64,
+    bytes : u64,
+    size : u64,
+    ranges : Vec<(u64, u64)>, // sorted and not overlapping, so it stays small for sequential reads
+    pattern : AccessPattern,
+    opens : u64
+}
+
+impl FileAccess {
+    fn add_range(&mut self, start : u64, end : u64) {
+        if start >= end {
+            return;
+        }
+        let (mut start, mut end) = (start, end);
+        // everything touching the new range gets merged into it
+        self.ranges.retain(|&(other_start, other_end)| {
+            if other_end < start || other_start > end {
+                return true;
+            }
+            start = start.min(other_start);
+            end = end.max(other_end);
+            false
+        });
+        let position = self.ranges.partition_point(|&(other_start, _)| other_start < start);
+        self.ranges.insert(position, (start, end));
+    }
+}
+
+/// Counters the filesystem updates on every read, shared with the mount session.
+#[derive(Debug, Default)]
+pub struct AccessStats {
+    files : HashMap<Arc<OsString>, FileAccess>,
+    dropped : u64
+}
+
+impl AccessStats {
+    fn record(&mut self, path : &Arc<OsString>, file_size : u64, offset : u64, returned : u64) {
+        let file = self.files.entry(path.clone()).or_default();
+        file.reads += 1;
+        file.bytes += returned;
+        file.size = file_size;
+        file.add_range(offset.min(file_size), (offset + returned).min(file_size));
+        file.pattern.record(offset, returned);
+    }
+
+    fn record_open(&mut self, path : &Arc<OsString>) {
+        self.files.entry(path.clone()).or_default().opens += 1;
+    }
+
+    /// Events that couldn't be delivered.
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    pub fn snapshot(&self) -> StatsSnapshot {
+        let mut snapshot = StatsSnapshot {
+            dropped: self.dropped,
+            ..StatsSnapshot::default()
+        };
+        for (path, file) in &self.files {
+            let covered = file.ranges.iter().map(|(start, end)| end - start).sum();
+            snapshot.reads += file.reads;
+            snapshot.bytes += file.bytes;
+            snapshot.covered += covered;
+            snapshot.opens += file.opens;
+            snapshot.files.insert(path.to_string_lossy().into_owned(), FileCounters {
+                reads: file.reads,
+                bytes: file.bytes,
+                size: file.size,
+                covered,
+                pattern: file.pattern.clone(),
+                opens: file.opens
+            });
+        }
+        snapshot
+    }
+}
+
+/// The events of a mount as a `futures_core::Stream`, ends when the filesystem is unmounted.
+#[cfg(feature = "tokio")]
+#[derive(Debug)]
+pub struct EventStream {
+    receiver : tokio::sync::mpsc::Receiver<Event>
+}
+
+#[cfg(feature = "tokio")]
+impl EventStream {
+    /// Returns the stream and the sink to give to the filesystem, up to `buffer` events are queued before reads wait.
+    pub fn new(buffer : usize) -> (Self, EventSink) {
+        let (sender, receiver) = tokio::sync::mpsc::channel(buffer);
+        (Self { receiver }, EventSink::Channel(sender))
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl futures_core::Stream for EventStream {
+    type Item = Event;
+
+    fn poll_next(mut self: std::pin::Pin<&mut Self>, cx: &mut std::task::Context<'_>) -> std::task::Poll<Option<Event>> {
+        self.receiver.poll_recv(cx)
+    }
+}
+
+/// The events of a mount in a lock-free single-producer single-consumer ring, for headless captures at high rates.
+/// Reads never wait for the consumer, events that don't fit into the ring are dropped and counted in the stats.
+#[cfg(feature = "ring")]
+#[derive(Debug)]
+pub struct EventRing {
+    consumer : rtrb::Consumer<Event>
+}
+
+#[cfg(feature = "ring")]
+impl EventRing {
+    /// Returns the ring and the sink to give to the filesystem, the ring holds up to `capacity` events.
+    pub fn new(capacity : usize) -> (Self, EventSink) {
+        let (producer, consumer) = rtrb::RingBuffer::new(capacity);
+        (Self { consumer }, EventSink::Ring(producer))
+    }
+
+    /// The oldest event, `None` if the ring is empty.
+    pub fn pop(&mut self) -> Option<Event> {
+        self.consumer.pop().ok()
+    }
+
+    /// Moves all events in the ring to `events`, returns how many there were.
+    pub fn drain_into(&mut self, events : &mut Vec<Event>) -> usize {
+        let count = self.consumer.slots();
+        match self.consumer.read_chunk(count) {
+            Ok(chunk) => events.extend(chunk),
+            Err(_) => return 0
+        }
+        count
+    }
+
+    /// Whether the filesystem is gone and all of its events were taken out.
+    pub fn is_finished(&self) -> bool {
+        self.consumer.is_abandoned() && self.consumer.is_empty()
+    }
+}
+
+/// A read handed to the source, to report it once it completed.
+#[derive(Debug)]
+pub(crate) struct PendingRead {
+    pub time : chrono::DateTime<Utc>,
+    pub start : std::time::Instant,
+    pub process : Process,
+    pub path : Arc<OsString>,
+    pub offset : i64,
+    pub size : u32,
+    pub file_size : u64,
+    pub handle : u64
+}
+
+/// Turns accesses into events and sends them to the sink, shared with the io_uring worker which completes reads after
+/// the FUSE handler returned. The sink has a lock of its own, so a full channel or a slow callback doesn't keep the
+/// other threads from turning their accesses into events meanwhile.
+#[derive(Debug)]
+pub(crate) struct Reporter {
+    state : Mutex<ReporterState>,
+    sink : Mutex<EventSink>,
+    stats : Arc<Mutex<AccessStats>> // the one of the state, counts the events the sink lost
+}
+
+impl Reporter {
+    fn new(state : ReporterState, sink : EventSink) -> Self {
+        Self { stats: state.stats.clone(), state: Mutex::new(state), sink: Mutex::new(sink) }
+    }
+
+    pub fn state(&self) -> MutexGuard<'_, ReporterState> {
+        self.state.lock().unwrap()
+    }
+
+    /// See `ReporterState::report`.
+    pub fn report(&self, read : &PendingRead, latency : Duration, result : Result<&[u8], c_int>) {
+        let events = self.state().report(read, latency, result);
+        self.send(events);
+    }
+
+    pub fn report_handle(&self, process : Process, path : &Arc<OsString>, handle : u64, open : bool) {
+        let events = self.state().report_handle(process, path, handle, open);
+        self.send(events);
+    }
+
+    pub fn report_event(&self, process : Process, path : &Arc<OsString>, event : EventType) {
+        let events = self.state().report_event(process, path, event);
+        self.send(events);
+    }
+
+    pub fn report_metadata(&self, process : Process, path : &Arc<OsString>, operation : MetadataOperation, found : bool) {
+        let events = self.state().report_metadata(process, path, operation, found);
+        self.send(events);
+    }
+
+    /// Delivers an event that is neither filtered nor processed by the plugins, e.g. a marker.
+    pub fn deliver(&self, event : Event) {
+        let events = self.state().record(vec![event]);
+        self.send(events);
+    }
+
+    /// Hands the events to the sink, with only the sink locked.
+    fn send(&self, events : Vec<Event>) {
+        if events.is_empty() {
+            return;
+        }
+        let mut sink = self.sink.lock().unwrap();
+        let mut lost = 0;
+        for event in events {
+            if !sink.send(event) {
+                lost += 1;
+            }
+        }
+        drop(sink);
+        if lost > 0 {
+            self.stats.lock().unwrap().dropped += lost;
+        }
+    }
+}
+
+/// What decides which accesses become events and records them, the events are sent by the `Reporter`.
+#[derive(Debug)]
+pub(crate) struct ReporterState {
+    capture_payloads : bool,
+    filter : PathFilter,
+    filter_enabled : bool, // turned off through .ffam/ctl
+    paused : bool, // through .ffam/ctl as well
+    stats : Arc<Mutex<AccessStats>>,
+    recorder : Arc<Mutex<Option<Recorder>>>, // started and stopped by the mount session
+    plugins : Plugins
+}
+
+impl ReporterState {
+    /// The events of a read that returned `result`, the data read or the error, after `latency`.
+    fn report(&mut self, read : &PendingRead, latency : Duration, result : Result<&[u8], c_int>) -> Vec<Event> {
+        if self.paused || (self.filter_enabled && !self.filter.matches(&read.path.to_string_lossy())) {
+            return Vec::new();
+        }
+        let data = match result {
+            Ok(data) if self.capture_payloads => Some(Arc::new(data.to_vec())),
+            _ => None
+        };
+        let event = Event {
+            time: read.time,
+            process: read.process.clone(),
+            event: EventType::Read(ReadEvent {
+                file: read.path.clone(),
+                offset: read.offset as usize,
+                size: read.size as usize,
+                file_size: read.file_size,
+                handle: read.handle,
+                latency,
+                data
+            })
+        };
+        let events = self.plugins.process(event);
+        // a read a plugin vetoed is not counted either, the derived events come after the kept one
+        if matches!(events.first().map(|event| &event.event), Some(EventType::Read(_))) {
+            let returned = result.map_or(0, |data| data.len() as u64);
+            self.stats.lock().unwrap().record(&read.path, read.file_size, read.offset as u64, returned);
+        }
+        self.record(events)
+    }
+
+    /// The events of `process` opening or closing `path` with `handle`.
+    fn report_handle(&mut self, process : Process, path : &Arc<OsString>, handle : u64, open : bool) -> Vec<Event> {
+        if self.paused || (self.filter_enabled && !self.filter.matches(&path.to_string_lossy())) {
+            return Vec::new();
+        }
+        let event = HandleEvent { file: path.clone(), handle };
+        let event = match open {
+            true => EventType::Open(event),
+            false => EventType::Close(event)
+        };
+        let event = Event { time: Utc::now(), process, event };
+        let events = self.plugins.process(event);
+        if matches!(events.first().map(|event| &event.event), Some(EventType::Open(_))) {
+            self.stats.lock().unwrap().record_open(path);
+        }
+        self.record(events)
+    }
+
+    /// The events of an event of `process` about `path` that needs no counting, e.g. a write or a resolved link.
+    fn report_event(&mut self, process : Process, path : &Arc<OsString>, event : EventType) -> Vec<Event> {
+        if self.paused || (self.filter_enabled && !self.filter.matches(&path.to_string_lossy())) {
+            return Vec::new();
+        }
+        let event = Event { time: Utc::now(), process, event };
+        let events = self.plugins.process(event);
+        self.record(events)
+    }
+
+    /// The events of an access of `path` by `process` that didn't read its content.
+    fn report_metadata(&mut self, process : Process, path : &Arc<OsString>, operation : MetadataOperation, found : bool) -> Vec<Event> {
+        if self.paused || (self.filter_enabled && !self.filter.matches(&path.to_string_lossy())) {
+            return Vec::new();
+        }
+        let event = Event {
+            time: Utc::now(),
+            process,
+            event: EventType::Metadata(MetadataEvent { file: path.clone(), operation, found })
+        };
+        let events = self.plugins.process(event);
+        self.record(events)
+    }
+
+    /// Records the events if a recording is running, they are sent after the state is released.
+    fn record(&mut self, events : Vec<Event>) -> Vec<Event> {
+        let mut recorder = self.recorder.lock().unwrap();
+        for event in &events {
+            if let Some(Err(err)) = recorder.as_mut().map(|recorder| recorder.write(event)) {
+                log!(warn, "Recording failed, stopping it: {err}");
+                *recorder = None;
+            }
+        }
+        events
+    }
+}
+
+/// Processes cached before those without open handles are dropped.
+const MAX_CACHED_PROCESSES : usize = 256;
+
+/// A process whose requests were seen, with its start time, which tells it apart from later processes with its pid.
+#[derive(Debug)]
+struct CachedProcess {
+    process : Process,
+    start : Option<u64>,
+    handles : usize // open handles of the process, it is dropped when the last one is closed
+}
+
+#[derive(Debug)]
+pub struct FileAccessTrackingFs {
+    source : Arc<dyn Source>, // shared with the prefetches
+    directory: Arc<Mutex<Directory>>, // shared with the mount session, which can change it while mounted
+    reporter : Arc<Reporter>,
+    rules : Vec<Rule>,
+    read_only : bool,
+    write_through : bool, // never when read only
+    metadata_events : bool,
+    direct_io : bool,
+    kernel : crate::KernelOptions,
+    ttl : Duration, // zero with direct io
+    processes : HashMap<u32, CachedProcess>, // by pid, only valid as long as the start time matches
+    #[cfg(feature = "tokio")]
+    pub(crate) session_end : Option<tokio::sync::oneshot::Sender<()>>, // dropped together with the filesystem when the session ends
+    read_buffer : Vec<u8>, // reused by every read, grows to the largest request the kernel makes
+    generated : HashMap<u64, Vec<u8>>, // content of the generated files per handle, from its last read at offset 0
+    handles : HashMap<u64, (u64, Arc<OsString>, Process)>, // open handles with the inode, the path and the process that opened them
+    prefetcher : Option<Prefetcher>, // None if prefetching is disabled
+    #[cfg(feature = "io-uring")]
+    uring : Option<UringReader>, // None if reading through io_uring is disabled or not supported by the kernel
+    delay : Duration, // added by the rules to the request being served, its reply is sent that much later
+    delayer : Option<Delayer>, // started on the first delayed reply
+    next_handle : u64
+}
+
+impl FileAccessTrackingFs {
+    #[cfg(feature = "tokio")]
+    pub fn new(source : &str, event_sender : tokio::sync::mpsc::Sender<Event>, options : &crate::MountOptions, progress : &ScanProgress) -> crate::Result<Self> {
+        Self::with_sink(DiskSource::new(source), EventSink::Channel(event_sender), options, progress)
+    }
+
+    /// Calls `callback` for every event right from the FUSE handler, without a channel in between.
+    pub fn with_callback(source : &str, callback : impl FnMut(Event) + Send + 'static, options : &crate::MountOptions, progress : &ScanProgress) -> crate::Result<Self> {
+        Self::with_sink(DiskSource::new(source), EventSink::Callback(Box::new(callback)), options, progress)
+    }
+
+    /// Serves the files of any `Source`, e.g. a `MemorySource` in tests.
+    pub fn with_sink(source : impl Source, event_sink : EventSink, options : &crate::MountOptions, progress : &ScanProgress) -> crate::Result<Self> {
+        let source : Arc<dyn Source> = Arc::new(source);
+        let mut directory = Directory::new(source.clone(), options.case_sensitivity, progress)?;
+        let generated = [(options.stats_file, control::STATS, Generated::Stats), (options.control_file, control::CONTROL, Generated::Control)];
+        for (_, path, generated) in generated.into_iter().filter(|(enabled, _, _)| *enabled) {
+            // a .ffam directory of the source is kept, only the generated files are added to it
+            if directory.find_path(OsStr::new(control::DIRECTORY)).is_none() {
+                directory.put(OsStr::new(control::DIRECTORY), None)?;
+            }
+            if let Err(err) = directory.put_generated(OsStr::new(path), generated) {
+                log!(warn, "Failed to add {path}: {err}");
+            }
+        }
+        let reporter = Arc::new(Reporter::new(ReporterState {
+            capture_payloads: options.capture_payloads,
+            filter: options.filter.clone(),
+            filter_enabled: true,
+            paused: false,
+            stats: Arc::new(Mutex::new(AccessStats::default())),
+            recorder: Arc::new(Mutex::new(None)),
+            plugins: Plugins::default()
+        }, event_sink));
+        #[cfg(feature = "io-uring")]
+        let uring = match options.io_uring {
+            true => UringReader::new(reporter.clone()).inspect_err(|err| log!(warn, "io_uring is not available, reading synchronously: {err}")).ok(),
+            false => None
+        };
+        #[cfg(feature = "io-uring")]
+        let prefetch = options.prefetch && uring.is_none(); // reads through io_uring don't wait for each other anyway
+        #[cfg(not(feature = "io-uring"))]
+        let prefetch = options.prefetch;
+        let write_through = options.write_through && !options.read_only;
+        let prefetch = prefetch && !write_through; // prefetched content would be outdated after a write
+
+        Ok(Self {
+            source,
+            directory: Arc::new(Mutex::new(directory)),
+            reporter,
+            rules: options.rules.clone(),
+            read_only: options.read_only,
+            write_through,
+            metadata_events: options.metadata_events,
+            direct_io: options.direct_io,
+            kernel: options.kernel.clone(),
+            ttl: if options.direct_io { Duration::ZERO } else { options.ttl },
+            processes: HashMap::new(),
+            #[cfg(feature = "tokio")]
+            session_end: None,
+            read_buffer: Vec::new(),
+            generated: HashMap::new(),
+            handles: HashMap::new(),
+            prefetcher: prefetch.then(Prefetcher::default),
+            #[cfg(feature = "io-uring")]
+            uring,
+            delay: Duration::ZERO,
+            delayer: None,
+            next_handle: 1
+        })
+    }
+
+    /// The counters of this filesystem, they keep being updated after it was mounted.
+    pub fn stats(&self) -> Arc<Mutex<AccessStats>> {
+        self.reporter.state().stats.clone()
+    }
+
+    pub(crate) fn recorder(&self) -> Arc<Mutex<Option<Recorder>>> {
+        self.reporter.state().recorder.clone()
+    }
+
+    pub(crate) fn set_plugins(&mut self, plugins : Plugins) {
+        self.reporter.state().plugins = plugins;
+    }
+
+    pub(crate) fn directory(&self) -> Arc<Mutex<Directory>> {
+        self.directory.clone()
+    }
+
+    /// Inode of a path relative to the mounted root, "" is the root itself.
+    pub fn inode(&self, path : &str) -> Option<u64> {
+        self.directory.lock().unwrap().find_path(OsStr::new(path)).map(|entry| entry.inode)
+    }
+
+    /// Serves a read on behalf of process `pid` and reports it, this is the read handler without the FUSE reply.
+    pub fn read_file(&mut self, pid : u32, ino : u64, fh : u64, offset : i64, size : u32) -> Result<Vec<u8>, c_int> {
+        let mut buffer = Vec::new();
+        let read = self.read_into(pid, ino, fh, offset, size, &mut buffer)?;
+        buffer.truncate(read);
+        Ok(buffer)
+    }
+
+    /// Reads into the start of `buffer`, which is grown to `size` if needed, and returns how many bytes were read.
+    fn read_into(&mut self, pid : u32, ino : u64, fh : u64, offset : i64, size : u32, buffer : &mut Vec<u8>) -> Result<usize, c_int> {
+        let process = self.process(pid);
+        let directory = self.directory.lock().unwrap();
+        let Some(entry) = directory.entry(ino) else {
+            return Err(ENOENT);
+        };
+        if let Data::Generated(generated) = entry.data {
+            // not an access of the game, neither reported nor counted
+            drop(directory);
+            return Ok(self.read_generated(generated, fh, offset, size, buffer));
+        }
+        log!(trace, "Reading {} from {offset} to {}", entry.name.display(), offset as usize+size as usize);
+        let data = entry.data.clone();
+        let pending = PendingRead {
+            time: Utc::now(),
+            start: std::time::Instant::now(),
+            process,
+            path: entry.path.clone(),
+            offset,
+            size,
+            file_size: directory.attr(entry).size,
+            handle: fh
+        };
+        // reading the source and sending may block, the session must still be able to change the tree meanwhile
+        drop(directory);
+        if buffer.len() < size as usize {
+            buffer.resize(size as usize, 0);
+        }
+        let path = &pending.path;
+        let prefetcher = self.prefetcher.as_mut().filter(|_| matches!(data, Data::Source));
+        let prefetched = prefetcher.as_ref().and_then(|prefetcher| prefetcher.read(fh, path, offset as u64, &mut buffer[..size as usize]));
+        let result = match prefetched {
+            Some(read) => Ok(read),
+            None => data.read(self.source.as_ref(), path, &mut buffer[..size as usize], offset as usize).map_err(|_| ENOENT)
+        };
+        if let (Some(prefetcher), Ok(read)) = (prefetcher, result) {
+            prefetcher.record(&self.source, fh, path, offset as u64, read, size as usize);
+        }
+        let latency = pending.start.elapsed();
+        self.reporter.report(&pending, latency, result.map(|read| &buffer[..read]));
+        result
+    }
+
+    /// Reads a generated file, a read at offset 0 generates the content anew, the following ones continue in it.
+    fn read_generated(&mut self, generated : Generated, fh : u64, offset : i64, size : u32, buffer : &mut Vec<u8>) -> usize {
+        if offset == 0 || !self.generated.contains_key(&fh) {
+            let content = self.generate(generated);
+            self.generated.insert(fh, content);
+        }
+        let content = &self.generated[&fh];
+        let start = (offset as usize).min(content.len());
+        let end = (start + size as usize).min(content.len());
+        buffer.clear();
+        buffer.extend_from_slice(&content[start..end]);
+        end - start
+    }
+
+    fn generate(&self, generated : Generated) -> Vec<u8> {
+        match generated {
+            Generated::Stats => control::stats(&self.stats().lock().unwrap()),
+            Generated::Control => {
+                let recording = self.recorder().lock().unwrap().is_some();
+                let reporter = self.reporter.state();
+                control::state(reporter.paused, reporter.filter_enabled, recording)
+            }
+        }
+    }
+
+    /// Attributes of a generated file, its size is the one of the content it would have now.
+    fn generated_attr(&self, generated : Generated, mut attr : Attributes) -> Attributes {
+        attr.size = self.generate(generated).len() as u64;
+        attr.perm = match generated {
+            Generated::Stats => 0o444,
+            Generated::Control => 0o666
+        };
+        attr
+    }
+
+    /// The kind of generated file of an inode, the kernel mustn't cache those or trust their size.
+    fn generated(&self, ino : u64) -> Option<Generated> {
+        self.directory.lock().unwrap().root.find_ino(ino).and_then(|entry| entry.data.generated())
+    }
+
+    /// Runs the commands written to `.ffam/ctl` by process `pid`, one per line. Returns how many bytes were taken,
+    /// all or none if a line isn't a command.
+    pub fn write_control(&mut self, pid : u32, ino : u64, data : &[u8]) -> Result<u32, c_int> {
+        if self.generated(ino) != Some(Generated::Control) {
+            return Err(libc::EACCES);
+        }
+        let text = std::str::from_utf8(data).map_err(|_| libc::EINVAL)?;
+        let commands = text.lines().filter(|line| !line.trim().is_empty()).map(str::parse).collect::<Result<Vec<Command>, _>>();
+        let commands = match commands {
+            Ok(commands) => commands,
+            Err(err) => {
+                log!(warn, "{err}");
+                return Err(libc::EINVAL);
+            }
+        };
+        for command in commands {
+            log!(debug, "Control command {command:?} from {pid}");
+            self.control(pid, command);
+        }
+        Ok(data.len() as u32)
+    }
+
+    fn control(&mut self, pid : u32, command : Command) {
+        match command {
+            Command::Pause => self.reporter.state().paused = true,
+            Command::Resume => self.reporter.state().paused = false,
+            Command::Filter(enabled) => self.reporter.state().filter_enabled = enabled,
+            Command::Marker(label) => {
+                let event = Event {
+                    time: Utc::now(),
+                    process: self.process(pid),
+                    event: EventType::Marker(label)
+                };
+                self.reporter.deliver(event);
+            }
+            Command::Flush => {
+                if let Some(Err(err)) = self.recorder().lock().unwrap().as_mut().map(Recorder::flush) {
+                    log!(warn, "Flushing the recording failed: {err}");
+                }
+            }
+        }
+    }
+
+    /// Opens the file of a handle for io_uring, it is read synchronously if that fails.
+    #[cfg(feature = "io-uring")]
+    fn open_uring(&mut self, ino : u64, fh : u64) {
+        let Some(uring) = &mut self.uring else {
+            return;
+        };
+        let directory = self.directory.lock().unwrap();
+        let Some(entry) = directory.root.find_ino(ino).filter(|entry| matches!(entry.data, Data::Source)) else {
+            return;
+        };
+        match self.source.open(&entry.path) {
+            Some(Ok(file)) => uring.open(fh, file),
+            Some(Err(err)) => log!(debug, "Opening {} for io_uring failed: {err}", entry.path.display()),
+            None => ()
+        }
+    }
+
+    /// Submits a read of a handle opened for io_uring, returns the reply if the read has to be served synchronously.
+    #[cfg(feature = "io-uring")]
+    fn read_uring(&mut self, pid : u32, ino : u64, fh : u64, offset : i64, size : u32, reply : ReplyData) -> Option<ReplyData> {
+        // the ring replies as soon as the read completes, delayed reads are served synchronously
+        if !self.delay.is_zero() {
+            return Some(reply);
+        }
+        let Some(file) = self.uring.as_ref().and_then(|uring| uring.file(fh)) else {
+            return Some(reply);
+        };
+        let process = self.process(pid);
+        let directory = self.directory.lock().unwrap();
+        // the entry may have been replaced since the handle was opened
+        let Some(entry) = directory.root.find_ino(ino).filter(|entry| matches!(entry.data, Data::Source)) else {
+            return Some(reply);
+        };
+        log!(trace, "Submitting read of {} from {offset} to {}", entry.name.display(), offset as usize+size as usize);
+        let read = UringRead {
+            file,
+            buffer: vec![0; size as usize],
+            read: PendingRead {
+                time: Utc::now(),
+                start: std::time::Instant::now(),
+                process,
+                path: entry.path.clone(),
+                offset,
+                size,
+                file_size: directory.attr(entry).size,
+                handle: fh
+            },
+            reply
+        };
+        drop(directory);
+        match self.uring.as_ref().unwrap().submit(read) {
+            Ok(()) => None,
+            Err(read) => {
+                // the worker stopped, the next reads don't try the ring anymore
+                self.uring = None;
+                Some(read.reply)
+            }
+        }
+    }
+
+    /// Looks up `name` in the directory `parent` on behalf of process `pid`, this is the lookup handler without the reply.
+    pub fn lookup_entry(&mut self, pid : u32, parent : u64, name : &OsStr) -> Result<Attributes, c_int> {
+        self.check_process(pid);
+        let directory = self.directory.lock().unwrap();
+        let Some(parent_entry) = directory.root.find_ino(parent) else {
+            return Err(ENOENT);
+        };
+        let Some(entry) = parent_entry.find_name(name, directory.case) else {
+            log!(debug, "Failed to find {}, parent: {parent}", name.display());
+            if self.metadata_events {
+                let path = Arc::new(Entry::join_path(&parent_entry.path, name));
+                drop(directory);
+                self.report_metadata(pid, &path, MetadataOperation::Lookup, false);
+            }
+            return Err(ENOENT);
+        };
+        let (path, attr, generated) = (entry.path.clone(), directory.attr(entry), entry.data.generated());
+        drop(directory);
+        self.apply_rules(Operation::Lookup, pid, &path, None)?;
+        if generated.is_none() {
+            self.report_metadata(pid, &path, MetadataOperation::Lookup, true);
+        }
+        match generated {
+            Some(generated) => Ok(self.generated_attr(generated, attr)),
+            None => Ok(attr)
+        }
+    }
+
+    /// The attributes of an inode asked for by process `pid`, this is the getattr handler without the reply.
+    pub fn stat_entry(&mut self, pid : u32, ino : u64) -> Result<Attributes, c_int> {
+        let attr = self.attributes(ino)?;
+        if self.metadata_events && self.generated(ino).is_none() {
+            let path = self.directory.lock().unwrap().entry(ino).map(|entry| entry.path.clone());
+            if let Some(path) = path {
+                self.report_metadata(pid, &path, MetadataOperation::GetAttr, true);
+            }
+        }
+        Ok(attr)
+    }
+
+    pub fn attributes(&self, ino : u64) -> Result<Attributes, c_int> {
+        let directory = self.directory.lock().unwrap();
+        let Some(entry) = directory.entry(ino) else {
+            return Err(ENOENT);
+        };
+        let (attr, generated) = (directory.attr(entry), entry.data.generated());
+        drop(directory);
+        match generated {
+            Some(generated) => Ok(self.generated_attr(generated, attr)),
+            None => Ok(attr)
+        }
+    }
+
+    /// Opens a handle for process `pid` and reports it, every open gets its own, so the prefetcher can tell sequential
+    /// readers of the same file apart and consumers of the events which reads belong to one open.
+    pub fn open_handle(&mut self, pid : u32, ino : u64) -> Result<u64, c_int> {
+        self.check_process(pid);
+        self.apply_rules_to_inode(Operation::Open, pid, ino, None)?;
+        let fh = self.next_handle;
+        self.next_handle += 1;
+        // generated files are not files of the game
+        let path = self.directory.lock().unwrap().root.find_ino(ino).filter(|entry| entry.data.generated().is_none()).map(|entry| entry.path.clone());
+        if let Some(path) = path {
+            let cached = self.cached_process(pid);
+            cached.handles += 1;
+            let process = cached.process.clone();
+            self.handles.insert(fh, (ino, path.clone(), process.clone()));
+            self.directory.lock().unwrap().opened(ino);
+            self.reporter.report_handle(process, &path, fh, true);
+        }
+        #[cfg(feature = "io-uring")]
+        self.open_uring(ino, fh);
+        Ok(fh)
+    }
+
+    /// Closes a handle and reports it, for the process that opened it.
+    pub fn release_handle(&mut self, fh : u64) {
+        self.generated.remove(&fh);
+        if let Some((ino, path, process)) = self.handles.remove(&fh) {
+            self.forget_handle(&process);
+            self.reporter.report_handle(process, &path, fh, false);
+            self.directory.lock().unwrap().closed(ino);
+        }
+        if let Some(prefetcher) = &mut self.prefetcher {
+            prefetcher.close(fh);
+        }
+        #[cfg(feature = "io-uring")]
+        if let Some(uring) = &mut self.uring {
+            uring.close(fh);
+        }
+    }
+
+    /// The metadata and content of a file of the source, for open handles of it after it is removed or replaced.
+    /// The file is opened before it is gone, so nothing has to be copied; only sources without files on disk are
+    /// copied into memory, which is where they are anyway.
+    fn keep_open(&self, path : &OsStr) -> Result<(SourceMetadata, Data), c_int> {
+        let source_errno = |err : std::io::Error| err.raw_os_error().unwrap_or(libc::EIO);
+        let metadata = self.source.stat(path).map_err(source_errno)?;
+        if let Some(file) = self.source.open(path) {
+            return Ok((metadata, Data::Open(OpenFile(Arc::new(file.map_err(source_errno)?)))));
+        }
+        let mut content = vec![0; metadata.size as usize];
+        let mut read = 0;
+        while read < content.len() {
+            match self.source.read(path, &mut content[read..], read as u64).map_err(source_errno)? {
+                0 => break,
+                count => read += count
+            }
+        }
+        content.truncate(read);
+        Ok((SourceMetadata { size: read as u64, ..metadata }, Data::Memory(Arc::new(content))))
+    }
+
+    /// Creates an empty file for process `pid` and reports it, in the source with `MountOptions::write_through`,
+    /// otherwise only in memory.
+    pub fn create_entry(&mut self, pid : u32, parent : u64, name : &OsStr) -> Result<Attributes, c_int> {
+        if self.read_only {
+            return Err(EROFS);
+        }
+        let (name, path) = self.directory.lock().unwrap().new_path(parent, name)?;
+        let path = Arc::new(path);
+        self.apply_rules(Operation::Write, pid, &path, None)?;
+        let mut directory = self.directory.lock().unwrap();
+        if directory.find_child(parent, &name).is_some() {
+            return Err(libc::EEXIST);
+        }
+        if self.write_through {
+            self.source.create(&path).map_err(errno)?;
+        }
+        log!(debug, "Created {}", path.display());
+        let attr = directory.create_file(parent, &name, self.write_through)?;
+        drop(directory);
+        self.report_event(pid, &path, EventType::Change(ChangeEvent { file: path.clone(), operation: ChangeOperation::Create }));
+        Ok(attr)
+    }
+
+    /// Writes to a file of the source for process `pid` and reports it, returns how many bytes were written.
+    /// Only files of the source can be written and only with `MountOptions::write_through`.
+    pub fn write_data(&mut self, pid : u32, ino : u64, fh : u64, offset : i64, data : &[u8]) -> Result<u32, c_int> {
+        let path = self.source_file(ino)?;
+        self.apply_rules(Operation::Write, pid, &path, Some(data.len() as u64))?;
+        let written = self.source.write(&path, data, offset as u64).map_err(errno)?;
+        // stat'ed again when needed, writes may go past the end
+        self.directory.lock().unwrap().restat(ino);
+        let event = WriteEvent { file: path.clone(), offset: offset as u64, size: written, handle: fh };
+        self.report_event(pid, &path, EventType::Write(event));
+        Ok(written as u32)
+    }
+
+    /// Truncates or extends a file of the source to `size` for process `pid` and reports it.
+    pub fn truncate(&mut self, pid : u32, ino : u64, size : u64) -> Result<Attributes, c_int> {
+        let path = self.source_file(ino)?;
+        self.apply_rules(Operation::Write, pid, &path, Some(size))?;
+        self.source.truncate(&path, size).map_err(errno)?;
+        self.directory.lock().unwrap().restat(ino);
+        self.report_event(pid, &path, EventType::Change(ChangeEvent { file: path.clone(), operation: ChangeOperation::Truncate { size } }));
+        self.attributes(ino)
+    }
+
+    /// Changes the permission bits of an entry of the source for process `pid` and reports it, like `truncate`.
+    pub fn set_permissions(&mut self, pid : u32, ino : u64, mode : u32) -> Result<Attributes, c_int> {
+        let path = self.source_entry(ino)?;
+        self.apply_rules(Operation::Write, pid, &path, None)?;
+        self.source.set_permissions(&path, mode).map_err(errno)?;
+        self.directory.lock().unwrap().restat(ino);
+        let operation = ChangeOperation::Permissions { mode: mode & 0o7777 };
+        self.report_event(pid, &path, EventType::Change(ChangeEvent { file: path.clone(), operation }));
+        self.attributes(ino)
+    }
+
+    /// Changes the owner or the group of an entry of the source for process `pid` and reports it, like `truncate`.
+    pub fn set_owner(&mut self, pid : u32, ino : u64, uid : Option<u32>, gid : Option<u32>) -> Result<Attributes, c_int> {
+        let path = self.source_entry(ino)?;
+        self.apply_rules(Operation::Write, pid, &path, None)?;
+        self.source.set_owner(&path, uid, gid).map_err(errno)?;
+        self.directory.lock().unwrap().restat(ino);
+        self.report_event(pid, &path, EventType::Change(ChangeEvent { file: path.clone(), operation: ChangeOperation::Owner { uid, gid } }));
+        self.attributes(ino)
+    }
+
+    /// Sets the access or modification time of an entry of the source for process `pid` and reports it, like `truncate`.
+    pub fn set_times(&mut self, pid : u32, ino : u64, accessed : Option<SystemTime>, modified : Option<SystemTime>) -> Result<Attributes, c_int> {
+        let path = self.source_entry(ino)?;
+        self.apply_rules(Operation::Write, pid, &path, None)?;
+        self.source.set_times(&path, accessed, modified).map_err(errno)?;
+        self.directory.lock().unwrap().restat(ino);
+        let operation = ChangeOperation::Times { accessed: accessed.map(Into::into), modified: modified.map(Into::into) };
+        self.report_event(pid, &path, EventType::Change(ChangeEvent { file: path.clone(), operation }));
+        self.attributes(ino)
+    }
+
+    /// The changes of a setattr call one after the other, stopping at the first that fails. ENOSYS if there are none
+    /// this filesystem supports.
+    fn set_attributes(&mut self, pid : u32, ino : u64, size : Option<u64>, mode : Option<u32>, (uid, gid) : (Option<u32>, Option<u32>),
+                      (accessed, modified) : (Option<SystemTime>, Option<SystemTime>)) -> Result<Attributes, c_int> {
+        let mut attr = Err(libc::ENOSYS);
+        if let Some(size) = size {
+            attr = Ok(self.truncate(pid, ino, size)?);
+        }
+        if let Some(mode) = mode {
+            attr = Ok(self.set_permissions(pid, ino, mode)?);
+        }
+        if uid.is_some() || gid.is_some() {
+            attr = Ok(self.set_owner(pid, ino, uid, gid)?);
+        }
+        if accessed.is_some() || modified.is_some() {
+            attr = Ok(self.set_times(pid, ino, accessed, modified)?);
+        }
+        attr
+    }
+
+    /// Removes the file `name` of the directory `parent` for process `pid` and reports it. Files of the source are
+    /// only removed with `MountOptions::write_through`, files created in memory always can be.
+    pub fn unlink_entry(&mut self, pid : u32, parent : u64, name : &OsStr) -> Result<(), c_int> {
+        if self.read_only {
+            return Err(EROFS);
+        }
+        let directory = self.directory.lock().unwrap();
+        let Some(entry) = directory.find_child(parent, name) else {
+            return Err(ENOENT);
+        };
+        if entry.info.is_dir() {
+            return Err(libc::EISDIR);
+        }
+        let (path, inode, in_source, open) = (entry.path.clone(), entry.inode, self.in_source(entry)?, directory.is_open(entry.inode));
+        drop(directory);
+        self.apply_rules(Operation::Write, pid, &path, None)?;
+        // open handles keep reading what the file had
+        let content = match in_source && open {
+            true => Some(self.keep_open(&path)?),
+            false => None
+        };
+        if in_source {
+            self.source.remove_file(&path).map_err(errno)?;
+        }
+        log!(debug, "Removed {}", path.display());
+        // the kernel forgets the entry itself, the change doesn't need to be invalidated
+        let mut directory = self.directory.lock().unwrap();
+        if let Some(entry) = directory.detach(parent, inode) {
+            directory.keep_detached(entry, content);
+        }
+        drop(directory);
+        self.report_event(pid, &path, EventType::Change(ChangeEvent { file: path.clone(), operation: ChangeOperation::Remove }));
+        Ok(())
+    }
+
+    /// Creates an empty directory for process `pid` and reports it, like `create_entry`.
+    pub fn make_directory(&mut self, pid : u32, parent : u64, name : &OsStr) -> Result<Attributes, c_int> {
+        if self.read_only {
+            return Err(EROFS);
+        }
+        let (name, path) = self.directory.lock().unwrap().new_path(parent, name)?;
+        let path = Arc::new(path);
+        self.apply_rules(Operation::Write, pid, &path, None)?;
+        let mut directory = self.directory.lock().unwrap();
+        if directory.find_child(parent, &name).is_some() {
+            return Err(libc::EEXIST);
+        }
+        if self.write_through {
+            self.source.create_dir(&path).map_err(errno)?;
+        }
+        log!(debug, "Created directory {}", path.display());
+        let attr = directory.create_directory(parent, &name, self.write_through)?;
+        drop(directory);
+        self.report_event(pid, &path, EventType::Change(ChangeEvent { file: path.clone(), operation: ChangeOperation::CreateDirectory }));
+        Ok(attr)
+    }
+
+    /// Removes the empty directory `name` of `parent` for process `pid` and reports it, like `unlink_entry`.
+    pub fn remove_directory(&mut self, pid : u32, parent : u64, name : &OsStr) -> Result<(), c_int> {
+        if self.read_only {
+            return Err(EROFS);
+        }
+        let directory = self.directory.lock().unwrap();
+        let Some(entry) = directory.find_child(parent, name) else {
+            return Err(ENOENT);
+        };
+        match &entry.info {
+            EntryInfo::Directory(entries) if !entries.is_empty() => return Err(libc::ENOTEMPTY),
+            EntryInfo::Directory(_) => (),
+            EntryInfo::File | EntryInfo::Symlink(_) => return Err(libc::ENOTDIR)
+        }
+        let (path, in_source) = (entry.path.clone(), self.in_source(entry)?);
+        drop(directory);
+        self.apply_rules(Operation::Write, pid, &path, None)?;
+        if in_source {
+            self.source.remove_dir(&path).map_err(errno)?;
+        }
+        log!(debug, "Removed directory {}", path.display());
+        let _ = self.directory.lock().unwrap().remove(&path);
+        self.report_event(pid, &path, EventType::Change(ChangeEvent { file: path.clone(), operation: ChangeOperation::RemoveDirectory }));
+        Ok(())
+    }
+
+    /// Moves the entry `name` of `parent` to `new_name` in `new_parent` for process `pid` and reports it, replacing an
+    /// entry there unless `no_replace`. Entries of the source are only moved with `MountOptions::write_through`.
+    pub fn rename_entry(&mut self, pid : u32, parent : u64, name : &OsStr, new_parent : u64, new_name : &OsStr, no_replace : bool) -> Result<(), c_int> {
+        if self.read_only {
+            return Err(EROFS);
+        }
+        let directory = self.directory.lock().unwrap();
+        let Some(entry) = directory.find_child(parent, name) else {
+            return Err(ENOENT);
+        };
+        let (path, inode, in_source) = (entry.path.clone(), entry.inode, self.in_source(entry)?);
+        let Some(new_parent_entry) = directory.root.find_ino(new_parent) else {
+            return Err(ENOENT);
+        };
+        let target = new_parent_entry.find_name(new_name, directory.case).filter(|target| target.inode != inode);
+        if no_replace && target.is_some() {
+            return Err(libc::EEXIST);
+        }
+        // the generated files can't be replaced, and neither can files of the source unless writing through
+        let replaced_open_in_source = match target {
+            Some(target) => self.in_source(target)? && directory.is_open(target.inode),
+            None => false
+        };
+        // names may match ignoring case, a replaced entry keeps its name so the source doesn't end up with both
+        let new_name = target.map_or_else(|| directory.case.new_name(new_name), |target| target.name.to_os_string());
+        let new_path = Entry::join_path(&new_parent_entry.path, &new_name);
+        drop(directory);
+        self.apply_rules(Operation::Write, pid, &path, None)?;
+        // open handles of the replaced file keep reading what it had, not what replaces it
+        let content = match replaced_open_in_source {
+            true => Some(self.keep_open(&new_path)?),
+            false => None
+        };
+        if in_source {
+            self.source.rename(&path, &new_path).map_err(errno)?;
+        }
+        log!(debug, "Renamed {} to {}", path.display(), new_path.display());
+        let mut directory = self.directory.lock().unwrap();
+        if let Some(replaced) = directory.rename(parent, name, new_parent, &new_name)? {
+            directory.keep_detached(replaced, content);
+        }
+        drop(directory);
+        self.report_event(pid, &path, EventType::Change(ChangeEvent { file: path.clone(), operation: ChangeOperation::Rename { to: new_path } }));
+        Ok(())
+    }
+
+    /// The target of the symlink `ino`, resolved for process `pid` and reported.
+    pub fn read_link(&mut self, pid : u32, ino : u64) -> Result<OsString, c_int> {
+        let directory = self.directory.lock().unwrap();
+        let Some(entry) = directory.root.find_ino(ino) else {
+            return Err(ENOENT);
+        };
+        let EntryInfo::Symlink(target) = &entry.info else {
+            return Err(libc::EINVAL);
+        };
+        let (path, target) = (entry.path.clone(), target.clone());
+        drop(directory);
+        self.apply_rules(Operation::Lookup, pid, &path, None)?;
+        self.report_event(pid, &path, EventType::Link(LinkEvent { file: path.clone(), target: target.clone() }));
+        Ok(target)
+    }
+
+    /// Creates a symlink to `target` for process `pid` and reports it, like `create_entry`.
+    pub fn create_symlink(&mut self, pid : u32, parent : u64, name : &OsStr, target : &OsStr) -> Result<Attributes, c_int> {
+        if self.read_only {
+            return Err(EROFS);
+        }
+        let (name, path) = self.directory.lock().unwrap().new_path(parent, name)?;
+        let path = Arc::new(path);
+        self.apply_rules(Operation::Write, pid, &path, None)?;
+        let mut directory = self.directory.lock().unwrap();
+        if directory.find_child(parent, &name).is_some() {
+            return Err(libc::EEXIST);
+        }
+        if self.write_through {
+            self.source.symlink(&path, target).map_err(errno)?;
+        }
+        log!(debug, "Linked {} to {}", path.display(), target.display());
+        let attr = directory.create_symlink(parent, &name, target, self.write_through)?;
+        drop(directory);
+        self.report_event(pid, &path, EventType::Change(ChangeEvent { file: path.clone(), operation: ChangeOperation::Symlink { target: target.to_os_string() } }));
+        Ok(attr)
+    }
+
+    /// The value of the extended attribute `name` of `ino` for process `pid`, reported with the metadata events.
+    /// Only entries of the source have attributes.
+    pub fn get_xattr(&mut self, pid : u32, ino : u64, name : &str) -> Result<Vec<u8>, c_int> {
+        let (path, in_source) = self.xattr_entry(ino)?;
+        self.apply_rules(Operation::Lookup, pid, &path, None)?;
+        let value = match in_source {
+            true => self.source.get_xattr(&path, name).map_err(xattr_errno)?,
+            false => None
+        };
+        self.report_xattr(pid, &path, XattrOperation::Get, Some(name), value.as_ref().map(Vec::len));
+        value.ok_or(libc::ENODATA)
+    }
+
+    /// The names of the extended attributes of `ino`, each followed by a 0 byte, like `get_xattr`.
+    pub fn list_xattr(&mut self, pid : u32, ino : u64) -> Result<Vec<u8>, c_int> {
+        let (path, in_source) = self.xattr_entry(ino)?;
+        self.apply_rules(Operation::Lookup, pid, &path, None)?;
+        let names = match in_source {
+            true => self.source.list_xattr(&path).map_err(xattr_errno)?,
+            false => Vec::new()
+        };
+        self.report_xattr(pid, &path, XattrOperation::List, None, Some(names.len()));
+        Ok(names)
+    }
+
+    /// Sets an extended attribute of an entry of the source for process `pid` and reports it, only with
+    /// `MountOptions::write_through`.
+    pub fn set_xattr(&mut self, pid : u32, ino : u64, name : &str, value : &[u8], flags : i32) -> Result<(), c_int> {
+        if self.read_only {
+            return Err(EROFS);
+        }
+        let directory = self.directory.lock().unwrap();
+        let Some(entry) = directory.root.find_ino(ino) else {
+            return Err(ENOENT);
+        };
+        let (path, in_source) = (entry.path.clone(), self.in_source(entry)?);
+        drop(directory);
+        if !in_source {
+            return Err(libc::ENOTSUP);
+        }
+        self.apply_rules(Operation::Write, pid, &path, Some(value.len() as u64))?;
+        self.source.set_xattr(&path, name, value, flags).map_err(xattr_errno)?;
+        self.report_xattr(pid, &path, XattrOperation::Set, Some(name), Some(value.len()));
+        Ok(())
+    }
+
+    /// The path of an entry and whether it is one of the source, which has extended attributes. The generated files
+    /// don't support them, they are not files of the game.
+    fn xattr_entry(&self, ino : u64) -> Result<(Arc<OsString>, bool), c_int> {
+        match self.directory.lock().unwrap().root.find_ino(ino) {
+            Some(Entry { data: Data::Generated(_), .. }) => Err(libc::ENOTSUP),
+            Some(entry) => Ok((entry.path.clone(), matches!(entry.data, Data::Source))),
+            None => Err(ENOENT)
+        }
+    }
+
+    /// Whether changing an entry has to change the source as well, EACCES if the entry can't be changed at all.
+    fn in_source(&self, entry : &Entry) -> Result<bool, c_int> {
+        match entry.data {
+            Data::Source if self.write_through => Ok(true),
+            Data::Memory(_) | Data::Virtual => Ok(false),
+            Data::Source | Data::Generated(_) | Data::Open(_) => Err(libc::EACCES)
+        }
+    }
+
+    /// The path of a file of the source that may be written, EROFS if nothing may be.
+    fn source_file(&self, ino : u64) -> Result<Arc<OsString>, c_int> {
+        if !self.write_through {
+            return Err(EROFS);
+        }
+        let directory = self.directory.lock().unwrap();
+        match directory.root.find_ino(ino) {
+            Some(entry) if entry.info.is_dir() => Err(libc::EISDIR),
+            Some(Entry { data: Data::Source, path, .. }) => Ok(path.clone()),
+            Some(_) => Err(libc::EACCES),
+            None => Err(ENOENT)
+        }
+    }
+
+    /// The path of a file or directory of the source whose metadata may be changed, EROFS if nothing may be.
+    fn source_entry(&self, ino : u64) -> Result<Arc<OsString>, c_int> {
+        if !self.write_through {
+            return Err(EROFS);
+        }
+        let directory = self.directory.lock().unwrap();
+        match directory.root.find_ino(ino) {
+            Some(Entry { data: Data::Source, path, .. }) => Ok(path.clone()),
+            Some(_) => Err(libc::EACCES),
+            None => Err(ENOENT)
+        }
+    }
+
+    /// Entries of a directory from `offset` on, starting with "." and "..", as inode, type and name.
+    /// Drivers read directories in several calls, the rules only apply to the first at offset 0.
+    pub fn directory_entries(&mut self, pid : u32, ino : u64, offset : usize) -> Result<Vec<(u64, EntryKind, Arc<OsString>)>, c_int> {
+        if offset == 0 {
+            self.apply_rules_to_inode(Operation::ReadDir, pid, ino, None)?;
+        }
+        let directory = self.directory.lock().unwrap();
+        let Some(directory_entry) = directory.root.find_ino(ino) else {
+            return Err(ENOENT);
+        };
+        let EntryInfo::Directory(entries) = &directory_entry.info else {
+            return Err(ENOENT);
+        };
+        let dots = [(1, EntryKind::Directory, Arc::new(OsString::from("."))), (1, EntryKind::Directory, Arc::new(OsString::from("..")))];
+        let entries = entries.iter().map(|entry| {
+            let kind = match entry.info {
+                EntryInfo::Directory(_) => EntryKind::Directory,
+                EntryInfo::File => EntryKind::RegularFile,
+                EntryInfo::Symlink(_) => EntryKind::Symlink
+            };
+            (entry.inode, kind, entry.name.clone())
+        });
+        let entries : Vec<_> = dots.into_iter().chain(entries).skip(offset).collect();
+        if offset == 0 && self.metadata_events {
+            let path = directory_entry.path.clone();
+            drop(directory);
+            self.report_metadata(pid, &path, MetadataOperation::ReadDir, true);
+        }
+        Ok(entries)
+    }
+
+    /// Applies the rules to an access of `path`, returns the errno if a rule denies it. A delay isn't waited for here,
+    /// it is added to the reply of the request, see `reply`.
+    fn apply_rules(&mut self, operation : Operation, pid : u32, path : &Arc<OsString>, size : Option<u64>) -> Result<(), c_int> {
+        if self.rules.is_empty() {
+            return Ok(());
+        }
+        let verdict = rules::evaluate(&self.rules, operation, pid, &path.to_string_lossy(), size);
+        for (source, message) in verdict.derived {
+            self.report_event(pid, path, EventType::Derived { plugin: source.to_string(), message });
+        }
+        self.delay += verdict.delay;
+        match verdict.deny {
+            Some(errno) => Err(errno),
+            None => Ok(())
+        }
+    }
+
+    /// Like `apply_rules` for the entry of an inode, unknown inodes are left to the handler.
+    fn apply_rules_to_inode(&mut self, operation : Operation, pid : u32, ino : u64, size : Option<u64>) -> Result<(), c_int> {
+        if self.rules.is_empty() {
+            return Ok(());
+        }
+        let path = self.directory.lock().unwrap().root.find_ino(ino).map(|entry| entry.path.clone());
+        match path {
+            Some(path) => self.apply_rules(operation, pid, &path, size),
+            None => Ok(())
+        }
+    }
+
+    /// The delay the rules added since the last call. Backends that don't reply through `reply` wait for it themselves,
+    /// without blocking other requests.
+    pub fn take_delay(&mut self) -> Duration {
+        std::mem::take(&mut self.delay)
+    }
+
+    /// Sends the reply to a FUSE request, after the delay the rules added to it by the delayer, so other requests are
+    /// served in the meantime.
+    fn reply(&mut self, reply : impl FnOnce() + Send + 'static) {
+        let delay = self.take_delay();
+        match delay.is_zero() {
+            true => reply(),
+            false => self.delayer.get_or_insert_with(Delayer::new).send(delay, Box::new(reply))
+        }
+    }
+
+    fn report_event(&mut self, pid : u32, path : &Arc<OsString>, event : EventType) {
+        let process = self.process(pid);
+        self.reporter.report_event(process, path, event);
+    }
+
+    /// Reports an access of extended attributes, getting and listing them only with the metadata events.
+    fn report_xattr(&mut self, pid : u32, path : &Arc<OsString>, operation : XattrOperation, name : Option<&str>, size : Option<usize>) {
+        if operation != XattrOperation::Set && !self.metadata_events {
+            return;
+        }
+        let event = XattrEvent { file: path.clone(), operation, name: name.map(String::from), size };
+        self.report_event(pid, path, EventType::Xattr(event));
+    }
+
+    /// Reports a metadata access if those are reported at all.
+    fn report_metadata(&mut self, pid : u32, path : &Arc<OsString>, operation : MetadataOperation, found : bool) {
+        if !self.metadata_events {
+            return;
+        }
+        let process = self.process(pid);
+        self.reporter.report_metadata(process, path, operation, found);
+    }
+
+    /// Looks up the name and parent of a process, they are cached since they are needed for every event.
+    fn process(&mut self, pid : u32) -> Process {
+        self.cached_process(pid).process.clone()
+    }
+
+    /// The cached process `pid`, looked up on a miss. Whether the pid still belongs to the same process is only
+    /// checked by `check_process`, reading /proc for every read would cost more than the cache saves. Entries
+    /// without open handles are dropped once there are many, so processes that are gone don't pile up.
+    fn cached_process(&mut self, pid : u32) -> &mut CachedProcess {
+        if self.processes.len() >= MAX_CACHED_PROCESSES && !self.processes.contains_key(&pid) {
+            self.processes.retain(|_, cached| cached.handles > 0);
+        }
+        self.processes.entry(pid).or_insert_with(|| {
+            let start = platform::process_start(pid);
+            let (name, parent) = platform::process_info(pid);
+            CachedProcess {
+                process: Process {
+                    pid,
+                    name: Arc::new(name.unwrap_or_else(|| String::from("unknown"))),
+                    parent,
+                    uid: None,
+                    gid: None
+                },
+                start,
+                handles: 0
+            }
+        })
+    }
+
+    /// Looks up the cached process `pid` again if the pid now belongs to a process that started later, on opens and
+    /// lookups, which come before the reads of a process. The uid and gid of the request are kept.
+    fn check_process(&mut self, pid : u32) {
+        let Some(cached) = self.processes.get(&pid) else {
+            return;
+        };
+        if cached.start == platform::process_start(pid) {
+            return;
+        }
+        let (uid, gid) = (cached.process.uid, cached.process.gid);
+        self.processes.remove(&pid);
+        let process = &mut self.cached_process(pid).process;
+        process.uid = uid;
+        process.gid = gid;
+    }
+
+    /// Drops the cached process that opened a handle once its last handle is closed, a later request of the pid
+    /// looks it up again, in case it was reused or the process executed another program.
+    fn forget_handle(&mut self, process : &Process) {
+        let Some(cached) = self.processes.get_mut(&process.pid) else {
+            return;
+        };
+        // the entry may be of a newer process with the same pid, its handles are counted on their own
+        if !Arc::ptr_eq(&cached.process.name, &process.name) {
+            return;
+        }
+        cached.handles = cached.handles.saturating_sub(1);
+        if cached.handles == 0 {
+            self.processes.remove(&process.pid);
+        }
+    }
+
+    /// The pid of the process that made a FUSE request, its uid and gid are kept for its events.
+    /// They are the ones of the request, which may change during the life of the process, e.g. after setuid.
+    fn caller(&mut self, req : &Request) -> u32 {
+        let process = &mut self.cached_process(req.pid()).process;
+        process.uid = Some(req.uid());
+        process.gid = Some(req.gid());
+        req.pid()
+    }
+}
+
+/// The errno of a failed access of the source, EIO if it has none, e.g. because the source can't be written at all.
+fn errno(err : std::io::Error) -> c_int {
+    log!(warn, "Writing to the source failed: {err}");
+    err.raw_os_error().unwrap_or(libc::EIO)
+}
+
+/// The errno of a failed access of extended attributes, ENOTSUP if the source doesn't support them. Not logged,
+/// programs probe attributes that don't exist all the time.
+fn xattr_errno(err : std::io::Error) -> c_int {
+    err.raw_os_error().unwrap_or(libc::ENOTSUP)
+}
+
+/// Replies to getxattr and listxattr, which ask for the size first with a `size` of 0.
+fn reply_xattr(data : Result<Vec<u8>, c_int>, size : u32, reply : ReplyXattr) {
+    match data {
+        Ok(data) if size == 0 => reply.size(data.len() as u32),
+        Ok(data) if data.len() > size as usize => reply.error(libc::ERANGE),
+        Ok(data) => reply.data(&data),
+        Err(errno) => reply.error(errno)
+    }
+}
+
+impl Filesystem for FileAccessTrackingFs {
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
+    fn init(
+        &mut self,
+        _req: &Request,
+        config: &mut KernelConfig,
+    ) -> std::result::Result<(), c_int> {
+        // without passthrough support the reads just go through this process, which is slower but works
+        if !self.kernel.passthrough {
+            log!(debug, "Passthrough is turned off, all reads go through this process");
+        } else if !platform::PASSTHROUGH {
+            log!(debug, "The kernel has no passthrough, all reads go through this process");
+        } else if let Err(unsupported) = config.add_capabilities(consts::FUSE_PASSTHROUGH) {
+            log!(warn, "Kernel does not support capabilities {unsupported:#x}");
+        } else if let Err(max) = config.set_max_stack_depth(self.kernel.max_stack_depth) {
+            log!(warn, "Kernel only supports a stack depth of {max}, using that instead of {}", self.kernel.max_stack_depth);
+            let _ = config.set_max_stack_depth(max);
+        }
+        if let Some(readahead) = self.kernel.max_readahead {
+            if let Err(nearest) = config.set_max_readahead(readahead) {
+                log!(warn, "Kernel rejected a readahead of {readahead} bytes, using {nearest}");
+                let _ = config.set_max_readahead(nearest);
+            }
+        }
+        if let Some(background) = self.kernel.max_background {
+            if let Err(nearest) = config.set_max_background(background) {
+                log!(warn, "Kernel rejected {background} background requests, using {nearest}");
+                let _ = config.set_max_background(nearest);
+            }
+        }
+        Ok(())
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all, fields(parent, ?name)))]
+    fn lookup(&mut self, req: &Request, parent: u64, name: &OsStr, reply: ReplyEntry) {
+        let pid = self.caller(req);
+        let (entry, ttl) = (self.lookup_entry(pid, parent, name), self.ttl);
+        self.reply(move || match entry {
+            Ok(attr) => reply.entry(&ttl, &attr.into(), 0),
+            Err(errno) => reply.error(errno)
+        });
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all, fields(ino)))]
+    fn getattr(&mut self, req: &Request, ino: u64, _fh: Option<u64>, reply: ReplyAttr) {
+        let pid = self.caller(req);
+        let (attr, ttl) = (self.stat_entry(pid, ino), self.ttl);
+        self.reply(move || match attr {
+            Ok(attr) => reply.attr(&ttl, &attr.into()),
+            Err(errno) => reply.error(errno)
+        });
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all, fields(ino, fh, offset, size)))]
+    fn read(
+        &mut self,
+        req: &Request,
+        ino: u64,
+        fh: u64,
+        offset: i64,
+        size: u32,
+        _flags: i32,
+        _lock: Option<u64>,
+        reply: ReplyData,
+    ) {
+        let pid = self.caller(req);
+        if let Err(errno) = self.apply_rules_to_inode(Operation::Read, pid, ino, Some(size as u64)) {
+            self.reply(move || reply.error(errno));
+            return;
+        }
+        #[cfg(feature = "io-uring")]
+        let Some(reply) = self.read_uring(pid, ino, fh, offset, size, reply) else {
+            return;
+        };
+        // taken out of self for the duration of the read, read_into needs self mutably as well
+        let mut buffer = std::mem::take(&mut self.read_buffer);
+        match (self.read_into(pid, ino, fh, offset, size, &mut buffer), self.delay.is_zero()) {
+            (Ok(read), true) => reply.data(&buffer[..read]),
+            (Ok(read), false) => {
+                // the buffer is reused by the next read, a delayed reply needs a copy
+                let data = buffer[..read].to_vec();
+                self.reply(move || reply.data(&data));
+            }
+            (Err(errno), _) => self.reply(move || reply.error(errno))
+        }
+        self.read_buffer = buffer;
+    }
+
+    fn open(&mut self, req: &Request<'_>, ino: u64, _flags: i32, reply: ReplyOpen) {
+        // generated files change size between reads, with direct io the kernel reads them up to the end anyway
+        let flags = match self.direct_io || self.generated(ino).is_some() {
+            true => consts::FOPEN_DIRECT_IO,
+            false => 0
+        };
+        let pid = self.caller(req);
+        let handle = self.open_handle(pid, ino);
+        self.reply(move || match handle {
+            Ok(fh) => reply.opened(fh, flags),
+            Err(errno) => reply.error(errno)
+        });
+    }
+
+    fn release(
+        &mut self,
+        _req: &Request<'_>,
+        _ino: u64,
+        fh: u64,
+        _flags: i32,
+        _lock_owner: Option<u64>,
+        _flush: bool,
+        reply: ReplyEmpty,
+    ) {
+        self.release_handle(fh);
+        reply.ok();
+    }
+    
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all, fields(parent, ?name)))]
+    fn create(
+            &mut self,
+            req: &Request<'_>,
+            parent: u64,
+            name: &OsStr,
+            _mode: u32,
+            _umask: u32,
+            _flags: i32,
+            reply: fuser::ReplyCreate,
+        ) {
+        let pid = self.caller(req);
+        let created = self.create_entry(pid, parent, name).and_then(|attr| Ok((attr, self.open_handle(pid, attr.ino)?)));
+        let (ttl, flags) = (self.ttl, if self.direct_io { consts::FOPEN_DIRECT_IO } else { 0 });
+        self.reply(move || match created {
+            Ok((attr, fh)) => reply.created(&ttl, &attr.into(), 0, fh, flags),
+            Err(errno) => reply.error(errno)
+        });
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all, fields(ino, fh, offset)))]
+    fn write(
+            &mut self,
+            req: &Request<'_>,
+            ino: u64,
+            fh: u64,
+            offset: i64,
+            data: &[u8],
+            _write_flags: u32,
+            _flags: i32,
+            _lock_owner: Option<u64>,
+            reply: fuser::ReplyWrite,
+        ) {
+        let pid = self.caller(req);
+        let written = match self.generated(ino) {
+            Some(_) => self.write_control(pid, ino, data),
+            None => self.write_data(pid, ino, fh, offset, data)
+        };
+        self.reply(move || match written {
+            Ok(written) => reply.written(written),
+            Err(errno) => reply.error(errno)
+        });
+    }
+
+    fn unlink(&mut self, req: &Request<'_>, parent: u64, name: &OsStr, reply: ReplyEmpty) {
+        let pid = self.caller(req);
+        let unlinked = self.unlink_entry(pid, parent, name);
+        self.reply(move || match unlinked {
+            Ok(()) => reply.ok(),
+            Err(errno) => reply.error(errno)
+        });
+    }
+
+    fn getxattr(&mut self, req: &Request<'_>, ino: u64, name: &OsStr, size: u32, reply: ReplyXattr) {
+        let Some(name) = name.to_str() else {
+            reply.error(libc::ENODATA);
+            return;
+        };
+        let pid = self.caller(req);
+        let data = self.get_xattr(pid, ino, name);
+        self.reply(move || reply_xattr(data, size, reply));
+    }
+
+    fn listxattr(&mut self, req: &Request<'_>, ino: u64, size: u32, reply: ReplyXattr) {
+        let pid = self.caller(req);
+        let data = self.list_xattr(pid, ino);
+        self.reply(move || reply_xattr(data, size, reply));
+    }
+
+    fn setxattr(&mut self, req: &Request<'_>, ino: u64, name: &OsStr, value: &[u8], flags: i32, _position: u32, reply: ReplyEmpty) {
+        let Some(name) = name.to_str() else {
+            reply.error(libc::EINVAL);
+            return;
+        };
+        let pid = self.caller(req);
+        let set = self.set_xattr(pid, ino, name, value, flags);
+        self.reply(move || match set {
+            Ok(()) => reply.ok(),
+            Err(errno) => reply.error(errno)
+        });
+    }
+
+    fn readlink(&mut self, req: &Request<'_>, ino: u64, reply: ReplyData) {
+        let pid = self.caller(req);
+        let target = self.read_link(pid, ino);
+        self.reply(move || match target {
+            Ok(target) => reply.data(target.as_bytes()),
+            Err(errno) => reply.error(errno)
+        });
+    }
+
+    fn symlink(&mut self, req: &Request<'_>, parent: u64, link_name: &OsStr, target: &std::path::Path, reply: ReplyEntry) {
+        let pid = self.caller(req);
+        let (entry, ttl) = (self.create_symlink(pid, parent, link_name, target.as_os_str()), self.ttl);
+        self.reply(move || match entry {
+            Ok(attr) => reply.entry(&ttl, &attr.into(), 0),
+            Err(errno) => reply.error(errno)
+        });
+    }
+
+    fn mkdir(&mut self, req: &Request<'_>, parent: u64, name: &OsStr, _mode: u32, _umask: u32, reply: ReplyEntry) {
+        let pid = self.caller(req);
+        let (entry, ttl) = (self.make_directory(pid, parent, name), self.ttl);
+        self.reply(move || match entry {
+            Ok(attr) => reply.entry(&ttl, &attr.into(), 0),
+            Err(errno) => reply.error(errno)
+        });
+    }
+
+    fn rmdir(&mut self, req: &Request<'_>, parent: u64, name: &OsStr, reply: ReplyEmpty) {
+        let pid = self.caller(req);
+        let removed = self.remove_directory(pid, parent, name);
+        self.reply(move || match removed {
+            Ok(()) => reply.ok(),
+            Err(errno) => reply.error(errno)
+        });
+    }
+
+    /// Exchanging two entries is not supported.
+    fn rename(&mut self, req: &Request<'_>, parent: u64, name: &OsStr, newparent: u64, newname: &OsStr, flags: u32, reply: ReplyEmpty) {
+        if flags & !libc::RENAME_NOREPLACE != 0 {
+            reply.error(libc::EINVAL);
+            return;
+        }
+        let pid = self.caller(req);
+        let renamed = self.rename_entry(pid, parent, name, newparent, newname, flags & libc::RENAME_NOREPLACE != 0);
+        self.reply(move || match renamed {
+            Ok(()) => reply.ok(),
+            Err(errno) => reply.error(errno)
+        });
+    }
+
+    /// Only changing the size is supported, of files of the source with `MountOptions::write_through` and truncating the
+    /// control file, which `echo pause > .ffam/ctl` does before writing.
+    fn setattr(
+            &mut self,
+            req: &Request<'_>,
+            ino: u64,
+            mode: Option<u32>,
+            uid: Option<u32>,
+            gid: Option<u32>,
+            size: Option<u64>,
+            atime: Option<fuser::TimeOrNow>,
+            mtime: Option<fuser::TimeOrNow>,
+            _ctime: Option<std::time::SystemTime>,
+            _fh: Option<u64>,
+            _crtime: Option<std::time::SystemTime>,
+            _chgtime: Option<std::time::SystemTime>,
+            _bkuptime: Option<std::time::SystemTime>,
+            _flags: Option<u32>,
+            reply: ReplyAttr,
+        ) {
+        let attr = match (self.generated(ino), size) {
+            (Some(Generated::Control), None | Some(0)) => self.attributes(ino),
+            (None, _) => {
+                let pid = self.caller(req);
+                let time = |time : Option<fuser::TimeOrNow>| time.map(|time| match time {
+                    fuser::TimeOrNow::SpecificTime(time) => time,
+                    fuser::TimeOrNow::Now => SystemTime::now()
+                });
+                self.set_attributes(pid, ino, size, mode, (uid, gid), (time(atime), time(mtime)))
+            }
+            _ => Err(libc::ENOSYS)
+        };
+        let ttl = self.ttl;
+        self.reply(move || match attr {
+            Ok(attr) => reply.attr(&ttl, &attr.into()),
+            Err(errno) => reply.error(errno)
+        });
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all, fields(ino, offset)))]
+    fn readdir(
+        &mut self,
+        req: &Request,
+        ino: u64,
+        _fh: u64,
+        offset: i64,
+        mut reply: ReplyDirectory,
+    ) {
+        let pid = self.caller(req);
+        let entries = self.directory_entries(pid, ino, offset as usize);
+        self.reply(move || match entries {
+            Ok(entries) => {
+                for (i, (inode, kind, name)) in entries.iter().enumerate() {
+                    // the offset of an entry is the index of the next one
+                    if reply.add(*inode, offset + i as i64 + 1, (*kind).into(), name.as_os_str()) {
+                        break;
+                    }
+                }
+                reply.ok();
+            }
+            Err(errno) => reply.error(errno)
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::MemorySource;
+
+    /// Accesses of the tests come from no real process.
+    const PID : u32 = 0;
+    const ROOT : u64 = 1;
+
+    fn source() -> MemorySource {
+        MemorySource::new()
+            .file("Data/Map.bin", b"0123456789".to_vec())
+            .file("Data/Text.txt", "hello")
+            .file("readme.txt", "")
+    }
+
+    /// The filesystem and the events it delivered so far.
+    fn mount(source : MemorySource, options : &crate::MountOptions) -> (FileAccessTrackingFs, Arc<Mutex<Vec<Event>>>) {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = {
+            let events = events.clone();
+            EventSink::Callback(Box::new(move |event| events.lock().unwrap().push(event)))
+        };
+        let fs = FileAccessTrackingFs::with_sink(source, sink, options, &ScanProgress::default()).unwrap();
+        (fs, events)
+    }
+
+    fn changes(events : &Mutex<Vec<Event>>) -> Vec<ChangeEvent> {
+        events.lock().unwrap().iter().filter_map(|event| match &event.event {
+            EventType::Change(change) => Some(change.clone()),
+            _ => None
+        }).collect()
+    }
+
+    #[test]
+    fn lookup_ignores_case_by_default() {
+        let (mut fs, _) = mount(source(), &crate::MountOptions::default());
+        let data = fs.lookup_entry(PID, ROOT, OsStr::new("data")).unwrap();
+        assert_eq!(data.kind, EntryKind::Directory);
+        let map = fs.lookup_entry(PID, data.ino, OsStr::new("MAP.BIN")).unwrap();
+        assert_eq!(map.kind, EntryKind::RegularFile);
+        assert_eq!(map.size, 10);
+        assert_eq!(fs.lookup_entry(PID, data.ino, OsStr::new("Missing.bin")), Err(ENOENT));
+        assert_eq!(fs.lookup_entry(PID, 12345, OsStr::new("Map.bin")), Err(ENOENT));
+    }
+
+    #[test]
+    fn lookup_is_exact_when_case_sensitive() {
+        let options = crate::MountOptions { case_sensitivity: CaseSensitivity::Sensitive, ..Default::default() };
+        let (mut fs, _) = mount(source(), &options);
+        assert_eq!(fs.lookup_entry(PID, ROOT, OsStr::new("data")), Err(ENOENT));
+        assert!(fs.lookup_entry(PID, ROOT, OsStr::new("Data")).is_ok());
+    }
+
+    #[test]
+    fn read_file_serves_ranges_and_reports_them() {
+        let (mut fs, events) = mount(source(), &crate::MountOptions::default());
+        let ino = fs.inode("Data/Map.bin").unwrap();
+        let fh = fs.open_handle(PID, ino).unwrap();
+        assert_eq!(fs.read_file(PID, ino, fh, 2, 4).unwrap(), b"2345");
+        // reads past the end are cut short
+        assert_eq!(fs.read_file(PID, ino, fh, 8, 10).unwrap(), b"89");
+        fs.release_handle(fh);
+        let events = events.lock().unwrap();
+        let reads : Vec<_> = events.iter().filter_map(|event| match &event.event {
+            EventType::Read(read) => Some((read.file.as_os_str().to_os_string(), read.offset, read.size, read.handle)),
+            _ => None
+        }).collect();
+        // the requested size is reported, what was returned follows from the size of the file
+        assert_eq!(reads, [(OsString::from("Data/Map.bin"), 2, 4, fh), (OsString::from("Data/Map.bin"), 8, 10, fh)]);
+        assert!(matches!(&events.first().unwrap().event, EventType::Open(open) if open.handle == fh));
+        assert!(matches!(&events.last().unwrap().event, EventType::Close(close) if close.handle == fh));
+    }
+
+    #[test]
+    fn processes_are_forgotten_when_their_last_handle_is_closed() {
+        let (mut fs, _) = mount(source(), &crate::MountOptions::default());
+        let pid = std::process::id();
+        let ino = fs.inode("Data/Map.bin").unwrap();
+        let first = fs.open_handle(pid, ino).unwrap();
+        let second = fs.open_handle(pid, ino).unwrap();
+        assert_eq!(fs.processes[&pid].handles, 2);
+        fs.release_handle(first);
+        assert_eq!(fs.processes[&pid].handles, 1);
+        fs.release_handle(second);
+        assert!(!fs.processes.contains_key(&pid));
+    }
+
+    /// Vetoes every read, like a plugin that hides the reads of some files.
+    struct VetoReads;
+
+    impl crate::plugin::Plugin for VetoReads {
+        fn name(&self) -> &str {
+            "veto"
+        }
+
+        fn on_event(&mut self, event : &Event, _derived : &mut Vec<String>) -> crate::plugin::Verdict {
+            match event.event {
+                EventType::Read(_) => crate::plugin::Verdict::Veto,
+                _ => crate::plugin::Verdict::Keep
+            }
+        }
+    }
+
+    #[test]
+    fn processes_are_only_checked_again_on_opens() {
+        let (mut fs, events) = mount(source(), &crate::MountOptions::default());
+        let pid = std::process::id();
+        let ino = fs.inode("Data/Map.bin").unwrap();
+        let fh = fs.open_handle(pid, ino).unwrap();
+        // as if the pid had been reused by another process since it was cached
+        let cached = fs.processes.get_mut(&pid).unwrap();
+        cached.start = cached.start.map(|start| start + 1);
+        cached.process.name = Arc::new(String::from("previous"));
+        fs.read_file(pid, ino, fh, 0, 4).unwrap();
+        assert_eq!(events.lock().unwrap().last().map(|event| event.process.name.as_str()), Some("previous"));
+        fs.open_handle(pid, ino).unwrap();
+        assert_ne!(fs.processes[&pid].process.name.as_str(), "previous");
+    }
+
+    #[test]
+    fn vetoed_reads_are_not_counted() {
+        let (mut fs, events) = mount(source(), &crate::MountOptions::default());
+        let mut plugins = Plugins::default();
+        plugins.add(Box::new(VetoReads));
+        fs.set_plugins(plugins);
+        let ino = fs.inode("Data/Map.bin").unwrap();
+        let fh = fs.open_handle(PID, ino).unwrap();
+        fs.read_file(PID, ino, fh, 0, 4).unwrap();
+        let stats = fs.stats().lock().unwrap().snapshot();
+        assert_eq!((stats.reads, stats.opens), (0, 1));
+        assert!(!events.lock().unwrap().iter().any(|event| matches!(event.event, EventType::Read(_))));
+    }
+
+    #[test]
+    fn rename_moves_entries_created_in_memory() {
+        let (mut fs, events) = mount(source(), &crate::MountOptions::default());
+        let data = fs.inode("Data").unwrap();
+        fs.create_entry(PID, ROOT, OsStr::new("save.dat")).unwrap();
+        fs.rename_entry(PID, ROOT, OsStr::new("save.dat"), data, OsStr::new("Save1.dat"), false).unwrap();
+        assert_eq!(fs.lookup_entry(PID, ROOT, OsStr::new("save.dat")), Err(ENOENT));
+        assert!(fs.inode("Data/Save1.dat").is_some());
+        let operations : Vec<_> = changes(&events).into_iter().map(|change| change.operation).collect();
+        assert_eq!(operations, [ChangeOperation::Create, ChangeOperation::Rename { to: OsString::from("Data/Save1.dat") }]);
+    }
+
+    #[test]
+    fn rename_replaces_unless_told_not_to() {
+        let (mut fs, _) = mount(source(), &crate::MountOptions::default());
+        fs.create_entry(PID, ROOT, OsStr::new("a.dat")).unwrap();
+        fs.create_entry(PID, ROOT, OsStr::new("b.dat")).unwrap();
+        assert_eq!(fs.rename_entry(PID, ROOT, OsStr::new("a.dat"), ROOT, OsStr::new("b.dat"), true), Err(libc::EEXIST));
+        // the replaced entry keeps its name, there is only one entry ignoring case
+        fs.rename_entry(PID, ROOT, OsStr::new("a.dat"), ROOT, OsStr::new("B.DAT"), false).unwrap();
+        let names : Vec<_> = fs.directory_entries(PID, ROOT, 0).unwrap().into_iter().map(|(_, _, name)| name.as_os_str().to_os_string()).collect();
+        assert!(names.contains(&OsString::from("b.dat")));
+        assert!(!names.contains(&OsString::from("a.dat")) && !names.contains(&OsString::from("B.DAT")));
+    }
+
+    #[test]
+    fn rename_keeps_the_source_unless_writing_through() {
+        let (mut fs, events) = mount(source(), &crate::MountOptions::default());
+        assert_eq!(fs.rename_entry(PID, ROOT, OsStr::new("readme.txt"), ROOT, OsStr::new("other.txt"), false), Err(libc::EACCES));
+        assert!(fs.inode("readme.txt").is_some());
+        assert!(changes(&events).is_empty());
+    }
+
+    #[test]
+    fn unlink_removes_entries_created_in_memory() {
+        let (mut fs, events) = mount(source(), &crate::MountOptions::default());
+        fs.create_entry(PID, ROOT, OsStr::new("save.dat")).unwrap();
+        fs.unlink_entry(PID, ROOT, OsStr::new("SAVE.DAT")).unwrap();
+        assert_eq!(fs.lookup_entry(PID, ROOT, OsStr::new("save.dat")), Err(ENOENT));
+        assert_eq!(changes(&events).last().map(|change| change.operation.clone()), Some(ChangeOperation::Remove));
+        assert_eq!(fs.unlink_entry(PID, ROOT, OsStr::new("save.dat")), Err(ENOENT));
+    }
+
+    #[test]
+    fn open_files_stay_readable_after_unlinking_them() {
+        let (mut fs, _) = mount(source(), &crate::MountOptions::default());
+        fs.directory.lock().unwrap().put(OsStr::new("save.dat"), Some(b"saved".to_vec())).unwrap();
+        let ino = fs.inode("save.dat").unwrap();
+        let fh = fs.open_handle(PID, ino).unwrap();
+        fs.unlink_entry(PID, ROOT, OsStr::new("save.dat")).unwrap();
+        assert_eq!(fs.lookup_entry(PID, ROOT, OsStr::new("save.dat")), Err(ENOENT));
+        assert_eq!(fs.read_file(PID, ino, fh, 0, 10).unwrap(), b"saved");
+        assert_eq!(fs.attributes(ino).map(|attr| attr.size), Ok(5));
+        fs.release_handle(fh);
+        assert_eq!(fs.attributes(ino), Err(ENOENT));
+    }
+
+    #[test]
+    fn open_files_of_the_source_are_read_from_disk_after_unlinking_them() {
+        let dir = std::env::temp_dir().join(format!("ffam-unlink-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("archive.bin"), b"0123456789").unwrap();
+        let options = crate::MountOptions { write_through: true, ..Default::default() };
+        let mut fs = FileAccessTrackingFs::with_sink(DiskSource::new(&dir), EventSink::Discard, &options, &ScanProgress::default()).unwrap();
+        let ino = fs.inode("archive.bin").unwrap();
+        let fh = fs.open_handle(PID, ino).unwrap();
+        fs.unlink_entry(PID, ROOT, OsStr::new("archive.bin")).unwrap();
+        assert!(!dir.join("archive.bin").exists());
+        // the handle reads through the descriptor kept open, not from a copy
+        assert!(matches!(fs.directory.lock().unwrap().entry(ino).map(|entry| &entry.data), Some(Data::Open(_))));
+        assert_eq!(fs.read_file(PID, ino, fh, 2, 4).unwrap(), b"2345");
+        assert_eq!(fs.attributes(ino).map(|attr| attr.size), Ok(10));
+        fs.release_handle(fh);
+        assert_eq!(fs.attributes(ino), Err(ENOENT));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn metadata_changes_go_to_the_source_and_are_reported() {
+        let (mut fs, _) = mount(source(), &crate::MountOptions::default());
+        let ino = fs.inode("readme.txt").unwrap();
+        assert_eq!(fs.set_permissions(PID, ino, 0o600), Err(EROFS));
+
+        let dir = std::env::temp_dir().join(format!("ffam-setattr-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("Saves")).unwrap();
+        std::fs::write(dir.join("Saves/save.dat"), b"0123").unwrap();
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = {
+            let events = events.clone();
+            EventSink::Callback(Box::new(move |event| events.lock().unwrap().push(event)))
+        };
+        let options = crate::MountOptions { write_through: true, ..Default::default() };
+        let mut fs = FileAccessTrackingFs::with_sink(DiskSource::new(&dir), sink, &options, &ScanProgress::default()).unwrap();
+        let (saves, save) = (fs.inode("Saves").unwrap(), fs.inode("Saves/save.dat").unwrap());
+        assert_eq!(fs.set_permissions(PID, save, 0o100600).map(|attr| attr.perm), Ok(0o600));
+        assert_eq!(fs.set_permissions(PID, saves, 0o700).map(|attr| attr.perm), Ok(0o700));
+        // SAFETY: getuid can't fail
+        let uid = unsafe { libc::getuid() };
+        fs.set_owner(PID, save, Some(uid), None).unwrap();
+        let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000_000);
+        assert_eq!(fs.set_times(PID, save, None, Some(modified)).map(|attr| attr.mtime), Ok(modified));
+        assert_eq!(std::fs::metadata(dir.join("Saves/save.dat")).unwrap().modified().unwrap(), modified);
+        let operations : Vec<_> = changes(&events).into_iter().map(|change| change.operation).collect();
+        assert_eq!(operations, [
+            ChangeOperation::Permissions { mode: 0o600 },
+            ChangeOperation::Permissions { mode: 0o700 },
+            ChangeOperation::Owner { uid: Some(uid), gid: None },
+            ChangeOperation::Times { accessed: None, modified: Some(modified.into()) }
+        ]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn open_files_keep_their_content_when_renamed_over() {
+        let (mut fs, _) = mount(source(), &crate::MountOptions::default());
+        fs.directory.lock().unwrap().put(OsStr::new("save.tmp"), Some(b"new".to_vec())).unwrap();
+        fs.directory.lock().unwrap().put(OsStr::new("save.dat"), Some(b"old".to_vec())).unwrap();
+        let old = fs.inode("save.dat").unwrap();
+        let fh = fs.open_handle(PID, old).unwrap();
+        fs.rename_entry(PID, ROOT, OsStr::new("save.tmp"), ROOT, OsStr::new("save.dat"), false).unwrap();
+        assert_eq!(fs.read_file(PID, old, fh, 0, 10).unwrap(), b"old");
+        let new = fs.lookup_entry(PID, ROOT, OsStr::new("save.dat")).unwrap().ino;
+        assert_ne!(new, old);
+        let new_fh = fs.open_handle(PID, new).unwrap();
+        assert_eq!(fs.read_file(PID, new, new_fh, 0, 10).unwrap(), b"new");
+        fs.release_handle(fh);
+        assert_eq!(fs.read_file(PID, old, fh, 0, 10), Err(ENOENT));
+    }
+
+    #[test]
+    fn adding_an_existing_directory_keeps_its_entries() {
+        let (fs, _) = mount(source(), &crate::MountOptions::default());
+        let data = fs.inode("Data").unwrap();
+        let change = fs.directory.lock().unwrap().put(OsStr::new("data"), None).unwrap();
+        assert_eq!(change.inode, None);
+        assert_eq!(fs.inode("Data"), Some(data));
+        assert!(fs.inode("Data/Map.bin").is_some());
+    }
+
+    #[test]
+    fn removed_and_replaced_virtual_files_stay_readable_while_open() {
+        let (mut fs, _) = mount(source(), &crate::MountOptions::default());
+        fs.directory.lock().unwrap().put(OsStr::new("mods"), None).unwrap();
+        fs.directory.lock().unwrap().put(OsStr::new("mods/a.txt"), Some(b"first".to_vec())).unwrap();
+        fs.directory.lock().unwrap().put(OsStr::new("b.txt"), Some(b"second".to_vec())).unwrap();
+        let (a, b) = (fs.inode("mods/a.txt").unwrap(), fs.inode("b.txt").unwrap());
+        let (a_fh, b_fh) = (fs.open_handle(PID, a).unwrap(), fs.open_handle(PID, b).unwrap());
+        // removing the directory keeps the open file below it
+        fs.directory.lock().unwrap().remove(OsStr::new("mods")).unwrap();
+        assert_eq!(fs.inode("mods/a.txt"), None);
+        assert_eq!(fs.read_file(PID, a, a_fh, 0, 10).unwrap(), b"first");
+        // a directory replacing the file gets a new inode, the handle still reads the file
+        fs.directory.lock().unwrap().put(OsStr::new("b.txt"), None).unwrap();
+        assert_ne!(fs.inode("b.txt"), Some(b));
+        assert_eq!(fs.read_file(PID, b, b_fh, 0, 10).unwrap(), b"second");
+        fs.release_handle(a_fh);
+        fs.release_handle(b_fh);
+        assert_eq!(fs.attributes(a), Err(ENOENT));
+        assert_eq!(fs.attributes(b), Err(ENOENT));
+    }
+
+    #[test]
+    fn added_directories_are_virtual() {
+        let (fs, _) = mount(source(), &crate::MountOptions { control_file: true, ..Default::default() });
+        fs.directory.lock().unwrap().put(OsStr::new("mods"), None).unwrap();
+        let directory = fs.directory.lock().unwrap();
+        for path in ["mods", control::DIRECTORY] {
+            let entry = directory.find_path(OsStr::new(path)).unwrap();
+            assert!(directory.tree_entry(entry, &AccessStats::default()).is_virtual);
+            assert_eq!(fs.in_source(entry), Ok(false));
+        }
+    }
+
+    #[test]
+    fn control_files_take_one_command_per_line() {
+        let (mut fs, events) = mount(source(), &crate::MountOptions { control_file: true, ..Default::default() });
+        let ctl = fs.inode(control::CONTROL).unwrap();
+        let commands = b"marker start\n\npause\nfilter off\n";
+        assert_eq!(fs.write_control(PID, ctl, commands), Ok(commands.len() as u32));
+        let markers : Vec<_> = events.lock().unwrap().iter().filter_map(|event| match &event.event {
+            EventType::Marker(label) => Some(label.clone()),
+            _ => None
+        }).collect();
+        assert_eq!(markers, ["start"]);
+        assert!(fs.reporter.state().paused);
+        assert!(!fs.reporter.state().filter_enabled);
+        // nothing runs if one of the lines isn't a command
+        assert_eq!(fs.write_control(PID, ctl, b"resume\nstop\n"), Err(libc::EINVAL));
+        assert!(fs.reporter.state().paused);
+        assert_eq!(fs.write_control(PID, fs.inode("readme.txt").unwrap(), b"resume\n"), Err(libc::EACCES));
+    }
+
+    #[test]
+    fn unlink_refuses_directories_and_the_source() {
+        let (mut fs, _) = mount(source(), &crate::MountOptions::default());
+        assert_eq!(fs.unlink_entry(PID, ROOT, OsStr::new("Data")), Err(libc::EISDIR));
+        assert_eq!(fs.unlink_entry(PID, ROOT, OsStr::new("readme.txt")), Err(libc::EACCES));
+        let (mut fs, _) = mount(source(), &crate::MountOptions { read_only: true, ..Default::default() });
+        assert_eq!(fs.unlink_entry(PID, ROOT, OsStr::new("readme.txt")), Err(EROFS));
+        assert_eq!(fs.create_entry(PID, ROOT, OsStr::new("save.dat")).map(|attr| attr.ino), Err(EROFS));
+    }
+
+    #[test]
+    fn rules_delay_and_tag_through_the_reporter() {
+        let rule = Rule {
+            name: String::from("slow"),
+            matches: rules::RuleMatch { glob: String::from("**/*.bin"), ..Default::default() },
+            actions: vec![rules::RuleAction::Tag(String::from("map")), rules::RuleAction::Delay(Duration::from_millis(5))]
+        };
+        let (mut fs, events) = mount(source(), &crate::MountOptions { rules: vec![rule], ..Default::default() });
+        let data = fs.inode("Data").unwrap();
+        fs.lookup_entry(PID, data, OsStr::new("Map.bin")).unwrap();
+        // the delay is left to the reply, the lookup itself doesn't wait
+        assert_eq!(fs.take_delay(), Duration::from_millis(5));
+        assert!(events.lock().unwrap().iter().any(|event| matches!(&event.event, EventType::Derived { message, .. } if message == "slow: map")));
+        events.lock().unwrap().clear();
+        fs.reporter.state().paused = true;
+        fs.lookup_entry(PID, data, OsStr::new("Map.bin")).unwrap();
+        assert!(events.lock().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,105 @@
+//! The FUSE backend, mounting through fuser.
+use fuser::{MountOption, SessionACL};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::backend::{Backend, BackendSession, Mounter};
+use crate::fs::{Change, FileAccessTrackingFs};
+use crate::platform;
+use crate::trace::log;
+use crate::MountOptions;
+
+/// The kernel's FUSE driver on Linux and fusefs on FreeBSD, through fuser.
+#[derive(Debug, Clone, Default)]
+pub struct FuseBackend {
+    pub mounter : Option<Arc<dyn Mounter>> // mounts instead of fuser if given
+}
+
+impl Backend for FuseBackend {
+    fn name(&self) -> &'static str {
+        "fuse"
+    }
+
+    fn mount(&self, fs : FileAccessTrackingFs, mountpoint : &Path, options : &MountOptions) -> std::io::Result<Box<dyn BackendSession>> {
+        if let Some(mounter) = &self.mounter {
+            let device = mounter.mount_fuse(mountpoint, options)?;
+            // the kernel already keeps others out without allow_other
+            let acl = match options.allow_other {
+                true => SessionACL::All,
+                false => SessionACL::RootAndOwner
+            };
+            let session = match fuser::Session::from_fd(fs, device, acl).spawn() {
+                Ok(session) => session,
+                Err(err) => {
+                    let _ = mounter.unmount(mountpoint, true);
+                    return Err(err);
+                }
+            };
+            return Ok(Box::new(FuseSession { session: Some(session), mounted: Some((mountpoint.to_path_buf(), mounter.clone())) }));
+        }
+        let mut mount_options = vec![MountOption::FSName("passthrough".to_string())];
+        if options.allow_other {
+            mount_options.push(MountOption::AllowOther);
+        }
+        if options.auto_unmount && platform::AUTO_UNMOUNT {
+            mount_options.push(MountOption::AutoUnmount);
+        }
+        if options.read_only {
+            mount_options.push(MountOption::RO);
+        }
+        let session = fuser::spawn_mount2(fs, mountpoint, &mount_options)?;
+        Ok(Box::new(FuseSession { session: Some(session), mounted: None }))
+    }
+}
+
+/// A FUSE mount of `FuseBackend`. One made by a `Mounter` has to be unmounted by it as well, fuser doesn't know its
+/// mountpoint, fuser unmounts the others itself.
+#[derive(Debug)]
+struct FuseSession {
+    session : Option<fuser::BackgroundSession>,
+    mounted : Option<(PathBuf, Arc<dyn Mounter>)>
+}
+
+impl FuseSession {
+    /// Unmounts, which ends the thread serving the filesystem, and waits for it.
+    fn stop(&mut self) {
+        let Some(session) = self.session.take() else {
+            return;
+        };
+        if let Some((mountpoint, mounter)) = &self.mounted {
+            // the thread only ends with the mount, so a busy mount is detached instead
+            let unmounted = mounter.unmount(mountpoint, false).or_else(|err| {
+                log!(warn, "Unmounting {} failed, unmounting it lazily: {err}", mountpoint.display());
+                mounter.unmount(mountpoint, true)
+            });
+            if let Err(err) = unmounted {
+                log!(error, "Unmounting {} lazily failed: {err}", mountpoint.display());
+                return;
+            }
+        }
+        session.join();
+    }
+}
+
+impl BackendSession for FuseSession {
+    fn invalidate(&self, change : &Change) {
+        let Some(session) = &self.session else {
+            return;
+        };
+        let notifier = session.notifier();
+        if let Some(inode) = change.inode {
+            let _ = notifier.inval_inode(inode, 0, 0);
+        }
+        let _ = notifier.inval_entry(change.parent, &change.name);
+    }
+
+    fn join(mut self : Box<Self>) {
+        self.stop();
+    }
+}
+
+impl Drop for FuseSession {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
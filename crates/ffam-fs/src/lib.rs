@@ -0,0 +1,51 @@
+//! The monitoring FUSE filesystem: mounts a source directory and reports every read as an `Event`.
+#[cfg(feature = "engine")]
+pub mod backend;
+#[cfg(feature = "engine")]
+pub mod control;
+#[cfg(feature = "engine")]
+mod delay;
+#[cfg(feature = "engine")]
+pub mod error;
+#[cfg(feature = "engine")]
+pub mod fs;
+#[cfg(feature = "engine")]
+mod fuse;
+#[cfg(feature = "engine")]
+mod mount;
+#[cfg(feature = "engine")]
+pub mod nfs;
+#[cfg(feature = "engine")]
+pub mod platform;
+#[cfg(feature = "engine")]
+pub mod plugin;
+#[cfg(feature = "engine")]
+mod prefetch;
+#[cfg(feature = "engine")]
+pub mod recorder;
+#[cfg(feature = "engine")]
+pub mod registry;
+#[cfg(feature = "engine")]
+pub mod rules;
+#[cfg(feature = "schema")]
+pub mod schema;
+#[cfg(feature = "engine")]
+pub mod source;
+#[cfg(feature = "engine")]
+mod trace;
+#[cfg(feature = "io-uring")]
+mod uring;
+
+#[cfg(all(feature = "io-uring", not(target_os = "linux")))]
+compile_error!("io_uring is only available on Linux, build without the io-uring feature");
+
+#[cfg(feature = "tracing")]
+#[doc(hidden)]
+pub use tracing;
+
+#[cfg(feature = "engine")]
+pub use error::{Error, Result};
+#[cfg(feature = "engine")]
+pub use mount::{unmount_on_panic, KernelOptions, MountBuilder, MountOptions, MountSession};
+#[cfg(feature = "tokio")]
+pub use mount::MountHandle;
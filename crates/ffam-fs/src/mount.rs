@@ -0,0 +1,539 @@
+use serde::{Deserialize, Serialize};
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, Once};
+use std::time::Duration;
+
+use crate::backend::{Backend, BackendKind, BackendSession, Mounter};
+use crate::error::{Error, Result};
+use crate::fs::{AccessStats, CaseSensitivity, Change, Directory, Event, EventSink, FileAccessTrackingFs, PathFilter, ScanProgress, StatsSnapshot, TreeEntry};
+#[cfg(feature = "tokio")]
+use crate::fs::EventStream;
+use crate::platform::{self, is_mounted};
+use crate::plugin::{Plugin, Plugins};
+use crate::recorder::{RecordFormat, Recorder};
+use crate::registry::{self, ActiveMount, Registration};
+use crate::rules::Rule;
+use crate::trace::log;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MountOptions {
+    pub allow_other : bool, // let other users (e.g. a game running as another user) access the mount
+    pub auto_unmount : bool, // unmount when the process exits, even if it crashes
+    pub read_only : bool,
+    pub ttl : Duration, // how long the kernel may cache attributes and lookups
+    pub filter : PathFilter, // which accesses produce events, the files stay accessible either way
+    pub capture_payloads : bool,
+    pub prefetch : bool, // read ahead of handles reading sequentially, the events stay the same
+    pub io_uring : bool, // read files of the source through io_uring, needs the io-uring feature and replaces prefetching
+    pub rules : Vec<Rule>, // evaluated for every access, in this order
+    pub backend : BackendKind,
+    pub stats_file : bool, // live statistics as json at .ffam/stats in the mount
+    pub control_file : bool, // commands written to .ffam/ctl control the monitor, see `control::Command`
+    pub metadata_events : bool, // report lookups, attribute requests, directory listings and reads of extended attributes
+    pub write_through : bool, // forward creates, writes, truncates, unlinks and metadata changes to the source, ignored when read only
+    pub direct_io : bool, // bypass the kernel's caches, so every read of a program reaches the monitor
+    pub case_sensitivity : CaseSensitivity, // how the names programs look up are compared to those of the entries
+    pub kernel : KernelOptions
+}
+
+/// What the FUSE backend asks the kernel for when mounting. A value the kernel rejects is logged and replaced by
+/// the nearest one it accepts, a capability it doesn't have is left out, the mount works either way.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KernelOptions {
+    pub passthrough : bool, // ask for FUSE_PASSTHROUGH, on platforms that have it
+    pub max_stack_depth : u32, // filesystems stacked below this one, passthrough needs at least 1
+    pub max_readahead : Option<u32>, // in bytes, None keeps the kernel's
+    pub max_background : Option<u16> // background requests like readahead at a time, None keeps the kernel's
+}
+
+impl Default for KernelOptions {
+    fn default() -> Self {
+        Self {
+            passthrough: true,
+            max_stack_depth: 2,
+            max_readahead: None,
+            max_background: None
+        }
+    }
+}
+
+impl Default for MountOptions {
+    fn default() -> Self {
+        Self {
+            allow_other: false,
+            auto_unmount: false,
+            read_only: false,
+            ttl: Duration::from_secs(1),
+            filter: PathFilter::default(),
+            capture_payloads: false,
+            prefetch: true,
+            io_uring: false,
+            rules: Vec::new(),
+            backend: BackendKind::Fuse,
+            stats_file: true,
+            control_file: true,
+            metadata_events: false,
+            write_through: false,
+            direct_io: false,
+            case_sensitivity: CaseSensitivity::default(),
+            kernel: KernelOptions::default()
+        }
+    }
+}
+
+/// Configures and starts a monitored mount of `source` at `mountpoint`.
+///
+/// ```ignore
+/// let session = MountBuilder::new("/games/europa", "/tmp/monitored")
+///     .read_only(true)
+///     .exclude("sounds/")
+///     .events(sender)
+///     .mount()?;
+/// ```
+#[derive(Debug)]
+pub struct MountBuilder {
+    source : String,
+    mountpoint : String,
+    options : MountOptions,
+    event_sink : EventSink,
+    plugins : Plugins,
+    progress : Arc<ScanProgress>,
+    backend : Option<Box<dyn Backend>>, // the one of the options if not given
+    mounter : Option<Arc<dyn Mounter>>
+}
+
+impl MountBuilder {
+    pub fn new(source : impl Into<String>, mountpoint : impl Into<String>) -> Self {
+        Self {
+            source: source.into(),
+            mountpoint: mountpoint.into(),
+            options: MountOptions::default(),
+            event_sink: EventSink::Discard,
+            plugins: Plugins::default(),
+            progress: Arc::new(ScanProgress::default()),
+            backend: None,
+            mounter: None
+        }
+    }
+
+    /// Replaces all options at once, e.g. with ones loaded from the settings.
+    pub fn options(mut self, options : MountOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    pub fn allow_other(mut self, allow_other : bool) -> Self {
+        self.options.allow_other = allow_other;
+        self
+    }
+
+    pub fn auto_unmount(mut self, auto_unmount : bool) -> Self {
+        self.options.auto_unmount = auto_unmount;
+        self
+    }
+
+    pub fn read_only(mut self, read_only : bool) -> Self {
+        self.options.read_only = read_only;
+        self
+    }
+
+    pub fn ttl(mut self, ttl : Duration) -> Self {
+        self.options.ttl = ttl;
+        self
+    }
+
+    /// Only report paths containing `pattern`, can be given multiple times.
+    pub fn include(mut self, pattern : impl Into<String>) -> Self {
+        self.options.filter.include.push(pattern.into());
+        self
+    }
+
+    /// Don't report paths containing `pattern`, can be given multiple times.
+    pub fn exclude(mut self, pattern : impl Into<String>) -> Self {
+        self.options.filter.exclude.push(pattern.into());
+        self
+    }
+
+    pub fn capture_payloads(mut self, capture_payloads : bool) -> Self {
+        self.options.capture_payloads = capture_payloads;
+        self
+    }
+
+    pub fn prefetch(mut self, prefetch : bool) -> Self {
+        self.options.prefetch = prefetch;
+        self
+    }
+
+    /// Whether the mount gets a `.ffam/stats` file with the live statistics, on by default.
+    pub fn stats_file(mut self, stats_file : bool) -> Self {
+        self.options.stats_file = stats_file;
+        self
+    }
+
+    /// Whether lookups, attribute requests and directory listings are reported as `EventType::Metadata`, off by default.
+    pub fn metadata_events(mut self, metadata_events : bool) -> Self {
+        self.options.metadata_events = metadata_events;
+        self
+    }
+
+    /// Whether files can be created, written, truncated and removed and their metadata changed, which changes the source
+    /// itself, off by default.
+    /// Otherwise writes fail and files created in the mount only exist in memory until it is unmounted.
+    pub fn write_through(mut self, write_through : bool) -> Self {
+        self.options.write_through = write_through;
+        self
+    }
+
+    /// Opens every file with direct io and lets the kernel cache neither attributes nor lookups, off by default.
+    /// Otherwise a file read again may be served from the page cache without an event, with it the log records
+    /// every read request of a program, at the cost of speed. `ttl` is ignored then.
+    pub fn direct_io(mut self, direct_io : bool) -> Self {
+        self.options.direct_io = direct_io;
+        self
+    }
+
+    /// How the names programs look up are compared, ignoring their case and keeping that of new entries by default.
+    /// Of names in the source that only differ in case, the one of exactly the name looked up is found, otherwise
+    /// the first of them in byte order.
+    pub fn case_sensitivity(mut self, case_sensitivity : CaseSensitivity) -> Self {
+        self.options.case_sensitivity = case_sensitivity;
+        self
+    }
+
+    /// What to ask the kernel for when mounting with FUSE, see `KernelOptions`.
+    pub fn kernel(mut self, kernel : KernelOptions) -> Self {
+        self.options.kernel = kernel;
+        self
+    }
+
+    /// Whether the mount gets a `.ffam/ctl` file that takes commands, on by default.
+    pub fn control_file(mut self, control_file : bool) -> Self {
+        self.options.control_file = control_file;
+        self
+    }
+
+    /// Reads the source through io_uring, so the FUSE thread doesn't wait for the disk and concurrent reads overlap.
+    /// Falls back to normal reads if the crate is built without the io-uring feature or the kernel doesn't support it.
+    pub fn io_uring(mut self, io_uring : bool) -> Self {
+        self.options.io_uring = io_uring;
+        self
+    }
+
+    /// Adds a rule that tags, delays or denies matching accesses, after the rules added before.
+    pub fn rule(mut self, rule : Rule) -> Self {
+        self.options.rules.push(rule);
+        self
+    }
+
+    /// Where the events are sent, a tokio or std sender. Without one the mount works but reports nothing.
+    pub fn events(mut self, event_sender : impl Into<EventSink>) -> Self {
+        self.event_sink = event_sender.into();
+        self
+    }
+
+    /// Calls `callback` for every event instead of sending it, replaces a sender given with `events`.
+    /// It runs inside the FUSE handler, so a slow callback slows down the reads.
+    pub fn on_event(mut self, callback : impl FnMut(Event) + Send + 'static) -> Self {
+        self.event_sink = EventSink::Callback(Box::new(callback));
+        self
+    }
+
+    /// Runs every reported event through `plugin` before it is delivered, can be given multiple times.
+    pub fn plugin(mut self, plugin : Box<dyn Plugin>) -> Self {
+        self.plugins.add(plugin);
+        self
+    }
+
+    /// Mounts with another driver than the one of the options, see `backend::Backend`.
+    pub fn backend(mut self, backend : impl Backend + 'static) -> Self {
+        self.backend = Some(Box::new(backend));
+        self
+    }
+
+    /// Lets `mounter` mount and unmount for the backend of the options, e.g. when this process can't because it
+    /// dropped root. It also unmounts stale and leftover mounts.
+    pub fn mounter(mut self, mounter : Arc<dyn Mounter>) -> Self {
+        self.mounter = Some(mounter);
+        self
+    }
+
+    /// Shares the progress of the source scan, which also allows cancelling it.
+    pub fn progress(mut self, progress : Arc<ScanProgress>) -> Self {
+        self.progress = progress;
+        self
+    }
+
+    /// Scans the source and mounts it, this blocks until the scan is done.
+    pub fn mount(self) -> Result<MountSession> {
+        self.spawn(|_| ())
+    }
+
+    /// Like `mount`, but scans on a blocking thread of the tokio runtime and returns a handle that can be awaited.
+    #[cfg(feature = "tokio")]
+    pub async fn mount_async(self) -> Result<MountHandle> {
+        let (end_sender, ended) = tokio::sync::oneshot::channel();
+        let session = tokio::task::spawn_blocking(move || self.spawn(|fs| fs.session_end = Some(end_sender)))
+            .await
+            .map_err(|err| Error::SessionThread(err.to_string()))??;
+        Ok(MountHandle { session, ended })
+    }
+
+    /// Like `mount_async`, but the events are returned as a stream. This replaces a sender or callback given before.
+    #[cfg(feature = "tokio")]
+    pub async fn mount_stream(mut self, buffer : usize) -> Result<(MountHandle, EventStream)> {
+        let (stream, sink) = EventStream::new(buffer);
+        self.event_sink = sink;
+        Ok((self.mount_async().await?, stream))
+    }
+
+    /// Like `mount`, but the events go through a lock-free ring holding up to `capacity` events, see `EventRing`.
+    /// This replaces a sender or callback given before.
+    #[cfg(feature = "ring")]
+    pub fn mount_ring(mut self, capacity : usize) -> Result<(MountSession, crate::fs::EventRing)> {
+        let (ring, sink) = crate::fs::EventRing::new(capacity);
+        self.event_sink = sink;
+        Ok((self.mount()?, ring))
+    }
+
+    /// Mounts after `prepare` got the filesystem, e.g. to hand it what is dropped when the session ends.
+    fn spawn(self, prepare : impl FnOnce(&mut FileAccessTrackingFs)) -> Result<MountSession> {
+        // left behind by a crashed monitor, it is dead anyway and mounting over it fails
+        if platform::is_stale(self.mountpoint.as_ref()) {
+            log!(warn, "{} is a stale mount, unmounting it lazily", self.mountpoint);
+            registry::recover(self.mountpoint.as_ref(), self.mounter.as_deref());
+        }
+        // resolved before mounting, a dead mount can't be resolved anymore
+        let mountpoint = std::fs::canonicalize(&self.mountpoint).unwrap_or_else(|_| PathBuf::from(&self.mountpoint));
+        let backend = self.backend.unwrap_or_else(|| self.options.backend.backend(self.mounter.clone()));
+        // before the scan, a second monitor on the same mountpoint should fail right away
+        let registration = registry::register(&ActiveMount {
+            pid: std::process::id(),
+            source: self.source.clone(),
+            mountpoint: mountpoint.clone(),
+            backend: backend.name().to_string(),
+            mounted_at: chrono::Utc::now()
+        })?;
+        let mut fs = FileAccessTrackingFs::with_sink(crate::source::DiskSource::new(&self.source), self.event_sink, &self.options, &self.progress)?;
+        prepare(&mut fs);
+        fs.set_plugins(self.plugins);
+        let stats = fs.stats();
+        let directory = fs.directory();
+        let recorder = fs.recorder();
+        let session = backend.mount(fs, self.mountpoint.as_ref(), &self.options).map_err(|source| Error::Mount {
+            mountpoint: self.mountpoint.clone(),
+            source
+        })?;
+        log!(info, "Mounted {} at {} with {}", self.source, self.mountpoint, backend.name());
+        Ok(MountSession { session, guard: UnmountGuard::new(mountpoint, self.mounter), stats, directory, recorder, _registration: registration })
+    }
+}
+
+/// A mountpoint and who mounted it, if not the backend itself.
+type Mounted = (PathBuf, Option<Arc<dyn Mounter>>);
+
+/// Mountpoints of all live sessions, for the panic hook.
+static MOUNTED : Mutex<Vec<Mounted>> = Mutex::new(Vec::new());
+static PANIC_HOOK : Once = Once::new();
+
+/// Makes any panic lazily unmount all live sessions, after the hook set before has run. For applications that end
+/// on a panic, so the mounts don't outlive them. Libraries must not call it, a panic caught with `catch_unwind`
+/// would leave the process running without its mounts.
+pub fn unmount_on_panic() {
+    PANIC_HOOK.call_once(|| {
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            previous(info);
+            // the panic may have happened while the list was locked
+            if let Ok(mounted) = MOUNTED.try_lock() {
+                for (mountpoint, mounter) in mounted.iter() {
+                    force_unmount(mountpoint, mounter.as_deref());
+                }
+            }
+        }));
+    });
+}
+
+/// Lazily unmounts (`fusermount -uz`, `umount -f` on FreeBSD) a mountpoint that is still mounted when the guard is dropped.
+/// Otherwise a crashed session leaves a dead mountpoint behind ("Transport endpoint is not connected").
+#[derive(Debug)]
+struct UnmountGuard {
+    mountpoint : PathBuf,
+    mounter : Option<Arc<dyn Mounter>>,
+    armed : bool
+}
+
+impl UnmountGuard {
+    fn new(mountpoint : PathBuf, mounter : Option<Arc<dyn Mounter>>) -> Self {
+        MOUNTED.lock().unwrap().push((mountpoint.clone(), mounter.clone()));
+        Self { mountpoint, mounter, armed: true }
+    }
+
+    /// After a clean unmount there is nothing left to clean up.
+    fn disarm(mut self) {
+        self.armed = false;
+    }
+}
+
+impl Drop for UnmountGuard {
+    fn drop(&mut self) {
+        // this may run while unwinding, so a poisoned lock must not panic again
+        let mut mounted = MOUNTED.lock().unwrap_or_else(|err| err.into_inner());
+        if let Some(position) = mounted.iter().position(|(mountpoint, _)| *mountpoint == self.mountpoint) {
+            mounted.remove(position);
+        }
+        drop(mounted);
+        if self.armed && is_mounted(&self.mountpoint) {
+            log!(warn, "{} is still mounted, unmounting it lazily", self.mountpoint.display());
+            force_unmount(&self.mountpoint, self.mounter.as_deref());
+        }
+    }
+}
+
+/// Lazily unmounts with `mounter` if there is one, otherwise like a user would, see `platform::force_unmount`.
+pub(crate) fn force_unmount(mountpoint : &Path, mounter : Option<&dyn Mounter>) {
+    match mounter {
+        Some(mounter) => {
+            if let Err(err) = mounter.unmount(mountpoint, true) {
+                log!(warn, "Unmounting {} lazily failed: {err}", mountpoint.display());
+            }
+        }
+        None => platform::force_unmount(mountpoint)
+    }
+}
+
+/// A mounted filesystem, returned by `MountBuilder::mount`.
+///
+/// Dropping it unmounts. If the mountpoint is still mounted afterwards, e.g. because it was busy or the session thread
+/// crashed, it gets unmounted lazily, which also happens for all sessions when the process panics after `unmount_on_panic`.
+#[derive(Debug)]
+pub struct MountSession {
+    session : Box<dyn BackendSession>,
+    guard : UnmountGuard, // after the session, so it only cleans up what the normal unmount left behind
+    stats : Arc<Mutex<AccessStats>>,
+    directory : Arc<Mutex<Directory>>,
+    recorder : Arc<Mutex<Option<Recorder>>>,
+    _registration : Registration // last, the mountpoint is only free again after unmounting
+}
+
+impl MountSession {
+    /// Current per-file counters and totals, counted by the filesystem itself so nobody has to aggregate the events.
+    pub fn stats(&self) -> StatsSnapshot {
+        self.stats.lock().unwrap().snapshot()
+    }
+
+    /// Events that couldn't be delivered, `StatsSnapshot::dropped` without copying the counters of every file.
+    pub fn dropped(&self) -> u64 {
+        self.stats.lock().unwrap().dropped()
+    }
+
+    /// Every path of the mounted tree with its inode, size and access counters, depth first.
+    /// Lets tools check what the mount exposes without walking the mountpoint, which would count as accesses.
+    pub fn tree(&self) -> Vec<TreeEntry> {
+        let stats = self.stats.lock().unwrap();
+        self.directory.lock().unwrap().list(&stats)
+    }
+
+    /// A single entry of the mounted tree, "" is the root.
+    pub fn entry(&self, path : impl AsRef<OsStr>) -> Option<TreeEntry> {
+        let stats = self.stats.lock().unwrap();
+        let directory = self.directory.lock().unwrap();
+        directory.find_path(path.as_ref()).map(|entry| directory.tree_entry(entry, &stats))
+    }
+
+    /// Starts writing all further events to `path`, independent of where they are delivered.
+    /// A recording that is already running gets finished first.
+    pub fn record_to(&self, path : impl AsRef<Path>, format : RecordFormat) -> Result<()> {
+        let new = Recorder::create(path.as_ref(), format)?;
+        if let Some(old) = self.recorder.lock().unwrap().replace(new) {
+            let _ = old.finish();
+        }
+        Ok(())
+    }
+
+    /// Stops the recording and flushes the file, does nothing if there is none.
+    pub fn stop_recording(&self) -> Result<()> {
+        match self.recorder.lock().unwrap().take() {
+            Some(recorder) => recorder.finish(),
+            None => Ok(())
+        }
+    }
+
+    /// False after `stop_recording` and also if writing failed.
+    pub fn is_recording(&self) -> bool {
+        self.recorder.lock().unwrap().is_some()
+    }
+
+    /// Adds a virtual file to the mounted tree or replaces an existing file with it, e.g. to swap in a modded file.
+    /// The parent directory must exist.
+    pub fn add_file(&self, path : impl AsRef<OsStr>, content : impl Into<Vec<u8>>) -> Result<()> {
+        let change = self.directory.lock().unwrap().put(path.as_ref(), Some(content.into()))?;
+        self.invalidate(&change);
+        Ok(())
+    }
+
+    /// Adds an empty virtual directory, replacing an existing file of that name, which stays readable through handles
+    /// still open on it. An existing directory is kept as it is.
+    pub fn add_directory(&self, path : impl AsRef<OsStr>) -> Result<()> {
+        let change = self.directory.lock().unwrap().put(path.as_ref(), None)?;
+        self.invalidate(&change);
+        Ok(())
+    }
+
+    /// Removes a file or directory from the mounted tree, the source is not touched. Files that are still open stay
+    /// readable through their handles.
+    pub fn remove(&self, path : impl AsRef<OsStr>) -> Result<()> {
+        let change = self.directory.lock().unwrap().remove(path.as_ref())?;
+        self.invalidate(&change);
+        Ok(())
+    }
+
+    /// Makes the kernel forget what it cached about a changed entry, otherwise it may keep serving the old one.
+    fn invalidate(&self, change : &Change) {
+        self.session.invalidate(change);
+    }
+
+    /// Unmounts and waits for the session thread to finish.
+    pub fn join(self) {
+        let Self { session, guard, _registration, .. } = self;
+        session.join();
+        guard.disarm();
+    }
+}
+
+/// A mounted filesystem, returned by `MountBuilder::mount_async`.
+///
+/// Dropping the handle unmounts as well, but blocks the current thread until the session is joined.
+#[cfg(feature = "tokio")]
+pub struct MountHandle {
+    session : MountSession,
+    ended : tokio::sync::oneshot::Receiver<()>
+}
+
+#[cfg(feature = "tokio")]
+impl MountHandle {
+    pub fn stats(&self) -> StatsSnapshot {
+        self.session.stats()
+    }
+
+    /// The mounted session, e.g. to query or change the tree.
+    pub fn session(&self) -> &MountSession {
+        &self.session
+    }
+
+    /// Resolves once the session is over, e.g. because the mountpoint was unmounted with `fusermount -u`.
+    pub async fn ended(&mut self) {
+        // nothing is ever sent, the filesystem just drops the sender when the session thread finishes
+        let _ = (&mut self.ended).await;
+    }
+
+    /// Unmounts and waits for the session thread to finish without blocking the runtime.
+    pub async fn unmount(self) -> Result<()> {
+        let session = self.session;
+        tokio::task::spawn_blocking(move || session.join())
+            .await
+            .map_err(|err| Error::SessionThread(err.to_string()))
+    }
+}
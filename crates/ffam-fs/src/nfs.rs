@@ -0,0 +1,748 @@
+//! A read-only NFSv3 server on localhost as a backend for systems without FUSE, e.g. locked-down containers.
+//!
+//! Only what a client needs to browse and read is implemented: the MOUNT protocol and the NFS procedures that
+//! don't change anything, everything else fails with `NFS3ERR_ROFS`. There is no portmapper, the kernel is told
+//! the port (`mount -t nfs -o port=…,mountport=…`). NFS has no processes, so all accesses come from pid 0.
+//!
+//! Like a FUSE mount, only root and the user serving it may use it unless `MountOptions::allow_other`. The uid of
+//! the AUTH_SYS credentials is only believed on connections of root, like the one of the kernel's NFS client, which
+//! sends the uid of the process it calls for; on any other connection the caller is the user owning its socket,
+//! looked up in /proc/net/tcp. That only exists on Linux, elsewhere the server has to allow other users.
+//! Since reads are stateless, a file is opened with its first read and closed once it wasn't read for `HANDLE_IDLE`.
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::io::{Read, Write};
+use std::net::{IpAddr, Ipv4Addr, Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant, UNIX_EPOCH};
+
+use crate::backend::{Backend, BackendSession, Mounter};
+use crate::fs::{Attributes, Change, EntryKind, FileAccessTrackingFs};
+use crate::trace::log;
+use crate::MountOptions;
+
+const MOUNT_PROGRAM : u32 = 100005;
+const NFS_PROGRAM : u32 = 100003;
+/// Both programs are served in version 3 only.
+const VERSION : u32 = 3;
+/// Largest read the client is told to make, also limits the records accepted from it.
+const MAX_READ : u32 = 1024 * 1024;
+/// NFS has no processes, the pid given to the filesystem for every access.
+const NFS_PID : u32 = 0;
+/// How long a file opened by a read stays open without being read, then it is closed and reported as such.
+const HANDLE_IDLE : Duration = Duration::from_secs(5);
+/// The only credentials accepted, AUTH_NULL carries no uid to check.
+const AUTH_SYS : u32 = 1;
+/// Connections served at once, each has its own thread. The kernel's client needs one, more are refused.
+const MAX_CONNECTIONS : usize = 16;
+
+// nfsstat3, see `status` for the errno values they stand for
+const NFS3_OK : u32 = 0;
+const NFS3ERR_PERM : u32 = 1;
+const NFS3ERR_NOENT : u32 = 2;
+const NFS3ERR_IO : u32 = 5;
+const NFS3ERR_NXIO : u32 = 6;
+const NFS3ERR_ACCES : u32 = 13;
+const NFS3ERR_EXIST : u32 = 17;
+const NFS3ERR_XDEV : u32 = 18;
+const NFS3ERR_NODEV : u32 = 19;
+const NFS3ERR_NOTDIR : u32 = 20;
+const NFS3ERR_ISDIR : u32 = 21;
+const NFS3ERR_INVAL : u32 = 22;
+const NFS3ERR_FBIG : u32 = 27;
+const NFS3ERR_NOSPC : u32 = 28;
+const NFS3ERR_ROFS : u32 = 30;
+const NFS3ERR_MLINK : u32 = 31;
+const NFS3ERR_NAMETOOLONG : u32 = 63;
+const NFS3ERR_NOTEMPTY : u32 = 66;
+const NFS3ERR_DQUOT : u32 = 69;
+const NFS3ERR_STALE : u32 = 70;
+const NFS3ERR_BADHANDLE : u32 = 10001;
+const NFS3ERR_NOTSUPP : u32 = 10004;
+const NFS3ERR_JUKEBOX : u32 = 10008;
+
+/// Where the server listens and whether the kernel mounts it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NfsOptions {
+    pub port : u16, // on 127.0.0.1, 0 picks a free one
+    pub kernel_mount : bool // mount the export at the mountpoint (needs root), otherwise only serve it for other clients
+}
+
+impl Default for NfsOptions {
+    fn default() -> Self {
+        Self { port: 0, kernel_mount: true }
+    }
+}
+
+/// Serves the filesystem over NFSv3 on localhost.
+#[derive(Debug, Clone, Default)]
+pub struct NfsBackend {
+    pub options : NfsOptions,
+    pub mounter : Option<Arc<dyn Mounter>> // does the kernel mount instead of `mount` if given
+}
+
+/// The options of the kernel mount of the server on the local `port`. Never with suid or device files, whoever
+/// serves it decides what they contain.
+pub fn client_options(port : u16, options : &MountOptions) -> String {
+    // NFS clients have no direct io for a whole mount, only the attribute and lookup caches can be turned off
+    let caching = match options.direct_io {
+        true => "noac,lookupcache=none",
+        false => "actimeo=1"
+    };
+    format!("vers=3,proto=tcp,port={port},mountport={port},nolock,noacl,ro,nosuid,nodev,{caching}")
+}
+
+impl Backend for NfsBackend {
+    fn name(&self) -> &'static str {
+        "nfs"
+    }
+
+    fn mount(&self, fs : FileAccessTrackingFs, mountpoint : &Path, options : &MountOptions) -> std::io::Result<Box<dyn BackendSession>> {
+        if !options.allow_other && !cfg!(target_os = "linux") {
+            // without /proc/net/tcp anyone could claim to be the user
+            return Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "the NFS server can only be limited to its user on Linux, allow other users to serve it"));
+        }
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, self.options.port))?;
+        let port = listener.local_addr()?.port();
+        let root = fs.inode("").unwrap_or(1);
+        // SAFETY: geteuid can't fail
+        let user = (!options.allow_other).then(|| unsafe { libc::geteuid() });
+        let server = Arc::new(Mutex::new(Server { fs, root, user, handles: HashMap::new() }));
+        let stop = Arc::new(AtomicBool::new(false));
+        let connections : Arc<Mutex<HashMap<u64, TcpStream>>> = Arc::default();
+        {
+            let (stop, server) = (stop.clone(), Arc::downgrade(&server));
+            std::thread::Builder::new().name("ffam-nfs-idle".to_string()).spawn(move || close_idle(server, stop))?;
+        }
+        let accept = {
+            let (stop, connections) = (stop.clone(), connections.clone());
+            std::thread::Builder::new().name("ffam-nfs".to_string()).spawn(move || accept(listener, server, stop, connections))?
+        };
+        log!(info, "Serving NFSv3 on 127.0.0.1:{port}");
+        let mut session = NfsSession { port, mountpoint: None, mounter: self.mounter.clone(), stop, connections, accept: Some(accept) };
+        if self.options.kernel_mount {
+            // dropping the session on failure stops the server again
+            match &self.mounter {
+                Some(mounter) => mounter.mount_nfs(port, mountpoint, options)?,
+                None => mount(port, mountpoint, options)?
+            }
+            session.mountpoint = Some(mountpoint.to_path_buf());
+        }
+        Ok(Box::new(session))
+    }
+}
+
+/// Mounts the server on the local `port` with `mount -t nfs`.
+pub fn mount(port : u16, mountpoint : &Path, options : &MountOptions) -> std::io::Result<()> {
+    let status = Command::new("mount")
+        .args(["-t", "nfs", "-o"])
+        .arg(client_options(port, options))
+        .arg("127.0.0.1:/")
+        .arg(mountpoint)
+        .stdin(Stdio::null())
+        .status()?;
+    match status.success() {
+        true => Ok(()),
+        false => Err(std::io::Error::other(format!("mount -t nfs failed with {status}")))
+    }
+}
+
+#[derive(Debug)]
+struct NfsSession {
+    port : u16,
+    mountpoint : Option<PathBuf>, // if the kernel mounted it
+    mounter : Option<Arc<dyn Mounter>>, // that mounted it
+    stop : Arc<AtomicBool>,
+    connections : Arc<Mutex<HashMap<u64, TcpStream>>>, // the open ones, by the number of their connection
+    accept : Option<JoinHandle<()>>
+}
+
+impl NfsSession {
+    fn stop(&mut self) {
+        if let Some(mountpoint) = self.mountpoint.take() {
+            let unmounted = match &self.mounter {
+                Some(mounter) => mounter.unmount(&mountpoint, false).is_ok(),
+                None => Command::new("umount").arg(&mountpoint).stdin(Stdio::null()).status().is_ok_and(|status| status.success())
+            };
+            if !unmounted {
+                log!(warn, "Unmounting {} failed", mountpoint.display());
+            }
+        }
+        self.stop.store(true, Ordering::SeqCst);
+        // wakes the accepting thread, which then sees that it should stop
+        let _ = TcpStream::connect((Ipv4Addr::LOCALHOST, self.port));
+        for (_, connection) in self.connections.lock().unwrap().drain() {
+            let _ = connection.shutdown(Shutdown::Both);
+        }
+        if let Some(accept) = self.accept.take() {
+            let _ = accept.join();
+        }
+    }
+}
+
+impl BackendSession for NfsSession {
+    fn invalidate(&self, _change : &Change) {
+        // NFS clients can't be told, they revalidate after the attribute cache timeout (actimeo)
+    }
+
+    fn join(mut self : Box<Self>) {
+        self.stop();
+    }
+}
+
+impl Drop for NfsSession {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[derive(Debug)]
+struct Server {
+    fs : FileAccessTrackingFs,
+    root : u64,
+    user : Option<u32>, // the only uid besides root that may call, None with `allow_other`
+    handles : HashMap<u64, (u64, Instant)> // open handle per inode and when it was last read, NFS reads are stateless
+}
+
+impl Server {
+    /// Whether a call with these credentials may be answered on a connection of `owner`. Its uid is only believed
+    /// if the owner is root, any other process could claim whatever uid it likes.
+    fn allows(&self, flavor : u32, credentials : &[u8], owner : u32) -> bool {
+        if flavor != AUTH_SYS {
+            return false;
+        }
+        // authsys_parms: stamp, machine name, uid, gid and the other groups
+        let mut credentials = Xdr { data: credentials, position: 0 };
+        let uid = match (credentials.u32().and_then(|_| credentials.opaque()).and_then(|_| credentials.u32()), owner) {
+            (None, _) => return false,
+            (Some(uid), 0) => uid,
+            (Some(_), owner) => owner
+        };
+        match self.user {
+            None => true,
+            Some(user) => uid == 0 || uid == user
+        }
+    }
+
+    /// Closes the handles that weren't read for `HANDLE_IDLE`.
+    fn close_idle(&mut self) {
+        let fs = &mut self.fs;
+        self.handles.retain(|_, (handle, last_read)| {
+            let idle = last_read.elapsed() >= HANDLE_IDLE;
+            if idle {
+                fs.release_handle(*handle);
+            }
+            !idle
+        });
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        for (_, (handle, _)) in self.handles.drain() {
+            self.fs.release_handle(handle);
+        }
+    }
+}
+
+/// Closes idle handles every second until the session stops or the server is gone.
+fn close_idle(server : Weak<Mutex<Server>>, stop : Arc<AtomicBool>) {
+    while !stop.load(Ordering::SeqCst) {
+        std::thread::sleep(Duration::from_secs(1));
+        match server.upgrade() {
+            Some(server) => server.lock().unwrap().close_idle(),
+            None => return
+        }
+    }
+}
+
+fn accept(listener : TcpListener, server : Arc<Mutex<Server>>, stop : Arc<AtomicBool>, connections : Arc<Mutex<HashMap<u64, TcpStream>>>) {
+    let mut workers : Vec<JoinHandle<()>> = Vec::new();
+    for (id, stream) in (0..).zip(listener.incoming()) {
+        if stop.load(Ordering::SeqCst) {
+            break;
+        }
+        let Ok(stream) = stream else {
+            continue;
+        };
+        // the threads of closed connections are done, only the running ones have to be joined
+        workers.retain(|worker| !worker.is_finished());
+        if workers.len() >= MAX_CONNECTIONS {
+            log!(warn, "Refusing an NFS connection, {MAX_CONNECTIONS} are open already");
+            continue;
+        }
+        let Some(owner) = peer_uid(&stream) else {
+            log!(warn, "Refusing an NFS connection, its owner is unknown");
+            continue;
+        };
+        if let Ok(clone) = stream.try_clone() {
+            connections.lock().unwrap().insert(id, clone);
+        }
+        let (server, connections) = (server.clone(), connections.clone());
+        workers.push(std::thread::spawn(move || {
+            serve_connection(stream, server, owner);
+            connections.lock().unwrap().remove(&id);
+        }));
+    }
+    // the filesystem is dropped once the last connection is done, which ends the session
+    for worker in workers {
+        let _ = worker.join();
+    }
+}
+
+/// The uid of the process that owns the other end of a connection to the server, from /proc/net/tcp.
+fn peer_uid(stream : &TcpStream) -> Option<u32> {
+    // as the kernel prints them, the address in network order read as a native integer
+    let address = |address : SocketAddr| match address.ip() {
+        IpAddr::V4(ip) => Some(format!("{:08X}:{:04X}", u32::from_ne_bytes(ip.octets()), address.port())),
+        IpAddr::V6(_) => None
+    };
+    // the socket of the client has our peer as its local address
+    let (local, remote) = (address(stream.peer_addr().ok()?)?, address(stream.local_addr().ok()?)?);
+    let table = std::fs::read_to_string("/proc/net/tcp").ok()?;
+    table.lines().skip(1).find_map(|line| {
+        let fields : Vec<&str> = line.split_whitespace().collect();
+        match (fields.get(1), fields.get(2), fields.get(7)) {
+            (Some(from), Some(to), Some(uid)) if *from == local && *to == remote => uid.parse().ok(),
+            _ => None
+        }
+    })
+}
+
+/// Answers the calls of one client until it disconnects. Calls come in records of fragments, each with a
+/// 4 byte header holding the length and whether it is the last fragment.
+fn serve_connection(mut stream : TcpStream, server : Arc<Mutex<Server>>, owner : u32) {
+    let mut record = Vec::new();
+    loop {
+        let mut header = [0; 4];
+        if stream.read_exact(&mut header).is_err() {
+            return;
+        }
+        let header = u32::from_be_bytes(header);
+        let length = (header & 0x7fff_ffff) as usize;
+        if record.len() + length > MAX_READ as usize + 4096 {
+            log!(warn, "NFS client sent a record that is too large, closing the connection");
+            return;
+        }
+        let start = record.len();
+        record.resize(start + length, 0);
+        if stream.read_exact(&mut record[start..]).is_err() {
+            return;
+        }
+        if header & 0x8000_0000 == 0 {
+            continue;
+        }
+        let reply = match call(&record, &server, owner) {
+            Some(reply) => reply,
+            None => {
+                log!(debug, "Ignoring a malformed RPC call");
+                record.clear();
+                continue;
+            }
+        };
+        record.clear();
+        let header = (reply.len() as u32 | 0x8000_0000).to_be_bytes();
+        if stream.write_all(&header).and_then(|()| stream.write_all(&reply)).is_err() {
+            return;
+        }
+    }
+}
+
+/// Decodes XDR, everything is big endian and padded to 4 bytes.
+struct Xdr<'a> {
+    data : &'a [u8],
+    position : usize
+}
+
+impl<'a> Xdr<'a> {
+    fn bytes(&mut self, length : usize) -> Option<&'a [u8]> {
+        let bytes = self.data.get(self.position..self.position + length)?;
+        self.position += length.div_ceil(4) * 4;
+        Some(bytes)
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_be_bytes(self.bytes(4)?.try_into().ok()?))
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        Some(u64::from_be_bytes(self.bytes(8)?.try_into().ok()?))
+    }
+
+    fn opaque(&mut self) -> Option<&'a [u8]> {
+        let length = self.u32()? as usize;
+        self.bytes(length)
+    }
+
+    fn string(&mut self) -> Option<&'a str> {
+        std::str::from_utf8(self.opaque()?).ok()
+    }
+
+    /// A file name, which may be any bytes.
+    fn name(&mut self) -> Option<&'a OsStr> {
+        Some(OsStr::from_bytes(self.opaque()?))
+    }
+
+    /// A file handle, which is the inode.
+    fn handle(&mut self) -> Option<u64> {
+        Some(u64::from_be_bytes(self.opaque()?.try_into().ok()?))
+    }
+}
+
+/// Encodes XDR.
+#[derive(Default)]
+struct Reply(Vec<u8>);
+
+impl Reply {
+    fn u32(&mut self, value : u32) -> &mut Self {
+        self.0.extend_from_slice(&value.to_be_bytes());
+        self
+    }
+
+    fn u64(&mut self, value : u64) -> &mut Self {
+        self.0.extend_from_slice(&value.to_be_bytes());
+        self
+    }
+
+    fn bool(&mut self, value : bool) -> &mut Self {
+        self.u32(value as u32)
+    }
+
+    fn opaque(&mut self, bytes : &[u8]) -> &mut Self {
+        self.u32(bytes.len() as u32);
+        self.0.extend_from_slice(bytes);
+        self.0.resize(self.0.len().div_ceil(4) * 4, 0);
+        self
+    }
+
+    fn handle(&mut self, inode : u64) -> &mut Self {
+        self.opaque(&inode.to_be_bytes())
+    }
+
+    /// fattr3
+    fn attributes(&mut self, attr : &Attributes) -> &mut Self {
+        let kind = match attr.kind {
+            EntryKind::Directory => 2,
+            EntryKind::Symlink => 5,
+            EntryKind::RegularFile => 1
+        };
+        self.u32(kind)
+            .u32(attr.perm as u32)
+            .u32(attr.nlink)
+            .u32(attr.uid)
+            .u32(attr.gid)
+            .u64(attr.size)
+            .u64(attr.size)
+            .u32(0).u32(0) // rdev
+            .u64(0) // fsid
+            .u64(attr.ino);
+        for time in [attr.atime, attr.mtime, attr.ctime] {
+            let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+            self.u32(since_epoch.as_secs() as u32).u32(since_epoch.subsec_nanos());
+        }
+        self
+    }
+
+    /// post_op_attr
+    fn post_op(&mut self, attr : Option<&Attributes>) -> &mut Self {
+        match attr {
+            Some(attr) => self.bool(true).attributes(attr),
+            None => self.bool(false)
+        }
+    }
+}
+
+/// Handles one RPC call on a connection of `owner`, `None` if it can't even be decoded far enough to reply.
+fn call(record : &[u8], server : &Mutex<Server>, owner : u32) -> Option<Vec<u8>> {
+    let mut args = Xdr { data: record, position: 0 };
+    let xid = args.u32()?;
+    let message_type = args.u32()?;
+    if message_type != 0 {
+        return None;
+    }
+    let (rpc_version, program, version, procedure) = (args.u32()?, args.u32()?, args.u32()?, args.u32()?);
+    let (flavor, credentials) = (args.u32()?, args.opaque()?);
+    // the verifier, AUTH_SYS has none
+    args.u32()?;
+    args.opaque()?;
+    let mut reply = Reply::default();
+    reply.u32(xid).u32(1); // REPLY
+    if rpc_version != 2 {
+        reply.u32(1).u32(0).u32(2).u32(2); // MSG_DENIED, RPC_MISMATCH, supported from 2 to 2
+        return Some(reply.0);
+    }
+    let mut server = server.lock().unwrap();
+    if !server.allows(flavor, credentials, owner) {
+        reply.u32(1).u32(1).u32(5); // MSG_DENIED, AUTH_ERROR, AUTH_TOOWEAK
+        return Some(reply.0);
+    }
+    reply.u32(0).u32(0).u32(0); // MSG_ACCEPTED, AUTH_NULL verifier with no body
+    if program != MOUNT_PROGRAM && program != NFS_PROGRAM {
+        reply.u32(1); // PROG_UNAVAIL
+        return Some(reply.0);
+    }
+    if version != VERSION {
+        reply.u32(2).u32(VERSION).u32(VERSION); // PROG_MISMATCH
+        return Some(reply.0);
+    }
+    let position = reply.0.len();
+    reply.u32(0); // SUCCESS
+    let handled = match program {
+        MOUNT_PROGRAM => server.mount_procedure(procedure, &mut args, &mut reply),
+        _ => server.nfs_procedure(procedure, &mut args, &mut reply)
+    };
+    // a delay of the rules only holds back this connection, the other ones are served meanwhile
+    let delay = server.fs.take_delay();
+    drop(server);
+    if !delay.is_zero() {
+        std::thread::sleep(delay);
+    }
+    match handled {
+        Some(true) => {}
+        Some(false) => {
+            reply.0.truncate(position);
+            reply.u32(3); // PROC_UNAVAIL
+        }
+        None => {
+            reply.0.truncate(position);
+            reply.u32(4); // GARBAGE_ARGS
+        }
+    }
+    Some(reply.0)
+}
+
+impl Server {
+    /// Writes the result of a MOUNT procedure, `Some(false)` if it doesn't exist and `None` if the arguments are garbage.
+    fn mount_procedure(&mut self, procedure : u32, args : &mut Xdr, reply : &mut Reply) -> Option<bool> {
+        match procedure {
+            0 => {} // NULL
+            1 => {
+                // MNT, every path gets the root, there is only one export
+                args.string()?;
+                reply.u32(NFS3_OK).handle(self.root).u32(1).u32(1); // one auth flavor: AUTH_UNIX
+            }
+            2 => {
+                reply.bool(false); // DUMP, no mounts are remembered
+            }
+            3 | 4 => {} // UMNT and UMNTALL
+            5 => {
+                // EXPORT, "/" to everyone
+                reply.bool(true).opaque(b"/").bool(false).bool(false);
+            }
+            _ => return Some(false)
+        }
+        Some(true)
+    }
+
+    fn attributes(&self, inode : u64) -> Option<Attributes> {
+        self.fs.attributes(inode).ok()
+    }
+
+    /// Writes the result of an NFS procedure, `Some(false)` if it doesn't exist and `None` if the arguments are garbage.
+    fn nfs_procedure(&mut self, procedure : u32, args : &mut Xdr, reply : &mut Reply) -> Option<bool> {
+        match procedure {
+            0 => {} // NULL
+            1 => {
+                // GETATTR
+                match self.fs.stat_entry(NFS_PID, args.handle()?) {
+                    Ok(attr) => reply.u32(NFS3_OK).attributes(&attr),
+                    Err(_) => reply.u32(NFS3ERR_BADHANDLE)
+                };
+            }
+            3 => {
+                // LOOKUP
+                let (directory, name) = (args.handle()?, args.name()?);
+                let dir_attr = self.attributes(directory);
+                let result = match name.as_bytes() {
+                    b"." => self.fs.attributes(directory),
+                    b".." => self.fs.attributes(self.root), // parents aren't tracked, the client resolves ".." itself
+                    _ => self.fs.lookup_entry(NFS_PID, directory, name)
+                };
+                match result {
+                    Ok(attr) => reply.u32(NFS3_OK).handle(attr.ino).post_op(Some(&attr)).post_op(dir_attr.as_ref()),
+                    Err(errno) => reply.u32(status(errno)).post_op(dir_attr.as_ref())
+                };
+            }
+            4 => {
+                // ACCESS, reading, looking up and executing is allowed, changing nothing
+                let (inode, requested) = (args.handle()?, args.u32()?);
+                match self.attributes(inode) {
+                    Some(attr) => reply.u32(NFS3_OK).post_op(Some(&attr)).u32(requested & (0x01 | 0x02 | 0x20)),
+                    None => reply.u32(NFS3ERR_BADHANDLE).post_op(None)
+                };
+            }
+            5 => {
+                // READLINK
+                let inode = args.handle()?;
+                let attr = self.attributes(inode);
+                match self.fs.read_link(NFS_PID, inode) {
+                    Ok(target) => reply.u32(NFS3_OK).post_op(attr.as_ref()).opaque(target.as_bytes()),
+                    Err(errno) => reply.u32(status(errno)).post_op(attr.as_ref())
+                };
+            }
+            6 => {
+                // READ
+                let (inode, offset, count) = (args.handle()?, args.u64()?, args.u32()?.min(MAX_READ));
+                self.read(inode, offset, count, reply);
+            }
+            16 | 17 => {
+                // READDIR and READDIRPLUS
+                let (inode, cookie) = (args.handle()?, args.u64()?);
+                args.bytes(8)?; // cookie verifier, the tree of a mount only changes through the session
+                let count = args.u32()?;
+                let count = if procedure == 17 { args.u32()? } else { count };
+                self.read_directory(inode, cookie, count, procedure == 17, reply);
+            }
+            18 => {
+                // FSSTAT, nothing is free on a read-only export
+                let attr = self.attributes(args.handle()?);
+                reply.u32(NFS3_OK).post_op(attr.as_ref()).u64(0).u64(0).u64(0).u64(0).u64(0).u64(0).u32(0);
+            }
+            19 => {
+                // FSINFO
+                let attr = self.attributes(args.handle()?);
+                reply.u32(NFS3_OK).post_op(attr.as_ref())
+                    .u32(MAX_READ).u32(MAX_READ).u32(4096) // rtmax, rtpref, rtmult
+                    .u32(0).u32(0).u32(4096) // wtmax, wtpref, wtmult
+                    .u32(64 * 1024) // dtpref
+                    .u64(u64::MAX) // maxfilesize
+                    .u32(1).u32(0) // time_delta
+                    .u32(0x08); // FSF3_HOMOGENEOUS
+            }
+            20 => {
+                // PATHCONF
+                let attr = self.attributes(args.handle()?);
+                reply.u32(NFS3_OK).post_op(attr.as_ref()).u32(1).u32(255).bool(true).bool(true).bool(false).bool(true);
+            }
+            // everything that changes something, with as many empty wcc_data or post_op_attr as the result has
+            2 | 7..=13 | 21 => {
+                reply.u32(NFS3ERR_ROFS).bool(false).bool(false);
+            }
+            14 => {
+                reply.u32(NFS3ERR_ROFS).bool(false).bool(false).bool(false).bool(false);
+            }
+            15 => {
+                reply.u32(NFS3ERR_ROFS).bool(false).bool(false).bool(false);
+            }
+            _ => return Some(false)
+        }
+        Some(true)
+    }
+
+    fn read(&mut self, inode : u64, offset : u64, count : u32, reply : &mut Reply) {
+        let handle = match self.handles.get_mut(&inode) {
+            Some((handle, last_read)) => {
+                *last_read = Instant::now();
+                Ok(*handle)
+            }
+            None => self.fs.open_handle(NFS_PID, inode).inspect(|handle| {
+                self.handles.insert(inode, (*handle, Instant::now()));
+            })
+        };
+        let attr = self.attributes(inode);
+        let data = handle.and_then(|handle| self.fs.read_file(NFS_PID, inode, handle, offset as i64, count));
+        match data {
+            Ok(data) => {
+                let eof = attr.as_ref().is_none_or(|attr| offset + data.len() as u64 >= attr.size);
+                reply.u32(NFS3_OK).post_op(attr.as_ref()).u32(data.len() as u32).bool(eof).opaque(&data);
+            }
+            Err(errno) => {
+                reply.u32(status(errno)).post_op(attr.as_ref());
+            }
+        }
+    }
+
+    fn read_directory(&mut self, inode : u64, cookie : u64, count : u32, plus : bool, reply : &mut Reply) {
+        let dir_attr = self.attributes(inode);
+        if dir_attr.as_ref().is_some_and(|attr| attr.kind != EntryKind::Directory) {
+            reply.u32(NFS3ERR_NOTDIR).post_op(dir_attr.as_ref());
+            return;
+        }
+        let entries = match self.fs.directory_entries(NFS_PID, inode, cookie as usize) {
+            Ok(entries) => entries,
+            Err(errno) => {
+                reply.u32(status(errno)).post_op(dir_attr.as_ref());
+                return;
+            }
+        };
+        reply.u32(NFS3_OK).post_op(dir_attr.as_ref()).u64(0); // cookie verifier
+        // the reply has to fit into `count` bytes, the header and the end of the list take about 128
+        let limit = reply.0.len() + (count as usize).saturating_sub(128);
+        let mut eof = true;
+        for (index, (entry_inode, _, name)) in entries.iter().enumerate() {
+            // the client makes up "." and ".." itself
+            if name.as_bytes() == b"." || name.as_bytes() == b".." {
+                continue;
+            }
+            let before = reply.0.len();
+            reply.bool(true).u64(*entry_inode).opaque(name.as_bytes()).u64(cookie + index as u64 + 1);
+            if plus {
+                let attr = self.attributes(*entry_inode);
+                reply.post_op(attr.as_ref()).bool(true).handle(*entry_inode);
+            }
+            if reply.0.len() > limit {
+                reply.0.truncate(before);
+                eof = false;
+                break;
+            }
+        }
+        reply.bool(false).bool(eof);
+    }
+}
+
+/// nfsstat3 of an errno of the filesystem, NFS3ERR_IO for those NFSv3 has no error for.
+fn status(errno : i32) -> u32 {
+    match errno {
+        libc::EPERM => NFS3ERR_PERM,
+        libc::ENOENT => NFS3ERR_NOENT,
+        libc::ENXIO => NFS3ERR_NXIO,
+        libc::EACCES => NFS3ERR_ACCES,
+        libc::EEXIST => NFS3ERR_EXIST,
+        libc::EXDEV => NFS3ERR_XDEV,
+        libc::ENODEV => NFS3ERR_NODEV,
+        libc::ENOTDIR => NFS3ERR_NOTDIR,
+        libc::EISDIR => NFS3ERR_ISDIR,
+        libc::EINVAL => NFS3ERR_INVAL,
+        libc::EFBIG => NFS3ERR_FBIG,
+        libc::ENOSPC => NFS3ERR_NOSPC,
+        libc::EROFS => NFS3ERR_ROFS,
+        libc::EMLINK => NFS3ERR_MLINK,
+        libc::ENAMETOOLONG => NFS3ERR_NAMETOOLONG,
+        libc::ENOTEMPTY => NFS3ERR_NOTEMPTY,
+        libc::EDQUOT => NFS3ERR_DQUOT,
+        libc::ESTALE => NFS3ERR_STALE,
+        libc::ENOSYS | libc::EOPNOTSUPP => NFS3ERR_NOTSUPP,
+        libc::EAGAIN => NFS3ERR_JUKEBOX, // the client retries later
+        _ => NFS3ERR_IO
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_peer_is_the_owner_of_the_client_socket() {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let _client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (stream, _) = listener.accept().unwrap();
+        // SAFETY: geteuid can't fail
+        assert_eq!(peer_uid(&stream), Some(unsafe { libc::geteuid() }));
+    }
+
+    #[test]
+    fn errors_map_to_their_nfs_status() {
+        assert_eq!(status(libc::ENOENT), NFS3ERR_NOENT);
+        assert_eq!(status(libc::ENAMETOOLONG), NFS3ERR_NAMETOOLONG);
+        assert_eq!(status(libc::EOPNOTSUPP), NFS3ERR_NOTSUPP);
+        assert_eq!(status(libc::EBADF), NFS3ERR_IO);
+        assert_eq!(status(-1), NFS3ERR_IO);
+    }
+}
@@ -0,0 +1,153 @@
+//! What differs between the systems the filesystem runs on: Linux and FreeBSD with its fusefs driver.
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// Whether the kernel can serve reads of opened files without this process (`FUSE_PASSTHROUGH`), fusefs can't.
+pub(crate) const PASSTHROUGH : bool = cfg!(target_os = "linux");
+/// Whether mounts can be unmounted when the process exits, that needs fusermount, which FreeBSD doesn't have.
+/// Without it only the unmount guard of the session cleans up.
+pub const AUTO_UNMOUNT : bool = cfg!(target_os = "linux");
+
+/// Name and parent pid of a process, `None` if it is gone already.
+#[cfg(target_os = "linux")]
+pub(crate) fn process_info(pid : u32) -> (Option<String>, Option<u32>) {
+    let name = std::fs::read_to_string(format!("/proc/{pid}/comm")).ok().map(|name| name.trim_end().to_string());
+    // the name in the stat line is in parentheses and may contain spaces, the parent pid is the second field after it
+    let parent = std::fs::read_to_string(format!("/proc/{pid}/stat")).ok()
+        .and_then(|stat| stat.rsplit_once(')')?.1.split_whitespace().nth(1)?.parse().ok());
+    (name, parent)
+}
+
+/// Name and parent pid of a process, `None` if it is gone already. FreeBSD rarely has procfs mounted, so they come from sysctl.
+#[cfg(target_os = "freebsd")]
+pub(crate) fn process_info(pid : u32) -> (Option<String>, Option<u32>) {
+    let mib = [libc::CTL_KERN, libc::KERN_PROC, libc::KERN_PROC_PID, pid as libc::c_int];
+    // SAFETY: kinfo_proc is plain data, all zeroes is a valid value
+    let mut info : libc::kinfo_proc = unsafe { std::mem::zeroed() };
+    let mut size = std::mem::size_of::<libc::kinfo_proc>();
+    // SAFETY: the kernel writes at most `size` bytes into `info`
+    let result = unsafe {
+        libc::sysctl(mib.as_ptr(), mib.len() as libc::c_uint, &mut info as *mut libc::kinfo_proc as *mut libc::c_void, &mut size, std::ptr::null(), 0)
+    };
+    if result != 0 || size == 0 {
+        return (None, None);
+    }
+    // SAFETY: ki_comm is null terminated by the kernel
+    let name = unsafe { std::ffi::CStr::from_ptr(info.ki_comm.as_ptr()) }.to_string_lossy().into_owned();
+    (Some(name), Some(info.ki_ppid as u32))
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "freebsd")))]
+pub(crate) fn process_info(_pid : u32) -> (Option<String>, Option<u32>) {
+    (None, None)
+}
+
+/// When a process started, in clock ticks since boot, `None` if it is gone already. A pid together with its start
+/// time names one process, pids are reused.
+#[cfg(target_os = "linux")]
+pub(crate) fn process_start(pid : u32) -> Option<u64> {
+    // the start time is field 22, the 20th after the name
+    std::fs::read_to_string(format!("/proc/{pid}/stat")).ok()
+        .and_then(|stat| stat.rsplit_once(')')?.1.split_whitespace().nth(19)?.parse().ok())
+}
+
+/// When a process started, in microseconds since the epoch, `None` if it is gone already.
+#[cfg(target_os = "freebsd")]
+pub(crate) fn process_start(pid : u32) -> Option<u64> {
+    let mib = [libc::CTL_KERN, libc::KERN_PROC, libc::KERN_PROC_PID, pid as libc::c_int];
+    // SAFETY: kinfo_proc is plain data, all zeroes is a valid value
+    let mut info : libc::kinfo_proc = unsafe { std::mem::zeroed() };
+    let mut size = std::mem::size_of::<libc::kinfo_proc>();
+    // SAFETY: the kernel writes at most `size` bytes into `info`
+    let result = unsafe {
+        libc::sysctl(mib.as_ptr(), mib.len() as libc::c_uint, &mut info as *mut libc::kinfo_proc as *mut libc::c_void, &mut size, std::ptr::null(), 0)
+    };
+    if result != 0 || size == 0 {
+        return None;
+    }
+    Some(info.ki_start.tv_sec as u64 * 1_000_000 + info.ki_start.tv_usec as u64)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "freebsd")))]
+pub(crate) fn process_start(_pid : u32) -> Option<u64> {
+    None
+}
+
+/// Whether something is mounted at `mountpoint`, which has to be canonical. If that can't be told it counts as mounted,
+/// unmounting something that isn't mounted just fails.
+#[cfg(not(target_os = "freebsd"))]
+pub fn is_mounted(mountpoint : &Path) -> bool {
+    let Ok(mountinfo) = std::fs::read_to_string("/proc/self/mountinfo") else {
+        return true;
+    };
+    let mountpoint = mountpoint.to_string_lossy();
+    // the fifth field is the mountpoint, with whitespace and backslashes escaped as octal
+    mountinfo.lines()
+        .filter_map(|line| line.split(' ').nth(4))
+        .any(|point| point.replace("\\040", " ").replace("\\011", "\t").replace("\\012", "\n").replace("\\134", "\\") == mountpoint)
+}
+
+/// Whether something is mounted at `mountpoint`, which has to be canonical.
+#[cfg(target_os = "freebsd")]
+pub fn is_mounted(mountpoint : &Path) -> bool {
+    use std::os::unix::ffi::OsStrExt;
+    let Ok(path) = std::ffi::CString::new(mountpoint.as_os_str().as_bytes()) else {
+        return false;
+    };
+    // SAFETY: statfs is plain data, all zeroes is a valid value
+    let mut stat : libc::statfs = unsafe { std::mem::zeroed() };
+    // SAFETY: the path is null terminated and `stat` is written by the kernel
+    if unsafe { libc::statfs(path.as_ptr(), &mut stat) } != 0 {
+        // a mount whose filesystem process is gone fails with ENOTCONN, it still has to be unmounted
+        return std::io::Error::last_os_error().raw_os_error() == Some(libc::ENOTCONN);
+    }
+    // SAFETY: f_mntonname is null terminated by the kernel
+    let mounted_on = unsafe { std::ffi::CStr::from_ptr(stat.f_mntonname.as_ptr()) };
+    mounted_on.to_bytes() == mountpoint.as_os_str().as_bytes()
+}
+
+/// Whether `mountpoint` is a mount whose filesystem process is gone, e.g. after a crash. Accessing it fails with
+/// "Transport endpoint is not connected" until it is unmounted.
+pub fn is_stale(mountpoint : &Path) -> bool {
+    matches!(std::fs::metadata(mountpoint), Err(err) if err.raw_os_error() == Some(libc::ENOTCONN))
+}
+
+/// Unmounts like a user would, fails if the mountpoint is busy.
+pub(crate) fn unmount(mountpoint : &Path) -> std::io::Result<()> {
+    let commands : &[(&str, &str)] = match cfg!(target_os = "freebsd") {
+        true => &[("umount", "")],
+        false => &[("fusermount3", "-u"), ("fusermount", "-u"), ("umount", "")]
+    };
+    let mut last_error = std::io::Error::from(std::io::ErrorKind::NotFound);
+    for (program, flag) in commands {
+        let mut command = Command::new(program);
+        if !flag.is_empty() {
+            command.arg(flag);
+        }
+        match command.arg(mountpoint).stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::null()).status() {
+            Ok(status) if status.success() => return Ok(()),
+            Ok(status) => last_error = std::io::Error::other(format!("{program} failed with {status}")),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+            Err(err) => last_error = err
+        }
+    }
+    Err(last_error)
+}
+
+/// Unmounts even if the mountpoint is busy or its filesystem process is gone.
+pub fn force_unmount(mountpoint : &Path) {
+    // lazily with fusermount on Linux, like fuser prefer fusermount3 and fall back to fusermount on older systems,
+    // an NFS mount isn't FUSE and needs umount. FreeBSD unmounts fusefs with umount, which can only force
+    let commands : &[(&str, &str)] = match cfg!(target_os = "freebsd") {
+        true => &[("umount", "-f")],
+        false => &[("fusermount3", "-uz"), ("fusermount", "-uz"), ("umount", "-l")]
+    };
+    for (program, flag) in commands {
+        let status = Command::new(program).arg(flag).arg(mountpoint)
+            .stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::null())
+            .status();
+        if status.is_ok_and(|status| status.success()) {
+            return;
+        }
+    }
+}
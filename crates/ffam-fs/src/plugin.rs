@@ -0,0 +1,119 @@
+use crate::fs::{Event, EventType};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    Keep,
+    Veto // the event is neither recorded nor delivered, derived events still are
+}
+
+/// Processes the events of a mount before they are delivered, e.g. an analyzer for the archives of a game.
+///
+/// Plugins run on the FUSE thread in the order they were added, a slow plugin slows down the reads.
+pub trait Plugin: Send {
+    fn name(&self) -> &str;
+
+    /// Messages pushed to `derived` are delivered as `EventType::Derived` right after the event.
+    fn on_event(&mut self, event : &Event, derived : &mut Vec<String>) -> Verdict;
+
+    /// Called once when the filesystem goes away, to write custom outputs.
+    fn finish(&mut self) {}
+}
+
+/// The plugins of one mount.
+#[derive(Default)]
+pub struct Plugins {
+    plugins : Vec<Box<dyn Plugin>>
+}
+
+impl Plugins {
+    pub fn add(&mut self, plugin : Box<dyn Plugin>) {
+        self.plugins.push(plugin);
+    }
+
+    /// Runs the event through all plugins, returns what should be delivered.
+    pub fn process(&mut self, event : Event) -> Vec<Event> {
+        if self.plugins.is_empty() {
+            return vec![event];
+        }
+        let mut keep = true;
+        let mut derived = Vec::new();
+        for plugin in &mut self.plugins {
+            let mut messages = Vec::new();
+            // a vetoed event is not shown to the plugins after the one vetoing it
+            if keep && plugin.on_event(&event, &mut messages) == Verdict::Veto {
+                keep = false;
+            }
+            derived.extend(messages.into_iter().map(|message| Event {
+                time: event.time,
+                process: event.process.clone(),
+                event: EventType::Derived {
+                    plugin: plugin.name().to_string(),
+                    message
+                }
+            }));
+        }
+        let mut events = Vec::with_capacity(derived.len() + 1);
+        if keep {
+            events.push(event);
+        }
+        events.extend(derived);
+        events
+    }
+}
+
+impl Drop for Plugins {
+    fn drop(&mut self) {
+        for plugin in &mut self.plugins {
+            plugin.finish();
+        }
+    }
+}
+
+impl std::fmt::Debug for Plugins {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.plugins.iter().map(|plugin| plugin.name())).finish()
+    }
+}
+
+/// Name of the function a plugin library has to export:
+/// `#[unsafe(no_mangle)] pub fn fuse_file_access_monitor_plugin() -> Box<dyn Plugin>`.
+#[cfg(feature = "plugins")]
+pub const ENTRY_POINT : &str = "fuse_file_access_monitor_plugin";
+
+/// A plugin from a dynamic library, the library stays loaded as long as the plugin exists.
+#[cfg(feature = "plugins")]
+struct LibraryPlugin {
+    plugin : Box<dyn Plugin>, // dropped before the library that contains its code
+    _library : libloading::Library
+}
+
+#[cfg(feature = "plugins")]
+impl Plugin for LibraryPlugin {
+    fn name(&self) -> &str {
+        self.plugin.name()
+    }
+
+    fn on_event(&mut self, event : &Event, derived : &mut Vec<String>) -> Verdict {
+        self.plugin.on_event(event, derived)
+    }
+
+    fn finish(&mut self) {
+        self.plugin.finish()
+    }
+}
+
+/// Loads a plugin from a dynamic library exporting `ENTRY_POINT`.
+///
+/// Rust has no stable ABI, so the library must be built with the same compiler and version of this crate.
+#[cfg(feature = "plugins")]
+pub fn load(path : &std::path::Path) -> crate::Result<Box<dyn Plugin>> {
+    let error = |err : libloading::Error| crate::Error::Plugin { path: path.to_path_buf(), message: err.to_string() };
+    // SAFETY: running the library's initializers and calling the entry point is only sound for plugins built
+    // against this crate, which is what the documentation requires
+    unsafe {
+        let library = libloading::Library::new(path).map_err(error)?;
+        let entry : libloading::Symbol<fn() -> Box<dyn Plugin>> = library.get(ENTRY_POINT.as_bytes()).map_err(error)?;
+        let plugin = entry();
+        Ok(Box::new(LibraryPlugin { plugin, _library: library }))
+    }
+}
@@ -0,0 +1,100 @@
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::sync::{Arc, Mutex};
+
+use crate::source::Source;
+use crate::trace::log;
+
+/// Reads in a row that continue where the previous one ended before a handle counts as sequential.
+const SEQUENTIAL_READS : u32 = 2;
+/// Read ahead at least this much, or 8 times the size of the reads, whatever is larger.
+const MIN_PREFETCH : usize = 512 * 1024;
+
+/// Data read ahead for one handle.
+#[derive(Debug, Default)]
+struct Ahead {
+    offset : u64,
+    data : Vec<u8>,
+    eof : bool, // the source had no more data after `data`
+    pending : bool // a prefetch is running, the old data stays usable until it is done
+}
+
+#[derive(Debug)]
+struct Stream {
+    path : Arc<OsString>,
+    next_offset : u64,
+    sequential : u32,
+    ahead : Arc<Mutex<Ahead>>
+}
+
+/// Reads ahead of handles that read a file sequentially, so streaming loads of the game aren't slowed down
+/// by one source access per read. The reads are still reported as they come from the kernel.
+#[derive(Debug, Default)]
+pub(crate) struct Prefetcher {
+    streams : HashMap<u64, Stream> // by file handle
+}
+
+impl Prefetcher {
+    /// Serves a read from data prefetched for the handle, `None` if it wasn't prefetched.
+    pub fn read(&self, fh : u64, path : &Arc<OsString>, offset : u64, buffer : &mut [u8]) -> Option<usize> {
+        let stream = self.streams.get(&fh).filter(|stream| stream.path == *path)?;
+        let ahead = stream.ahead.lock().unwrap();
+        let end = ahead.offset + ahead.data.len() as u64;
+        // a read past the prefetched data is only complete if the file ends there
+        if offset < ahead.offset || offset > end || (offset + buffer.len() as u64 > end && !ahead.eof) {
+            return None;
+        }
+        let start = (offset - ahead.offset) as usize;
+        let read = buffer.len().min(ahead.data.len() - start);
+        buffer[..read].copy_from_slice(&ahead.data[start..start + read]);
+        Some(read)
+    }
+
+    /// Records a read of `size` bytes that returned `read` bytes and starts prefetching once the handle reads sequentially.
+    pub fn record(&mut self, source : &Arc<dyn Source>, fh : u64, path : &Arc<OsString>, offset : u64, read : usize, size : usize) {
+        let stream = self.streams.entry(fh).or_insert_with(|| Stream {
+            path: path.clone(),
+            next_offset: u64::MAX,
+            sequential: 0,
+            ahead: Arc::default()
+        });
+        if stream.path != *path {
+            // the entry of the handle got replaced, nothing prefetched belongs to it anymore
+            stream.path = path.clone();
+            stream.ahead = Arc::default();
+        }
+        stream.sequential = if offset == stream.next_offset { stream.sequential + 1 } else { 0 };
+        stream.next_offset = offset + read as u64;
+        if stream.sequential < SEQUENTIAL_READS || read < size {
+            return;
+        }
+        let length = (size * 8).max(MIN_PREFETCH);
+        let mut ahead = stream.ahead.lock().unwrap();
+        let end = ahead.offset + ahead.data.len() as u64;
+        // refilled once half of the prefetched data is used up
+        if ahead.pending || ahead.eof && stream.next_offset >= ahead.offset || stream.next_offset + (length / 2) as u64 <= end {
+            return;
+        }
+        ahead.pending = true;
+        drop(ahead);
+        let (source, path, offset, slot) = (source.clone(), path.clone(), stream.next_offset, stream.ahead.clone());
+        rayon::spawn(move || {
+            let mut data = vec![0; length];
+            let result = source.read(&path, &mut data, offset);
+            let mut ahead = slot.lock().unwrap();
+            ahead.pending = false;
+            match result {
+                Ok(read) => {
+                    data.truncate(read);
+                    *ahead = Ahead { offset, data, eof: read < length, pending: false };
+                }
+                Err(err) => log!(debug, "Prefetching {} at {offset} failed: {err}", path.display())
+            }
+        });
+    }
+
+    /// Forgets a handle when it is released.
+    pub fn close(&mut self, fh : u64) {
+        self.streams.remove(&fh);
+    }
+}
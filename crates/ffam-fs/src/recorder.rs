@@ -0,0 +1,58 @@
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use crate::fs::Event;
+use crate::schema::Header;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RecordFormat {
+    Text, // one line per event, like the log in the UI
+    JsonLines // one json object per line, after a `Header` line, can be read back with serde_json
+}
+
+/// Writes every event of a mount to a file, in addition to the sink the events are delivered to.
+#[derive(Debug)]
+pub struct Recorder {
+    path : PathBuf,
+    format : RecordFormat,
+    writer : BufWriter<File>
+}
+
+impl Recorder {
+    pub fn create(path : &Path, format : RecordFormat) -> crate::Result<Self> {
+        let file = File::create(path).map_err(|err| crate::Error::io(path, err))?;
+        let mut writer = BufWriter::new(file);
+        if format == RecordFormat::JsonLines {
+            // readers check the version before reading the events
+            serde_json::to_writer(&mut writer, &Header::current()).map_err(std::io::Error::from)
+                .and_then(|()| writeln!(writer))
+                .map_err(|err| crate::Error::io(path, err))?;
+        }
+        Ok(Self {
+            path: path.to_path_buf(),
+            format,
+            writer
+        })
+    }
+
+    pub fn write(&mut self, event : &Event) -> std::io::Result<()> {
+        match self.format {
+            RecordFormat::Text => writeln!(self.writer, "{event}"),
+            RecordFormat::JsonLines => {
+                serde_json::to_writer(&mut self.writer, event)?;
+                writeln!(self.writer)
+            }
+        }
+    }
+
+    /// Writes the buffered events to the file, the recording goes on.
+    pub fn flush(&mut self) -> std::io::Result<()> {
+        self.writer.flush()
+    }
+
+    pub fn finish(mut self) -> crate::Result<()> {
+        self.writer.flush().map_err(|err| crate::Error::io(&self.path, err))
+    }
+}
@@ -0,0 +1,175 @@
+//! The mounts of all running monitors of this user, so a second monitor doesn't mount over the first and sessions can
+//! be listed and unmounted from another process.
+//!
+//! Every mount has a json file in the runtime directory, locked with `flock` for as long as it is mounted. The kernel
+//! drops the lock when the process dies, so the files of crashed monitors don't count. They are kept while their mount
+//! is stale, see `stale`, and removed by the next listing after that.
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::Write;
+use std::os::fd::AsRawFd;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+
+use crate::backend::Mounter;
+use crate::error::{Error, Result};
+use crate::platform;
+
+/// A mount of a running monitor.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ActiveMount {
+    pub pid : u32,
+    pub source : String,
+    pub mountpoint : PathBuf, // canonical
+    pub backend : String,
+    pub mounted_at : DateTime<Utc>
+}
+
+/// Keeps a mount registered until it is dropped.
+#[derive(Debug)]
+pub struct Registration {
+    path : PathBuf,
+    _file : File // holds the lock
+}
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        // removed while still locked, so nobody can lock the file and find it gone afterwards
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// `$XDG_RUNTIME_DIR/fuse_file_access_monitor`, or a directory of the user in the temporary directory.
+pub fn directory() -> PathBuf {
+    match std::env::var_os("XDG_RUNTIME_DIR") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir).join("fuse_file_access_monitor"),
+        // SAFETY: getuid can't fail
+        _ => std::env::temp_dir().join(format!("fuse_file_access_monitor-{}", unsafe { libc::getuid() }))
+    }
+}
+
+/// Registers a mount, fails with `Error::AlreadyMounted` if a running monitor has one at the same mountpoint.
+pub fn register(mount : &ActiveMount) -> Result<Registration> {
+    let dir = directory();
+    create_directory(&dir)?;
+    let path = dir.join(file_name(&mount.mountpoint));
+    loop {
+        let file = File::options().read(true).write(true).create(true).truncate(false).open(&path).map_err(|err| Error::io(&path, err))?;
+        if !try_lock(&file) {
+            let pid = read(&path).map(|mount| mount.pid).unwrap_or_default();
+            return Err(Error::AlreadyMounted { mountpoint: mount.mountpoint.display().to_string(), pid });
+        }
+        // the previous owner may have removed the file between opening and locking it, then it is not the registered one
+        if !same_file(&file, &path) {
+            continue;
+        }
+        let mut file = file;
+        file.set_len(0).and_then(|()| file.write_all(&serde_json::to_vec(mount)?)).map_err(|err| Error::io(&path, err))?;
+        return Ok(Registration { path, _file: file });
+    }
+}
+
+/// The mounts of all running monitors, also of this one.
+pub fn active() -> Vec<ActiveMount> {
+    let Ok(entries) = std::fs::read_dir(directory()) else {
+        return Vec::new();
+    };
+    let mut mounts = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(file) = File::open(&path) else {
+            continue;
+        };
+        if try_lock(&file) {
+            // nobody holds it, the monitor is gone, the file is kept while its mount is stale so `stale` finds it
+            let stale = read(&path).is_some_and(|mount| platform::is_stale(&mount.mountpoint));
+            if !stale && same_file(&file, &path) {
+                let _ = std::fs::remove_file(&path);
+            }
+            continue;
+        }
+        if let Some(mount) = read(&path) {
+            mounts.push(mount);
+        }
+    }
+    mounts.sort_by_key(|mount| mount.mounted_at);
+    mounts
+}
+
+/// Mounts of monitors that crashed and left them behind, they have to be unmounted before anything can be mounted there.
+pub fn stale() -> Vec<ActiveMount> {
+    let Ok(entries) = std::fs::read_dir(directory()) else {
+        return Vec::new();
+    };
+    entries.flatten()
+        .filter(|entry| File::open(entry.path()).is_ok_and(|file| try_lock(&file)))
+        .filter_map(|entry| read(&entry.path()))
+        .filter(|mount| platform::is_stale(&mount.mountpoint))
+        .collect()
+}
+
+/// Lazily unmounts a stale mount, with `mounter` if given, and forgets it, returns whether it is gone.
+pub fn recover(mountpoint : &Path, mounter : Option<&dyn Mounter>) -> bool {
+    crate::mount::force_unmount(mountpoint, mounter);
+    let recovered = !platform::is_stale(mountpoint);
+    if recovered {
+        let path = directory().join(file_name(mountpoint));
+        // unless a monitor mounted there again in the meantime
+        if File::open(&path).is_ok_and(|file| try_lock(&file)) {
+            let _ = std::fs::remove_file(&path);
+        }
+    }
+    recovered
+}
+
+/// Unmounts the mount of a running monitor, which then ends its session like after `fusermount -u`.
+/// Fails with `Error::NotMounted` if no monitor has this mountpoint. Unmounts with `mounter` if given.
+pub fn request_unmount(mountpoint : &Path, mounter : Option<&dyn Mounter>) -> Result<()> {
+    let mountpoint = std::fs::canonicalize(mountpoint).unwrap_or_else(|_| mountpoint.to_path_buf());
+    if !active().iter().any(|mount| mount.mountpoint == mountpoint) {
+        return Err(Error::NotMounted(mountpoint.display().to_string()));
+    }
+    match mounter {
+        Some(mounter) => mounter.unmount(&mountpoint, false),
+        None => platform::unmount(&mountpoint)
+    }.map_err(|err| Error::io(&mountpoint, err))
+}
+
+/// Creates the directory unless it exists. The name of the one in the temporary directory can be guessed, so a
+/// directory that isn't a private one of this user is refused rather than trusted.
+fn create_directory(dir : &Path) -> Result<()> {
+    use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+    std::fs::DirBuilder::new().recursive(true).mode(0o700).create(dir).map_err(|err| Error::io(dir, err))?;
+    let metadata = std::fs::symlink_metadata(dir).map_err(|err| Error::io(dir, err))?;
+    // SAFETY: geteuid can't fail
+    let private = metadata.is_dir() && metadata.uid() == unsafe { libc::geteuid() } && metadata.permissions().mode() & 0o077 == 0;
+    match private {
+        true => Ok(()),
+        false => Err(Error::io(dir, std::io::Error::new(std::io::ErrorKind::PermissionDenied, "not a private directory of this user")))
+    }
+}
+
+/// A name per mountpoint, FNV-1a of the path, which is stable unlike the std hasher.
+fn file_name(mountpoint : &Path) -> String {
+    use std::os::unix::ffi::OsStrExt;
+    let hash = mountpoint.as_os_str().as_bytes().iter()
+        .fold(0xcbf29ce484222325u64, |hash, byte| (hash ^ *byte as u64).wrapping_mul(0x100000001b3));
+    format!("{hash:016x}.json")
+}
+
+fn try_lock(file : &File) -> bool {
+    // SAFETY: the descriptor is open for as long as `file` lives
+    unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) == 0 }
+}
+
+fn same_file(file : &File, path : &Path) -> bool {
+    match (file.metadata(), std::fs::metadata(path)) {
+        (Ok(opened), Ok(current)) => opened.ino() == current.ino() && opened.dev() == current.dev(),
+        _ => false
+    }
+}
+
+fn read(path : &Path) -> Option<ActiveMount> {
+    serde_json::from_slice(&std::fs::read(path).ok()?).ok()
+}
@@ -0,0 +1,198 @@
+use serde::{Deserialize, Deserializer, Serialize};
+use std::time::Duration;
+
+/// Source of the derived events of `RuleAction::Tag`.
+pub const TAG_SOURCE : &str = "rule";
+/// Source of the derived events of `RuleAction::Notify`, frontends show them as notifications.
+pub const NOTIFY_SOURCE : &str = "rule-notify";
+
+/// Accesses the filesystem can apply rules to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Operation {
+    Lookup,
+    Open,
+    Read,
+    ReadDir,
+    Write // writing, truncating, creating, renaming or removing an entry
+}
+
+/// Conditions of a rule, all set conditions have to match.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RuleMatch {
+    pub glob : String, // path relative to the mounted root ignoring case, see `glob_matches`, empty matches every path
+    pub operations : Vec<Operation>, // empty matches every operation
+    pub pid : Option<u32>,
+    pub min_size : Option<u64>, // size of a read, rules with a size never match other operations
+    pub max_size : Option<u64>
+}
+
+/// What happens when a rule matches.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleAction {
+    Tag(String), // a derived event with the text after the access
+    Notify(String), // like a tag, but meant to be shown as a notification
+    Delay(Duration), // before the access is served, e.g. to simulate a slow disk
+    Deny(#[serde(deserialize_with = "positive_errno")] i32) // fails the access with this errno instead of serving it
+}
+
+/// An errno of `RuleAction::Deny`, replying with 0 would report success and negative values aren't errors at all.
+fn positive_errno<'de, D : Deserializer<'de>>(deserializer : D) -> Result<i32, D::Error> {
+    match i32::deserialize(deserializer)? {
+        errno if errno > 0 => Ok(errno),
+        errno => Err(serde::de::Error::custom(format!("deny needs an errno greater than 0, got {errno}")))
+    }
+}
+
+/// Maps accesses to actions, evaluated by the filesystem for every access before it is served.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rule {
+    pub name : String,
+    #[serde(rename = "match")]
+    pub matches : RuleMatch,
+    pub actions : Vec<RuleAction>
+}
+
+impl Rule {
+    pub fn applies(&self, operation : Operation, pid : u32, path : &str, size : Option<u64>) -> bool {
+        let condition = &self.matches;
+        let size_matches = match (size, condition.min_size, condition.max_size) {
+            (_, None, None) => true,
+            (Some(size), min, max) => min.is_none_or(|min| size >= min) && max.is_none_or(|max| size <= max),
+            (None, _, _) => false
+        };
+        (condition.operations.is_empty() || condition.operations.contains(&operation))
+            && condition.pid.is_none_or(|rule_pid| rule_pid == pid)
+            && size_matches
+            && (condition.glob.is_empty() || glob_matches(&condition.glob.to_lowercase(), &path.to_lowercase()))
+    }
+}
+
+/// What the rules decided for one access.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Verdict {
+    pub derived : Vec<(&'static str, String)>, // source and message of the derived events to deliver
+    pub delay : Duration, // sum of the delays of all matching rules
+    pub deny : Option<i32> // errno of the first matching rule that denies
+}
+
+/// Evaluates all rules for an access.
+pub fn evaluate(rules : &[Rule], operation : Operation, pid : u32, path : &str, size : Option<u64>) -> Verdict {
+    let mut verdict = Verdict::default();
+    for rule in rules.iter().filter(|rule| rule.applies(operation, pid, path, size)) {
+        for action in &rule.actions {
+            match action {
+                RuleAction::Tag(text) => verdict.derived.push((TAG_SOURCE, format!("{}: {text}", rule.name))),
+                RuleAction::Notify(text) => verdict.derived.push((NOTIFY_SOURCE, format!("{}: {text}", rule.name))),
+                RuleAction::Delay(delay) => verdict.delay += *delay,
+                RuleAction::Deny(errno) => {
+                    verdict.deny.get_or_insert(*errno);
+                }
+            }
+        }
+    }
+    verdict
+}
+
+/// Matches a path against a glob: `*` matches within a path component, `**` across components and `?` one character.
+pub fn glob_matches(glob : &str, path : &str) -> bool {
+    let glob : Vec<char> = glob.chars().collect();
+    let path : Vec<char> = path.chars().collect();
+    matches_from(&glob, &path)
+}
+
+fn matches_from(glob : &[char], path : &[char]) -> bool {
+    match glob {
+        [] => path.is_empty(),
+        ['*', '*', rest @ ..] => {
+            // "**/" also matches no directory at all
+            let rest_without_slash = rest.strip_prefix(&['/']).unwrap_or(rest);
+            (0..=path.len()).any(|skip| matches_from(rest, &path[skip..])) || matches_from(rest_without_slash, path)
+        }
+        ['*', rest @ ..] => {
+            let component = path.iter().position(|c| *c == '/').unwrap_or(path.len());
+            (0..=component).any(|skip| matches_from(rest, &path[skip..]))
+        }
+        ['?', rest @ ..] => matches!(path, [c, ..] if *c != '/') && matches_from(rest, &path[1..]),
+        [c, rest @ ..] => path.first() == Some(c) && matches_from(rest, &path[1..])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(name : &str, matches : RuleMatch, actions : Vec<RuleAction>) -> Rule {
+        Rule { name: name.to_string(), matches, actions }
+    }
+
+    fn glob(glob : &str) -> RuleMatch {
+        RuleMatch { glob: glob.to_string(), ..Default::default() }
+    }
+
+    #[test]
+    fn globs() {
+        assert!(glob_matches("*.bin", "map.bin"));
+        assert!(!glob_matches("*.bin", "data/map.bin"));
+        assert!(glob_matches("data/*.bin", "data/map.bin"));
+        assert!(glob_matches("**/*.bin", "map.bin"));
+        assert!(glob_matches("**/*.bin", "data/maps/map.bin"));
+        assert!(glob_matches("data/**", "data/maps/map.bin"));
+        assert!(glob_matches("map?.bin", "map1.bin"));
+        assert!(!glob_matches("data?map.bin", "data/map.bin"));
+        assert!(!glob_matches("map.bin", "map.bin.bak"));
+    }
+
+    #[test]
+    fn conditions_all_have_to_match() {
+        let mut matches = glob("Data/*.BIN");
+        assert!(rule("", matches.clone(), Vec::new()).applies(Operation::Read, 1, "data/map.bin", Some(10)));
+        matches.operations = vec![Operation::Open];
+        assert!(!rule("", matches.clone(), Vec::new()).applies(Operation::Read, 1, "data/map.bin", Some(10)));
+        assert!(rule("", matches.clone(), Vec::new()).applies(Operation::Open, 1, "data/map.bin", None));
+        matches.pid = Some(2);
+        assert!(!rule("", matches, Vec::new()).applies(Operation::Open, 1, "data/map.bin", None));
+    }
+
+    #[test]
+    fn size_conditions_only_match_reads_in_range() {
+        let matches = RuleMatch { min_size: Some(4096), max_size: Some(65536), ..Default::default() };
+        let rule = rule("", matches, Vec::new());
+        assert!(rule.applies(Operation::Read, 1, "map.bin", Some(4096)));
+        assert!(!rule.applies(Operation::Read, 1, "map.bin", Some(4095)));
+        assert!(!rule.applies(Operation::Read, 1, "map.bin", Some(65537)));
+        assert!(!rule.applies(Operation::Open, 1, "map.bin", None));
+    }
+
+    #[test]
+    fn verdicts_combine_all_matching_rules() {
+        let rules = [
+            rule("slow", glob("**/*.bin"), vec![RuleAction::Delay(Duration::from_millis(10)), RuleAction::Tag("map".to_string())]),
+            rule("missing", glob("data/*"), vec![RuleAction::Deny(libc::ENOENT), RuleAction::Delay(Duration::from_millis(5))]),
+            rule("broken", glob("**"), vec![RuleAction::Deny(libc::EIO), RuleAction::Notify("read".to_string())]),
+            rule("other", glob("*.txt"), vec![RuleAction::Deny(libc::EACCES)])
+        ];
+        let verdict = evaluate(&rules, Operation::Read, 1, "data/map.bin", Some(10));
+        assert_eq!(verdict.delay, Duration::from_millis(15));
+        assert_eq!(verdict.deny, Some(libc::ENOENT));
+        assert_eq!(verdict.derived, [(TAG_SOURCE, "slow: map".to_string()), (NOTIFY_SOURCE, "broken: read".to_string())]);
+        assert_eq!(evaluate(&rules, Operation::Read, 1, "readme.md", None).deny, Some(libc::EIO));
+    }
+
+    #[test]
+    fn rules_round_trip() {
+        let rules = vec![rule("slow", glob("**/*.bin"), vec![RuleAction::Delay(Duration::from_millis(10)), RuleAction::Deny(libc::EIO)])];
+        let json = serde_json::to_string(&rules).unwrap();
+        assert!(json.contains(r#""match":{"glob":"**/*.bin""#));
+        assert_eq!(serde_json::from_str::<Vec<Rule>>(&json).unwrap(), rules);
+    }
+
+    #[test]
+    fn deny_needs_an_errno() {
+        assert_eq!(serde_json::from_str::<RuleAction>(r#"{"deny":5}"#).unwrap(), RuleAction::Deny(libc::EIO));
+        assert!(serde_json::from_str::<RuleAction>(r#"{"deny":0}"#).is_err());
+        assert!(serde_json::from_str::<RuleAction>(r#"{"deny":-2}"#).is_err());
+    }
+}
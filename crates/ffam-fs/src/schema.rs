@@ -0,0 +1,523 @@
+//! The events as they are serialized in recordings, saved sessions and exports.
+//!
+//! Available on its own with `default-features = false, features = ["schema"]`, which only pulls in serde and chrono,
+//! so tools reading the exports don't need fuser or iced. Changes that break deserializing existing files
+//! only happen together with a new major version of this crate and a new `SCHEMA_VERSION`.
+use chrono::Utc;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::borrow::Borrow;
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::ffi::{OsStr, OsString};
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Version of the serialized format, increased whenever existing files can't be read anymore.
+pub const SCHEMA_VERSION : u32 = 1;
+
+/// The version of the format a file was written with, the first line of JSON-lines recordings and the
+/// `schema_version` field of saved sessions. Files written before it was added have none and count as version 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Header {
+    #[serde(default = "first_version")]
+    pub schema_version : u32
+}
+
+fn first_version() -> u32 {
+    1
+}
+
+impl Header {
+    pub fn current() -> Self {
+        Self { schema_version: SCHEMA_VERSION }
+    }
+
+    /// Whether the events of the file can be read by this version of the crate.
+    pub fn is_supported(&self) -> bool {
+        (1..=SCHEMA_VERSION).contains(&self.schema_version)
+    }
+}
+
+/// Extension of saved sessions, a json object with the `Event`s in its `events` field.
+pub const SESSION_EXTENSION : &str = "json";
+
+/// A string of the interning table, looked up by `&str` so finding an existing one doesn't allocate.
+#[derive(PartialEq, Eq)]
+struct Interned(Arc<String>);
+
+impl Hash for Interned {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.as_str().hash(state)
+    }
+}
+
+impl Borrow<str> for Interned {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+/// A path of the interning table, like `Interned`.
+#[derive(PartialEq, Eq)]
+struct InternedPath(Arc<OsString>);
+
+impl Hash for InternedPath {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.as_os_str().hash(state)
+    }
+}
+
+impl Borrow<OsStr> for InternedPath {
+    fn borrow(&self) -> &OsStr {
+        &self.0
+    }
+}
+
+thread_local! {
+    // paths and process names repeat for almost every event, but there are only as many as files and processes
+    static INTERNED : RefCell<HashSet<Interned>> = RefCell::default();
+    static INTERNED_PATHS : RefCell<HashSet<InternedPath>> = RefCell::default();
+}
+
+/// Returns the shared copy of `text`, so events read from a recording or session share one string per path
+/// the way events of a mount share the path of their tree entry.
+pub fn intern(text : &str) -> Arc<String> {
+    INTERNED.with_borrow_mut(|interned| match interned.get(text) {
+        Some(existing) => existing.0.clone(),
+        None => {
+            let new = Arc::new(text.to_string());
+            interned.insert(Interned(new.clone()));
+            new
+        }
+    })
+}
+
+fn deserialize_interned<'de, D : Deserializer<'de>>(deserializer : D) -> Result<Arc<String>, D::Error> {
+    struct Visitor;
+
+    impl serde::de::Visitor<'_> for Visitor {
+        type Value = Arc<String>;
+
+        fn expecting(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.write_str("a string")
+        }
+
+        fn visit_str<E : serde::de::Error>(self, text : &str) -> Result<Arc<String>, E> {
+            Ok(intern(text))
+        }
+    }
+
+    deserializer.deserialize_str(Visitor)
+}
+
+/// Drops the strings and paths no event refers to anymore, e.g. those of a session that was closed. The tables live
+/// as long as the thread, so this is called whenever many events were dropped.
+pub fn forget_unused() {
+    INTERNED.with_borrow_mut(|interned| interned.retain(|text| Arc::strong_count(&text.0) > 1));
+    INTERNED_PATHS.with_borrow_mut(|interned| interned.retain(|path| Arc::strong_count(&path.0) > 1));
+}
+
+/// Returns the shared copy of `path` like `intern`.
+pub fn intern_path(path : &OsStr) -> Arc<OsString> {
+    INTERNED_PATHS.with_borrow_mut(|interned| match interned.get(path) {
+        Some(existing) => existing.0.clone(),
+        None => {
+            let new = Arc::new(path.to_os_string());
+            interned.insert(InternedPath(new.clone()));
+            new
+        }
+    })
+}
+
+/// A path of the source from its bytes. Other systems than Unix don't have paths that aren't unicode, there the
+/// invalid bytes are replaced.
+fn path_from_bytes(bytes : Vec<u8>) -> OsString {
+    #[cfg(unix)]
+    return std::os::unix::ffi::OsStringExt::from_vec(bytes);
+    #[cfg(not(unix))]
+    return OsString::from(String::from_utf8_lossy(&bytes).into_owned());
+}
+
+/// Paths are written as strings, only those that aren't UTF-8 as an array of their bytes, so files written before
+/// paths could be anything else stay readable.
+fn serialize_path<S : Serializer>(path : &OsStr, serializer : S) -> Result<S::Ok, S::Error> {
+    match path.to_str() {
+        Some(path) => serializer.serialize_str(path),
+        None => serializer.serialize_bytes(path.as_encoded_bytes())
+    }
+}
+
+fn deserialize_interned_path<'de, D : Deserializer<'de>>(deserializer : D) -> Result<Arc<OsString>, D::Error> {
+    struct Visitor;
+
+    impl<'de> serde::de::Visitor<'de> for Visitor {
+        type Value = Arc<OsString>;
+
+        fn expecting(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.write_str("a string or the bytes of a path")
+        }
+
+        fn visit_str<E : serde::de::Error>(self, text : &str) -> Result<Arc<OsString>, E> {
+            Ok(intern_path(OsStr::new(text)))
+        }
+
+        fn visit_bytes<E : serde::de::Error>(self, bytes : &[u8]) -> Result<Arc<OsString>, E> {
+            Ok(intern_path(&path_from_bytes(bytes.to_vec())))
+        }
+
+        fn visit_seq<A : serde::de::SeqAccess<'de>>(self, mut seq : A) -> Result<Arc<OsString>, A::Error> {
+            let mut bytes = Vec::new();
+            while let Some(byte) = seq.next_element()? {
+                bytes.push(byte);
+            }
+            Ok(intern_path(&path_from_bytes(bytes)))
+        }
+    }
+
+    deserializer.deserialize_any(Visitor)
+}
+
+fn deserialize_path<'de, D : Deserializer<'de>>(deserializer : D) -> Result<OsString, D::Error> {
+    deserialize_interned_path(deserializer).map(|path| path.as_os_str().to_os_string())
+}
+
+#[derive(Eq, PartialEq, Debug, Clone, Serialize, Deserialize)]
+pub struct ReadEvent {
+    #[serde(serialize_with = "serialize_path", deserialize_with = "deserialize_interned_path")]
+    pub file: Arc<OsString>,
+    pub offset: usize,
+    pub size: usize,
+    pub file_size: u64,
+    pub handle: u64,
+    pub latency: Duration, // time it took to serve the read from the source
+    #[serde(skip)] // payloads would make saved sessions huge
+    pub data: Option<Arc<Vec<u8>>> // payload that was returned to the reader, if capturing is enabled
+}
+
+impl std::fmt::Display for ReadEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Reading {} bytes (offset {}) from {}", self.size, self.offset, self.file.display())
+    }
+}
+
+/// A file opened or closed, the reads in between have the same handle. Every open gets its own handle.
+#[derive(Eq, PartialEq, Debug, Clone, Serialize, Deserialize)]
+pub struct HandleEvent {
+    #[serde(serialize_with = "serialize_path", deserialize_with = "deserialize_interned_path")]
+    pub file: Arc<OsString>,
+    pub handle: u64
+}
+
+/// Content written to a file of the source, only with `MountOptions::write_through`.
+#[derive(Eq, PartialEq, Debug, Clone, Serialize, Deserialize)]
+pub struct WriteEvent {
+    #[serde(serialize_with = "serialize_path", deserialize_with = "deserialize_interned_path")]
+    pub file: Arc<OsString>,
+    pub offset: u64,
+    pub size: usize,
+    pub handle: u64
+}
+
+impl std::fmt::Display for WriteEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Writing {} bytes (offset {}) to {}", self.size, self.offset, self.file.display())
+    }
+}
+
+/// A symlink resolved by the kernel for a program.
+#[derive(Eq, PartialEq, Debug, Clone, Serialize, Deserialize)]
+pub struct LinkEvent {
+    #[serde(serialize_with = "serialize_path", deserialize_with = "deserialize_interned_path")]
+    pub file: Arc<OsString>,
+    #[serde(serialize_with = "serialize_path", deserialize_with = "deserialize_path")]
+    pub target: OsString
+}
+
+impl std::fmt::Display for LinkEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Resolving {} to {}", self.file.display(), self.target.display())
+    }
+}
+
+#[derive(Eq, PartialEq, Debug, Clone, Copy, Serialize, Deserialize)]
+#[non_exhaustive]
+pub enum XattrOperation {
+    Get,
+    List,
+    Set
+}
+
+/// An access of the extended attributes of a file, getting and listing them is only reported with
+/// `MountOptions::metadata_events` like other metadata.
+#[derive(Eq, PartialEq, Debug, Clone, Serialize, Deserialize)]
+pub struct XattrEvent {
+    #[serde(serialize_with = "serialize_path", deserialize_with = "deserialize_interned_path")]
+    pub file: Arc<OsString>,
+    pub operation: XattrOperation,
+    pub name: Option<String>, // None when listing
+    pub size: Option<usize> // of the value or the list, None if the attribute doesn't exist
+}
+
+impl std::fmt::Display for XattrEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = self.name.as_deref().unwrap_or_default();
+        match self.operation {
+            XattrOperation::Get => write!(f, "Getting attribute {name} of {}", self.file.display())?,
+            XattrOperation::List => write!(f, "Listing the attributes of {}", self.file.display())?,
+            XattrOperation::Set => write!(f, "Setting attribute {name} of {}", self.file.display())?
+        }
+        match self.size {
+            Some(_) => Ok(()),
+            None => write!(f, " (not found)")
+        }
+    }
+}
+
+#[derive(Eq, PartialEq, Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
+pub enum ChangeOperation {
+    Create,
+    Truncate { size : u64 },
+    Remove,
+    Rename {
+        #[serde(serialize_with = "serialize_path", deserialize_with = "deserialize_path")]
+        to : OsString // the new path, `file` is the old one
+    },
+    CreateDirectory,
+    RemoveDirectory,
+    Symlink {
+        #[serde(serialize_with = "serialize_path", deserialize_with = "deserialize_path")]
+        target : OsString
+    },
+    Permissions { mode : u32 }, // only the permission bits
+    Owner { uid : Option<u32>, gid : Option<u32> }, // `None` wasn't changed
+    Times { accessed : Option<chrono::DateTime<Utc>>, modified : Option<chrono::DateTime<Utc>> }
+}
+
+/// A change of the tree or of the size or metadata of a file, made by the monitored program.
+#[derive(Eq, PartialEq, Debug, Clone, Serialize, Deserialize)]
+pub struct ChangeEvent {
+    #[serde(serialize_with = "serialize_path", deserialize_with = "deserialize_interned_path")]
+    pub file: Arc<OsString>,
+    pub operation: ChangeOperation
+}
+
+impl std::fmt::Display for ChangeEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.operation {
+            ChangeOperation::Create => write!(f, "Creating {}", self.file.display()),
+            ChangeOperation::Truncate { size } => write!(f, "Truncating {} to {size} bytes", self.file.display()),
+            ChangeOperation::Remove => write!(f, "Removing {}", self.file.display()),
+            ChangeOperation::Rename { to } => write!(f, "Renaming {} to {}", self.file.display(), to.display()),
+            ChangeOperation::CreateDirectory => write!(f, "Creating directory {}", self.file.display()),
+            ChangeOperation::RemoveDirectory => write!(f, "Removing directory {}", self.file.display()),
+            ChangeOperation::Symlink { target } => write!(f, "Linking {} to {}", self.file.display(), target.display()),
+            ChangeOperation::Permissions { mode } => write!(f, "Changing the permissions of {} to {mode:o}", self.file.display()),
+            ChangeOperation::Owner { .. } => write!(f, "Changing the owner of {}", self.file.display()),
+            ChangeOperation::Times { .. } => write!(f, "Changing the times of {}", self.file.display())
+        }
+    }
+}
+
+#[derive(Eq, PartialEq, Debug, Clone, Copy, Serialize, Deserialize)]
+#[non_exhaustive]
+pub enum MetadataOperation {
+    Lookup, // a path was resolved, e.g. by stat or before opening
+    GetAttr, // the attributes of an entry were asked for again
+    ReadDir // a directory was listed
+}
+
+impl std::fmt::Display for MetadataOperation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Lookup => "Looking up",
+            Self::GetAttr => "Getting the attributes of",
+            Self::ReadDir => "Listing"
+        })
+    }
+}
+
+/// An access of a path that didn't read any content.
+#[derive(Eq, PartialEq, Debug, Clone, Serialize, Deserialize)]
+pub struct MetadataEvent {
+    #[serde(serialize_with = "serialize_path", deserialize_with = "deserialize_interned_path")]
+    pub file: Arc<OsString>,
+    pub operation: MetadataOperation,
+    pub found: bool // false for lookups of paths that don't exist, which shows what a program looked for in vain
+}
+
+impl std::fmt::Display for MetadataEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.operation, self.file.display())?;
+        if !self.found {
+            f.write_str(" (not found)")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Eq, PartialEq, Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
+pub enum EventType {
+    Read(ReadEvent),
+    Open(HandleEvent),
+    Close(HandleEvent), // attributed to the process that opened the file, the kernel doesn't tell who closed it
+    Metadata(MetadataEvent), // only reported with `MountOptions::metadata_events`
+    Write(WriteEvent),
+    Change(ChangeEvent),
+    Link(LinkEvent),
+    Xattr(XattrEvent),
+    Marker(String), // inserted by the user to annotate the log
+    Derived {
+        plugin : String,
+        message : String
+    }
+}
+
+impl std::fmt::Display for EventType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Read(event) => write!(f, "{}", event),
+            Self::Open(event) => write!(f, "Opening {} (handle {})", event.file.display(), event.handle),
+            Self::Close(event) => write!(f, "Closing {} (handle {})", event.file.display(), event.handle),
+            Self::Metadata(event) => write!(f, "{}", event),
+            Self::Write(event) => write!(f, "{}", event),
+            Self::Change(event) => write!(f, "{}", event),
+            Self::Link(event) => write!(f, "{}", event),
+            Self::Xattr(event) => write!(f, "{}", event),
+            Self::Marker(label) => write!(f, "--- {label} ---"),
+            Self::Derived { plugin, message } => write!(f, "[{plugin}] {message}")
+        }
+    }
+}
+
+/// The process that caused an event.
+#[derive(Eq, PartialEq, Debug, Clone, Serialize, Deserialize)]
+pub struct Process {
+    pub pid: u32,
+    #[serde(deserialize_with = "deserialize_interned")]
+    pub name: Arc<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent: Option<u32>, // pid of the parent process, if it was still running when the process was first seen
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uid: Option<u32>, // of the request, not known for markers and backends whose driver doesn't tell
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gid: Option<u32>
+}
+
+impl std::fmt::Display for Process {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.name, self.pid)
+    }
+}
+
+#[derive(Eq, PartialEq, Debug, Clone, Serialize, Deserialize)]
+pub struct Event {
+    pub time: chrono::DateTime<Utc>,
+    pub process: Process,
+    pub event : EventType
+}
+
+impl std::fmt::Display for Event {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[{}] [{}] {}", self.time, self.process, self.event)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(event : EventType) -> Event {
+        Event {
+            time: chrono::DateTime::from_timestamp(1_700_000_000, 0).unwrap(),
+            process: Process { pid: 42, name: intern("Game.exe"), parent: Some(1), uid: Some(1000), gid: None },
+            event
+        }
+    }
+
+    fn read(file : &OsStr) -> Event {
+        let read = ReadEvent { file: intern_path(file), offset: 4096, size: 512, file_size: 1 << 20, handle: 7, latency: Duration::from_micros(30), data: None };
+        event(EventType::Read(read))
+    }
+
+    fn round_trip(event : &Event) -> Event {
+        serde_json::from_str(&serde_json::to_string(event).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn events_round_trip() {
+        let events = [
+            read(OsStr::new("Data/Map.bin")),
+            event(EventType::Change(ChangeEvent { file: intern_path(OsStr::new("a")), operation: ChangeOperation::Rename { to: OsString::from("b") } })),
+            event(EventType::Xattr(XattrEvent { file: intern_path(OsStr::new("a")), operation: XattrOperation::List, name: None, size: Some(0) })),
+            event(EventType::Marker("start".to_string())),
+            event(EventType::Derived { plugin: "rule".to_string(), message: "slow".to_string() })
+        ];
+        for event in &events {
+            assert_eq!(round_trip(event), *event);
+        }
+    }
+
+    #[test]
+    fn payloads_are_not_saved() {
+        let mut event = read(OsStr::new("Data/Map.bin"));
+        if let EventType::Read(read) = &mut event.event {
+            read.data = Some(Arc::new(vec![1, 2, 3]));
+        }
+        assert!(matches!(round_trip(&event).event, EventType::Read(read) if read.data.is_none()));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn paths_that_are_not_utf8_round_trip_as_bytes() {
+        use std::os::unix::ffi::OsStrExt;
+        let file = OsStr::from_bytes(b"Data/Stra\xdfe.bin"); // Latin-1
+        let event = read(file);
+        let json = serde_json::to_string(&event).unwrap();
+        assert!(json.contains("[68,97,116,97,47"));
+        assert_eq!(round_trip(&event), event);
+    }
+
+    #[test]
+    fn files_without_the_newer_fields_can_be_read() {
+        let json = r#"{"time":"2023-11-14T22:13:20Z","process":{"pid":42,"name":"Game.exe"},"event":{"Marker":"start"}}"#;
+        let event : Event = serde_json::from_str(json).unwrap();
+        assert_eq!(event.process.parent, None);
+        assert_eq!(event.process.uid, None);
+    }
+
+    #[test]
+    fn headers_tell_the_version() {
+        let header : Header = serde_json::from_str(&serde_json::to_string(&Header::current()).unwrap()).unwrap();
+        assert_eq!(header, Header::current());
+        assert!(header.is_supported());
+        // sessions saved before the header was added
+        let header : Header = serde_json::from_str(r#"{"name":"old","events":[]}"#).unwrap();
+        assert_eq!(header.schema_version, 1);
+        assert!(!Header { schema_version: SCHEMA_VERSION + 1 }.is_supported());
+    }
+
+    #[test]
+    fn unused_strings_are_forgotten() {
+        let path = intern_path(OsStr::new("Data/Forgotten.bin"));
+        let pointer = Arc::as_ptr(&path);
+        forget_unused();
+        // still used, so still shared
+        assert!(Arc::ptr_eq(&path, &intern_path(OsStr::new("Data/Forgotten.bin"))));
+        drop(path);
+        forget_unused();
+        INTERNED_PATHS.with_borrow(|interned| assert!(interned.iter().all(|path| Arc::as_ptr(&path.0) != pointer)));
+    }
+
+    #[test]
+    fn deserialized_paths_and_names_are_shared() {
+        let (a, b) = (round_trip(&read(OsStr::new("Data/Map.bin"))), round_trip(&read(OsStr::new("Data/Map.bin"))));
+        assert!(Arc::ptr_eq(&a.process.name, &b.process.name));
+        match (a.event, b.event) {
+            (EventType::Read(a), EventType::Read(b)) => assert!(Arc::ptr_eq(&a.file, &b.file)),
+            _ => unreachable!()
+        }
+    }
+}
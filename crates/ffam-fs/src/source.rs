@@ -0,0 +1,350 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::ffi::{CString, OsStr, OsString};
+use std::fs::File;
+use std::io::{Error, ErrorKind};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::{FileExt, MetadataExt, PermissionsExt};
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SourceMetadata {
+    pub is_dir : bool,
+    pub is_symlink : bool, // the link itself, links are never followed
+    pub size : u64,
+    pub permissions : u16, // the mode without the kind of file
+    pub accessed : SystemTime,
+    pub modified : SystemTime,
+    pub changed : SystemTime // of the metadata
+}
+
+impl SourceMetadata {
+    /// Metadata of an entry created now with the usual permissions, for sources that don't keep any.
+    pub fn new(is_dir : bool, is_symlink : bool, size : u64) -> Self {
+        let now = SystemTime::now();
+        let permissions = match (is_dir, is_symlink) {
+            (_, true) => 0o777,
+            (true, false) => 0o755,
+            (false, false) => 0o644
+        };
+        Self { is_dir, is_symlink, size, permissions, accessed: now, modified: now, changed: now }
+    }
+}
+
+/// A time as stat(2) returns it, seconds and nanoseconds since the epoch, which may be negative.
+fn time(seconds : i64, nanoseconds : i64) -> SystemTime {
+    let offset = Duration::new(seconds.unsigned_abs(), 0);
+    let time = if seconds < 0 { UNIX_EPOCH - offset } else { UNIX_EPOCH + offset };
+    time + Duration::from_nanos(nanoseconds.max(0) as u64)
+}
+
+/// The reverse of `time`, the nanoseconds are always positive.
+fn timespec(time : SystemTime) -> libc::timespec {
+    let (seconds, nanoseconds) = match time.duration_since(UNIX_EPOCH) {
+        Ok(since) => (since.as_secs() as i64, since.subsec_nanos() as i64),
+        Err(err) => match err.duration() {
+            before if before.subsec_nanos() == 0 => (-(before.as_secs() as i64), 0),
+            before => (-(before.as_secs() as i64) - 1, 1_000_000_000 - before.subsec_nanos() as i64)
+        }
+    };
+    libc::timespec { tv_sec: seconds as libc::time_t, tv_nsec: nanoseconds as _ }
+}
+
+/// Where the monitored filesystem gets its files from.
+///
+/// Paths are relative to the root of the source and separated by '/', the root itself is "". Names are bytes like on
+/// Unix, they don't have to be UTF-8.
+/// The initial scan calls it from several threads at once.
+pub trait Source: std::fmt::Debug + Send + Sync + 'static {
+    /// Names of the entries of a directory.
+    fn read_dir(&self, path : &OsStr) -> std::io::Result<Vec<OsString>>;
+    /// Names of the entries of a directory with whether they are directories, `None` if that needs a `stat`, which
+    /// symlinks always do.
+    /// The scan only stats entries of unknown kind, so a source that knows them spares it one disk access per file.
+    fn read_dir_kinds(&self, path : &OsStr) -> std::io::Result<Vec<(OsString, Option<bool>)>> {
+        Ok(self.read_dir(path)?.into_iter().map(|name| (name, None)).collect())
+    }
+    fn stat(&self, path : &OsStr) -> std::io::Result<SourceMetadata>;
+    fn read(&self, path : &OsStr, buffer : &mut [u8], offset : u64) -> std::io::Result<usize>;
+    /// The target of a symlink, only called for entries `stat` reported as one.
+    fn read_link(&self, _path : &OsStr) -> std::io::Result<OsString> {
+        Err(Error::from(ErrorKind::Unsupported))
+    }
+
+    /// The value of an extended attribute, `None` if the entry doesn't have it. Symlinks are not followed by the
+    /// attribute methods.
+    fn get_xattr(&self, _path : &OsStr, _name : &str) -> std::io::Result<Option<Vec<u8>>> {
+        Err(Error::from(ErrorKind::Unsupported))
+    }
+    /// The names of the extended attributes, each followed by a 0 byte, as listxattr(2) returns them.
+    fn list_xattr(&self, _path : &OsStr) -> std::io::Result<Vec<u8>> {
+        Err(Error::from(ErrorKind::Unsupported))
+    }
+
+    /// Opens the file on disk behind `path`, for reading it through io_uring. `None` if the source has no files on disk.
+    fn open(&self, _path : &OsStr) -> Option<std::io::Result<File>> {
+        None
+    }
+
+    /// Creates an empty file, only called with `MountOptions::write_through` like the other writing methods.
+    /// Sources that can't be written keep the defaults, which fail.
+    fn create(&self, _path : &OsStr) -> std::io::Result<()> {
+        Err(Error::from(ErrorKind::Unsupported))
+    }
+    fn write(&self, _path : &OsStr, _data : &[u8], _offset : u64) -> std::io::Result<usize> {
+        Err(Error::from(ErrorKind::Unsupported))
+    }
+    fn truncate(&self, _path : &OsStr, _size : u64) -> std::io::Result<()> {
+        Err(Error::from(ErrorKind::Unsupported))
+    }
+    fn remove_file(&self, _path : &OsStr) -> std::io::Result<()> {
+        Err(Error::from(ErrorKind::Unsupported))
+    }
+    fn create_dir(&self, _path : &OsStr) -> std::io::Result<()> {
+        Err(Error::from(ErrorKind::Unsupported))
+    }
+    fn remove_dir(&self, _path : &OsStr) -> std::io::Result<()> {
+        Err(Error::from(ErrorKind::Unsupported))
+    }
+    /// Moves a file or directory, replacing what is at `to`, like `std::fs::rename`.
+    fn rename(&self, _from : &OsStr, _to : &OsStr) -> std::io::Result<()> {
+        Err(Error::from(ErrorKind::Unsupported))
+    }
+    fn symlink(&self, _path : &OsStr, _target : &OsStr) -> std::io::Result<()> {
+        Err(Error::from(ErrorKind::Unsupported))
+    }
+    /// `flags` are the ones of setxattr(2), XATTR_CREATE or XATTR_REPLACE.
+    fn set_xattr(&self, _path : &OsStr, _name : &str, _value : &[u8], _flags : i32) -> std::io::Result<()> {
+        Err(Error::from(ErrorKind::Unsupported))
+    }
+    /// Sets the permission bits of the mode, like chmod(2).
+    fn set_permissions(&self, _path : &OsStr, _mode : u32) -> std::io::Result<()> {
+        Err(Error::from(ErrorKind::Unsupported))
+    }
+    /// Changes the owner and the group of an entry, `None` keeps it. Symlinks are not followed.
+    fn set_owner(&self, _path : &OsStr, _uid : Option<u32>, _gid : Option<u32>) -> std::io::Result<()> {
+        Err(Error::from(ErrorKind::Unsupported))
+    }
+    /// Sets the access and modification time of an entry, `None` keeps it. Symlinks are not followed.
+    fn set_times(&self, _path : &OsStr, _accessed : Option<SystemTime>, _modified : Option<SystemTime>) -> std::io::Result<()> {
+        Err(Error::from(ErrorKind::Unsupported))
+    }
+}
+
+/// A directory on disk, this is what gets mounted normally.
+#[derive(Debug, Clone)]
+pub struct DiskSource {
+    root : PathBuf
+}
+
+impl DiskSource {
+    pub fn new(root : impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    fn c_path(&self, path : &OsStr) -> std::io::Result<CString> {
+        Ok(CString::new(self.root.join(path).as_os_str().as_bytes())?)
+    }
+}
+
+/// Calls a function like getxattr(2) for the size first and then for the content, again if it grew in between.
+fn xattr_buffer(call : impl Fn(*mut libc::c_void, usize) -> isize) -> std::io::Result<Vec<u8>> {
+    loop {
+        let size = call(std::ptr::null_mut(), 0);
+        if size < 0 {
+            return Err(Error::last_os_error());
+        }
+        let mut buffer = vec![0u8; size as usize];
+        let read = call(buffer.as_mut_ptr().cast(), buffer.len());
+        if read >= 0 {
+            buffer.truncate(read as usize);
+            return Ok(buffer);
+        }
+        let err = Error::last_os_error();
+        if err.raw_os_error() != Some(libc::ERANGE) {
+            return Err(err);
+        }
+    }
+}
+
+impl Source for DiskSource {
+    fn read_dir(&self, path : &OsStr) -> std::io::Result<Vec<OsString>> {
+        let mut names = Vec::new();
+        for entry in std::fs::read_dir(self.root.join(path))? {
+            names.push(entry?.file_name());
+        }
+        Ok(names)
+    }
+
+    fn read_dir_kinds(&self, path : &OsStr) -> std::io::Result<Vec<(OsString, Option<bool>)>> {
+        let mut entries = Vec::new();
+        for entry in std::fs::read_dir(self.root.join(path))? {
+            let entry = entry?;
+            // the kind comes with the directory listing on most filesystems, symlinks are left to `stat`
+            let is_dir = entry.file_type().ok().filter(|kind| !kind.is_symlink()).map(|kind| kind.is_dir());
+            entries.push((entry.file_name(), is_dir));
+        }
+        Ok(entries)
+    }
+
+    fn stat(&self, path : &OsStr) -> std::io::Result<SourceMetadata> {
+        // symlinks are not followed, so a link to a parent directory can't make the scan loop forever
+        let meta = std::fs::symlink_metadata(self.root.join(path))?;
+        Ok(SourceMetadata {
+            is_dir: meta.is_dir(),
+            is_symlink: meta.file_type().is_symlink(),
+            size: meta.len(),
+            permissions: (meta.mode() & 0o7777) as u16,
+            accessed: time(meta.atime(), meta.atime_nsec()),
+            modified: time(meta.mtime(), meta.mtime_nsec()),
+            changed: time(meta.ctime(), meta.ctime_nsec())
+        })
+    }
+
+    fn read(&self, path : &OsStr, buffer : &mut [u8], offset : u64) -> std::io::Result<usize> {
+        File::open(self.root.join(path))?.read_at(buffer, offset)
+    }
+
+    fn read_link(&self, path : &OsStr) -> std::io::Result<OsString> {
+        Ok(std::fs::read_link(self.root.join(path))?.into_os_string())
+    }
+
+    fn get_xattr(&self, path : &OsStr, name : &str) -> std::io::Result<Option<Vec<u8>>> {
+        let (path, name) = (self.c_path(path)?, CString::new(name)?);
+        // SAFETY: both strings are null terminated, the buffer has the given size
+        let value = xattr_buffer(|buffer, size| unsafe { libc::lgetxattr(path.as_ptr(), name.as_ptr(), buffer, size) });
+        match value {
+            Err(err) if err.raw_os_error() == Some(libc::ENODATA) => Ok(None),
+            value => value.map(Some)
+        }
+    }
+
+    fn list_xattr(&self, path : &OsStr) -> std::io::Result<Vec<u8>> {
+        let path = self.c_path(path)?;
+        // SAFETY: as above
+        xattr_buffer(|buffer, size| unsafe { libc::llistxattr(path.as_ptr(), buffer.cast(), size) })
+    }
+
+    fn open(&self, path : &OsStr) -> Option<std::io::Result<File>> {
+        Some(File::open(self.root.join(path)))
+    }
+
+    fn create(&self, path : &OsStr) -> std::io::Result<()> {
+        File::options().write(true).create_new(true).open(self.root.join(path)).map(|_| ())
+    }
+
+    fn write(&self, path : &OsStr, data : &[u8], offset : u64) -> std::io::Result<usize> {
+        File::options().write(true).open(self.root.join(path))?.write_at(data, offset)
+    }
+
+    fn truncate(&self, path : &OsStr, size : u64) -> std::io::Result<()> {
+        File::options().write(true).open(self.root.join(path))?.set_len(size)
+    }
+
+    fn remove_file(&self, path : &OsStr) -> std::io::Result<()> {
+        std::fs::remove_file(self.root.join(path))
+    }
+
+    fn create_dir(&self, path : &OsStr) -> std::io::Result<()> {
+        std::fs::create_dir(self.root.join(path))
+    }
+
+    fn remove_dir(&self, path : &OsStr) -> std::io::Result<()> {
+        std::fs::remove_dir(self.root.join(path))
+    }
+
+    fn rename(&self, from : &OsStr, to : &OsStr) -> std::io::Result<()> {
+        std::fs::rename(self.root.join(from), self.root.join(to))
+    }
+
+    fn symlink(&self, path : &OsStr, target : &OsStr) -> std::io::Result<()> {
+        std::os::unix::fs::symlink(target, self.root.join(path))
+    }
+
+    fn set_xattr(&self, path : &OsStr, name : &str, value : &[u8], flags : i32) -> std::io::Result<()> {
+        let (path, name) = (self.c_path(path)?, CString::new(name)?);
+        // SAFETY: both strings are null terminated, the value is passed with its length
+        match unsafe { libc::lsetxattr(path.as_ptr(), name.as_ptr(), value.as_ptr().cast(), value.len(), flags) } {
+            0 => Ok(()),
+            _ => Err(Error::last_os_error())
+        }
+    }
+
+    fn set_permissions(&self, path : &OsStr, mode : u32) -> std::io::Result<()> {
+        std::fs::set_permissions(self.root.join(path), std::fs::Permissions::from_mode(mode & 0o7777))
+    }
+
+    fn set_owner(&self, path : &OsStr, uid : Option<u32>, gid : Option<u32>) -> std::io::Result<()> {
+        std::os::unix::fs::lchown(self.root.join(path), uid, gid)
+    }
+
+    fn set_times(&self, path : &OsStr, accessed : Option<SystemTime>, modified : Option<SystemTime>) -> std::io::Result<()> {
+        let path = self.c_path(path)?;
+        let omit = libc::timespec { tv_sec: 0, tv_nsec: libc::UTIME_OMIT };
+        let times = [accessed.map_or(omit, timespec), modified.map_or(omit, timespec)];
+        // SAFETY: the path is null terminated and there are two times like utimensat(2) expects
+        match unsafe { libc::utimensat(libc::AT_FDCWD, path.as_ptr(), times.as_ptr(), libc::AT_SYMLINK_NOFOLLOW) } {
+            0 => Ok(()),
+            _ => Err(Error::last_os_error())
+        }
+    }
+}
+
+/// Files held in memory, to test the filesystem without a real directory.
+/// Directories exist implicitly as the parents of the files.
+#[derive(Debug, Clone, Default)]
+pub struct MemorySource {
+    files : BTreeMap<String, Vec<u8>>
+}
+
+impl MemorySource {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn file(mut self, path : impl Into<String>, content : impl Into<Vec<u8>>) -> Self {
+        self.files.insert(path.into(), content.into());
+        self
+    }
+
+    fn is_dir(&self, path : &str) -> bool {
+        path.is_empty() || self.files.keys().any(|file| file.strip_prefix(path).is_some_and(|rest| rest.starts_with('/')))
+    }
+}
+
+/// The paths of a `MemorySource` are strings, so one that isn't can't exist.
+fn key(path : &OsStr) -> std::io::Result<&str> {
+    path.to_str().ok_or_else(|| Error::from(ErrorKind::NotFound))
+}
+
+impl Source for MemorySource {
+    fn read_dir(&self, path : &OsStr) -> std::io::Result<Vec<OsString>> {
+        let path = key(path)?;
+        if !self.is_dir(path) {
+            return Err(Error::from(ErrorKind::NotFound));
+        }
+        let prefix = if path.is_empty() { String::new() } else { format!("{path}/") };
+        let names : BTreeSet<&str> = self.files.keys()
+            .filter_map(|file| file.strip_prefix(&prefix))
+            .filter_map(|rest| rest.split('/').next())
+            .collect();
+        Ok(names.into_iter().map(OsString::from).collect())
+    }
+
+    fn stat(&self, path : &OsStr) -> std::io::Result<SourceMetadata> {
+        let path = key(path)?;
+        match self.files.get(path) {
+            Some(content) => Ok(SourceMetadata::new(false, false, content.len() as u64)),
+            None if self.is_dir(path) => Ok(SourceMetadata::new(true, false, 0)),
+            None => Err(Error::from(ErrorKind::NotFound))
+        }
+    }
+
+    fn read(&self, path : &OsStr, buffer : &mut [u8], offset : u64) -> std::io::Result<usize> {
+        let content = self.files.get(key(path)?).ok_or_else(|| Error::from(ErrorKind::NotFound))?;
+        let start = (offset as usize).min(content.len());
+        let end = (start + buffer.len()).min(content.len());
+        buffer[..end - start].copy_from_slice(&content[start..end]);
+        Ok(end - start)
+    }
+}
@@ -0,0 +1,19 @@
+/// `log!(debug, "format", args...)` logs through `tracing` when the feature is enabled, otherwise it compiles to nothing.
+/// It is exported so that the application logs the same way as the engine.
+#[cfg(feature = "tracing")]
+#[macro_export]
+macro_rules! log {
+    ($level:ident, $($arg:tt)+) => {
+        $crate::tracing::$level!($($arg)+)
+    };
+}
+
+#[cfg(not(feature = "tracing"))]
+#[macro_export]
+macro_rules! log {
+    ($level:ident, $($arg:tt)+) => {{
+        // the closure is never called, it only keeps the arguments from being unused
+        let _ = || format!($($arg)+);
+    }};
+}
+pub(crate) use crate::log;
@@ -0,0 +1,189 @@
+use fuser::ReplyData;
+use io_uring::{opcode, types, IoUring};
+use libc::{EIO, ENOENT};
+use std::collections::HashMap;
+use std::fs::File;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use std::sync::mpsc::{Receiver, Sender, TryRecvError};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+
+use crate::fs::{PendingRead, Reporter};
+use crate::trace::log;
+
+/// Entries of the ring, one is taken by the eventfd read and the rest limits the reads in flight, more wait in the channel.
+const QUEUE_DEPTH : u32 = 256;
+/// User data of the read of the eventfd that wakes the worker for new reads.
+const WAKE : u64 = u64::MAX;
+
+/// A read waiting for io_uring, the reply is sent when it completes.
+#[derive(Debug)]
+pub(crate) struct UringRead {
+    pub file : Arc<File>,
+    pub buffer : Vec<u8>,
+    pub read : PendingRead,
+    pub reply : ReplyData
+}
+
+/// Serves reads of the source through io_uring. The FUSE handler only submits the read and returns,
+/// a worker thread replies to the kernel and reports the event once the read completed.
+#[derive(Debug)]
+pub(crate) struct UringReader {
+    files : HashMap<u64, Arc<File>>, // by file handle, reads need a file descriptor
+    requests : Option<Sender<UringRead>>, // None once dropping, which lets the worker finish
+    wake : OwnedFd, // eventfd, the worker waits on it together with the reads
+    worker : Option<JoinHandle<()>>
+}
+
+impl UringReader {
+    pub fn new(reporter : Arc<Reporter>) -> std::io::Result<Self> {
+        let ring = IoUring::new(QUEUE_DEPTH)?;
+        let wake = unsafe { libc::eventfd(0, libc::EFD_CLOEXEC) };
+        if wake < 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        // SAFETY: the descriptor was just created and isn't owned by anything else
+        let wake = unsafe { OwnedFd::from_raw_fd(wake) };
+        let worker_wake = wake.try_clone()?;
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let worker = std::thread::Builder::new()
+            .name("ffam-uring".to_string())
+            .spawn(move || complete_reads(ring, worker_wake, receiver, reporter))?;
+        Ok(Self {
+            files: HashMap::new(),
+            requests: Some(sender),
+            wake,
+            worker: Some(worker)
+        })
+    }
+
+    pub fn open(&mut self, fh : u64, file : File) {
+        self.files.insert(fh, Arc::new(file));
+    }
+
+    pub fn close(&mut self, fh : u64) {
+        self.files.remove(&fh);
+    }
+
+    /// The file opened for a handle, `None` if it is read synchronously.
+    pub fn file(&self, fh : u64) -> Option<Arc<File>> {
+        self.files.get(&fh).cloned()
+    }
+
+    /// Hands a read to the worker, gives it back if the worker is gone so it can be served synchronously.
+    pub fn submit(&self, read : UringRead) -> Result<(), UringRead> {
+        let Some(requests) = &self.requests else {
+            return Err(read);
+        };
+        requests.send(read).map_err(|err| err.0)?;
+        self.wake();
+        Ok(())
+    }
+
+    fn wake(&self) {
+        let one = 1u64;
+        // SAFETY: writes 8 bytes from a live u64 to the eventfd
+        unsafe { libc::write(self.wake.as_raw_fd(), &one as *const u64 as *const libc::c_void, 8) };
+    }
+}
+
+impl Drop for UringReader {
+    fn drop(&mut self) {
+        // closing the channel before waking lets the worker see that it is done once the reads in flight completed
+        self.requests = None;
+        self.wake();
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+/// Runs on the worker thread until the reader is dropped and all reads in flight are replied to, or until waiting for
+/// the ring fails. Then the reads fail and the worker stops, which makes `submit` hand the next reads back.
+fn complete_reads(mut ring : IoUring, wake : OwnedFd, requests : Receiver<UringRead>, reporter : Arc<Reporter>) {
+    let mut in_flight : HashMap<u64, UringRead> = HashMap::new();
+    let mut next_id = 0u64;
+    let mut counter = Box::new([0u8; 8]); // filled by the eventfd read, leaked if the worker stops while it is armed
+    let mut wake_armed = false;
+    let mut open = true;
+    loop {
+        while in_flight.len() < QUEUE_DEPTH as usize - 1 {
+            match requests.try_recv() {
+                Ok(mut read) => {
+                    let id = next_id;
+                    next_id += 1;
+                    let entry = opcode::Read::new(types::Fd(read.file.as_raw_fd()), read.buffer.as_mut_ptr(), read.buffer.len() as u32)
+                        .offset(read.read.offset as u64)
+                        .build()
+                        .user_data(id);
+                    // the buffer is on the heap, moving the read into the map doesn't move it
+                    in_flight.insert(id, read);
+                    push(&mut ring, &entry);
+                }
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    open = false;
+                    break;
+                }
+            }
+        }
+        // the eventfd read must have completed before returning, it writes into `counter`
+        if !open && !wake_armed && in_flight.is_empty() {
+            return;
+        }
+        // armed after taking the requests, so the wake of the closed channel can't be used up by a read armed before
+        if open && !wake_armed {
+            let entry = opcode::Read::new(types::Fd(wake.as_raw_fd()), counter.as_mut_ptr(), 8).build().user_data(WAKE);
+            push(&mut ring, &entry);
+            wake_armed = true;
+        }
+        match ring.submit_and_wait(1) {
+            Ok(_) => (),
+            Err(err) if err.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(err) => {
+                log!(warn, "Waiting for io_uring failed, reading synchronously: {err}");
+                if wake_armed {
+                    Box::leak(counter);
+                }
+                fail(in_flight.into_values().chain(requests.try_iter()), &reporter);
+                return;
+            }
+        }
+        let completed : Vec<(u64, i32)> = ring.completion().map(|entry| (entry.user_data(), entry.result())).collect();
+        for (id, result) in completed {
+            if id == WAKE {
+                wake_armed = false;
+                continue;
+            }
+            let Some(read) = in_flight.remove(&id) else {
+                continue;
+            };
+            let latency = read.read.start.elapsed();
+            // errors of the source are reported as missing files, like the synchronous reads do
+            let result = match result {
+                bytes if bytes >= 0 => Ok(bytes as usize),
+                _ => Err(ENOENT)
+            };
+            match result {
+                Ok(length) => read.reply.data(&read.buffer[..length]),
+                Err(err) => read.reply.error(err)
+            }
+            reporter.report(&read.read, latency, result.map(|length| &read.buffer[..length]));
+        }
+    }
+}
+
+/// Replies EIO to reads that can't be completed anymore. The kernel may still write into the buffers of reads that were
+/// submitted, so they are leaked.
+fn fail(reads : impl Iterator<Item = UringRead>, reporter : &Reporter) {
+    for mut read in reads {
+        std::mem::forget(std::mem::take(&mut read.buffer));
+        read.reply.error(EIO);
+        reporter.report(&read.read, read.read.start.elapsed(), Err(EIO));
+    }
+}
+
+fn push(ring : &mut IoUring, entry : &io_uring::squeue::Entry) {
+    // SAFETY: the buffers of all entries stay alive until their completion is received
+    unsafe { ring.submission().push(entry) }.expect("the queue has room for every read in flight");
+}
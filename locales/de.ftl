@@ -0,0 +1,369 @@
+window-title = FUSE-Dateizugriffsmonitor
+
+## Unmounted screen
+source-directory = Quellverzeichnis
+source-directory-label = Quellverzeichnis:
+mountpoint = Einhängepunkt
+mountpoint-label = Einhängepunkt:
+drop-tip = Tipp: Ordner können auf das Fenster gezogen werden.
+capture-payloads = Gelesene Daten aufzeichnen (Doppelklick auf ein Ereignis zeigt sie an)
+use-dropped-as = { $path } verwenden als
+mount = Einhängen
+mounting = Wird eingehängt...
+scan-progress = { $directories } Verzeichnisse durchsucht, { $files } Dateien gefunden
+mount-cancelled = Das Einhängen wurde abgebrochen.
+unmounting = Wird ausgehängt...
+language = Sprache
+
+## Mounted screen
+unmount = Aushängen
+highlight-rules = Hervorhebungsregeln
+pin-selected-event = Ausgewähltes Ereignis anheften
+clear-log = Protokoll leeren
+export-path = Exportpfad
+export-log = Protokoll exportieren
+events-logged = { $count } Ereignisse protokolliert.
+events-logged-capped = Zeige die letzten { $count } von { $total } Ereignissen (vollständiges Protokoll im Export).
+spill-failed = Verworfene Ereignisse konnten nicht für den Export aufbewahrt werden: { $error }
+events-lost = { $count } Ereignisse gingen verloren, das Protokoll kam nicht hinterher.
+browse-spilled = Ältere Ereignisse ansehen
+spilled-page = Ältere Ereignisse, Seite { $page } von { $pages }
+spilled-page-failed = Ältere Ereignisse konnten nicht gelesen werden: { $error }
+close = Schließen
+cancel = Abbrechen
+dismiss = Schließen
+
+## Highlight rules
+highlight-rules-help = Zeilen, die ein Muster enthalten, werden eingefärbt. Die erste passende Regel gewinnt.
+highlight-pattern = Muster, z. B. .sav oder sounds/
+remove = Entfernen
+add-rule = Regel hinzufügen
+color-red = Rot
+color-orange = Orange
+color-yellow = Gelb
+color-green = Grün
+color-blue = Blau
+color-purple = Lila
+color-gray = Grau
+
+## Timestamps
+timestamp-utc = UTC-Zeit
+timestamp-local = Ortszeit
+timestamp-since-mount = Zeit seit dem Einhängen
+
+## Details and bookmarks
+event-details = Ereignisdetails
+pinned-events = Angeheftete Ereignisse
+unpin = Lösen
+detail-time = Zeit
+detail-operation = Vorgang
+detail-file = Datei
+detail-full-path = Vollständiger Pfad
+detail-offset = Offset
+detail-size = Größe
+detail-file-size = Dateigröße
+detail-handle = Handle
+detail-latency = Latenz
+detail-coverage = Bisher gelesen
+detail-payload = Daten
+detail-renamed-to = Umbenannt in
+detail-target = Verknüpfungsziel
+detail-attribute = Attribut
+detail-mode = Berechtigungen
+detail-uid = Besitzer
+detail-gid = Gruppe
+detail-accessed = Letzter Zugriff
+detail-modified = Geändert
+operation-read = Lesen
+operation-open = Öffnen
+operation-close = Schließen
+operation-lookup = Nachschlagen
+operation-getattr = Attribute
+operation-readdir = Auflisten
+operation-write = Schreiben
+operation-create = Anlegen
+operation-truncate = Kürzen
+operation-remove = Löschen
+operation-rename = Umbenennen
+operation-mkdir = Verzeichnis anlegen
+operation-rmdir = Verzeichnis löschen
+operation-symlink = Verknüpfung anlegen
+operation-chmod = Berechtigungen ändern
+operation-chown = Besitzer ändern
+operation-utimens = Zeiten ändern
+operation-readlink = Verknüpfung auflösen
+operation-getxattr = Attribut lesen
+operation-listxattr = Attribute auflisten
+operation-setxattr = Attribut setzen
+not-found = (nicht gefunden)
+operation-marker = Markierung
+operation-derived = Von Plugin { $plugin }
+detail-label = Bezeichnung
+bytes = { $count } Bytes
+coverage = { $covered } von { $total } Bytes ({ $percentage } %)
+payload-captured = { $count } Bytes aufgezeichnet
+payload-not-captured = nicht aufgezeichnet
+
+## Hex viewer
+hex-more-bytes = ... { $count } weitere Bytes nicht angezeigt
+hex-no-payload = Für dieses Ereignis wurden keine Daten aufgezeichnet.
+
+## Confirmation
+unexported-log = Das Protokoll mit { $count } Ereignissen wurde noch nicht exportiert.
+save-to = Speichern unter:
+save-and-unmount = Speichern und aushängen
+save-and-exit = Speichern und beenden
+unmount-without-saving = Ohne Speichern aushängen
+exit-without-saving = Ohne Speichern beenden
+
+## Status bar
+uptime = Seit { $time }
+event-count = { $count } Ereignisse
+event-rate = { $rate } Ereignisse/s
+sink-queue = Warteschlange { $queued }/{ $capacity }
+sink-closed = Warteschlange geschlossen
+events-dropped = { $count } verloren
+
+## Notifications
+mount-failed = Einhängen fehlgeschlagen: { $error }
+hint-fuse-device-missing = /dev/fuse existiert nicht. Das Laden des fuse-Moduls legt es an, in einem Container muss es hineingereicht werden.
+hint-fuse-module-not-loaded = Das fuse-Kernelmodul ist nicht geladen. Bitte mit "sudo modprobe fuse" laden.
+hint-fusermount-missing = fusermount3 wurde nicht gefunden. Bitte das fuse3-Paket der Distribution installieren.
+hint-mountpoint-busy = Unter { $mountpoint } ist bereits etwas eingehängt. Bitte mit "fusermount3 -u { $mountpoint }" aushängen oder einen anderen Einhängepunkt wählen.
+hint-fusefs-not-loaded = /dev/fuse existiert nicht. Bitte das fusefs-Modul mit "kldload fusefs" laden (fusefs_load="YES" in /boot/loader.conf lädt es beim Start).
+hint-mountpoint-busy-freebsd = Unter { $mountpoint } ist bereits etwas eingehängt. Bitte mit "umount { $mountpoint }" aushängen oder einen anderen Einhängepunkt wählen.
+hint-permission-denied-freebsd = Keine Berechtigung, unter { $mountpoint } einzuhängen. Einhängen als normaler Benutzer benötigt "sysctl vfs.usermount=1", ein eigenes Verzeichnis und Zugriff auf /dev/fuse (Gruppe operator).
+hint-allow-other = Der Zugriff für andere Benutzer benötigt "user_allow_other" in /etc/fuse.conf. Bitte dort eintragen oder die Option deaktivieren.
+hint-permission-denied = Keine Berechtigung, unter { $mountpoint } einzuhängen. Bitte ein eigenes Verzeichnis wählen und prüfen, ob /dev/fuse zugänglich ist.
+hint-mountpoint-not-empty = Der Einhängepunkt ist nicht leer. Bitte ein leeres Verzeichnis wählen, sein Inhalt ist während des Einhängens verborgen.
+mountpoint-not-directory = Der Einhängepunkt ist kein Verzeichnis.
+source-not-directory = Die Quelle ist kein Verzeichnis.
+not-a-folder = { $path } ist kein Ordner.
+unmount-without-mount = Irgendwie wurde Aushängen gedrückt, obwohl nichts eingehängt war...? Na gut.
+exported = { $count } Ereignisse nach { $path } exportiert.
+export-failed = Export des Protokolls nach { $path } fehlgeschlagen: { $error }
+html-exported = HTML-Bericht nach { $path } geschrieben.
+html-export-failed = Schreiben des HTML-Berichts nach { $path } fehlgeschlagen: { $error }
+export-html = Als HTML exportieren
+
+## Other monitors
+
+active-mounts = Von anderen Monitoren eingehängt
+active-mount = { $mountpoint } ← { $source } (PID { $pid })
+unmount-other-failed = Aushängen fehlgeschlagen: { $error }
+stale-mounts = Ein abgestürzter Monitor hat diese Einhängepunkte hinterlassen, sie sind bis zum Aushängen nicht zugänglich ("Transport endpoint is not connected"):
+recover-stale-mounts = Aushängen
+stale-mount-not-recovered = { $mountpoint } konnte nicht ausgehängt werden. Bitte als Besitzer "fusermount3 -uz { $mountpoint }" versuchen.
+
+## Setup
+
+setup-title = Vor dem Einhängen ist noch etwas einzurichten
+setup-fuse-device-missing = /dev/fuse existiert nicht, das fuse-Kernelmodul ist nicht geladen.
+setup-fuse-device-not-passed = /dev/fuse ist im Container nicht verfügbar. Bitte mit "--device /dev/fuse --cap-add SYS_ADMIN" (Docker) oder "--device /dev/fuse" (Podman) starten.
+setup-fusermount-not-setuid = { $path } ist nicht setuid root, normale Benutzer können damit nicht einhängen.
+setup-fuse-device-group = Keine Berechtigung für /dev/fuse. Bitte der Gruppe { $group } beitreten und neu anmelden.
+setup-fuse-device-not-accessible = Keine Berechtigung für /dev/fuse.
+setup-user-mount-disabled = Normale Benutzer dürfen nicht einhängen (vfs.usermount ist 0).
+setup-nfs-client-missing = mount.nfs wurde nicht gefunden. Bitte den NFS-Client der Distribution installieren.
+setup-nfs-needs-root = Einhängen über NFS braucht root. Den Monitor als root starten oder FUSE verwenden.
+setup-copy-command = Befehl kopieren
+setup-check-again = Erneut prüfen
+setup-use-nfs = Stattdessen NFS verwenden
+setup-complete = Alles für das Einhängen ist eingerichtet.
+
+## Tray icon
+tray-enabled = Während des Einhängens ein Symbol im Infobereich anzeigen
+tray-minimize = In den Infobereich minimieren
+tray-show = Fenster anzeigen
+tray-activity = { $count } Ereignisse, { $rate } Ereignisse/s
+tray-failed = Das Symbol im Infobereich konnte nicht erstellt werden: { $error }
+
+## Mount options
+advanced-options = Erweiterte Optionen
+script-help = Rhai-Skript, das für jedes Ereignis ausgeführt wird. Variablen: kind, path, process, pid, offset, size. Funktionen: tag(text), notify(text), redirect(file), log(text). Ergibt es false, wird das Ereignis verworfen.
+script-placeholder = if path.ends_with(".wav") {"{"} false {"}"}
+script-notification-summary = Skript-Benachrichtigung
+allow-other = Anderen Benutzern Zugriff erlauben (allow_other)
+auto-unmount = Beim Beenden automatisch aushängen (auto_unmount)
+read-only = Schreibgeschützt einhängen
+stats-file = Live-Statistiken als JSON unter .ffam/stats im Einhängepunkt
+control-file = Befehle aus .ffam/ctl im Einhängepunkt annehmen (pause, marker, filter, flush)
+metadata-events = Auch Nachschlagen, Attributabfragen und Verzeichnislisten melden
+direct-io = Kernel-Cache umgehen, damit jeder Lesezugriff gemeldet wird (langsamer)
+write-through = Angelegte und geänderte Dateien ins Quellverzeichnis schreiben
+nfs-backend = Über einen lokalen NFS-Server statt FUSE bereitstellen (Einhängen braucht root)
+case-sensitivity-label = Namen:
+case-sensitive = Groß-/Kleinschreibung beachten
+case-insensitive = Groß-/Kleinschreibung ignorieren, neue Namen kleingeschrieben
+case-insensitive-preserving = Groß-/Kleinschreibung ignorieren, bei neuen Namen beibehalten (wie Windows)
+ttl-label = Cache-Dauer für Attribute (s):
+include-label = Nur Pfade überwachen, die enthalten:
+exclude-label = Pfade nicht überwachen, die enthalten:
+filter-placeholder = Durch Kommas getrennt, z. B. .sav, sounds/
+ttl-invalid = Die Cache-Dauer muss eine nicht negative Anzahl Sekunden sein.
+max-log-entries-label = Ereignisse im Protokoll:
+archive-index-label = Archiv-Index:
+archive-index-placeholder = .json oder Zeilen aus Container<TAB>Offset<TAB>Größe<TAB>Asset
+
+## Processes
+all-processes = Alle Prozesse
+detail-process = Prozess
+detail-pid = PID
+detail-uid = UID
+detail-user = UID / GID
+
+## Grouping
+group-reads = Aufeinanderfolgende Lesezugriffe gruppieren
+read-group = { $count } Lesezugriffe, { $size } aus { $file } (Doppelklick zum Aufklappen)
+read-summary = { $count } Lesezugriffe, { $size } aus { $file }
+live-view-summarized = Mehr als { $rate } Ereignisse/s, die Live-Ansicht zeigt eine Zeile pro Datei und Sekunde. Suche und Export enthalten weiterhin jedes Ereignis.
+
+## File list
+file-list = Dateiliste
+all-files = Alle Dateien
+file-entry = { $file } ({ $reads } Lesezugriffe, { $pattern })
+file-stats = { $file }: { $reads } Lesezugriffe, { $bytes } gelesen, { $covered } von { $total } abgedeckt ({ $percentage }%), { $pattern }: { $sequential }% sequenziell, im Schnitt { $seek } übersprungen
+pattern-sequential = sequenziell
+pattern-strided = in festen Abständen
+pattern-random = wahlfrei
+
+## Copying accessed files
+copy-target = Verzeichnis, in das die bisher gelesenen Dateien kopiert oder extrahiert werden
+copy-accessed = Gelesene Dateien kopieren
+files-copied = { $count } Dateien ({ $size }) nach { $target } kopiert.
+copy-failed = Kopieren der gelesenen Dateien fehlgeschlagen: { $error }
+extract-sparse = Gelesenes extrahieren (lückenhaft)
+extract-concatenated = Gelesenes extrahieren (aneinandergereiht)
+reads-extracted = { $size } aus { $count } Dateien nach { $target } extrahiert.
+extract-failed = Extrahieren des Gelesenen fehlgeschlagen: { $error }
+
+## Manifest
+manifest-path = Manifest, ein .json-Array oder ein Pfad pro Zeile
+compare-manifest = Mit Manifest vergleichen
+manifest-accessed = Erwartet und gelesen: { $count }
+manifest-not-accessed = Erwartet, aber nie gelesen: { $count }
+manifest-missing = Erwartet, aber fehlt in der Quelle: { $count }
+manifest-unexpected = Gelesen, aber nicht erwartet: { $count }
+
+## Timeline
+timeline = Zeitleiste
+timeline-reset = Gesamte Sitzung
+
+## Sessions
+session-name = Sitzungsname, z.B. Vanilla-Lauf
+save-session = Sitzung speichern
+load-session = Sitzung laden
+session-saved = Sitzung { $name } gespeichert.
+session-save-failed = Die Sitzung konnte nicht gespeichert werden: { $error }
+session-load-failed = Die Sitzung konnte nicht geladen werden: { $error }
+compare-sessions = Sitzungen vergleichen:
+compare = Vergleichen
+comparison-title = Von { $first } und { $second } gelesene Dateien, größte Unterschiede zuerst
+compare-reads = Lesezugriffe
+compare-bytes = Bytes
+
+## Markers
+marker-label = Bezeichnung der Markierung (optional)
+add-marker = Markieren (Strg+M)
+marker-default = Markierung { $number }
+
+## Treemap
+treemap = Treemap
+treemap-up = Nach oben
+treemap-title = { $dir }: { $size } gelesen. Ein Klick auf ein Verzeichnis öffnet es.
+
+## Flame graph
+flame-graph = Flame-Graph
+flame-graph-reset = Alles zeigen
+flame-graph-title = { $count } Zugriffe im gewählten Zeitfenster. Ein Klick auf einen Balken vergrößert ihn.
+
+## Access order
+access-order = Zugriffsreihenfolge
+access-order-time = Erster Lesezugriff
+access-order-title = { $count } Dateien in der Reihenfolge ihres ersten Lesezugriffs im gewählten Zeitfenster.
+
+## Redundant reads
+redundant-reads = Wiederholte Lesezugriffe
+redundant-bytes = Erneut gelesen
+redundant-rereads = Wiederholungen
+redundant-ranges = Bereiche
+redundant-max-repeats = Meiste Zugriffe auf einen Bereich
+redundant-per-second = Wiederholungen pro Sekunde
+redundant-title = { $count } Dateien wurden im gewählten Zeitfenster wiederholt an denselben Stellen gelesen, { $size } wurden erneut gelesen.
+
+## Process usage
+process-usage = Prozesse
+process-usage-files = Dateien
+process-usage-tree-files = Dateien mit Kindprozessen
+process-usage-tree-bytes = Bytes mit Kindprozessen
+process-usage-title = { $count } Prozesse haben im gewählten Zeitfenster Dateien gelesen. Kindprozesse stehen unter ihrem Elternprozess.
+
+## External log
+external-log-path = Protokoll eines anderen Programms, z.B. das Debug-Protokoll des Spiels
+load-external-log = Verschränkt anzeigen
+external-log-title = { $lines } Zeilen des externen Protokolls zwischen den Ereignissen des gewählten Zeitfensters, { $shown } von { $total } Einträgen werden angezeigt.
+external-offset = Versatz in Sekunden
+external-anchor = oder ausrichten
+external-anchor-marker = Markierung
+external-anchor-text = an der Protokollzeile mit
+external-align = Ausrichten
+external-anchor-not-found = Keine Protokollzeile enthält "{ $text }".
+
+## Startup phases
+startup-phases = Startphasen
+phases-from-first-read = Ab dem ersten Lesen statt ab dem Einhängen
+phases-hint = Enden der Phasen in Sekunden, durch Kommas getrennt. Pro Phase werden die Dateien mit den meisten gelesenen Bytes aufgeführt.
+phases-invalid = Die Enden der Phasen müssen aufsteigende, durch Kommas getrennte Sekunden sein, die Standardphasen werden angezeigt.
+phase-title = { $from } s bis { $to } s: { $size } aus { $files } Dateien
+phase-title-last = Nach { $from } s: { $size } aus { $files } Dateien
+
+## Unused files
+refresh = Aktualisieren
+unused-files = Ungenutzte Dateien
+unused-directory = Verzeichnis
+unused-bytes = Ungenutzt
+unused-total-bytes = Gesamt
+unused-file-count = Ungenutzte Dateien
+unused-title = { $count } Dateien mit { $size } von { $total } in der Quelle wurden nie gelesen.
+
+## Search
+search-placeholder = Alle Ereignisse durchsuchen (Strg+F)
+search-previous = Zurück
+search-next = Weiter
+search-position = { $current } von { $count }
+search-count = { $count } Treffer
+search-match-hidden = (durch die aktuellen Filter ausgeblendet)
+
+## Filter presets
+path-include = Pfade zeigen, die enthalten, z. B. .dds
+path-exclude = Pfade ausblenden, die enthalten
+filter-presets = Filtervorlagen
+preset-name = Name der Vorlage
+save-preset = Vorlage speichern
+delete-preset = Vorlage löschen
+
+## Statistics
+statistics = Statistik
+statistics-title = Lesezugriffe im gewählten Zeitfenster
+statistics-read-size = Größe der Lesezugriffe
+statistics-read-latency = Dauer der Lesezugriffe
+statistics-samples = { $count } Lesezugriffe
+
+## Watched paths
+watch-rules = Beobachtete Pfade
+watch-rules-help = Zugriffe auf Pfade, die ein Muster enthalten, werden mit einer Desktop-Benachrichtigung gemeldet und können einen Befehl ausführen. Der Befehl erhält Datei, Prozess, PID, Offset und Größe als $1 bis $5 und als FFAM_FILE, FFAM_PROCESS, FFAM_PID, FFAM_OFFSET, FFAM_SIZE und FFAM_TIME.
+watch-command = Befehl, z.B. gcore $3
+watch-pattern = Muster, z. B. Data/world.bin
+watch-notify = Benachrichtigen
+watch-hits = { $count } Zugriffe, zuletzt um { $last }
+watch-never = Noch nicht gelesen
+add-watch = Pfad beobachten
+watch-selected-file = Ausgewählte Datei beobachten
+watch-sound = Ton abspielen
+watch-notification-summary = Beobachteter Pfad gelesen: { $pattern }
+watch-notification-body = { $process } hat { $file } gelesen
+rule-notification-summary = Eine Regel des Mounts hat gegriffen
+
+## Columns
+columns = Spalten
+columns-help = Angezeigte Felder der Protokollzeilen in dieser Reihenfolge. Der Export verwendet dasselbe Layout.
@@ -0,0 +1,369 @@
+window-title = FUSE File Access Monitor
+
+## Unmounted screen
+source-directory = Source Directory
+source-directory-label = Source Directory:
+mountpoint = Mountpoint
+mountpoint-label = Mountpoint:
+drop-tip = Tip: folders can be dropped onto the window.
+capture-payloads = Capture read payloads (double-click an event to inspect it)
+use-dropped-as = Use { $path } as
+mount = Mount
+mounting = Mounting...
+scan-progress = Scanned { $directories } directories, found { $files } files
+mount-cancelled = Mounting was cancelled.
+unmounting = Unmounting...
+language = Language
+
+## Mounted screen
+unmount = Unmount
+highlight-rules = Highlight rules
+pin-selected-event = Pin selected event
+clear-log = Clear log
+export-path = Export path
+export-log = Export log
+events-logged = { $count } events logged.
+events-logged-capped = Showing the last { $count } of { $total } events (full log in export).
+spill-failed = Failed to keep dropped events for the export: { $error }
+events-lost = { $count } events were lost, the log couldn't keep up.
+browse-spilled = Browse older events
+spilled-page = Older events, page { $page } of { $pages }
+spilled-page-failed = Failed to read older events: { $error }
+close = Close
+cancel = Cancel
+dismiss = Dismiss
+
+## Highlight rules
+highlight-rules-help = Lines containing a pattern are colored, the first matching rule wins.
+highlight-pattern = Pattern, e.g. .sav or sounds/
+remove = Remove
+add-rule = Add rule
+color-red = Red
+color-orange = Orange
+color-yellow = Yellow
+color-green = Green
+color-blue = Blue
+color-purple = Purple
+color-gray = Gray
+
+## Timestamps
+timestamp-utc = UTC time
+timestamp-local = Local time
+timestamp-since-mount = Time since mount
+
+## Details and bookmarks
+event-details = Event details
+pinned-events = Pinned events
+unpin = Unpin
+detail-time = Time
+detail-operation = Operation
+detail-file = File
+detail-full-path = Full path
+detail-offset = Offset
+detail-size = Size
+detail-file-size = File size
+detail-handle = Handle
+detail-latency = Latency
+detail-coverage = Coverage so far
+detail-payload = Payload
+detail-renamed-to = Renamed to
+detail-target = Link target
+detail-attribute = Attribute
+detail-mode = Permissions
+detail-uid = Owner
+detail-gid = Group
+detail-accessed = Accessed
+detail-modified = Modified
+operation-read = Read
+operation-open = Open
+operation-close = Close
+operation-lookup = Lookup
+operation-getattr = Attributes
+operation-readdir = List
+operation-write = Write
+operation-create = Create
+operation-truncate = Truncate
+operation-remove = Remove
+operation-rename = Rename
+operation-mkdir = Create directory
+operation-rmdir = Remove directory
+operation-symlink = Create link
+operation-chmod = Change permissions
+operation-chown = Change owner
+operation-utimens = Change times
+operation-readlink = Resolve link
+operation-getxattr = Get attribute
+operation-listxattr = List attributes
+operation-setxattr = Set attribute
+not-found = (not found)
+operation-marker = Marker
+operation-derived = From plugin { $plugin }
+detail-label = Label
+bytes = { $count } bytes
+coverage = { $covered } of { $total } bytes ({ $percentage }%)
+payload-captured = { $count } bytes captured
+payload-not-captured = not captured
+
+## Hex viewer
+hex-more-bytes = ... { $count } more bytes not shown
+hex-no-payload = No payload was captured for this event.
+
+## Confirmation
+unexported-log = The log of { $count } events has not been exported yet.
+save-to = Save to:
+save-and-unmount = Save and unmount
+save-and-exit = Save and exit
+unmount-without-saving = Unmount without saving
+exit-without-saving = Exit without saving
+
+## Status bar
+uptime = Up { $time }
+event-count = { $count } events
+event-rate = { $rate } events/s
+sink-queue = Queue { $queued }/{ $capacity }
+sink-closed = Queue closed
+events-dropped = { $count } lost
+
+## Notifications
+mount-failed = Mounting failed: { $error }
+hint-fuse-device-missing = /dev/fuse does not exist. Loading the fuse module creates it, in a container it has to be passed in.
+hint-fuse-module-not-loaded = The fuse kernel module is not loaded. Load it with "sudo modprobe fuse".
+hint-fusermount-missing = fusermount3 was not found. Install the fuse3 package of your distribution.
+hint-mountpoint-busy = Something is already mounted at { $mountpoint }. Unmount it with "fusermount3 -u { $mountpoint }" or pick another mountpoint.
+hint-fusefs-not-loaded = /dev/fuse does not exist. Load the fusefs module with "kldload fusefs" (add fusefs_load="YES" to /boot/loader.conf to load it at boot).
+hint-mountpoint-busy-freebsd = Something is already mounted at { $mountpoint }. Unmount it with "umount { $mountpoint }" or pick another mountpoint.
+hint-permission-denied-freebsd = You are not allowed to mount at { $mountpoint }. Mounting as a normal user needs "sysctl vfs.usermount=1", a directory you own and access to /dev/fuse (the operator group).
+hint-allow-other = Allowing other users needs "user_allow_other" in /etc/fuse.conf. Add it or disable that option.
+hint-permission-denied = You are not allowed to mount at { $mountpoint }. Pick a directory you own and check that /dev/fuse is accessible.
+hint-mountpoint-not-empty = The mountpoint is not empty. Pick an empty directory, its contents are hidden while mounted.
+mountpoint-not-directory = Mountpoint is not a directory.
+source-not-directory = Source is not a directory.
+not-a-folder = { $path } is not a folder.
+unmount-without-mount = Somehow unmount was pressed, even though nothing was mounted...? Oh well.
+exported = Exported { $count } events to { $path }.
+export-failed = Exporting the log to { $path } failed: { $error }
+html-exported = Wrote the html report to { $path }.
+html-export-failed = Writing the html report to { $path } failed: { $error }
+export-html = Export html report
+
+## Other monitors
+
+active-mounts = Mounted by other monitors
+active-mount = { $mountpoint } ← { $source } (pid { $pid })
+unmount-other-failed = Unmounting failed: { $error }
+stale-mounts = A monitor crashed and left these mounts behind, they can't be accessed ("Transport endpoint is not connected") until they are unmounted:
+recover-stale-mounts = Unmount them
+stale-mount-not-recovered = { $mountpoint } could not be unmounted. Try "fusermount3 -uz { $mountpoint }" as its owner.
+
+## Setup
+
+setup-title = Mounting needs some setup first
+setup-fuse-device-missing = /dev/fuse does not exist, the fuse kernel module is not loaded.
+setup-fuse-device-not-passed = /dev/fuse is not available in the container. Start it with "--device /dev/fuse --cap-add SYS_ADMIN" (Docker) or "--device /dev/fuse" (Podman).
+setup-fusermount-not-setuid = { $path } is not setuid root, so normal users can't mount with it.
+setup-fuse-device-group = You are not allowed to use /dev/fuse. Join the { $group } group and log in again.
+setup-fuse-device-not-accessible = You are not allowed to use /dev/fuse.
+setup-user-mount-disabled = Normal users are not allowed to mount (vfs.usermount is 0).
+setup-nfs-client-missing = mount.nfs was not found. Install the NFS client of your distribution.
+setup-nfs-needs-root = Mounting over NFS needs root. Start the monitor as root or use FUSE.
+setup-copy-command = Copy command
+setup-check-again = Check again
+setup-use-nfs = Use NFS instead
+setup-complete = Everything needed for mounting is set up.
+
+## Tray icon
+tray-enabled = Show a tray icon while mounted
+tray-minimize = Minimize to tray
+tray-show = Show window
+tray-activity = { $count } events, { $rate } events/s
+tray-failed = The tray icon could not be created: { $error }
+
+## Mount options
+advanced-options = Advanced options
+script-help = Rhai script run for every event. Variables: kind, path, process, pid, offset, size. Functions: tag(text), notify(text), redirect(file), log(text). Evaluating to false drops the event.
+script-placeholder = if path.ends_with(".wav") {"{"} false {"}"}
+script-notification-summary = Script notification
+allow-other = Allow other users to access the mount (allow_other)
+auto-unmount = Unmount automatically when the monitor exits (auto_unmount)
+read-only = Mount read-only
+stats-file = Live statistics as json at .ffam/stats in the mount
+control-file = Take commands written to .ffam/ctl in the mount (pause, marker, filter, flush)
+metadata-events = Also report lookups, attribute requests and directory listings
+direct-io = Bypass the kernel cache, so every read is reported (slower)
+write-through = Write created and changed files to the source directory
+nfs-backend = Serve over a local NFS server instead of FUSE (mounting needs root)
+case-sensitivity-label = Names:
+case-sensitive = Case-sensitive
+case-insensitive = Ignore case, new names in lowercase
+case-insensitive-preserving = Ignore case, keep the case of new names (like Windows)
+ttl-label = Attribute cache TTL (s):
+include-label = Only monitor paths containing:
+exclude-label = Don't monitor paths containing:
+filter-placeholder = Comma separated, e.g. .sav, sounds/
+ttl-invalid = The TTL has to be a non-negative number of seconds.
+max-log-entries-label = Events kept in the log:
+archive-index-label = Archive index:
+archive-index-placeholder = .json or lines of container<TAB>offset<TAB>size<TAB>asset
+
+## Processes
+all-processes = All processes
+detail-process = Process
+detail-pid = PID
+detail-uid = UID
+detail-user = UID / GID
+
+## Grouping
+group-reads = Group consecutive reads
+read-group = { $count } reads, { $size } from { $file } (double-click to expand)
+read-summary = { $count } reads, { $size } from { $file }
+live-view-summarized = More than { $rate } events/s, the live view shows one line per file and second. Search and export still contain every event.
+
+## File list
+file-list = File list
+all-files = All files
+file-entry = { $file } ({ $reads } reads, { $pattern })
+file-stats = { $file }: { $reads } reads, { $bytes } read, { $covered } of { $total } covered ({ $percentage }%), { $pattern }: { $sequential }% sequential, { $seek } skipped on average
+pattern-sequential = sequential
+pattern-strided = strided
+pattern-random = random
+
+## Copying accessed files
+copy-target = Directory to copy or extract the files read so far to
+copy-accessed = Copy accessed files
+files-copied = Copied { $count } files ({ $size }) to { $target }.
+copy-failed = Failed to copy the accessed files: { $error }
+extract-sparse = Extract reads (sparse)
+extract-concatenated = Extract reads (concatenated)
+reads-extracted = Extracted { $size } read from { $count } files to { $target }.
+extract-failed = Failed to extract the reads: { $error }
+
+## Manifest
+manifest-path = Manifest, a .json array or one path per line
+compare-manifest = Compare with manifest
+manifest-accessed = Expected and read: { $count }
+manifest-not-accessed = Expected, but never read: { $count }
+manifest-missing = Expected, but missing from the source: { $count }
+manifest-unexpected = Read, but not expected: { $count }
+
+## Timeline
+timeline = Timeline
+timeline-reset = Whole session
+
+## Sessions
+session-name = Session name, e.g. vanilla run
+save-session = Save session
+load-session = Load session
+session-saved = Session { $name } saved.
+session-save-failed = Failed to save the session: { $error }
+session-load-failed = Failed to load the session: { $error }
+compare-sessions = Compare sessions:
+compare = Compare
+comparison-title = Files read by { $first } and { $second }, biggest differences first
+compare-reads = reads
+compare-bytes = bytes
+
+## Markers
+marker-label = Marker label (optional)
+add-marker = Mark (Ctrl+M)
+marker-default = Marker { $number }
+
+## Treemap
+treemap = Treemap
+treemap-up = Up
+treemap-title = { $dir }: { $size } read. Click a directory to open it.
+
+## Flame graph
+flame-graph = Flame graph
+flame-graph-reset = Show all
+flame-graph-title = { $count } accesses in the selected time window. Click a box to zoom in.
+
+## Access order
+access-order = Access order
+access-order-time = First read
+access-order-title = { $count } files in the order they were first read in the selected time window.
+
+## Redundant reads
+redundant-reads = Redundant reads
+redundant-bytes = Read again
+redundant-rereads = Rereads
+redundant-ranges = Ranges
+redundant-max-repeats = Most reads of a range
+redundant-per-second = Rereads per second
+redundant-title = { $count } files were read repeatedly at the same ranges in the selected time window, { $size } were read again.
+
+## Process usage
+process-usage = Processes
+process-usage-files = Files
+process-usage-tree-files = Files with children
+process-usage-tree-bytes = Bytes with children
+process-usage-title = { $count } processes read files in the selected time window. Child processes are listed below their parent.
+
+## External log
+external-log-path = Log of another program, e.g. the game's debug log
+load-external-log = Show interleaved
+external-log-title = { $lines } lines of the external log interleaved with the events of the selected time window, showing { $shown } of { $total } entries.
+external-offset = Offset in seconds
+external-anchor = or align
+external-anchor-marker = Marker
+external-anchor-text = with the log line containing
+external-align = Align
+external-anchor-not-found = No log line contains "{ $text }".
+
+## Startup phases
+startup-phases = Startup phases
+phases-from-first-read = From the first read instead of mounting
+phases-hint = Ends of the phases in seconds, separated by commas. The files with the most bytes read are listed per phase.
+phases-invalid = The ends of the phases have to be ascending seconds separated by commas, showing the default phases.
+phase-title = { $from } s to { $to } s: { $size } from { $files } files
+phase-title-last = After { $from } s: { $size } from { $files } files
+
+## Unused files
+refresh = Refresh
+unused-files = Unused files
+unused-directory = Directory
+unused-bytes = Unused
+unused-total-bytes = Total
+unused-file-count = Unused files
+unused-title = { $count } files with { $size } of { $total } in the source were never read.
+
+## Search
+search-placeholder = Search all events (Ctrl+F)
+search-previous = Previous
+search-next = Next
+search-position = { $current } of { $count }
+search-count = { $count } matches
+search-match-hidden = (hidden by the current filters)
+
+## Filter presets
+path-include = Show paths containing, e.g. .dds
+path-exclude = Hide paths containing
+filter-presets = Filter presets
+preset-name = Preset name
+save-preset = Save preset
+delete-preset = Delete preset
+
+## Statistics
+statistics = Statistics
+statistics-title = Reads in the selected time window
+statistics-read-size = Read size
+statistics-read-latency = Read latency
+statistics-samples = { $count } reads
+
+## Watched paths
+watch-rules = Watched paths
+watch-rules-help = Accesses to paths containing a pattern are announced with a desktop notification and can run a command. The command gets the file, process, pid, offset and size as $1 to $5 and as FFAM_FILE, FFAM_PROCESS, FFAM_PID, FFAM_OFFSET, FFAM_SIZE and FFAM_TIME.
+watch-command = Command, e.g. gcore $3
+watch-pattern = Pattern, e.g. Data/world.bin
+watch-notify = Notify
+watch-hits = { $count } accesses, last at { $last }
+watch-never = Not accessed yet
+add-watch = Add watched path
+watch-selected-file = Watch selected file
+watch-sound = Play a sound
+watch-notification-summary = Watched path accessed: { $pattern }
+watch-notification-body = { $process } read { $file }
+rule-notification-summary = A rule of the mount matched
+
+## Columns
+columns = Columns
+columns-help = Shown fields of the log lines, in this order. The export uses the same layout.
@@ -0,0 +1,512 @@
+use std::collections::{HashMap, HashSet};
+use std::ffi::{OsStr, OsString};
+use std::sync::Arc;
+
+use crate::fs::{Event, EventType};
+use crate::source::Source;
+
+/// Merges overlapping byte ranges (start, end) and returns how many distinct bytes they cover.
+pub fn covered_bytes(ranges : impl Iterator<Item = (u64, u64)>) -> u64 {
+    let mut ranges : Vec<_> = ranges.filter(|(start, end)| start < end).collect();
+    ranges.sort();
+    let mut covered = 0;
+    let mut current : Option<(u64, u64)> = None;
+    for (start, end) in ranges {
+        match current {
+            Some((current_start, current_end)) if start <= current_end => {
+                current = Some((current_start, current_end.max(end)));
+            }
+            Some((current_start, current_end)) => {
+                covered += current_end - current_start;
+                current = Some((start, end));
+            }
+            None => {
+                current = Some((start, end));
+            }
+        }
+    }
+    if let Some((start, end)) = current {
+        covered += end - start;
+    }
+    covered
+}
+
+/// Adds the range from `start` to `end` to sorted ranges that don't overlap, merging it with the ones it touches.
+pub fn add_range(ranges : &mut Vec<(u64, u64)>, start : u64, end : u64) {
+    if start >= end {
+        return;
+    }
+    // the ranges touching the new one are the ones from the first ending at its start to the last starting at its end
+    let first = ranges.partition_point(|(_, range_end)| *range_end < start);
+    let last = ranges.partition_point(|(range_start, _)| *range_start <= end);
+    let merged = match &ranges[first..last] {
+        [] => (start, end),
+        touched => (touched[0].0.min(start), touched[touched.len() - 1].1.max(end))
+    };
+    ranges.splice(first..last, [merged]);
+}
+
+/// Bytes of `file` that were read by the given events, reads past the end of the file are clamped.
+pub fn file_coverage<'a>(events : impl Iterator<Item = &'a Event>, file : &OsStr) -> u64 {
+    covered_bytes(events.filter_map(|event| match &event.event {
+        EventType::Read(read) if read.file.as_os_str() == file => {
+            let start = (read.offset as u64).min(read.file_size);
+            let end = (read.offset as u64 + read.size as u64).min(read.file_size);
+            Some((start, end))
+        }
+        _ => None
+    }))
+}
+
+/// Human readable size with a decimal unit, e.g. "14.2 MB".
+pub fn format_size(bytes : u64) -> String {
+    const UNITS: [&str; 5] = ["B", "kB", "MB", "GB", "TB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1000.0 && unit < UNITS.len() - 1 {
+        size /= 1000.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{size:.1} {}", UNITS[unit])
+    }
+}
+
+/// A direct child of a directory with the bytes read from it, for directories this includes all files below.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SizeEntry {
+    pub name : String,
+    pub path : String,
+    pub bytes : u64,
+    pub is_dir : bool
+}
+
+/// Sums the bytes read per file into the direct children of `dir` ("" is the root), biggest first.
+pub fn children_sizes<'a>(files : impl Iterator<Item = (&'a str, u64)>, dir : &str) -> Vec<SizeEntry> {
+    let mut children : Vec<SizeEntry> = Vec::new();
+    for (file, bytes) in files {
+        let relative = if dir.is_empty() {
+            file
+        } else {
+            match file.strip_prefix(dir).and_then(|rest| rest.strip_prefix('/')) {
+                Some(relative) => relative,
+                None => continue
+            }
+        };
+        let (name, is_dir) = match relative.split_once('/') {
+            Some((name, _)) => (name, true),
+            None => (relative, false)
+        };
+        match children.iter_mut().find(|child| child.name == name && child.is_dir == is_dir) {
+            Some(child) => child.bytes += bytes,
+            None => children.push(SizeEntry {
+                name: name.to_string(),
+                path: if dir.is_empty() { name.to_string() } else { format!("{dir}/{name}") },
+                bytes,
+                is_dir
+            })
+        }
+    }
+    children.sort_by_key(|child| std::cmp::Reverse(child.bytes));
+    children
+}
+
+/// Access counts along the path hierarchy, every node counts the accesses of everything below it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PathNode {
+    pub name : String,
+    pub path : String,
+    pub count : u64,
+    pub children : std::collections::BTreeMap<String, PathNode>
+}
+
+impl PathNode {
+    pub fn build<'a>(files : impl Iterator<Item = &'a str>) -> Self {
+        let mut root = PathNode::default();
+        for file in files {
+            root.count += 1;
+            let mut node = &mut root;
+            for component in file.split('/').filter(|component| !component.is_empty()) {
+                let path = if node.path.is_empty() { component.to_string() } else { format!("{}/{component}", node.path) };
+                node = node.children.entry(component.to_string()).or_insert_with(|| PathNode {
+                    name: component.to_string(),
+                    path,
+                    ..PathNode::default()
+                });
+                node.count += 1;
+            }
+        }
+        root
+    }
+
+    /// The node at `path` below this one ("" is this node).
+    pub fn find(&self, path : &str) -> Option<&PathNode> {
+        let mut node = self;
+        for component in path.split('/').filter(|component| !component.is_empty()) {
+            node = node.children.get(component)?;
+        }
+        Some(node)
+    }
+}
+
+/// Value below which `percentage` percent of the values lie, `sorted` has to be sorted ascending.
+pub fn percentile(sorted : &[u64], percentage : f64) -> u64 {
+    if sorted.is_empty() {
+        return 0;
+    }
+    let rank = (percentage / 100.0 * (sorted.len() - 1) as f64).round() as usize;
+    sorted[rank.min(sorted.len() - 1)]
+}
+
+/// Counts values in power of two buckets, returns (lower bound, upper bound, count) for every bucket from the smallest to the largest value.
+pub fn log2_histogram(values : &[u64]) -> Vec<(u64, u64, usize)> {
+    let bucket = |value : u64| if value == 0 { 0 } else { 64 - value.leading_zeros() as usize };
+    let Some(max) = values.iter().map(|value| bucket(*value)).max() else {
+        return Vec::new();
+    };
+    let min = values.iter().map(|value| bucket(*value)).min().unwrap_or(0);
+    let mut counts = vec![0; max + 1];
+    for value in values {
+        counts[bucket(*value)] += 1;
+    }
+    (min..=max).map(|i| {
+        let (from, to) = if i == 0 { (0, 0) } else { (1u64 << (i - 1), (1u64 << (i - 1)).saturating_mul(2) - 1) };
+        (from, to, counts[i])
+    }).collect()
+}
+
+/// The first read of a file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FirstAccess {
+    pub file : Arc<OsString>,
+    pub time : chrono::DateTime<chrono::Utc>,
+    pub process : Arc<String>
+}
+
+/// Files in the order they were first read, with the time and process of their first read.
+pub fn access_order<'a>(events : impl Iterator<Item = &'a Event>) -> Vec<FirstAccess> {
+    let mut seen = HashSet::new();
+    events.filter_map(|event| match &event.event {
+        EventType::Read(read) if seen.insert(read.file.clone()) => Some(FirstAccess {
+            file: read.file.clone(),
+            time: event.time,
+            process: event.process.name.clone()
+        }),
+        _ => None
+    }).collect()
+}
+
+/// Writes one tab separated line per file: seconds since `start`, time of the first read, process and path.
+pub fn write_access_order(out : &mut impl std::io::Write, accesses : &[FirstAccess], start : chrono::DateTime<chrono::Utc>) -> std::io::Result<()> {
+    for access in accesses {
+        let since_start = (access.time - start).num_milliseconds() as f64 / 1000.0;
+        writeln!(out, "t+{since_start:.3}s\t{}\t{}\t{}", access.time, access.process, access.file.display())?;
+    }
+    Ok(())
+}
+
+/// A directory with the sizes of the files below it, counting only the files that were never read in `files` and `bytes`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UnusedDirectory {
+    pub path : String,
+    pub files : usize,
+    pub bytes : u64,
+    pub total_bytes : u64
+}
+
+/// Files that were never read and the directories they are in, both biggest first.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UnusedReport {
+    pub files : Vec<(String, u64)>,
+    pub directories : Vec<UnusedDirectory>,
+    pub bytes : u64,
+    pub total_bytes : u64
+}
+
+/// Collects the files of `files` (path and size) for which `accessed` is false and sums them up per directory.
+pub fn unused_files<'a>(files : impl Iterator<Item = (&'a str, u64)>, accessed : impl Fn(&str) -> bool) -> UnusedReport {
+    let mut report = UnusedReport::default();
+    let mut directories : HashMap<&str, UnusedDirectory> = HashMap::new();
+    for (file, size) in files {
+        let unused = !accessed(file);
+        report.total_bytes += size;
+        if unused {
+            report.bytes += size;
+            report.files.push((file.to_string(), size));
+        }
+        let ancestors = file.match_indices('/').map(|(end, _)| &file[..end]);
+        for dir in ancestors {
+            let directory = directories.entry(dir).or_insert_with(|| UnusedDirectory { path: dir.to_string(), ..UnusedDirectory::default() });
+            directory.total_bytes += size;
+            if unused {
+                directory.files += 1;
+                directory.bytes += size;
+            }
+        }
+    }
+    report.files.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    report.directories = directories.into_values().filter(|directory| directory.files > 0).collect();
+    report.directories.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.path.cmp(&b.path)));
+    report
+}
+
+/// Paths and sizes of all files of a source.
+pub fn source_files(source : &dyn Source) -> std::io::Result<Vec<(String, u64)>> {
+    let mut files = Vec::new();
+    let mut directories = vec![OsString::new()];
+    while let Some(dir) = directories.pop() {
+        for (name, is_dir) in source.read_dir_kinds(&dir)? {
+            let path = match dir.is_empty() {
+                true => name,
+                false => [dir.as_os_str(), OsStr::new("/"), &name].into_iter().collect()
+            };
+            let is_dir = match is_dir {
+                Some(is_dir) => is_dir,
+                None => source.stat(&path)?.is_dir
+            };
+            if is_dir {
+                directories.push(path);
+            } else {
+                let size = source.stat(&path)?.size;
+                files.push((path.to_string_lossy().into_owned(), size));
+            }
+        }
+    }
+    Ok(files)
+}
+
+/// Writes the directories and then the files of the report, one tab separated line each with the unused bytes first.
+pub fn write_unused(out : &mut impl std::io::Write, report : &UnusedReport) -> std::io::Result<()> {
+    writeln!(out, "# {} of {} bytes in {} files were never read", report.bytes, report.total_bytes, report.files.len())?;
+    writeln!(out, "# directories: unused bytes, total bytes, unused files, path")?;
+    for directory in &report.directories {
+        writeln!(out, "{}\t{}\t{}\t{}/", directory.bytes, directory.total_bytes, directory.files, directory.path)?;
+    }
+    writeln!(out, "# files: size, path")?;
+    for (file, size) in &report.files {
+        writeln!(out, "{size}\t{file}")?;
+    }
+    Ok(())
+}
+
+/// Reads of a file that asked for a range that was already read before, e.g. a program polling a config file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RedundantReads {
+    pub file : Arc<OsString>,
+    pub ranges : usize, // distinct ranges that were read more than once
+    pub rereads : u64, // reads of a range after its first
+    pub bytes : u64, // requested by the rereads
+    pub max_repeats : u64, // reads of the range read most often
+    pub per_second : f64 // rereads per second between the first and the last read of the file
+}
+
+/// Files with reads of identical ranges, most redundant bytes first.
+pub fn redundant_reads<'a>(events : impl Iterator<Item = &'a Event>) -> Vec<RedundantReads> {
+    // reads per range and the times of the first and the last read, per file
+    let mut files : HashMap<&Arc<OsString>, (HashMap<(usize, usize), u64>, chrono::DateTime<chrono::Utc>, chrono::DateTime<chrono::Utc>)> = HashMap::new();
+    for event in events {
+        if let EventType::Read(read) = &event.event {
+            let (ranges, _, last) = files.entry(&read.file).or_insert_with(|| (HashMap::new(), event.time, event.time));
+            *ranges.entry((read.offset, read.size)).or_default() += 1;
+            *last = event.time;
+        }
+    }
+    let mut redundant : Vec<RedundantReads> = files.into_iter().filter_map(|(file, (ranges, first, last))| {
+        let repeated : Vec<(usize, u64)> = ranges.into_iter().filter(|(_, count)| *count > 1).map(|((_, size), count)| (size, count)).collect();
+        if repeated.is_empty() {
+            return None;
+        }
+        let rereads = repeated.iter().map(|(_, count)| count - 1).sum();
+        let seconds = (last - first).num_milliseconds() as f64 / 1000.0;
+        Some(RedundantReads {
+            file: file.clone(),
+            ranges: repeated.len(),
+            rereads,
+            bytes: repeated.iter().map(|(size, count)| *size as u64 * (count - 1)).sum(),
+            max_repeats: repeated.iter().map(|(_, count)| *count).max().unwrap_or(0),
+            per_second: if seconds > 0.0 { rereads as f64 / seconds } else { 0.0 }
+        })
+    }).collect();
+    redundant.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.file.cmp(&b.file)));
+    redundant
+}
+
+/// Writes one tab separated line per file: redundant bytes, rereads, ranges, reads of the most read range, rereads per second and path.
+pub fn write_redundant_reads(out : &mut impl std::io::Write, redundant : &[RedundantReads]) -> std::io::Result<()> {
+    writeln!(out, "# redundant bytes, rereads, ranges, max reads of a range, rereads per second, path")?;
+    for file in redundant {
+        writeln!(out, "{}\t{}\t{}\t{}\t{:.2}\t{}", file.bytes, file.rereads, file.ranges, file.max_repeats, file.per_second, file.file.display())?;
+    }
+    Ok(())
+}
+
+/// Reads of one process, and of it together with its child processes that read as well.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProcessUsage {
+    pub pid : u32,
+    pub name : Arc<String>,
+    pub depth : usize, // below the process at the root of its tree
+    pub files : usize,
+    pub reads : u64,
+    pub bytes : u64,
+    pub tree_files : usize, // distinct files read by the process and its descendants
+    pub tree_bytes : u64
+}
+
+/// Reads per process, in process trees: every process comes after its parent, roots and siblings with the most bytes first.
+/// Processes whose parent didn't read anything are roots.
+pub fn process_usage<'a>(events : impl Iterator<Item = &'a Event>) -> Vec<ProcessUsage> {
+    struct Reads<'a> {
+        name : Arc<String>,
+        parent : Option<u32>,
+        files : HashSet<&'a Arc<OsString>>,
+        reads : u64,
+        bytes : u64
+    }
+    let mut processes : HashMap<u32, Reads> = HashMap::new();
+    for event in events {
+        if let EventType::Read(read) = &event.event {
+            let process = processes.entry(event.process.pid).or_insert_with(|| Reads {
+                name: event.process.name.clone(),
+                parent: event.process.parent,
+                files: HashSet::new(),
+                reads: 0,
+                bytes: 0
+            });
+            process.files.insert(&read.file);
+            process.reads += 1;
+            process.bytes += read.size as u64;
+        }
+    }
+    // the files and bytes of every process count for all of its ancestors that read as well
+    let mut trees : HashMap<u32, (HashSet<&Arc<OsString>>, u64)> = HashMap::new();
+    for (pid, process) in &processes {
+        let mut ancestor = Some(*pid);
+        // a process can't be its own ancestor, but reused pids can make it look like one
+        let mut visited = HashSet::new();
+        while let Some(current) = ancestor.filter(|current| processes.contains_key(current) && visited.insert(*current)) {
+            let tree = trees.entry(current).or_default();
+            tree.0.extend(process.files.iter().copied());
+            tree.1 += process.bytes;
+            ancestor = processes[&current].parent;
+        }
+    }
+    let mut children : HashMap<Option<u32>, Vec<u32>> = HashMap::new();
+    for (pid, process) in &processes {
+        let parent = process.parent.filter(|parent| processes.contains_key(parent) && parent != pid);
+        children.entry(parent).or_default().push(*pid);
+    }
+    for pids in children.values_mut() {
+        pids.sort_by_key(|pid| (std::cmp::Reverse(trees[pid].1), *pid));
+    }
+    let mut usage = Vec::with_capacity(processes.len());
+    let mut stack : Vec<(u32, usize)> = children.get(&None).into_iter().flatten().rev().map(|pid| (*pid, 0)).collect();
+    let mut listed = HashSet::new();
+    loop {
+        // processes in a cycle of parents have no root, one of them is listed as the root once the others are done
+        let next = stack.pop().or_else(|| {
+            processes.keys().filter(|pid| !listed.contains(*pid)).min_by_key(|pid| (std::cmp::Reverse(trees[pid].1), **pid)).map(|pid| (*pid, 0))
+        });
+        let Some((pid, depth)) = next else {
+            break;
+        };
+        if !listed.insert(pid) {
+            continue;
+        }
+        let process = &processes[&pid];
+        let (tree_files, tree_bytes) = &trees[&pid];
+        usage.push(ProcessUsage {
+            pid,
+            name: process.name.clone(),
+            depth,
+            files: process.files.len(),
+            reads: process.reads,
+            bytes: process.bytes,
+            tree_files: tree_files.len(),
+            tree_bytes: *tree_bytes
+        });
+        stack.extend(children.get(&Some(pid)).into_iter().flatten().rev().map(|child| (*child, depth + 1)));
+    }
+    usage
+}
+
+/// Writes one tab separated line per process, the name indented by its depth in the process tree.
+pub fn write_process_usage(out : &mut impl std::io::Write, usage : &[ProcessUsage]) -> std::io::Result<()> {
+    writeln!(out, "# pid, files, reads, bytes, files with children, bytes with children, process")?;
+    for process in usage {
+        writeln!(out, "{}\t{}\t{}\t{}\t{}\t{}\t{}{}", process.pid, process.files, process.reads, process.bytes,
+            process.tree_files, process.tree_bytes, "  ".repeat(process.depth), process.name)?;
+    }
+    Ok(())
+}
+
+/// Default ends of the startup phases in seconds, the last phase has no end.
+pub const DEFAULT_PHASE_BOUNDS : [f64; 4] = [5.0, 15.0, 30.0, 60.0];
+
+/// Reads of one file within a phase.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PhaseFile {
+    pub file : Arc<OsString>,
+    pub reads : u64,
+    pub bytes : u64
+}
+
+/// Reads within a time window after the start, files with the most bytes first.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Phase {
+    pub from : f64, // seconds since the start
+    pub to : Option<f64>, // None for the last phase
+    pub reads : u64,
+    pub bytes : u64,
+    pub files : Vec<PhaseFile>
+}
+
+/// Parses ascending phase ends in seconds separated by commas, e.g. "5, 15, 30".
+pub fn parse_phase_bounds(text : &str) -> Option<Vec<f64>> {
+    let bounds : Vec<f64> = text.split(',').map(|bound| bound.trim().parse::<f64>().ok().filter(|bound| *bound > 0.0)).collect::<Option<_>>()?;
+    bounds.is_sorted_by(|a, b| a < b).then_some(bounds)
+}
+
+/// Buckets the reads into phases ending at `bounds` (seconds since `start`, ascending), reads before the start count for the first phase.
+pub fn phases<'a>(events : impl Iterator<Item = &'a Event>, start : chrono::DateTime<chrono::Utc>, bounds : &[f64]) -> Vec<Phase> {
+    let mut files : Vec<HashMap<&Arc<OsString>, (u64, u64)>> = vec![HashMap::new(); bounds.len() + 1];
+    for event in events {
+        if let EventType::Read(read) = &event.event {
+            let seconds = (event.time - start).num_milliseconds() as f64 / 1000.0;
+            let phase = bounds.partition_point(|bound| *bound <= seconds);
+            let file = files[phase].entry(&read.file).or_default();
+            file.0 += 1;
+            file.1 += read.size as u64;
+        }
+    }
+    files.into_iter().enumerate().map(|(index, files)| {
+        let mut files : Vec<PhaseFile> = files.into_iter().map(|(file, (reads, bytes))| PhaseFile { file: file.clone(), reads, bytes }).collect();
+        files.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.file.cmp(&b.file)));
+        Phase {
+            from: if index == 0 { 0.0 } else { bounds[index - 1] },
+            to: bounds.get(index).copied(),
+            reads: files.iter().map(|file| file.reads).sum(),
+            bytes: files.iter().map(|file| file.bytes).sum(),
+            files
+        }
+    }).collect()
+}
+
+/// Start time of a phase report from the first read instead of the mount, e.g. when the game was launched later.
+pub fn first_read_time<'a>(mut events : impl Iterator<Item = &'a Event>) -> Option<chrono::DateTime<chrono::Utc>> {
+    events.find(|event| matches!(event.event, EventType::Read(_))).map(|event| event.time)
+}
+
+/// Writes a `# from-to` header per phase with its totals, then the `top` files of the phase with the most bytes.
+pub fn write_phases(out : &mut impl std::io::Write, phases : &[Phase], top : usize) -> std::io::Result<()> {
+    for phase in phases {
+        let to = phase.to.map(|to| format!("{to}s")).unwrap_or_default();
+        writeln!(out, "# {}s-{to}: {} reads, {} bytes, {} files", phase.from, phase.reads, phase.bytes, phase.files.len())?;
+        for file in phase.files.iter().take(top) {
+            writeln!(out, "{}\t{}\t{}", file.bytes, file.reads, file.file.display())?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,108 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::fs::{Event, EventType};
+use crate::plugin::{Plugin, Verdict};
+
+/// Assets named in a derived event per read, the rest is only counted so huge reads don't flood the log.
+const MAX_NAMED_ASSETS : usize = 5;
+
+/// An asset stored inside a container file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Asset {
+    pub name : String,
+    pub offset : u64,
+    pub size : u64
+}
+
+/// Where the assets inside big container files (e.g. the `.bin` and `.dat` archives of Europa 1400) are stored.
+///
+/// Loaded from a json object mapping container paths to lists of assets, or from a text file with one
+/// `container<TAB>offset<TAB>size<TAB>name` line per asset, empty lines and lines starting with '#' are skipped.
+/// Container paths are relative to the mounted root, like the paths of the events.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ArchiveIndex {
+    containers : HashMap<String, Vec<Asset>> // sorted by offset
+}
+
+impl ArchiveIndex {
+    pub fn load(path : &Path) -> crate::Result<Self> {
+        let content = std::fs::read_to_string(path).map_err(|err| crate::Error::io(path, err))?;
+        let error = |message : String| crate::Error::ArchiveIndex { path: path.to_path_buf(), message };
+        let containers = match path.extension().and_then(|extension| extension.to_str()) {
+            Some("json") => serde_json::from_str(&content).map_err(|err| error(err.to_string()))?,
+            _ => Self::parse_lines(&content).map_err(error)?
+        };
+        Ok(Self::new(containers))
+    }
+
+    pub fn new(mut containers : HashMap<String, Vec<Asset>>) -> Self {
+        for assets in containers.values_mut() {
+            assets.sort_by_key(|asset| asset.offset);
+        }
+        Self { containers }
+    }
+
+    fn parse_lines(content : &str) -> Result<HashMap<String, Vec<Asset>>, String> {
+        let mut containers : HashMap<String, Vec<Asset>> = HashMap::new();
+        for (number, line) in content.lines().enumerate() {
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let fields : Vec<&str> = line.splitn(4, '\t').collect();
+            let [container, offset, size, name] = fields[..] else {
+                return Err(format!("line {} doesn't have 4 tab separated fields", number + 1));
+            };
+            let number = |field : &str| field.trim().parse::<u64>().map_err(|err| format!("line {}: {err}", number + 1));
+            containers.entry(container.to_string()).or_default().push(Asset {
+                name: name.to_string(),
+                offset: number(offset)?,
+                size: number(size)?
+            });
+        }
+        Ok(containers)
+    }
+
+    /// Assets of `container` that overlap the `size` bytes at `offset`, in the order they are stored.
+    pub fn assets(&self, container : &str, offset : u64, size : u64) -> impl Iterator<Item = &Asset> {
+        let assets = self.containers.get(container).map(Vec::as_slice).unwrap_or_default();
+        let end = offset + size;
+        // searching by the end relies on the assets not overlapping, which they don't in archives
+        let first = assets.partition_point(|asset| asset.offset + asset.size <= offset);
+        assets[first..].iter().take_while(move |asset| asset.offset < end).filter(move |asset| asset.offset + asset.size > offset)
+    }
+}
+
+/// Annotates reads of container files with the assets that were actually read, as derived events.
+#[derive(Debug)]
+pub struct ArchivePlugin {
+    index : ArchiveIndex
+}
+
+impl ArchivePlugin {
+    pub fn new(index : ArchiveIndex) -> Self {
+        Self { index }
+    }
+}
+
+impl Plugin for ArchivePlugin {
+    fn name(&self) -> &str {
+        "archive"
+    }
+
+    fn on_event(&mut self, event : &Event, derived : &mut Vec<String>) -> Verdict {
+        let EventType::Read(read) = &event.event else {
+            return Verdict::Keep;
+        };
+        let assets : Vec<&str> = self.index.assets(&read.file.to_string_lossy(), read.offset as u64, read.size as u64).map(|asset| asset.name.as_str()).collect();
+        match assets.len() {
+            0 => {}
+            count if count > MAX_NAMED_ASSETS => {
+                derived.push(format!("{}: {} and {} more", read.file.display(), assets[..MAX_NAMED_ASSETS].join(", "), count - MAX_NAMED_ASSETS));
+            }
+            _ => derived.push(format!("{}: {}", read.file.display(), assets.join(", ")))
+        }
+        Verdict::Keep
+    }
+}
@@ -0,0 +1,106 @@
+//! Running inside Docker or Podman, e.g. in a wrapper around a game server: no fusermount, no auto unmount, and
+//! root of a user namespace instead of a setuid helper.
+use serde::Serialize;
+
+use crate::backend::BackendKind;
+use crate::diagnostics::{self, Prerequisite};
+use crate::MountOptions;
+
+/// `1` turns container mode on without the `container` argument.
+pub const ENV_VAR : &str = "FFAM_CONTAINER";
+
+/// Whether container mode was asked for with the environment variable.
+pub fn requested() -> bool {
+    std::env::var(ENV_VAR).is_ok_and(|value| value == "1")
+}
+
+/// The container runtime this runs in, if it can be told. Only reported, container mode has to be chosen.
+pub fn detect() -> Option<String> {
+    if std::path::Path::new("/.dockerenv").exists() {
+        return Some("docker".to_string());
+    }
+    if std::path::Path::new("/run/.containerenv").exists() {
+        return Some("podman".to_string());
+    }
+    // set by podman, systemd-nspawn and lxc
+    if let Some(runtime) = std::env::var("container").ok().filter(|runtime| !runtime.is_empty()) {
+        return Some(runtime);
+    }
+    let cgroup = std::fs::read_to_string("/proc/1/cgroup").unwrap_or_default();
+    ["kubepods", "docker", "libpod", "containerd", "lxc"].into_iter().find(|name| cgroup.contains(name)).map(str::to_string)
+}
+
+/// Whether this runs in a user namespace, like rootless Podman. Its root may mount FUSE without fusermount.
+pub fn in_user_namespace() -> bool {
+    match std::fs::read_to_string("/proc/self/uid_map") {
+        // the initial namespace maps all ids to themselves
+        Ok(map) => map.split_whitespace().collect::<Vec<_>>() != ["0", "0", "4294967295"],
+        Err(_) => false
+    }
+}
+
+/// Adjusts the options for a container: other users of the container (the game server usually runs as its own
+/// user) may read the mount, and nothing is left to fusermount, which the image rarely has. The mount ends
+/// with the container's mount namespace anyway.
+pub fn mount_options(mut options : MountOptions) -> MountOptions {
+    options.auto_unmount = false;
+    options.allow_other = diagnostics::is_root() || diagnostics::user_allow_other();
+    options
+}
+
+/// What container mode found, printed as json by the `status` command.
+#[derive(Debug, Clone, Serialize)]
+pub struct Status {
+    pub container_mode : bool,
+    pub runtime : Option<String>, // see `detect`
+    pub user_namespace : bool,
+    pub root : bool,
+    pub fuse_device : bool, // /dev/fuse exists
+    pub allow_other : bool,
+    pub auto_unmount : bool,
+    pub backend : &'static str,
+    pub missing : Vec<Missing>,
+    pub ready : bool // nothing is missing, mounting should work
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Missing {
+    pub id : &'static str,
+    pub hint : String,
+    pub fix : Option<String>
+}
+
+/// The status for mounting with `options`, which are adjusted first in container mode.
+pub fn status(options : &MountOptions, container_mode : bool) -> Status {
+    let options = match container_mode {
+        true => mount_options(options.clone()),
+        false => options.clone()
+    };
+    let missing = check_prerequisites(&options, container_mode);
+    Status {
+        container_mode,
+        runtime: detect(),
+        user_namespace: in_user_namespace(),
+        root: diagnostics::is_root(),
+        fuse_device: std::path::Path::new("/dev/fuse").exists(),
+        allow_other: options.allow_other,
+        auto_unmount: options.auto_unmount,
+        backend: match options.backend {
+            BackendKind::Fuse => "fuse",
+            BackendKind::Nfs(_) => "nfs"
+        },
+        ready: missing.is_empty(),
+        missing: missing.iter().map(|missing| Missing { id: missing.id(), hint: missing.hint(), fix: missing.fix() }).collect()
+    }
+}
+
+/// Like `diagnostics::check_prerequisites`, but a missing /dev/fuse has to be passed into the container, it can't be loaded.
+pub fn check_prerequisites(options : &MountOptions, container_mode : bool) -> Vec<Prerequisite> {
+    let missing = diagnostics::check_prerequisites(options);
+    match container_mode {
+        true => missing.into_iter()
+            .map(|missing| if missing == Prerequisite::FuseDeviceMissing { Prerequisite::FuseDeviceNotPassed } else { missing })
+            .collect(),
+        false => missing
+    }
+}
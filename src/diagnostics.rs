@@ -0,0 +1,260 @@
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+
+use crate::backend::BackendKind;
+use crate::i18n::tr;
+use crate::MountOptions;
+
+/// Common reasons for a failed mount, each with a hint on how to fix it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MountProblem {
+    FuseDeviceMissing, // Linux only, on FreeBSD the device comes with the module
+    FuseModuleNotLoaded,
+    FusermountMissing, // Linux only, FreeBSD has no fusermount
+    AllowOtherNotPermitted,
+    MountpointBusy,
+    PermissionDenied,
+    MountpointNotEmpty
+}
+
+impl MountProblem {
+    /// Finds the causes from the error and the state of the system, the error alone is rarely specific enough.
+    /// The checks go in the order mounting depends on them and every one that fails is reported, none if the cause
+    /// is unknown.
+    pub fn diagnose(err : &crate::Error, mountpoint : &str, options : &MountOptions) -> Vec<Self> {
+        let mut problems = Vec::new();
+        let freebsd = cfg!(target_os = "freebsd");
+        if !Path::new("/dev/fuse").exists() {
+            problems.push(if freebsd { Self::FuseModuleNotLoaded } else { Self::FuseDeviceMissing });
+        }
+        if !freebsd && !fuse_module_loaded() {
+            problems.push(Self::FuseModuleNotLoaded);
+        }
+        if !freebsd && !fusermount_installed() && (!is_root() || options.auto_unmount) {
+            problems.push(Self::FusermountMissing);
+        }
+        if options.allow_other && !freebsd && !is_root() && !user_allow_other() {
+            problems.push(Self::AllowOtherNotPermitted);
+        }
+        let Some(err) = err.io_error() else {
+            return problems;
+        };
+        if err.raw_os_error() == Some(libc::EBUSY) || is_mounted(mountpoint) {
+            problems.push(Self::MountpointBusy);
+        }
+        // the checks above explain a denied mount better, these are what is left
+        if problems.is_empty() && (err.kind() == std::io::ErrorKind::PermissionDenied || err.raw_os_error() == Some(libc::EPERM)) {
+            problems.push(Self::PermissionDenied);
+        }
+        if problems.is_empty() && std::fs::read_dir(mountpoint).is_ok_and(|mut entries| entries.next().is_some()) {
+            problems.push(Self::MountpointNotEmpty);
+        }
+        problems
+    }
+
+    pub fn hint(&self, mountpoint : &str) -> String {
+        match self {
+            Self::FuseDeviceMissing => tr!("hint-fuse-device-missing"),
+            Self::FuseModuleNotLoaded if cfg!(target_os = "freebsd") => tr!("hint-fusefs-not-loaded"),
+            Self::FuseModuleNotLoaded => tr!("hint-fuse-module-not-loaded"),
+            Self::FusermountMissing => tr!("hint-fusermount-missing"),
+            Self::AllowOtherNotPermitted => tr!("hint-allow-other"),
+            Self::MountpointBusy if cfg!(target_os = "freebsd") => tr!("hint-mountpoint-busy-freebsd", mountpoint = mountpoint),
+            Self::MountpointBusy => tr!("hint-mountpoint-busy", mountpoint = mountpoint),
+            Self::PermissionDenied if cfg!(target_os = "freebsd") => tr!("hint-permission-denied-freebsd", mountpoint = mountpoint),
+            Self::PermissionDenied => tr!("hint-permission-denied", mountpoint = mountpoint),
+            Self::MountpointNotEmpty => tr!("hint-mountpoint-not-empty")
+        }
+    }
+}
+
+/// A prerequisite of mounting with the chosen options that is missing, found before mounting so it can be fixed
+/// instead of failing with the mount's error.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Prerequisite {
+    FuseDeviceMissing, // the module isn't loaded
+    FuseDeviceNotPassed, // in container mode, the container has to be started with it
+    FusermountMissing,
+    FusermountNotSetuid(PathBuf), // normal users can't mount through it
+    FuseDeviceNotAccessible(Option<String>), // the group that may use /dev/fuse, if joining it would help
+    AllowOtherNotPermitted,
+    UserMountDisabled, // FreeBSD's vfs.usermount
+    NfsClientMissing,
+    NfsNeedsRoot
+}
+
+impl Prerequisite {
+    /// What is missing and what fixing it does.
+    pub fn hint(&self) -> String {
+        match self {
+            Self::FuseDeviceMissing if cfg!(target_os = "freebsd") => tr!("hint-fusefs-not-loaded"),
+            Self::FuseDeviceMissing => tr!("setup-fuse-device-missing"),
+            Self::FuseDeviceNotPassed => tr!("setup-fuse-device-not-passed"),
+            Self::FusermountMissing => tr!("hint-fusermount-missing"),
+            Self::FusermountNotSetuid(path) => tr!("setup-fusermount-not-setuid", path = path.to_string_lossy()),
+            Self::FuseDeviceNotAccessible(Some(group)) => tr!("setup-fuse-device-group", group = group.as_str()),
+            Self::FuseDeviceNotAccessible(None) => tr!("setup-fuse-device-not-accessible"),
+            Self::AllowOtherNotPermitted => tr!("hint-allow-other"),
+            Self::UserMountDisabled => tr!("setup-user-mount-disabled"),
+            Self::NfsClientMissing => tr!("setup-nfs-client-missing"),
+            Self::NfsNeedsRoot => tr!("setup-nfs-needs-root")
+        }
+    }
+
+    /// A shell command that fixes it, to be run by the user.
+    pub fn fix(&self) -> Option<String> {
+        let sudo = if cfg!(target_os = "freebsd") { "" } else { "sudo " };
+        match self {
+            Self::FuseDeviceMissing if cfg!(target_os = "freebsd") => Some("kldload fusefs".to_string()),
+            Self::FuseDeviceMissing => Some("sudo modprobe fuse".to_string()),
+            Self::FuseDeviceNotPassed => None,
+            Self::FusermountMissing => Some(install_command("fuse3", "fuse3")),
+            Self::FusermountNotSetuid(path) => Some(format!("sudo chmod u+s {}", path.display())),
+            Self::FuseDeviceNotAccessible(Some(group)) if cfg!(target_os = "freebsd") => Some(format!("pw groupmod {group} -m $USER")),
+            Self::FuseDeviceNotAccessible(Some(group)) => Some(format!("sudo usermod -aG {group} $USER")),
+            Self::FuseDeviceNotAccessible(None) => Some(format!("{sudo}chmod 666 /dev/fuse")),
+            Self::AllowOtherNotPermitted => Some("echo user_allow_other | sudo tee -a /etc/fuse.conf".to_string()),
+            Self::UserMountDisabled => Some("sysctl vfs.usermount=1".to_string()),
+            Self::NfsClientMissing => Some(install_command("nfs-common", "nfs-utils")),
+            Self::NfsNeedsRoot => None
+        }
+    }
+
+    /// A stable name for machine-readable output.
+    pub fn id(&self) -> &'static str {
+        match self {
+            Self::FuseDeviceMissing => "fuse_device_missing",
+            Self::FuseDeviceNotPassed => "fuse_device_not_passed",
+            Self::FusermountMissing => "fusermount_missing",
+            Self::FusermountNotSetuid(_) => "fusermount_not_setuid",
+            Self::FuseDeviceNotAccessible(_) => "fuse_device_not_accessible",
+            Self::AllowOtherNotPermitted => "allow_other_not_permitted",
+            Self::UserMountDisabled => "user_mount_disabled",
+            Self::NfsClientMissing => "nfs_client_missing",
+            Self::NfsNeedsRoot => "nfs_needs_root"
+        }
+    }
+
+    /// Whether the NFS backend would work around it, which is worth offering when FUSE can't be set up.
+    pub fn nfs_avoids(&self) -> bool {
+        matches!(self, Self::FuseDeviceMissing | Self::FusermountMissing | Self::FusermountNotSetuid(_) | Self::FuseDeviceNotAccessible(_))
+            && is_root()
+    }
+}
+
+/// Checks what mounting with `options` needs. Doesn't find everything a mount can fail on, the mountpoint is
+/// checked when mounting.
+pub fn check_prerequisites(options : &MountOptions) -> Vec<Prerequisite> {
+    let mut missing = Vec::new();
+    if let BackendKind::Nfs(nfs) = &options.backend {
+        if nfs.kernel_mount {
+            if !is_root() {
+                missing.push(Prerequisite::NfsNeedsRoot);
+            }
+            if cfg!(target_os = "linux") && find_program(&["mount.nfs"]).is_none() {
+                missing.push(Prerequisite::NfsClientMissing);
+            }
+        }
+        return missing;
+    }
+    let device = Path::new("/dev/fuse");
+    if !device.exists() {
+        missing.push(Prerequisite::FuseDeviceMissing);
+    } else if !is_root() && !accessible(device) {
+        missing.push(Prerequisite::FuseDeviceNotAccessible(device_group(device)));
+    }
+    if cfg!(target_os = "freebsd") {
+        if !is_root() && sysctl("vfs.usermount").is_some_and(|value| value == "0") {
+            missing.push(Prerequisite::UserMountDisabled);
+        }
+        return missing;
+    }
+    // root mounts directly, also root of a user namespace, only auto unmount always goes through fusermount
+    match find_program(&["fusermount3", "fusermount"]) {
+        None if is_root() && !options.auto_unmount => {}
+        None => missing.push(Prerequisite::FusermountMissing),
+        Some(path) if !is_root() && path.metadata().is_ok_and(|metadata| metadata.mode() & 0o4000 == 0) => {
+            missing.push(Prerequisite::FusermountNotSetuid(path));
+        }
+        Some(_) => {}
+    }
+    if options.allow_other && !is_root() && !user_allow_other() {
+        missing.push(Prerequisite::AllowOtherNotPermitted);
+    }
+    missing
+}
+
+pub(crate) fn is_root() -> bool {
+    // SAFETY: geteuid can't fail
+    unsafe { libc::geteuid() == 0 }
+}
+
+fn accessible(path : &Path) -> bool {
+    use std::os::unix::ffi::OsStrExt;
+    let Ok(path) = std::ffi::CString::new(path.as_os_str().as_bytes()) else {
+        return false;
+    };
+    // SAFETY: the path is null terminated
+    unsafe { libc::access(path.as_ptr(), libc::R_OK | libc::W_OK) == 0 }
+}
+
+/// The group of the device if it may read and write it and the user isn't in it yet, joining it is the usual fix.
+fn device_group(device : &Path) -> Option<String> {
+    let metadata = device.metadata().ok()?;
+    if metadata.mode() & 0o060 != 0o060 || metadata.gid() == 0 {
+        return None;
+    }
+    let group = std::fs::read_to_string("/etc/group").ok()?.lines()
+        .map(|line| line.split(':').collect::<Vec<_>>())
+        .find(|fields| fields.len() >= 3 && fields[2].parse() == Ok(metadata.gid()))?[0]
+        .to_string();
+    Some(group)
+}
+
+fn find_program(names : &[&str]) -> Option<PathBuf> {
+    let path = std::env::var_os("PATH")?;
+    // mount helpers live in /sbin, which isn't in the PATH of normal users everywhere
+    let dirs = std::env::split_paths(&path).chain(["/sbin", "/usr/sbin"].map(PathBuf::from)).collect::<Vec<_>>();
+    names.iter().find_map(|name| dirs.iter().map(|dir| dir.join(name)).find(|path| path.is_file()))
+}
+
+/// How to install a package with the package manager of the system, `debian` is the name on Debian and Ubuntu.
+fn install_command(debian : &str, other : &str) -> String {
+    let managers = [("apt-get", "sudo apt-get install", debian), ("dnf", "sudo dnf install", other), ("zypper", "sudo zypper install", other),
+        ("pacman", "sudo pacman -S", other), ("apk", "sudo apk add", other)];
+    match managers.iter().find(|(program, _, _)| find_program(&[program]).is_some()) {
+        Some((_, install, package)) => format!("{install} {package}"),
+        None => format!("sudo apt-get install {debian}")
+    }
+}
+
+fn sysctl(name : &str) -> Option<String> {
+    let output = std::process::Command::new("sysctl").arg("-n").arg(name).output().ok()?;
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Whether the kernel knows the fuse filesystem, assumed if /proc can't be read.
+fn fuse_module_loaded() -> bool {
+    match std::fs::read_to_string("/proc/filesystems") {
+        Ok(filesystems) => filesystems.lines().any(|line| line.split_whitespace().last() == Some("fuse")),
+        Err(_) => true
+    }
+}
+
+fn fusermount_installed() -> bool {
+    find_program(&["fusermount3", "fusermount"]).is_some()
+}
+
+fn is_mounted(mountpoint : &str) -> bool {
+    match Path::new(mountpoint).canonicalize() {
+        Ok(mountpoint) => crate::platform::is_mounted(&mountpoint),
+        Err(_) => false
+    }
+}
+
+pub(crate) fn user_allow_other() -> bool {
+    match std::fs::read_to_string("/etc/fuse.conf") {
+        Ok(config) => config.lines().any(|line| line.trim() == "user_allow_other"),
+        Err(_) => false
+    }
+}
@@ -0,0 +1,152 @@
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeDelta, Utc};
+use std::path::Path;
+
+use crate::fs::{Event, EventType};
+
+/// Formats of timestamps with a date at the start of a line, without a time zone they are local time.
+const DATE_TIME_FORMATS : [&str; 4] = ["%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M:%S%.f", "%d.%m.%Y %H:%M:%S%.f", "%Y/%m/%d %H:%M:%S%.f"];
+
+/// A line of a log written by another program, e.g. the debug log of the game.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExternalLine {
+    pub time : DateTime<Utc>, // as written in the log, without the offset
+    pub text : String
+}
+
+/// A timestamped log of another program, shown interleaved with the events to see what it did around an access.
+///
+/// Lines start with a timestamp, optionally in brackets: a date and time (RFC 3339, `2025-01-31 12:00:00.123`,
+/// `31.01.2025 12:00:00`), a time of the day (`12:00:00.123`) on the day the monitoring started, or seconds since
+/// the start (`[12.345]`). Lines without a timestamp continue the line before them.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExternalLog {
+    pub lines : Vec<ExternalLine>
+}
+
+impl ExternalLog {
+    pub fn load(path : &Path, start : DateTime<Utc>) -> crate::Result<Self> {
+        let content = std::fs::read(path).map_err(|err| crate::Error::io(path, err))?;
+        // game logs are rarely UTF-8
+        Ok(Self::parse(&String::from_utf8_lossy(&content), start))
+    }
+
+    pub fn parse(content : &str, start : DateTime<Utc>) -> Self {
+        let mut lines = Vec::new();
+        let mut time = start;
+        let mut day = start.with_timezone(&Local).date_naive();
+        for line in content.lines() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let text = match parse_timestamp(line, start, day) {
+                Some((parsed, rest)) => {
+                    // a time of the day earlier than the line before crossed midnight
+                    let parsed = match parsed {
+                        Timestamp::Time(parsed) if parsed < time - TimeDelta::hours(12) => {
+                            day = day.succ_opt().unwrap_or(day);
+                            parsed + TimeDelta::days(1)
+                        }
+                        Timestamp::Time(parsed) | Timestamp::Full(parsed) => parsed
+                    };
+                    time = parsed;
+                    rest
+                }
+                None => line
+            };
+            lines.push(ExternalLine { time, text: text.trim_end().to_string() });
+        }
+        // the events are in order of time, so the lines have to be as well
+        lines.sort_by_key(|line| line.time);
+        Self { lines }
+    }
+
+    /// Offset that moves the first line containing `text` (ignoring case) to `time`, e.g. the time of a marker
+    /// that was added when the game logged that line.
+    pub fn anchor_offset(&self, text : &str, time : DateTime<Utc>) -> Option<TimeDelta> {
+        let text = text.to_lowercase();
+        let line = self.lines.iter().find(|line| line.text.to_lowercase().contains(&text))?;
+        Some(time - line.time)
+    }
+}
+
+enum Timestamp {
+    Full(DateTime<Utc>),
+    Time(DateTime<Utc>) // only a time of the day, the date was guessed
+}
+
+fn parse_timestamp(line : &str, start : DateTime<Utc>, day : NaiveDate) -> Option<(Timestamp, &str)> {
+    let bracketed = line.trim_start().strip_prefix('[');
+    let stamp = bracketed.unwrap_or(line.trim_start()).trim_start();
+    let (timestamp, rest) = if let Ok((time, rest)) = DateTime::parse_and_remainder(stamp, "%+") {
+        (Timestamp::Full(time.with_timezone(&Utc)), rest)
+    } else if let Some((time, rest)) = DATE_TIME_FORMATS.iter().find_map(|format| NaiveDateTime::parse_and_remainder(stamp, format).ok()) {
+        (Timestamp::Full(local(time)?), rest)
+    } else if let Ok((time, rest)) = NaiveTime::parse_and_remainder(stamp, "%H:%M:%S%.f") {
+        (Timestamp::Time(local(day.and_time(time))?), rest)
+    } else if bracketed.is_some() {
+        let end = stamp.find(']')?;
+        let seconds : f64 = stamp[..end].trim().parse().ok()?;
+        (Timestamp::Full(start + TimeDelta::milliseconds((seconds * 1000.0) as i64)), &stamp[end..])
+    } else {
+        return None;
+    };
+    // the rest must not continue the number, "12:00:00abc" isn't a timestamp
+    if rest.starts_with(|c : char| c.is_alphanumeric()) {
+        return None;
+    }
+    let rest = if bracketed.is_some() { rest.trim_start().strip_prefix(']')? } else { rest };
+    Some((timestamp, rest.trim_start()))
+}
+
+fn local(time : NaiveDateTime) -> Option<DateTime<Utc>> {
+    Some(time.and_local_timezone(Local).earliest()?.with_timezone(&Utc))
+}
+
+/// An event or a line of the external log, in the order of their times.
+#[derive(Debug, Clone, Copy)]
+pub enum Entry<'a> {
+    Event(&'a Event),
+    Line(DateTime<Utc>, &'a str) // time with the offset applied
+}
+
+impl Entry<'_> {
+    pub fn time(&self) -> DateTime<Utc> {
+        match self {
+            Self::Event(event) => event.time,
+            Self::Line(time, _) => *time
+        }
+    }
+}
+
+/// Merges events and the lines of the log moved by `offset`, lines come before events of the same time.
+pub fn interleave<'a>(events : impl Iterator<Item = &'a Event>, log : &'a ExternalLog, offset : TimeDelta) -> Vec<Entry<'a>> {
+    let mut lines = log.lines.iter().map(|line| Entry::Line(line.time + offset, &line.text)).peekable();
+    let mut entries = Vec::new();
+    for event in events {
+        while let Some(line) = lines.next_if(|line| line.time() <= event.time) {
+            entries.push(line);
+        }
+        entries.push(Entry::Event(event));
+    }
+    entries.extend(lines);
+    entries
+}
+
+/// Time of the first marker with this label.
+pub fn marker_time<'a>(mut events : impl Iterator<Item = &'a Event>, label : &str) -> Option<DateTime<Utc>> {
+    events.find_map(|event| match &event.event {
+        EventType::Marker(marker) if marker == label => Some(event.time),
+        _ => None
+    })
+}
+
+/// Writes one line per entry, lines of the log are marked with `>`.
+pub fn write_interleaved(out : &mut impl std::io::Write, entries : &[Entry]) -> std::io::Result<()> {
+    for entry in entries {
+        match entry {
+            Entry::Event(event) => writeln!(out, "{event}")?,
+            Entry::Line(time, text) => writeln!(out, "[{time}] > {text}")?
+        }
+    }
+    Ok(())
+}
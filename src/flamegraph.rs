@@ -0,0 +1,132 @@
+use iced::widget::canvas::{self, Frame, Geometry, Path, Stroke};
+use iced::{mouse, Color, Point, Rectangle, Renderer, Size, Theme};
+
+use crate::analysis::PathNode;
+use crate::ui::Message;
+
+const ROW_HEIGHT: f32 = 20.0;
+
+/// One box of the flame graph, position and width are fractions of the whole width.
+#[derive(Debug)]
+struct FlameFrame {
+    x : f32,
+    width : f32,
+    depth : usize,
+    label : String,
+    path : String,
+    count : u64
+}
+
+/// Flame graph over the path hierarchy: the root is at the bottom, every directory sits on top of its parent
+/// and is as wide as its share of the accesses. Clicking a box zooms into it.
+#[derive(Debug)]
+pub struct FlameGraph {
+    frames : Vec<FlameFrame>,
+    total : u64
+}
+
+impl FlameGraph {
+    pub fn new(root : &PathNode, root_label : String) -> Self {
+        let mut frames = Vec::new();
+        Self::add_frames(&mut frames, root, root_label, 0.0, 1.0, 0);
+        Self {
+            frames,
+            total: root.count
+        }
+    }
+
+    fn add_frames(frames : &mut Vec<FlameFrame>, node : &PathNode, label : String, x : f32, width : f32, depth : usize) {
+        frames.push(FlameFrame {
+            x,
+            width,
+            depth,
+            label,
+            path: node.path.clone(),
+            count: node.count
+        });
+        let mut child_x = x;
+        for child in node.children.values() {
+            let child_width = if node.count == 0 { 0.0 } else { width * child.count as f32 / node.count as f32 };
+            Self::add_frames(frames, child, child.name.clone(), child_x, child_width, depth + 1);
+            child_x += child_width;
+        }
+    }
+
+    fn frame_rect(frame : &FlameFrame, bounds : Size) -> Rectangle {
+        Rectangle::new(
+            Point::new(frame.x * bounds.width, bounds.height - (frame.depth + 1) as f32 * ROW_HEIGHT),
+            Size::new(frame.width * bounds.width, ROW_HEIGHT)
+        )
+    }
+
+    fn frame_at(&self, position : Point, bounds : Size) -> Option<&FlameFrame> {
+        self.frames.iter().find(|frame| Self::frame_rect(frame, bounds).contains(position))
+    }
+}
+
+/// Warm colors like a classic flame graph, derived from the name so a directory keeps its color when zooming.
+fn flame_color(name : &str) -> Color {
+    let hash = name.bytes().fold(5381u32, |hash, byte| hash.wrapping_mul(33) ^ byte as u32);
+    Color::from_rgb8(205 + (hash % 50) as u8, (hash / 50 % 180) as u8 + 40, (hash / 9000 % 55) as u8)
+}
+
+impl canvas::Program<Message> for FlameGraph {
+    type State = ();
+
+    fn update(&self, _state : &mut (), event : canvas::Event, bounds : Rectangle, cursor : mouse::Cursor) -> (canvas::event::Status, Option<Message>) {
+        let canvas::Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) = event else {
+            return (canvas::event::Status::Ignored, None);
+        };
+        match cursor.position_in(bounds).and_then(|position| self.frame_at(position, bounds.size())) {
+            Some(frame) => (canvas::event::Status::Captured, Some(Message::FlameGraphZoom(frame.path.clone()))),
+            None => (canvas::event::Status::Ignored, None)
+        }
+    }
+
+    fn draw(&self, _state : &(), renderer : &Renderer, _theme : &Theme, bounds : Rectangle, cursor : mouse::Cursor) -> Vec<Geometry> {
+        let mut frame = Frame::new(renderer, bounds.size());
+        for flame_frame in &self.frames {
+            let rect = Self::frame_rect(flame_frame, bounds.size());
+            if rect.width < 1.0 {
+                continue;
+            }
+            frame.fill_rectangle(rect.position(), rect.size(), flame_color(&flame_frame.label));
+            frame.stroke(&Path::rectangle(rect.position(), rect.size()), Stroke::default().with_color(Color::WHITE).with_width(0.5));
+            // roughly 7 pixels per character, the label is cut to what fits
+            let fitting = ((rect.width - 6.0) / 7.0) as usize;
+            if fitting >= 3 {
+                frame.fill_text(canvas::Text {
+                    content: flame_frame.label.chars().take(fitting).collect(),
+                    position: Point::new(rect.x + 3.0, rect.y + 3.0),
+                    color: Color::BLACK,
+                    size: 12.0.into(),
+                    ..canvas::Text::default()
+                });
+            }
+        }
+        if let Some(position) = cursor.position_in(bounds) {
+            if let Some(hovered) = self.frame_at(position, bounds.size()) {
+                let percentage = if self.total == 0 { 0.0 } else { hovered.count as f64 * 100.0 / self.total as f64 };
+                let content = format!("/{} ({}, {percentage:.1}%)", hovered.path, hovered.count);
+                let width = content.chars().count() as f32 * 7.5 + 8.0;
+                let position = Point::new(position.x.min(bounds.width - width).max(0.0), (position.y - 28.0).max(0.0));
+                frame.fill_rectangle(position, Size::new(width, 20.0), Color::from_rgba(0.0, 0.0, 0.0, 0.8));
+                frame.fill_text(canvas::Text {
+                    content,
+                    position: Point::new(position.x + 4.0, position.y + 3.0),
+                    color: Color::WHITE,
+                    size: 13.0.into(),
+                    ..canvas::Text::default()
+                });
+            }
+        }
+        vec![frame.into_geometry()]
+    }
+
+    fn mouse_interaction(&self, _state : &(), bounds : Rectangle, cursor : mouse::Cursor) -> mouse::Interaction {
+        match cursor.position_in(bounds).and_then(|position| self.frame_at(position, bounds.size())) {
+            Some(_) => mouse::Interaction::Pointer,
+            None => mouse::Interaction::default()
+        }
+    }
+}
@@ -0,0 +1,164 @@
+use iced::advanced::text::highlighter::{self, Highlighter};
+use iced::{Color, Font, Theme};
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+use crate::i18n::tr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HighlightColor {
+    Red,
+    Orange,
+    Yellow,
+    Green,
+    Blue,
+    Purple,
+    Gray
+}
+
+impl HighlightColor {
+    pub const ALL: [HighlightColor; 7] = [
+        Self::Red,
+        Self::Orange,
+        Self::Yellow,
+        Self::Green,
+        Self::Blue,
+        Self::Purple,
+        Self::Gray
+    ];
+
+    pub fn color(&self) -> Color {
+        match self {
+            Self::Red => Color::from_rgb8(0xe0, 0x3c, 0x3c),
+            Self::Orange => Color::from_rgb8(0xe8, 0x8a, 0x1a),
+            Self::Yellow => Color::from_rgb8(0xc9, 0xb0, 0x00),
+            Self::Green => Color::from_rgb8(0x2e, 0xa0, 0x43),
+            Self::Blue => Color::from_rgb8(0x3a, 0x7b, 0xd5),
+            Self::Purple => Color::from_rgb8(0x9b, 0x59, 0xb6),
+            Self::Gray => Color::from_rgb8(0x88, 0x88, 0x88)
+        }
+    }
+}
+
+impl std::fmt::Display for HighlightColor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::Red => tr!("color-red"),
+            Self::Orange => tr!("color-orange"),
+            Self::Yellow => tr!("color-yellow"),
+            Self::Green => tr!("color-green"),
+            Self::Blue => tr!("color-blue"),
+            Self::Purple => tr!("color-purple"),
+            Self::Gray => tr!("color-gray")
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Colors every log line containing `pattern` (case insensitive). The first matching rule wins.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HighlightRule {
+    pub pattern: String,
+    pub color: HighlightColor
+}
+
+impl HighlightRule {
+    pub fn matches(&self, line : &str) -> bool {
+        !self.pattern.is_empty() && line.to_lowercase().contains(&self.pattern.to_lowercase())
+    }
+}
+
+/// Finds the color of a log line, if any rule matches it.
+pub fn line_color(rules : &[HighlightRule], line : &str) -> Option<HighlightColor> {
+    rules.iter().find(|rule| rule.matches(line)).map(|rule| rule.color)
+}
+
+/// What the log highlighter needs to know, changing it re-highlights the whole log.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogHighlightSettings {
+    pub rules: Vec<HighlightRule>,
+    pub search: String // occurrences are marked in every line, ignoring ASCII case
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogHighlight {
+    Rule(HighlightColor),
+    SearchMatch
+}
+
+/// Byte ranges of all occurrences of `search` in `line`, ignoring ASCII case so the ranges stay valid for the original line.
+pub fn search_matches(line : &str, search : &str) -> Vec<Range<usize>> {
+    if search.is_empty() {
+        return Vec::new();
+    }
+    let line = line.to_ascii_lowercase();
+    let search = search.to_ascii_lowercase();
+    line.match_indices(&search).map(|(start, found)| start..start + found.len()).collect()
+}
+
+/// Highlighter for the event log, colors whole lines according to the highlight rules and marks search matches.
+pub struct LogHighlighter {
+    settings: LogHighlightSettings,
+    current_line: usize
+}
+
+impl Highlighter for LogHighlighter {
+    type Settings = LogHighlightSettings;
+    type Highlight = LogHighlight;
+    type Iterator<'a> = std::vec::IntoIter<(Range<usize>, LogHighlight)>;
+
+    fn new(settings: &Self::Settings) -> Self {
+        Self {
+            settings: settings.clone(),
+            current_line: 0
+        }
+    }
+
+    fn update(&mut self, new_settings: &Self::Settings) {
+        self.settings = new_settings.clone();
+        self.current_line = 0;
+    }
+
+    fn change_line(&mut self, line: usize) {
+        self.current_line = self.current_line.min(line);
+    }
+
+    fn highlight_line(&mut self, line: &str) -> Self::Iterator<'_> {
+        self.current_line += 1;
+        let color = line_color(&self.settings.rules, line);
+        // the ranges must not overlap, so the line color only fills the gaps between matches
+        let mut highlights = Vec::new();
+        let mut position = 0;
+        for found in search_matches(line, &self.settings.search) {
+            if let (Some(color), true) = (color, found.start > position) {
+                highlights.push((position..found.start, LogHighlight::Rule(color)));
+            }
+            position = found.end;
+            highlights.push((found, LogHighlight::SearchMatch));
+        }
+        if let (Some(color), true) = (color, line.len() > position) {
+            highlights.push((position..line.len(), LogHighlight::Rule(color)));
+        }
+        highlights.into_iter()
+    }
+
+    fn current_line(&self) -> usize {
+        self.current_line
+    }
+}
+
+pub fn to_format(highlight : &LogHighlight, _theme : &Theme) -> highlighter::Format<Font> {
+    match highlight {
+        LogHighlight::Rule(color) => highlighter::Format {
+            color: Some(color.color()),
+            font: None
+        },
+        LogHighlight::SearchMatch => highlighter::Format {
+            color: Some(Color::from_rgb8(0xff, 0x00, 0xc8)),
+            font: Some(Font {
+                weight: iced::font::Weight::Bold,
+                ..Font::DEFAULT
+            })
+        }
+    }
+}
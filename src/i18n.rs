@@ -0,0 +1,96 @@
+use fluent::concurrent::FluentBundle;
+use fluent::{FluentArgs, FluentResource};
+use serde::{Deserialize, Serialize};
+use std::sync::{LazyLock, RwLock};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Language {
+    English,
+    German
+}
+
+impl Language {
+    pub const ALL: [Language; 2] = [Self::English, Self::German];
+
+    fn identifier(&self) -> &'static str {
+        match self {
+            Self::English => "en",
+            Self::German => "de"
+        }
+    }
+
+    fn translations(&self) -> &'static str {
+        match self {
+            Self::English => include_str!("../locales/en.ftl"),
+            Self::German => include_str!("../locales/de.ftl")
+        }
+    }
+
+    /// The language of the system locale, English if there is no translation for it.
+    pub fn detect() -> Self {
+        match sys_locale::get_locale() {
+            Some(locale) if locale.starts_with("de") => Self::German,
+            _ => Self::English
+        }
+    }
+}
+
+impl std::fmt::Display for Language {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // always shown in the language itself, so it can be found regardless of the current one
+        match self {
+            Self::English => write!(f, "English"),
+            Self::German => write!(f, "Deutsch")
+        }
+    }
+}
+
+fn bundle(language : Language) -> FluentBundle<FluentResource> {
+    let identifier = language.identifier().parse().expect("Invalid language identifier");
+    let resource = FluentResource::try_new(language.translations().to_string()).expect("Failed to parse translations");
+    let mut bundle = FluentBundle::new_concurrent(vec![identifier]);
+    // the unicode isolation marks show up as boxes in the UI font
+    bundle.set_use_isolating(false);
+    bundle.add_resource(resource).expect("Duplicate translation ids");
+    bundle
+}
+
+static CURRENT: LazyLock<RwLock<(Language, FluentBundle<FluentResource>)>> = LazyLock::new(|| {
+    let language = Language::detect();
+    RwLock::new((language, bundle(language)))
+});
+static FALLBACK: LazyLock<FluentBundle<FluentResource>> = LazyLock::new(|| bundle(Language::English));
+
+pub fn language() -> Language {
+    CURRENT.read().unwrap().0
+}
+
+pub fn set_language(language : Language) {
+    *CURRENT.write().unwrap() = (language, bundle(language));
+}
+
+fn format(bundle : &FluentBundle<FluentResource>, id : &str, args : Option<&FluentArgs>) -> Option<String> {
+    let pattern = bundle.get_message(id)?.value()?;
+    let mut errors = Vec::new();
+    Some(bundle.format_pattern(pattern, args, &mut errors).to_string())
+}
+
+/// Looks up a message in the current language, falling back to English and then to the id itself.
+pub fn translate(id : &str, args : Option<&FluentArgs>) -> String {
+    format(&CURRENT.read().unwrap().1, id, args)
+        .or_else(|| format(&FALLBACK, id, args))
+        .unwrap_or_else(|| id.to_string())
+}
+
+/// `tr!("id")` or `tr!("id", name = value, ...)` translates a message of the locales/*.ftl files.
+macro_rules! tr {
+    ($id:literal) => {
+        $crate::i18n::translate($id, None)
+    };
+    ($id:literal, $($name:ident = $value:expr),+ $(,)?) => {{
+        let mut args = fluent::FluentArgs::new();
+        $(args.set(stringify!($name), $value);)+
+        $crate::i18n::translate($id, Some(&args))
+    }};
+}
+pub(crate) use tr;
@@ -1,12 +1,51 @@
-pub mod fs;
+pub mod analysis;
+pub mod archive;
+pub mod container;
+pub mod diagnostics;
+pub mod external_log;
+#[cfg(feature = "gui")]
+pub mod flamegraph;
+#[cfg(feature = "gui")]
+pub mod highlight;
+pub mod i18n;
+pub mod manifest;
+pub mod privileges;
+#[cfg(feature = "scripting")]
+pub mod script;
+#[cfg(feature = "gui")]
+pub mod settings;
+#[cfg(feature = "gui")]
+pub mod signals;
+#[cfg(feature = "gui")]
+pub mod spill;
+#[cfg(feature = "gui")]
+pub mod tray;
+#[cfg(feature = "gui")]
+pub mod treemap;
+#[cfg(feature = "gui")]
 pub mod ui;
+#[cfg(feature = "gui")]
+pub mod watch;
 
-use fuser::{BackgroundSession, MountOption};
+use std::path::PathBuf;
 
-use fs::Event;
+// the engine and the saved sessions are their own crates, so other projects can embed them without the application
+pub use ffam_export::{copy, extract, html, session};
+pub use ffam_fs::{backend, control, error, fs, nfs, platform, plugin, recorder, registry, rules, schema, source};
+pub use ffam_fs::{unmount_on_panic, Error, KernelOptions, MountBuilder, MountOptions, MountSession, Result};
+#[cfg(feature = "gui")]
+pub use ffam_fs::MountHandle;
 
-pub fn run_mount(mount_source : &str, mount_point : &str, event_sender : tokio::sync::mpsc::Sender<Event>) -> Result<BackgroundSession, std::io::Error> {
-    let options = vec![MountOption::FSName("passthrough".to_string())];
-    let fs = fs::FileAccessTrackingFs::new(mount_source, event_sender);
-    fuser::spawn_mount2(fs, mount_point, &options)
+/// Where settings and saved sessions are stored, `$XDG_CONFIG_HOME/fuse_file_access_monitor` by default.
+pub fn config_dir() -> Option<PathBuf> {
+    let config_dir = match std::env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(std::env::var_os("HOME")?).join(".config")
+    };
+    Some(config_dir.join(env!("CARGO_PKG_NAME")))
+}
+
+/// Where `session::Session`s are saved.
+pub fn sessions_dir() -> Option<PathBuf> {
+    Some(config_dir()?.join("sessions"))
 }
@@ -1,9 +1,333 @@
+use fuse_file_access_monitor::{analysis, container, copy, diagnostics, html, platform, privileges, registry, signals, watch, MountBuilder};
+use fuse_file_access_monitor::diagnostics::MountProblem;
+use fuse_file_access_monitor::extract::{self, ExtractMode};
+use fuse_file_access_monitor::fs::EventType;
+use fuse_file_access_monitor::external_log::{self, ExternalLog};
+use fuse_file_access_monitor::manifest::Manifest;
+use fuse_file_access_monitor::session::Session;
+use fuse_file_access_monitor::settings::Settings;
+use fuse_file_access_monitor::source::DiskSource;
 use fuse_file_access_monitor::ui::*;
 
+/// Files listed per phase of the startup report.
+const STARTUP_TOP_FILES : usize = 10;
+
+const USAGE : &str = "Usage: fuse_file_access_monitor [check | status [container] | run <source> <mountpoint> [container] [direct-io] | mounts | unmount <mountpoint> | recover [<mountpoint>] | access-order <session> | unused <session> | files <session> | copy-accessed <session> <target> | manifest <session> <manifest> | redundant <session> | processes <session> | startup <session> [<phase ends>] [first-read] | html <session> <output> | extract <session> <target> [sparse | concatenated] | interleave <session> <log> [<offset seconds> | <marker> <text>]]";
+
 fn main() -> iced::Result {
-    iced::application("FUSE File Access Monitor", AccessTrackingFsGui::update, AccessTrackingFsGui::view)
+    let args : Vec<String> = std::env::args().skip(1).collect();
+    if args == [privileges::HELPER_ARGUMENT] {
+        if let Err(err) = privileges::serve_helper() {
+            eprintln!("{err}");
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+    privileges::adopt_user_environment();
+    match privileges::drop_to_invoking_user() {
+        Ok(Some(user)) => eprintln!("Running as {}, mounting through a helper that keeps root", user.name),
+        Ok(None) => {}
+        Err(err) => eprintln!("Still running as root, switching to the user who ran sudo failed: {err}")
+    }
+    fuse_file_access_monitor::unmount_on_panic();
+    if !args.is_empty() {
+        let result = match args.iter().map(String::as_str).collect::<Vec<_>>().as_slice() {
+            ["check"] => check_setup(),
+            ["status"] => status(container::requested()),
+            ["status", "container"] => status(true),
+            ["run", source, mountpoint] => run(source, mountpoint, container::requested(), false),
+            ["run", source, mountpoint, "container"] => run(source, mountpoint, true, false),
+            ["run", source, mountpoint, "direct-io"] => run(source, mountpoint, container::requested(), true),
+            ["run", source, mountpoint, "container", "direct-io"] => run(source, mountpoint, true, true),
+            ["mounts"] => mounts(),
+            ["unmount", mountpoint] => registry::request_unmount(mountpoint.as_ref(), privileges::mounter().as_deref()),
+            ["recover"] => recover(None),
+            ["recover", mountpoint] => recover(Some(mountpoint)),
+            ["access-order", name] => access_order(name),
+            ["unused", name] => unused(name),
+            ["files", name] => files(name),
+            ["copy-accessed", name, target] => copy_accessed(name, target),
+            ["manifest", name, manifest] => compare_manifest(name, manifest),
+            ["redundant", name] => redundant(name),
+            ["processes", name] => processes(name),
+            ["startup", name] => startup(name, None, false),
+            ["startup", name, "first-read"] => startup(name, None, true),
+            ["startup", name, bounds] => startup(name, Some(bounds), false),
+            ["startup", name, bounds, "first-read"] => startup(name, Some(bounds), true),
+            ["html", name, output] => html_report(name, output),
+            ["extract", name, target] => extract_reads(name, target, ExtractMode::Sparse),
+            ["extract", name, target, "sparse"] => extract_reads(name, target, ExtractMode::Sparse),
+            ["extract", name, target, "concatenated"] => extract_reads(name, target, ExtractMode::Concatenated),
+            ["interleave", name, log] => interleave(name, log, Alignment::Offset("0")),
+            ["interleave", name, log, offset] => interleave(name, log, Alignment::Offset(offset)),
+            ["interleave", name, log, marker, text] => interleave(name, log, Alignment::Anchor(marker, text)),
+            _ => {
+                eprintln!("{USAGE}");
+                std::process::exit(2);
+            }
+        };
+        if let Err(err) = result {
+            eprintln!("{err}");
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+    let settings = Settings::load();
+    let position = match settings.window_position {
+        Some((x, y)) => iced::window::Position::Specific(iced::Point::new(x, y)),
+        None => iced::window::Position::Centered
+    };
+    iced::application(AccessTrackingFsGui::title, AccessTrackingFsGui::update, AccessTrackingFsGui::view)
         .subscription(AccessTrackingFsGui::subscription)
-        .centered()
-        .window_size((800.0, 600.0))
-        .run()
+        .exit_on_close_request(false)
+        .position(position)
+        .window_size(settings.window_size)
+        .run_with(move || (AccessTrackingFsGui::new(settings), iced::Task::none()))
+}
+
+fn load_session(name : &str) -> fuse_file_access_monitor::Result<Session> {
+    let dir = fuse_file_access_monitor::sessions_dir().ok_or(fuse_file_access_monitor::Error::NoConfigDir)?;
+    Session::load(&dir, name)
+}
+
+/// Prints what is missing for mounting with the saved options and the commands that fix it, fails if anything is.
+fn check_setup() -> fuse_file_access_monitor::Result<()> {
+    let missing = diagnostics::check_prerequisites(&Settings::load().mount_options);
+    if missing.is_empty() {
+        println!("Everything needed for mounting is set up.");
+        return Ok(());
+    }
+    for prerequisite in &missing {
+        println!("{}", prerequisite.hint());
+        if let Some(command) = prerequisite.fix() {
+            println!("    {command}");
+        }
+    }
+    std::process::exit(1);
+}
+
+/// Prints what mounting with the saved options would find as json, see `container::Status`.
+fn status(container_mode : bool) -> fuse_file_access_monitor::Result<()> {
+    use std::io::Write;
+    let status = container::status(&Settings::load().mount_options, container_mode);
+    let mut out = std::io::stdout().lock();
+    serde_json::to_writer_pretty(&mut out, &status).map_err(|err| fuse_file_access_monitor::Error::io("stdout", err.into()))?;
+    writeln!(out).map_err(|err| fuse_file_access_monitor::Error::io("stdout", err))
+}
+
+/// Mounts without the GUI and prints every event until the mount is unmounted, e.g. in a container next to a game server.
+/// `direct_io` turns on `MountOptions::direct_io` in addition to the saved options.
+fn run(source : &str, mountpoint : &str, container_mode : bool, direct_io : bool) -> fuse_file_access_monitor::Result<()> {
+    use std::io::Write;
+    let settings = Settings::load();
+    let mut options = settings.mount_options;
+    if container_mode {
+        options = container::mount_options(options);
+    }
+    options.direct_io |= direct_io;
+    for missing in container::check_prerequisites(&options, container_mode) {
+        eprintln!("{}", missing.hint());
+        if let Some(command) = missing.fix() {
+            eprintln!("    {command}");
+        }
+    }
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let mut builder = MountBuilder::new(source, mountpoint).options(options.clone()).events(sender);
+    if let Some(mounter) = privileges::mounter() {
+        builder = builder.mounter(mounter);
+    }
+    let session = builder.mount().inspect_err(|err| {
+        for problem in MountProblem::diagnose(err, mountpoint, &options) {
+            eprintln!("{}", problem.hint(mountpoint));
+        }
+    })?;
+    eprintln!("Mounted {source} at {mountpoint}");
+    let registered = std::path::PathBuf::from(mountpoint);
+    signals::on_shutdown(move |_| {
+        // ends the session like an external unmount, the loop below then returns
+        if let Err(err) = registry::request_unmount(&registered, privileges::mounter().as_deref()) {
+            eprintln!("{err}, press Ctrl+C again to unmount lazily");
+        }
+    });
+    let mut out = std::io::stdout().lock();
+    let mut last_run = std::collections::HashMap::new();
+    // the sender is dropped with the filesystem, when the mount is gone
+    for event in receiver {
+        if let EventType::Read(read) = &event.event {
+            watch::run_commands(&settings.watch_rules, &mut last_run, &event, read);
+        }
+        writeln!(out, "{event}").map_err(|err| fuse_file_access_monitor::Error::io("stdout", err))?;
+    }
+    session.join();
+    Ok(())
+}
+
+/// Prints the mounts of all running monitors: pid, mountpoint, source, backend and when it was mounted.
+fn mounts() -> fuse_file_access_monitor::Result<()> {
+    use std::io::Write;
+    let mut out = std::io::stdout().lock();
+    for mount in registry::active() {
+        writeln!(out, "{}\t{}\t{}\t{}\t{}", mount.pid, mount.mountpoint.display(), mount.source, mount.backend, mount.mounted_at.to_rfc3339())
+            .map_err(|err| fuse_file_access_monitor::Error::io("stdout", err))?;
+    }
+    Ok(())
+}
+
+/// Lazily unmounts the mounts crashed monitors left behind, and `mountpoint` if it is one of them.
+fn recover(mountpoint : Option<&str>) -> fuse_file_access_monitor::Result<()> {
+    let mut stale : Vec<_> = registry::stale().into_iter().map(|mount| mount.mountpoint).collect();
+    if let Some(mountpoint) = mountpoint.map(std::path::PathBuf::from).filter(|mountpoint| platform::is_stale(mountpoint)) {
+        stale.push(mountpoint);
+    }
+    for mountpoint in stale {
+        match registry::recover(&mountpoint, privileges::mounter().as_deref()) {
+            true => println!("Unmounted {}", mountpoint.display()),
+            false => eprintln!("Failed to unmount {}", mountpoint.display())
+        }
+    }
+    Ok(())
+}
+
+/// Prints the files of a saved session in the order they were first read.
+fn access_order(name : &str) -> fuse_file_access_monitor::Result<()> {
+    let session = load_session(name)?;
+    let accesses = analysis::access_order(session.events.iter());
+    analysis::write_access_order(&mut std::io::stdout().lock(), &accesses, session.mounted_at)
+        .map_err(|err| fuse_file_access_monitor::Error::io("stdout", err))
+}
+
+/// Prints the files of the source of a saved session that were never read, with the unused bytes per directory.
+fn unused(name : &str) -> fuse_file_access_monitor::Result<()> {
+    let session = load_session(name)?;
+    let accessed = session.file_totals();
+    let files = analysis::source_files(&DiskSource::new(&session.source))
+        .map_err(|err| fuse_file_access_monitor::Error::io(&session.source, err))?;
+    let report = analysis::unused_files(files.iter().map(|(file, size)| (file.as_str(), *size)), |file| accessed.contains_key(file));
+    analysis::write_unused(&mut std::io::stdout().lock(), &report)
+        .map_err(|err| fuse_file_access_monitor::Error::io("stdout", err))
+}
+
+/// Prints reads, bytes and the access pattern of every file read in a saved session.
+fn files(name : &str) -> fuse_file_access_monitor::Result<()> {
+    use std::io::Write;
+    let session = load_session(name)?;
+    let totals = session.file_totals();
+    let mut out = std::io::stdout().lock();
+    let written = writeln!(out, "# reads, bytes, pattern, sequential %, mean seek, path").and_then(|()| {
+        for (file, pattern) in session.file_patterns() {
+            let (reads, bytes) = totals.get(&file).copied().unwrap_or_default();
+            let sequential = pattern.sequential_share() * 100.0;
+            writeln!(out, "{reads}\t{bytes}\t{}\t{sequential:.0}\t{}\t{file}", pattern.kind(), pattern.mean_seek())?;
+        }
+        Ok(())
+    });
+    written.map_err(|err| fuse_file_access_monitor::Error::io("stdout", err))
+}
+
+/// Copies every file read in a saved session from its source to `target`, keeping the relative paths.
+fn copy_accessed(name : &str, target : &str) -> fuse_file_access_monitor::Result<()> {
+    let session = load_session(name)?;
+    let files = session.file_totals();
+    let (count, bytes) = copy::copy_files(session.source.as_ref(), files.keys().map(String::as_str), target.as_ref())?;
+    println!("Copied {count} files ({}) to {target}", analysis::format_size(bytes));
+    Ok(())
+}
+
+/// Writes the bytes of every file that were read in a saved session to `target`, read again from the source
+/// since sessions don't keep payloads.
+fn extract_reads(name : &str, target : &str, mode : ExtractMode) -> fuse_file_access_monitor::Result<()> {
+    let session = load_session(name)?;
+    let (count, bytes) = extract::extract_reads(session.events.iter(), session.source.as_ref(), target.as_ref(), mode)?;
+    println!("Extracted {} from {count} files to {target}", analysis::format_size(bytes));
+    Ok(())
+}
+
+/// Prints which files of a manifest a saved session read, which are missing from its source and what it read besides them.
+fn compare_manifest(name : &str, manifest : &str) -> fuse_file_access_monitor::Result<()> {
+    let session = load_session(name)?;
+    let manifest = Manifest::load(manifest.as_ref())?;
+    let files = session.file_totals();
+    let source = std::path::Path::new(&session.source);
+    let report = manifest.compare(files.keys().map(String::as_str), |file| source.join(file).is_file());
+    report.write(&mut std::io::stdout().lock()).map_err(|err| fuse_file_access_monitor::Error::io("stdout", err))
+}
+
+/// Prints the files of a saved session that were read more than once at the same range, most wasted bytes first.
+fn redundant(name : &str) -> fuse_file_access_monitor::Result<()> {
+    let session = load_session(name)?;
+    let redundant = analysis::redundant_reads(session.events.iter());
+    analysis::write_redundant_reads(&mut std::io::stdout().lock(), &redundant)
+        .map_err(|err| fuse_file_access_monitor::Error::io("stdout", err))
+}
+
+/// Prints the files and bytes read per process of a saved session, in process trees.
+fn processes(name : &str) -> fuse_file_access_monitor::Result<()> {
+    let session = load_session(name)?;
+    let usage = analysis::process_usage(session.events.iter());
+    analysis::write_process_usage(&mut std::io::stdout().lock(), &usage)
+        .map_err(|err| fuse_file_access_monitor::Error::io("stdout", err))
+}
+
+/// Prints the files that dominate each phase after mounting, or after the first read with `from_first_read`.
+fn startup(name : &str, bounds : Option<&str>, from_first_read : bool) -> fuse_file_access_monitor::Result<()> {
+    let bounds = match bounds.map(analysis::parse_phase_bounds) {
+        None => analysis::DEFAULT_PHASE_BOUNDS.to_vec(),
+        Some(Some(bounds)) => bounds,
+        Some(None) => {
+            eprintln!("Phase ends have to be ascending seconds separated by commas, e.g. 5,15,30");
+            std::process::exit(2);
+        }
+    };
+    let session = load_session(name)?;
+    let start = match from_first_read {
+        true => analysis::first_read_time(session.events.iter()).unwrap_or(session.mounted_at),
+        false => session.mounted_at
+    };
+    let phases = analysis::phases(session.events.iter(), start, &bounds);
+    analysis::write_phases(&mut std::io::stdout().lock(), &phases, STARTUP_TOP_FILES)
+        .map_err(|err| fuse_file_access_monitor::Error::io("stdout", err))
+}
+
+/// Writes a standalone html coverage report of a saved session to `output`.
+fn html_report(name : &str, output : &str) -> fuse_file_access_monitor::Result<()> {
+    let session = load_session(name)?;
+    let mut file = std::io::BufWriter::new(std::fs::File::create(output).map_err(|err| fuse_file_access_monitor::Error::io(output, err))?);
+    html::write_report(&mut file, &session)
+        .and_then(|()| std::io::Write::flush(&mut file))
+        .map_err(|err| fuse_file_access_monitor::Error::io(output, err))
+}
+
+/// How the lines of an external log are moved onto the time of the events.
+enum Alignment<'a> {
+    Offset(&'a str), // seconds added to the times of the log
+    Anchor(&'a str, &'a str) // label of a marker and text of the line logged at the time of the marker
+}
+
+/// Prints the events of a saved session interleaved with the lines of another program's log.
+fn interleave(name : &str, log : &str, alignment : Alignment) -> fuse_file_access_monitor::Result<()> {
+    let session = load_session(name)?;
+    let external = ExternalLog::load(log.as_ref(), session.mounted_at)?;
+    let offset = match alignment {
+        Alignment::Offset(seconds) => match seconds.parse::<f64>() {
+            Ok(seconds) => chrono::TimeDelta::milliseconds((seconds * 1000.0) as i64),
+            Err(_) => {
+                eprintln!("{seconds} is not a number of seconds");
+                std::process::exit(2);
+            }
+        },
+        Alignment::Anchor(marker, text) => {
+            let Some(time) = external_log::marker_time(session.events.iter(), marker) else {
+                eprintln!("The session has no marker {marker}");
+                std::process::exit(1);
+            };
+            let Some(offset) = external.anchor_offset(text, time) else {
+                eprintln!("No line of {log} contains {text}");
+                std::process::exit(1);
+            };
+            offset
+        }
+    };
+    let entries = external_log::interleave(session.events.iter(), &external, offset);
+    external_log::write_interleaved(&mut std::io::stdout().lock(), &entries)
+        .map_err(|err| fuse_file_access_monitor::Error::io("stdout", err))
 }
@@ -0,0 +1,70 @@
+use std::collections::BTreeSet;
+use std::path::Path;
+
+/// Files a mod or installation is expected to consist of, to check it against what a session read.
+///
+/// Loaded from a json array of paths or from a text file with one path per line, empty lines and lines starting
+/// with '#' are skipped. Paths are relative to the source, leading slashes and Windows separators are accepted.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Manifest {
+    pub files : BTreeSet<String>
+}
+
+/// The files of a manifest split up by whether they exist and were read, and the files that were read without being expected.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ManifestReport {
+    pub accessed : Vec<String>,
+    pub not_accessed : Vec<String>, // in the source, but never read
+    pub missing : Vec<String>, // not in the source
+    pub unexpected : Vec<String> // read, but not in the manifest
+}
+
+impl Manifest {
+    pub fn load(path : &Path) -> crate::Result<Self> {
+        let content = std::fs::read_to_string(path).map_err(|err| crate::Error::io(path, err))?;
+        let files : Vec<String> = match path.extension().and_then(|extension| extension.to_str()) {
+            Some("json") => serde_json::from_str(&content).map_err(|err| crate::Error::Manifest { path: path.to_path_buf(), message: err.to_string() })?,
+            _ => content.lines().map(str::trim).filter(|line| !line.is_empty() && !line.starts_with('#')).map(String::from).collect()
+        };
+        Ok(Self::new(files))
+    }
+
+    pub fn new(files : impl IntoIterator<Item = String>) -> Self {
+        let files = files.into_iter().map(|file| {
+            let file = file.replace('\\', "/");
+            file.trim_start_matches("./").trim_start_matches('/').to_string()
+        }).collect();
+        Self { files }
+    }
+
+    /// Checks the manifest against the files that were read, `exists` tells whether a path is in the source.
+    pub fn compare<'a>(&self, accessed : impl Iterator<Item = &'a str>, exists : impl Fn(&str) -> bool) -> ManifestReport {
+        let accessed : BTreeSet<&str> = accessed.collect();
+        let mut report = ManifestReport::default();
+        for file in &self.files {
+            if accessed.contains(file.as_str()) {
+                report.accessed.push(file.clone());
+            } else if exists(file) {
+                report.not_accessed.push(file.clone());
+            } else {
+                report.missing.push(file.clone());
+            }
+        }
+        report.unexpected = accessed.into_iter().filter(|file| !self.files.contains(*file)).map(String::from).collect();
+        report
+    }
+}
+
+impl ManifestReport {
+    /// Writes every list under a heading, one path per line.
+    pub fn write(&self, out : &mut impl std::io::Write) -> std::io::Result<()> {
+        let lists = [("accessed", &self.accessed), ("not accessed", &self.not_accessed), ("missing from the source", &self.missing), ("accessed but not expected", &self.unexpected)];
+        for (heading, files) in lists {
+            writeln!(out, "# {heading}: {}", files.len())?;
+            for file in files {
+                writeln!(out, "{file}")?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,446 @@
+//! Running as the user who started the monitor with sudo (or pkexec) instead of as root.
+//!
+//! Only mounting and unmounting need root. At the start the monitor spawns a helper process that keeps root and then
+//! gives up all of its own root ids for good, so the event pipeline, the GUI and every file written (settings,
+//! sessions, exports, recordings) belong to the user, and nothing in the monitor can take root back.
+//!
+//! The helper only does what `Mounter` asks for: it mounts FUSE and NFS at directories the user may write to, like
+//! fusermount, and unmounts its own mounts or FUSE mounts of the user. It talks to the monitor over a socket pair
+//! and lives as long as that: when the monitor exits, even by crashing, the helper lazily unmounts everything it
+//! mounted and exits as well. The helper needs Linux, elsewhere the monitor keeps running as root.
+use serde::{Deserialize, Serialize};
+use std::ffi::{CStr, CString};
+use std::fs::File;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use crate::backend::Mounter;
+use crate::MountOptions;
+
+/// The user who ran sudo or pkexec.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvokingUser {
+    pub uid : u32,
+    pub gid : u32,
+    pub name : String,
+    pub home : PathBuf
+}
+
+/// The argument the monitor starts the helper with, `main` then runs `serve_helper`.
+pub const HELPER_ARGUMENT : &str = "privileged-helper";
+/// Largest request or reply, only paths make them longer than a few bytes.
+const MAX_MESSAGE : usize = 64 * 1024;
+
+static DROPPED : OnceLock<InvokingUser> = OnceLock::new();
+static HELPER : OnceLock<Arc<Helper>> = OnceLock::new();
+
+/// The user who started this process as root through sudo or pkexec, `None` if it wasn't.
+pub fn invoking_user() -> Option<InvokingUser> {
+    if let Some(user) = DROPPED.get() {
+        return Some(user.clone());
+    }
+    // SAFETY: getuid can't fail
+    if unsafe { libc::getuid() } != 0 {
+        return None;
+    }
+    let uid : u32 = std::env::var("SUDO_UID").or_else(|_| std::env::var("PKEXEC_UID")).ok()?.parse().ok()?;
+    if uid == 0 {
+        return None;
+    }
+    lookup(uid)
+}
+
+fn lookup(uid : u32) -> Option<InvokingUser> {
+    let mut buffer = vec![0 as libc::c_char; 16 * 1024];
+    // SAFETY: passwd is plain data, all zeroes is a valid value
+    let mut passwd : libc::passwd = unsafe { std::mem::zeroed() };
+    let mut result = std::ptr::null_mut();
+    // SAFETY: the strings of `passwd` point into `buffer`, which outlives their use below
+    let found = unsafe { libc::getpwuid_r(uid, &mut passwd, buffer.as_mut_ptr(), buffer.len(), &mut result) };
+    if found != 0 || result.is_null() {
+        return None;
+    }
+    // SAFETY: getpwuid_r succeeded, so both are null terminated strings in `buffer`
+    let (name, home) = unsafe { (CStr::from_ptr(passwd.pw_name), CStr::from_ptr(passwd.pw_dir)) };
+    Some(InvokingUser { uid, gid: passwd.pw_gid, name: name.to_string_lossy().into_owned(), home: PathBuf::from(home.to_string_lossy().into_owned()) })
+}
+
+/// Points `HOME` and `XDG_RUNTIME_DIR` to the invoking user's, so settings, sessions and the mount registry are
+/// the same as without sudo. Has to be called at the start of `main`, before any other thread runs.
+pub fn adopt_user_environment() {
+    let Some(user) = invoking_user() else {
+        return;
+    };
+    let runtime_dir = PathBuf::from(format!("/run/user/{}", user.uid));
+    // SAFETY: called before any other thread exists, so nothing reads the environment concurrently
+    unsafe {
+        std::env::set_var("HOME", &user.home);
+        if runtime_dir.is_dir() {
+            std::env::set_var("XDG_RUNTIME_DIR", runtime_dir);
+        } else {
+            std::env::remove_var("XDG_RUNTIME_DIR");
+        }
+    }
+}
+
+/// Switches to the invoking user for good, if started through sudo and not done yet, after spawning the helper that
+/// keeps root. Has to be called at the start of `main`, like `adopt_user_environment`. Returns the user it switched to.
+pub fn drop_to_invoking_user() -> std::io::Result<Option<InvokingUser>> {
+    if DROPPED.get().is_some() || !cfg!(target_os = "linux") {
+        return Ok(None);
+    }
+    let Some(user) = invoking_user() else {
+        return Ok(None);
+    };
+    let helper = Helper::spawn()?;
+    let name = CString::new(user.name.as_str()).map_err(std::io::Error::other)?;
+    // the groups first, they can't be changed without root anymore afterwards. The saved ids are given up as well,
+    // otherwise root could be taken back
+    // SAFETY: plain system calls, glibc applies the id changes to all threads
+    unsafe {
+        check(libc::initgroups(name.as_ptr(), user.gid as _))?;
+        check(libc::setresgid(user.gid, user.gid, user.gid))?;
+        check(libc::setresuid(user.uid, user.uid, user.uid))?;
+    }
+    let _ = HELPER.set(Arc::new(helper));
+    let _ = DROPPED.set(user.clone());
+    Ok(Some(user))
+}
+
+/// Mounts and unmounts through the helper after `drop_to_invoking_user`, `None` before or without sudo.
+pub fn mounter() -> Option<Arc<dyn Mounter>> {
+    HELPER.get().map(|helper| helper.clone() as Arc<dyn Mounter>)
+}
+
+/// What the monitor asks the helper for.
+#[derive(Debug, Serialize, Deserialize)]
+enum Request {
+    MountFuse {
+        mountpoint : PathBuf,
+        allow_other : bool,
+        read_only : bool
+    },
+    MountNfs {
+        port : u16,
+        mountpoint : PathBuf,
+        direct_io : bool
+    },
+    Unmount {
+        mountpoint : PathBuf,
+        force : bool
+    }
+}
+
+/// The answer to every request, the error with its errno if it has one. `/dev/fuse` comes along with a FUSE mount.
+type Reply = Result<(), (Option<i32>, String)>;
+
+/// The monitor's end of the socket to the helper.
+#[derive(Debug)]
+struct Helper {
+    socket : Mutex<OwnedFd> // one request at a time, each gets its reply before the next one is sent
+}
+
+impl Helper {
+    fn spawn() -> std::io::Result<Self> {
+        use std::os::unix::process::CommandExt;
+        let (ours, theirs) = socket_pair()?;
+        std::process::Command::new(std::env::current_exe()?)
+            .arg(HELPER_ARGUMENT)
+            .stdin(theirs)
+            .stdout(std::process::Stdio::null())
+            // in its own process group, so Ctrl+C in the terminal doesn't end it before the monitor unmounted
+            .process_group(0)
+            .spawn()?;
+        Ok(Self { socket: Mutex::new(ours) })
+    }
+
+    fn request(&self, request : &Request) -> std::io::Result<Option<OwnedFd>> {
+        let socket = self.socket.lock().unwrap_or_else(|err| err.into_inner());
+        send(&socket, &serde_json::to_vec(request)?, None)?;
+        let (reply, device) = receive(&socket)?;
+        if reply.is_empty() {
+            return Err(std::io::Error::new(std::io::ErrorKind::BrokenPipe, "the privileged helper is gone"));
+        }
+        match serde_json::from_slice::<Reply>(&reply)? {
+            Ok(()) => Ok(device),
+            Err((Some(errno), _)) => Err(std::io::Error::from_raw_os_error(errno)),
+            Err((None, message)) => Err(std::io::Error::other(message))
+        }
+    }
+}
+
+impl Mounter for Helper {
+    fn mount_fuse(&self, mountpoint : &Path, options : &MountOptions) -> std::io::Result<OwnedFd> {
+        let request = Request::MountFuse { mountpoint: mountpoint.to_path_buf(), allow_other: options.allow_other, read_only: options.read_only };
+        self.request(&request)?.ok_or_else(|| std::io::Error::other("the privileged helper sent no FUSE device"))
+    }
+
+    fn mount_nfs(&self, port : u16, mountpoint : &Path, options : &MountOptions) -> std::io::Result<()> {
+        self.request(&Request::MountNfs { port, mountpoint: mountpoint.to_path_buf(), direct_io: options.direct_io }).map(drop)
+    }
+
+    fn unmount(&self, mountpoint : &Path, force : bool) -> std::io::Result<()> {
+        self.request(&Request::Unmount { mountpoint: mountpoint.to_path_buf(), force }).map(drop)
+    }
+}
+
+/// The helper, run by `main` when started with `HELPER_ARGUMENT`. Answers the requests coming in on stdin, the
+/// socket from the monitor, until the monitor closes it, then lazily unmounts whatever is still mounted.
+pub fn serve_helper() -> std::io::Result<()> {
+    let Some(user) = invoking_user() else {
+        return Err(std::io::Error::new(std::io::ErrorKind::PermissionDenied, "the helper has to be started as root through sudo or pkexec"));
+    };
+    let name = CString::new(user.name.as_str()).map_err(std::io::Error::other)?;
+    // the user's groups, so the access checks of the mountpoints see the same groups as the user
+    // SAFETY: plain system call, the helper has a single thread
+    check(unsafe { libc::initgroups(name.as_ptr(), user.gid as _) })?;
+    // SAFETY: stdin is the socket the monitor passed, nothing else uses it
+    let socket = unsafe { OwnedFd::from_raw_fd(libc::STDIN_FILENO) };
+    let mut mounted = Vec::new();
+    loop {
+        let message = match receive(&socket) {
+            Ok((message, _)) if !message.is_empty() => message,
+            _ => break // the monitor is gone
+        };
+        let (reply, device) : (Reply, _) = match serde_json::from_slice(&message).map_err(std::io::Error::from).and_then(|request| handle(request, &user, &mut mounted)) {
+            Ok(device) => (Ok(()), device),
+            Err(err) => (Err((err.raw_os_error(), err.to_string())), None)
+        };
+        if send(&socket, &serde_json::to_vec(&reply)?, device.as_ref()).is_err() {
+            break;
+        }
+    }
+    for mountpoint in mounted {
+        let _ = unmount(&mountpoint, true);
+    }
+    Ok(())
+}
+
+fn handle(request : Request, user : &InvokingUser, mounted : &mut Vec<PathBuf>) -> std::io::Result<Option<OwnedFd>> {
+    match request {
+        Request::MountFuse { mountpoint, allow_other, read_only } => {
+            let (mountpoint, directory) = open_mountpoint(&mountpoint, user)?;
+            let device = mount_fuse(&directory, allow_other, read_only, user)?;
+            mounted.push(mountpoint);
+            Ok(Some(device))
+        }
+        Request::MountNfs { port, mountpoint, direct_io } => {
+            let (mountpoint, directory) = open_mountpoint(&mountpoint, user)?;
+            mount_nfs(&directory, port, direct_io)?;
+            mounted.push(mountpoint);
+            Ok(None)
+        }
+        Request::Unmount { mountpoint, force } => {
+            // a stale mount can't be resolved, its path is already canonical when it was registered
+            let mountpoint = std::fs::canonicalize(&mountpoint).unwrap_or(mountpoint);
+            if !mounted.contains(&mountpoint) && !fuse_mount_of(&mountpoint, user.uid) {
+                return Err(std::io::Error::new(std::io::ErrorKind::PermissionDenied, "neither mounted by the monitor nor a FUSE mount of the user"));
+            }
+            unmount(&mountpoint, force)?;
+            mounted.retain(|other| *other != mountpoint);
+            Ok(None)
+        }
+    }
+}
+
+/// Opens `mountpoint` if it is a directory the user may write to, which fusermount demands as well, so the helper
+/// can't be used to mount over directories the user couldn't change anyway. Mounting at the opened directory
+/// (`/proc/self/fd/…`) instead of the path keeps the path from being swapped for a symlink after the check.
+fn open_mountpoint(mountpoint : &Path, user : &InvokingUser) -> std::io::Result<(PathBuf, File)> {
+    use std::os::unix::fs::OpenOptionsExt;
+    let mountpoint = std::fs::canonicalize(mountpoint)?;
+    let directory = std::fs::OpenOptions::new().read(true).custom_flags(libc::O_DIRECTORY | libc::O_NOFOLLOW).open(&mountpoint)?;
+    let path = mount_target(&directory)?;
+    // access checks with the real ids, which become the user's for the check. Root stays the effective and saved
+    // id, so the real ones can be set back
+    // SAFETY: plain system calls, the helper has a single thread
+    let writable = unsafe {
+        check(libc::setresgid(user.gid, u32::MAX, u32::MAX))?;
+        check(libc::setresuid(user.uid, u32::MAX, u32::MAX))?;
+        let writable = libc::access(path.as_ptr(), libc::W_OK) == 0;
+        if libc::setresuid(0, u32::MAX, u32::MAX) != 0 || libc::setresgid(0, u32::MAX, u32::MAX) != 0 {
+            std::process::abort();
+        }
+        writable
+    };
+    match writable {
+        true => Ok((mountpoint, directory)),
+        false => Err(std::io::Error::from_raw_os_error(libc::EACCES))
+    }
+}
+
+/// The path of an opened directory that mounting at it mounts at the directory itself.
+fn mount_target(directory : &File) -> std::io::Result<CString> {
+    Ok(CString::new(format!("/proc/self/fd/{}", directory.as_raw_fd()))?)
+}
+
+/// Mounts FUSE like fusermount does and returns the opened `/dev/fuse`, which the monitor then serves.
+#[cfg(target_os = "linux")]
+fn mount_fuse(directory : &File, allow_other : bool, read_only : bool, user : &InvokingUser) -> std::io::Result<OwnedFd> {
+    use std::os::unix::fs::MetadataExt;
+    let device = std::fs::OpenOptions::new().read(true).write(true).open("/dev/fuse")?;
+    let root_mode = directory.metadata()?.mode() & libc::S_IFMT;
+    let mut data = format!("fd={},rootmode={root_mode:o},user_id={},group_id={}", device.as_raw_fd(), user.uid, user.gid);
+    if allow_other {
+        data.push_str(",allow_other");
+    }
+    let mut flags = libc::MS_NOSUID | libc::MS_NODEV;
+    if read_only {
+        flags |= libc::MS_RDONLY;
+    }
+    let (target, data) = (mount_target(directory)?, CString::new(data)?);
+    // SAFETY: all strings are null terminated, the kernel only reads them
+    check(unsafe { libc::mount(c"passthrough".as_ptr(), target.as_ptr(), c"fuse".as_ptr(), flags, data.as_ptr().cast()) })?;
+    Ok(device.into())
+}
+
+/// Mounts the NFS server on the local `port`, with the same options as `nfs::mount`. The kernel takes the server's
+/// address as an option, so no `mount` program is needed, which couldn't mount at the opened directory.
+#[cfg(target_os = "linux")]
+fn mount_nfs(directory : &File, port : u16, direct_io : bool) -> std::io::Result<()> {
+    let options = MountOptions { direct_io, ..MountOptions::default() };
+    let data = CString::new(format!("{},addr=127.0.0.1", crate::nfs::client_options(port, &options)))?;
+    let target = mount_target(directory)?;
+    let flags = libc::MS_NOSUID | libc::MS_NODEV | libc::MS_RDONLY;
+    // SAFETY: all strings are null terminated, the kernel only reads them
+    check(unsafe { libc::mount(c"127.0.0.1:/".as_ptr(), target.as_ptr(), c"nfs".as_ptr(), flags, data.as_ptr().cast()) })
+}
+
+#[cfg(target_os = "linux")]
+fn unmount(mountpoint : &Path, force : bool) -> std::io::Result<()> {
+    let path = CString::new(mountpoint.as_os_str().as_bytes())?;
+    let flags = match force {
+        true => libc::MNT_DETACH | libc::UMOUNT_NOFOLLOW,
+        false => libc::UMOUNT_NOFOLLOW
+    };
+    // SAFETY: the path is null terminated
+    check(unsafe { libc::umount2(path.as_ptr(), flags) })
+}
+
+/// Whether a FUSE filesystem of the user with `uid` is mounted at `mountpoint`, which fusermount would unmount for them.
+#[cfg(target_os = "linux")]
+fn fuse_mount_of(mountpoint : &Path, uid : u32) -> bool {
+    let Ok(mountinfo) = std::fs::read_to_string("/proc/self/mountinfo") else {
+        return false;
+    };
+    let mountpoint = mountpoint.to_string_lossy();
+    let user_id = format!("user_id={uid}");
+    // the fifth field is the mountpoint, escaped like in `platform::is_mounted`, the filesystem type and its options
+    // follow the separator
+    mountinfo.lines().filter_map(|line| line.split_once(" - ")).any(|(fields, filesystem)| {
+        let point = fields.split(' ').nth(4).unwrap_or_default();
+        let mut filesystem = filesystem.split(' ');
+        let (kind, options) = (filesystem.next().unwrap_or_default(), filesystem.nth(1).unwrap_or_default());
+        point.replace("\\040", " ").replace("\\011", "\t").replace("\\012", "\n").replace("\\134", "\\") == mountpoint
+            && (kind == "fuse" || kind.starts_with("fuse."))
+            && options.split(',').any(|option| option == user_id)
+    })
+}
+
+#[cfg(not(target_os = "linux"))]
+fn mount_fuse(_directory : &File, _allow_other : bool, _read_only : bool, _user : &InvokingUser) -> std::io::Result<OwnedFd> {
+    Err(std::io::ErrorKind::Unsupported.into())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn mount_nfs(_directory : &File, _port : u16, _direct_io : bool) -> std::io::Result<()> {
+    Err(std::io::ErrorKind::Unsupported.into())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn unmount(_mountpoint : &Path, _force : bool) -> std::io::Result<()> {
+    Err(std::io::ErrorKind::Unsupported.into())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn fuse_mount_of(_mountpoint : &Path, _uid : u32) -> bool {
+    false
+}
+
+/// A connected pair of sockets that keep the boundaries of the messages.
+fn socket_pair() -> std::io::Result<(OwnedFd, OwnedFd)> {
+    let mut fds = [0; 2];
+    // SAFETY: on success socketpair writes two new descriptors to `fds`, which nothing else owns
+    unsafe {
+        check(libc::socketpair(libc::AF_UNIX, libc::SOCK_SEQPACKET | libc::SOCK_CLOEXEC, 0, fds.as_mut_ptr()))?;
+        Ok((OwnedFd::from_raw_fd(fds[0]), OwnedFd::from_raw_fd(fds[1])))
+    }
+}
+
+/// Sends one message, with `fd` attached if given.
+fn send(socket : &OwnedFd, message : &[u8], fd : Option<&OwnedFd>) -> std::io::Result<()> {
+    let mut iov = libc::iovec { iov_base: message.as_ptr() as *mut libc::c_void, iov_len: message.len() };
+    let mut control = [0u64; 4]; // room for one descriptor, aligned like a cmsghdr
+    // SAFETY: msghdr is plain data, all zeroes is a valid value
+    let mut header : libc::msghdr = unsafe { std::mem::zeroed() };
+    header.msg_iov = &mut iov;
+    header.msg_iovlen = 1;
+    if let Some(fd) = fd {
+        header.msg_control = control.as_mut_ptr().cast();
+        // SAFETY: the control buffer holds a header and one descriptor, which is all that is written
+        unsafe {
+            header.msg_controllen = libc::CMSG_SPACE(size_of::<libc::c_int>() as u32) as _;
+            let cmsg = libc::CMSG_FIRSTHDR(&header);
+            (*cmsg).cmsg_level = libc::SOL_SOCKET;
+            (*cmsg).cmsg_type = libc::SCM_RIGHTS;
+            (*cmsg).cmsg_len = libc::CMSG_LEN(size_of::<libc::c_int>() as u32) as _;
+            std::ptr::write_unaligned(libc::CMSG_DATA(cmsg).cast::<libc::c_int>(), fd.as_raw_fd());
+        }
+    }
+    loop {
+        // SAFETY: the header points to the message and the control buffer, which outlive the call
+        match unsafe { libc::sendmsg(socket.as_raw_fd(), &header, libc::MSG_NOSIGNAL) } {
+            sent if sent >= 0 => return Ok(()),
+            _ => {
+                let err = std::io::Error::last_os_error();
+                if err.kind() != std::io::ErrorKind::Interrupted {
+                    return Err(err);
+                }
+            }
+        }
+    }
+}
+
+/// Receives one message and the descriptor attached to it, an empty message once the other end is closed.
+fn receive(socket : &OwnedFd) -> std::io::Result<(Vec<u8>, Option<OwnedFd>)> {
+    let mut message = vec![0u8; MAX_MESSAGE];
+    let mut iov = libc::iovec { iov_base: message.as_mut_ptr().cast(), iov_len: message.len() };
+    let mut control = [0u64; 4];
+    // SAFETY: msghdr is plain data, all zeroes is a valid value
+    let mut header : libc::msghdr = unsafe { std::mem::zeroed() };
+    header.msg_iov = &mut iov;
+    header.msg_iovlen = 1;
+    header.msg_control = control.as_mut_ptr().cast();
+    header.msg_controllen = size_of_val(&control) as _;
+    let received = loop {
+        // SAFETY: the header points to the message and the control buffer, the kernel writes at most their lengths
+        match unsafe { libc::recvmsg(socket.as_raw_fd(), &mut header, libc::MSG_CMSG_CLOEXEC) } {
+            received if received >= 0 => break received as usize,
+            _ => {
+                let err = std::io::Error::last_os_error();
+                if err.kind() != std::io::ErrorKind::Interrupted {
+                    return Err(err);
+                }
+            }
+        }
+    };
+    message.truncate(received);
+    // SAFETY: the kernel filled the control buffer, a descriptor it passed is new and owned by nothing else
+    let fd = unsafe {
+        let cmsg = libc::CMSG_FIRSTHDR(&header);
+        match !cmsg.is_null() && (*cmsg).cmsg_level == libc::SOL_SOCKET && (*cmsg).cmsg_type == libc::SCM_RIGHTS {
+            true => Some(OwnedFd::from_raw_fd(std::ptr::read_unaligned(libc::CMSG_DATA(cmsg).cast::<libc::c_int>()))),
+            false => None
+        }
+    };
+    Ok((message, fd))
+}
+
+fn check(result : libc::c_int) -> std::io::Result<()> {
+    match result {
+        0 => Ok(()),
+        _ => Err(std::io::Error::last_os_error())
+    }
+}
@@ -0,0 +1,213 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::sync::mpsc::{Receiver, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::fs::{ChangeOperation, Event, EventType, MetadataOperation, XattrOperation};
+use crate::plugin::{Plugin, Verdict};
+use ffam_fs::log;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ScriptAction {
+    Tag(String),
+    Log(String),
+    Notify(String),
+    Redirect(String)
+}
+
+/// Actions that may block, run by the worker of the plugin rather than on the FUSE thread.
+#[derive(Debug)]
+enum WorkerAction {
+    Notify(String),
+    Redirect { file : String, line : String }
+}
+
+/// Actions queued for the worker before further ones are dropped.
+const WORKER_QUEUE : usize = 1024;
+/// At most one notification is shown in this time, scripts easily match hundreds of events a second.
+const NOTIFY_INTERVAL : Duration = Duration::from_secs(5);
+
+/// Runs a Rhai script for every event, to filter and react to events without recompiling.
+///
+/// The script sees the variables `kind` ("read", "open", "close", "lookup", "getattr", "readdir", "write", "create", "truncate", "remove", "rename", "mkdir", "rmdir", "symlink", "chmod", "chown", "utimens", "readlink", "getxattr", "listxattr", "setxattr", "marker" or "derived"), `path`, `process`, `pid`, `offset` and `size`.
+/// It can call `tag(text)` to add a line after the event, `notify(text)` for a desktop notification,
+/// `redirect(file)` to append the event to a file and `log(text)` to log through tracing.
+/// If the script evaluates to `false`, the event is dropped.
+///
+/// Notifications and redirects run on a worker thread, so a slow disk or a file inside the mount doesn't block the
+/// FUSE thread, which would deadlock in the latter case.
+pub struct ScriptPlugin {
+    engine : rhai::Engine,
+    ast : rhai::AST,
+    actions : Arc<Mutex<Vec<ScriptAction>>>,
+    worker : SyncSender<WorkerAction>, // the worker exits when the plugin is dropped
+    dropped : u64 // actions that didn't fit into the queue of the worker
+}
+
+impl ScriptPlugin {
+    pub fn new(script : &str) -> crate::Result<Self> {
+        let actions = Arc::new(Mutex::new(Vec::new()));
+        let mut engine = rhai::Engine::new();
+        // the script runs on the FUSE thread, an endless loop must not hang the mount
+        engine.set_max_operations(100_000);
+        let register = |engine : &mut rhai::Engine, name : &str, action : fn(String) -> ScriptAction| {
+            let actions = actions.clone();
+            engine.register_fn(name, move |text : &str| actions.lock().unwrap().push(action(text.to_string())));
+        };
+        register(&mut engine, "tag", ScriptAction::Tag);
+        register(&mut engine, "log", ScriptAction::Log);
+        register(&mut engine, "notify", ScriptAction::Notify);
+        register(&mut engine, "redirect", ScriptAction::Redirect);
+        let ast = engine.compile(script).map_err(|err| crate::Error::Script(err.to_string()))?;
+        let (worker, receiver) = std::sync::mpsc::sync_channel(WORKER_QUEUE);
+        std::thread::Builder::new().name(String::from("script")).spawn(move || run_worker(receiver))
+            .map_err(|err| crate::Error::Script(format!("Failed to start the worker: {err}")))?;
+        Ok(Self { engine, ast, actions, worker, dropped: 0 })
+    }
+
+    fn send(&mut self, action : WorkerAction) {
+        match self.worker.try_send(action) {
+            Ok(()) => (),
+            Err(TrySendError::Full(_)) => {
+                if self.dropped == 0 {
+                    log!(warn, "The script worker falls behind, dropping its notifications and redirects");
+                }
+                self.dropped += 1;
+            }
+            Err(TrySendError::Disconnected(_)) => log!(warn, "The script worker is gone")
+        }
+    }
+
+    fn scope(event : &Event) -> rhai::Scope<'static> {
+        let mut scope = rhai::Scope::new();
+        let (kind, path, offset, size) = match &event.event {
+            EventType::Read(read) => ("read", read.file.to_string_lossy().into_owned(), read.offset as i64, read.size as i64),
+            EventType::Open(open) => ("open", open.file.to_string_lossy().into_owned(), 0, 0),
+            EventType::Close(close) => ("close", close.file.to_string_lossy().into_owned(), 0, 0),
+            EventType::Metadata(metadata) => (match metadata.operation {
+                MetadataOperation::Lookup => "lookup",
+                MetadataOperation::GetAttr => "getattr",
+                MetadataOperation::ReadDir => "readdir",
+                _ => "metadata"
+            }, metadata.file.to_string_lossy().into_owned(), 0, 0),
+            EventType::Write(write) => ("write", write.file.to_string_lossy().into_owned(), write.offset as i64, write.size as i64),
+            EventType::Change(change) => match &change.operation {
+                ChangeOperation::Create => ("create", change.file.to_string_lossy().into_owned(), 0, 0),
+                ChangeOperation::Truncate { size } => ("truncate", change.file.to_string_lossy().into_owned(), 0, *size as i64),
+                ChangeOperation::Remove => ("remove", change.file.to_string_lossy().into_owned(), 0, 0),
+                ChangeOperation::Rename { .. } => ("rename", change.file.to_string_lossy().into_owned(), 0, 0),
+                ChangeOperation::CreateDirectory => ("mkdir", change.file.to_string_lossy().into_owned(), 0, 0),
+                ChangeOperation::RemoveDirectory => ("rmdir", change.file.to_string_lossy().into_owned(), 0, 0),
+                ChangeOperation::Symlink { .. } => ("symlink", change.file.to_string_lossy().into_owned(), 0, 0),
+                ChangeOperation::Permissions { .. } => ("chmod", change.file.to_string_lossy().into_owned(), 0, 0),
+                ChangeOperation::Owner { .. } => ("chown", change.file.to_string_lossy().into_owned(), 0, 0),
+                ChangeOperation::Times { .. } => ("utimens", change.file.to_string_lossy().into_owned(), 0, 0),
+                _ => ("change", change.file.to_string_lossy().into_owned(), 0, 0)
+            },
+            EventType::Link(link) => ("readlink", link.file.to_string_lossy().into_owned(), 0, 0),
+            EventType::Xattr(xattr) => (match xattr.operation {
+                XattrOperation::Get => "getxattr",
+                XattrOperation::List => "listxattr",
+                XattrOperation::Set => "setxattr",
+                _ => "xattr"
+            }, xattr.file.to_string_lossy().into_owned(), 0, xattr.size.unwrap_or_default() as i64),
+            EventType::Marker(label) => ("marker", label.clone(), 0, 0),
+            EventType::Derived { message, .. } => ("derived", message.clone(), 0, 0),
+            _ => ("unknown", String::new(), 0, 0) // added by a newer version of the schema
+        };
+        scope.push_constant("kind", kind);
+        scope.push_constant("path", path);
+        scope.push_constant("process", event.process.name.to_string());
+        scope.push_constant("pid", event.process.pid as i64);
+        scope.push_constant("offset", offset);
+        scope.push_constant("size", size);
+        scope
+    }
+}
+
+impl Plugin for ScriptPlugin {
+    fn name(&self) -> &str {
+        "script"
+    }
+
+    fn on_event(&mut self, event : &Event, derived : &mut Vec<String>) -> Verdict {
+        let mut scope = Self::scope(event);
+        let verdict = match self.engine.eval_ast_with_scope::<rhai::Dynamic>(&mut scope, &self.ast) {
+            Ok(result) if result.as_bool() == Ok(false) => Verdict::Veto,
+            Ok(_) => Verdict::Keep,
+            Err(err) => {
+                derived.push(format!("Script failed: {err}"));
+                Verdict::Keep
+            }
+        };
+        let actions = std::mem::take(&mut *self.actions.lock().unwrap());
+        for action in actions {
+            match action {
+                ScriptAction::Tag(text) => derived.push(text),
+                ScriptAction::Log(text) => log!(info, "{text}"),
+                ScriptAction::Notify(text) => self.send(WorkerAction::Notify(text)),
+                ScriptAction::Redirect(file) => self.send(WorkerAction::Redirect { file, line: event.to_string() })
+            }
+        }
+        verdict
+    }
+}
+
+/// Runs the actions until the plugin is dropped. Redirect files stay open, they are flushed whenever the queue is empty.
+fn run_worker(receiver : Receiver<WorkerAction>) {
+    let mut files : HashMap<String, Option<BufWriter<File>>> = HashMap::new(); // None if the file can't be opened
+    let mut last_notification : Option<Instant> = None;
+    let mut suppressed = 0u64;
+    while let Ok(mut action) = receiver.recv() {
+        loop {
+            match action {
+                WorkerAction::Notify(text) => match last_notification {
+                    Some(last) if last.elapsed() < NOTIFY_INTERVAL => suppressed += 1,
+                    _ => {
+                        let text = match suppressed {
+                            0 => text,
+                            _ => format!("{text} (+{suppressed})")
+                        };
+                        suppressed = 0;
+                        last_notification = Some(Instant::now());
+                        notify(text);
+                    }
+                }
+                WorkerAction::Redirect { file, line } => {
+                    let output = files.entry(file).or_insert_with_key(|file| {
+                        match std::fs::OpenOptions::new().create(true).append(true).open(file) {
+                            Ok(output) => Some(BufWriter::new(output)),
+                            Err(err) => {
+                                log!(warn, "Failed to open {file}, not redirecting to it: {err}");
+                                None
+                            }
+                        }
+                    });
+                    if let Some(output) = output {
+                        if let Err(err) = writeln!(output, "{line}") {
+                            log!(warn, "Failed to write a redirected event: {err}");
+                        }
+                    }
+                }
+            }
+            match receiver.try_recv() {
+                Ok(next) => action = next,
+                Err(_) => break
+            }
+        }
+        for output in files.values_mut().flatten() {
+            if let Err(err) = output.flush() {
+                log!(warn, "Failed to write redirected events: {err}");
+            }
+        }
+    }
+}
+
+fn notify(text : String) {
+    #[cfg(feature = "gui")]
+    crate::watch::show_notification(crate::i18n::tr!("script-notification-summary"), text, false);
+    #[cfg(not(feature = "gui"))]
+    log!(info, "{text}");
+}
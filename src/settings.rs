@@ -0,0 +1,114 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use crate::fs::PathFilter;
+use crate::highlight::HighlightRule;
+use crate::i18n::Language;
+use crate::ui::{LogColumn, TimestampMode};
+use crate::watch::WatchRule;
+use crate::MountOptions;
+
+/// Everything that is remembered across restarts, stored as json in the user's config directory.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub window_size : (f32, f32),
+    pub window_position : Option<(f32, f32)>, // centered if unknown
+    pub source : String,
+    pub mountpoint : String,
+    pub export_path : String,
+    pub mount_options : MountOptions,
+    pub tray_enabled : bool,
+    pub language : Option<Language>, // system language if not chosen yet
+    pub timestamp_mode : TimestampMode,
+    pub highlight_rules : Vec<HighlightRule>,
+    pub process_filter : Option<String>,
+    pub group_reads : bool,
+    pub max_log_entries : usize,
+    pub filter_presets : Vec<FilterPreset>,
+    pub watch_rules : Vec<WatchRule>,
+    pub watch_sound : bool,
+    pub script : String, // run for every event if not empty, see `ScriptPlugin`
+    pub archive_index : String, // see `ArchiveIndex`, not used if empty
+    pub columns : Vec<(LogColumn, bool)>
+}
+
+/// Named combination of display filters.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FilterPreset {
+    pub name : String,
+    pub process : Option<String>,
+    pub paths : PathFilter
+}
+
+impl std::fmt::Display for FilterPreset {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            window_size: (800.0, 600.0),
+            window_position: None,
+            source: String::new(),
+            mountpoint: String::new(),
+            export_path: String::from("access_log.txt"),
+            mount_options: MountOptions::default(),
+            tray_enabled: false,
+            language: None,
+            timestamp_mode: TimestampMode::Utc,
+            highlight_rules: Vec::new(),
+            process_filter: None,
+            group_reads: true,
+            max_log_entries: 50_000,
+            filter_presets: Vec::new(),
+            watch_rules: Vec::new(),
+            watch_sound: false,
+            script: String::new(),
+            archive_index: String::new(),
+            columns: LogColumn::defaults()
+        }
+    }
+}
+
+impl Settings {
+    fn path() -> Option<PathBuf> {
+        Some(crate::config_dir()?.join("settings.json"))
+    }
+
+    /// Loads the saved settings, falls back to the defaults if there are none or they can't be read.
+    pub fn load() -> Self {
+        let Some(path) = Self::path() else {
+            return Self::default();
+        };
+        match std::fs::read_to_string(&path) {
+            Ok(content) => match serde_json::from_str::<Self>(&content) {
+                Ok(mut settings) => {
+                    settings.migrate();
+                    settings
+                }
+                Err(err) => {
+                    eprintln!("Ignoring invalid settings in {}: {err}", path.display());
+                    Self::default()
+                }
+            }
+            Err(_) => Self::default()
+        }
+    }
+
+    /// Brings settings saved by an older version up to date.
+    fn migrate(&mut self) {
+        // layouts saved before a column was added don't have it, it couldn't be turned on otherwise
+        self.columns = LogColumn::complete(std::mem::take(&mut self.columns));
+    }
+
+    pub fn save(&self) -> std::io::Result<()> {
+        let path = Self::path().ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "No config directory"))?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)
+    }
+}
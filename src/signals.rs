@@ -0,0 +1,50 @@
+//! Shutting down cleanly on Ctrl+C (SIGINT), SIGTERM and SIGHUP, which a terminal or session sends on logout.
+//! Without handlers these kill the process right away and the mount is left dangling.
+use tokio::signal::unix::{signal, Signal, SignalKind};
+
+use crate::platform;
+use crate::privileges;
+use crate::registry;
+
+/// Calls `handler` on a background thread when the process is asked to stop. The handler should make the
+/// application unmount and exit, a second signal unmounts the mounts of this process lazily and exits
+/// right away, in case the first one got stuck.
+pub fn on_shutdown(handler : impl FnOnce(i32) + Send + 'static) {
+    let spawned = std::thread::Builder::new().name("signals".to_string()).spawn(move || {
+        let runtime = match tokio::runtime::Builder::new_current_thread().enable_io().build() {
+            Ok(runtime) => runtime,
+            Err(err) => {
+                eprintln!("Failed to listen for signals: {err}");
+                return;
+            }
+        };
+        runtime.block_on(async move {
+            let (Ok(mut interrupt), Ok(mut terminate), Ok(mut hangup)) =
+                (signal(SignalKind::interrupt()), signal(SignalKind::terminate()), signal(SignalKind::hangup())) else {
+                eprintln!("Failed to install the signal handlers");
+                return;
+            };
+            handler(next(&mut interrupt, &mut terminate, &mut hangup).await);
+            let signal = next(&mut interrupt, &mut terminate, &mut hangup).await;
+            let mounter = privileges::mounter();
+            for mount in registry::active().into_iter().filter(|mount| mount.pid == std::process::id()) {
+                match &mounter {
+                    Some(mounter) => drop(mounter.unmount(&mount.mountpoint, true)),
+                    None => platform::force_unmount(&mount.mountpoint)
+                }
+            }
+            std::process::exit(128 + signal);
+        });
+    });
+    if let Err(err) = spawned {
+        eprintln!("Failed to listen for signals: {err}");
+    }
+}
+
+async fn next(interrupt : &mut Signal, terminate : &mut Signal, hangup : &mut Signal) -> i32 {
+    tokio::select! {
+        _ = interrupt.recv() => libc::SIGINT,
+        _ = terminate.recv() => libc::SIGTERM,
+        _ = hangup.recv() => libc::SIGHUP
+    }
+}
@@ -0,0 +1,108 @@
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::PathBuf;
+
+use crate::fs::Event;
+
+/// Events per page, only the start of every page is indexed so the index stays small even for day-long sessions.
+pub const PAGE_SIZE : usize = 1000;
+
+/// Events that no longer fit into memory, kept on disk as JSON lines so they can still be paged through, exported
+/// in the current log format and saved with the session.
+///
+/// The file is created exclusively and only readable by the user, and it is unlinked right away, so no other process
+/// can open or replace it and it is gone when the spill file is dropped, even after a crash.
+/// It is memory-mapped for reading, so paging doesn't load more than the requested page.
+#[derive(Debug)]
+pub struct SpillFile {
+    file : File,
+    writer : BufWriter<File>,
+    size : u64,
+    len : usize,
+    pages : Vec<u64>, // offset of the first line of every page
+    map : Option<memmap2::Mmap> // mapped on the first read after the file grew
+}
+
+impl SpillFile {
+    /// Creates the file in `$XDG_RUNTIME_DIR`, or in the temporary directory if that isn't set.
+    pub fn create() -> std::io::Result<Self> {
+        let dir = std::env::var_os("XDG_RUNTIME_DIR").map(PathBuf::from).unwrap_or_else(std::env::temp_dir);
+        let mut attempt = 0u32;
+        let (path, file) = loop {
+            let path = dir.join(format!("{}-{}-{attempt}.jsonl", env!("CARGO_PKG_NAME"), std::process::id()));
+            // create_new refuses existing files and symlinks, so a planted file is skipped rather than written to
+            match OpenOptions::new().read(true).write(true).create_new(true).mode(0o600).open(&path) {
+                Ok(file) => break (path, file),
+                Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists && attempt < 100 => attempt += 1,
+                Err(err) => return Err(err)
+            }
+        };
+        std::fs::remove_file(path)?;
+        Ok(Self {
+            writer: BufWriter::new(file.try_clone()?),
+            file,
+            size: 0,
+            len: 0,
+            pages: Vec::new(),
+            map: None
+        })
+    }
+
+    /// Appends the events, oldest first.
+    pub fn append<'a>(&mut self, events : impl IntoIterator<Item = &'a Event>) -> std::io::Result<()> {
+        for event in events {
+            if self.len % PAGE_SIZE == 0 {
+                self.pages.push(self.size);
+            }
+            let line = serde_json::to_vec(event)?;
+            self.writer.write_all(&line)?;
+            self.writer.write_all(b"\n")?;
+            self.size += line.len() as u64 + 1;
+            self.len += 1;
+        }
+        self.writer.flush()?;
+        self.map = None;
+        Ok(())
+    }
+
+    /// Number of events in the file.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn page_count(&self) -> usize {
+        self.pages.len()
+    }
+
+    /// The events of a page, oldest first.
+    pub fn page(&mut self, page : usize) -> std::io::Result<Vec<Event>> {
+        let Some(&start) = self.pages.get(page) else {
+            return Ok(Vec::new());
+        };
+        let end = self.pages.get(page + 1).copied().unwrap_or(self.size);
+        if self.map.is_none() {
+            // SAFETY: the file is unlinked and only this process has it open, it is only appended to, so the mapped
+            // part never changes
+            self.map = Some(unsafe { memmap2::Mmap::map(&self.file)? });
+        }
+        let map = self.map.as_ref().unwrap();
+        map[start as usize..end as usize].split(|byte| *byte == b'\n')
+            .filter(|line| !line.is_empty())
+            .map(|line| serde_json::from_slice(line).map_err(std::io::Error::from))
+            .collect()
+    }
+
+    /// All events, oldest first, for saving them with the session.
+    pub fn events(&mut self) -> std::io::Result<Vec<Event>> {
+        let mut events = Vec::with_capacity(self.len);
+        for page in 0..self.page_count() {
+            events.extend(self.page(page)?);
+        }
+        Ok(events)
+    }
+}
@@ -0,0 +1,116 @@
+use ksni::blocking::TrayMethods;
+
+use crate::i18n::tr;
+
+/// Menu entries of the tray icon, forwarded to the UI.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrayAction {
+    Show,
+    Export,
+    Unmount
+}
+
+pub struct MonitorTray {
+    pub mount : String,
+    pub events : u64,
+    pub rate : u64,
+    actions : tokio::sync::mpsc::UnboundedSender<TrayAction>
+}
+
+impl MonitorTray {
+    pub fn new(mount : String, actions : tokio::sync::mpsc::UnboundedSender<TrayAction>) -> Self {
+        Self {
+            mount,
+            events: 0,
+            rate: 0,
+            actions
+        }
+    }
+
+    fn send(&self, action : TrayAction) {
+        // the UI is gone if this fails, nothing left to notify
+        let _ = self.actions.send(action);
+    }
+}
+
+impl ksni::Tray for MonitorTray {
+    fn id(&self) -> String {
+        env!("CARGO_PKG_NAME").into()
+    }
+
+    fn title(&self) -> String {
+        tr!("window-title")
+    }
+
+    fn icon_name(&self) -> String {
+        // the open folder signals that files are being accessed right now
+        if self.rate > 0 { "folder-open" } else { "folder" }.into()
+    }
+
+    fn tool_tip(&self) -> ksni::ToolTip {
+        ksni::ToolTip {
+            title: tr!("window-title"),
+            description: format!("{}\n{}", self.mount, tr!("tray-activity", count = self.events, rate = self.rate)),
+            ..Default::default()
+        }
+    }
+
+    fn activate(&mut self, _x: i32, _y: i32) {
+        self.send(TrayAction::Show);
+    }
+
+    fn menu(&self) -> Vec<ksni::MenuItem<Self>> {
+        use ksni::menu::*;
+        vec![
+            StandardItem {
+                label: tr!("tray-show"),
+                activate: Box::new(|this: &mut Self| this.send(TrayAction::Show)),
+                ..Default::default()
+            }.into(),
+            StandardItem {
+                label: tr!("export-log"),
+                activate: Box::new(|this: &mut Self| this.send(TrayAction::Export)),
+                ..Default::default()
+            }.into(),
+            MenuItem::Separator,
+            StandardItem {
+                label: tr!("unmount"),
+                icon_name: "media-eject".into(),
+                activate: Box::new(|this: &mut Self| this.send(TrayAction::Unmount)),
+                ..Default::default()
+            }.into(),
+        ]
+    }
+}
+
+/// Running tray icon, it is removed again when this is dropped.
+pub struct TrayIcon {
+    handle : ksni::blocking::Handle<MonitorTray>
+}
+
+impl TrayIcon {
+    pub fn spawn(tray : MonitorTray) -> Result<Self, ksni::Error> {
+        Ok(Self {
+            handle: tray.spawn()?
+        })
+    }
+
+    pub fn update(&self, events : u64, rate : u64) {
+        self.handle.update(|tray| {
+            tray.events = events;
+            tray.rate = rate;
+        });
+    }
+}
+
+impl std::fmt::Debug for TrayIcon {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "TrayIcon")
+    }
+}
+
+impl Drop for TrayIcon {
+    fn drop(&mut self) {
+        self.handle.shutdown().wait();
+    }
+}
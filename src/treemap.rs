@@ -0,0 +1,140 @@
+use iced::widget::canvas::{self, Frame, Geometry, Path, Stroke};
+use iced::{mouse, Color, Point, Rectangle, Renderer, Size, Theme};
+
+use crate::analysis::{self, SizeEntry};
+use crate::highlight::HighlightColor;
+use crate::ui::Message;
+
+/// Rectangles sized by the bytes read from the children of one directory, clicking a directory opens it.
+#[derive(Debug)]
+pub struct Treemap {
+    pub entries : Vec<SizeEntry>
+}
+
+impl Treemap {
+    fn layout(&self, bounds : Size) -> Vec<Rectangle> {
+        let sizes : Vec<f64> = self.entries.iter().map(|entry| entry.bytes as f64).collect();
+        squarify(&sizes, Rectangle::new(Point::ORIGIN, bounds))
+    }
+}
+
+impl canvas::Program<Message> for Treemap {
+    type State = ();
+
+    fn update(&self, _state : &mut (), event : canvas::Event, bounds : Rectangle, cursor : mouse::Cursor) -> (canvas::event::Status, Option<Message>) {
+        let canvas::Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) = event else {
+            return (canvas::event::Status::Ignored, None);
+        };
+        let Some(position) = cursor.position_in(bounds) else {
+            return (canvas::event::Status::Ignored, None);
+        };
+        let clicked = self.layout(bounds.size()).into_iter().zip(&self.entries).find(|(rect, _)| rect.contains(position));
+        match clicked {
+            Some((_, entry)) if entry.is_dir => (canvas::event::Status::Captured, Some(Message::TreemapOpen(entry.path.clone()))),
+            _ => (canvas::event::Status::Ignored, None)
+        }
+    }
+
+    fn draw(&self, _state : &(), renderer : &Renderer, _theme : &Theme, bounds : Rectangle, cursor : mouse::Cursor) -> Vec<Geometry> {
+        let mut frame = Frame::new(renderer, bounds.size());
+        let mut hovered = None;
+        for (i, (rect, entry)) in self.layout(bounds.size()).into_iter().zip(&self.entries).enumerate() {
+            let color = HighlightColor::ALL[i % HighlightColor::ALL.len()].color();
+            frame.fill_rectangle(rect.position(), rect.size(), Color { a: if entry.is_dir { 0.8 } else { 0.5 }, ..color });
+            frame.stroke(&Path::rectangle(rect.position(), rect.size()), Stroke::default().with_color(Color::BLACK).with_width(1.0));
+            let label = if entry.is_dir { format!("{}/", entry.name) } else { entry.name.clone() };
+            // labels only fit into rectangles of a reasonable size
+            if rect.width > 60.0 && rect.height > 30.0 {
+                frame.fill_text(canvas::Text {
+                    content: format!("{label}\n{}", analysis::format_size(entry.bytes)),
+                    position: Point::new(rect.x + 4.0, rect.y + 4.0),
+                    color: Color::WHITE,
+                    size: 12.0.into(),
+                    ..canvas::Text::default()
+                });
+            }
+            if cursor.position_in(bounds).is_some_and(|position| rect.contains(position)) {
+                hovered = Some((label, entry.bytes));
+            }
+        }
+        if let (Some((label, bytes)), Some(position)) = (hovered, cursor.position_in(bounds)) {
+            let content = format!("{label} ({})", analysis::format_size(bytes));
+            let width = content.chars().count() as f32 * 7.5 + 8.0;
+            let position = Point::new(position.x.min(bounds.width - width).max(0.0), (position.y + 16.0).min(bounds.height - 20.0));
+            frame.fill_rectangle(position, Size::new(width, 20.0), Color::from_rgba(0.0, 0.0, 0.0, 0.8));
+            frame.fill_text(canvas::Text {
+                content,
+                position: Point::new(position.x + 4.0, position.y + 3.0),
+                color: Color::WHITE,
+                size: 13.0.into(),
+                ..canvas::Text::default()
+            });
+        }
+        vec![frame.into_geometry()]
+    }
+
+    fn mouse_interaction(&self, _state : &(), bounds : Rectangle, cursor : mouse::Cursor) -> mouse::Interaction {
+        let over_dir = cursor.position_in(bounds).is_some_and(|position| {
+            self.layout(bounds.size()).into_iter().zip(&self.entries).any(|(rect, entry)| entry.is_dir && rect.contains(position))
+        });
+        if over_dir { mouse::Interaction::Pointer } else { mouse::Interaction::default() }
+    }
+}
+
+/// Squarified treemap layout: splits `bounds` into rectangles with areas proportional to `sizes`, keeping them close to squares.
+/// The sizes are expected to be sorted in descending order.
+fn squarify(sizes : &[f64], bounds : Rectangle) -> Vec<Rectangle> {
+    let total : f64 = sizes.iter().sum();
+    if total <= 0.0 {
+        return vec![Rectangle::new(Point::ORIGIN, Size::ZERO); sizes.len()];
+    }
+    let scale = (bounds.width * bounds.height) as f64 / total;
+    let areas : Vec<f64> = sizes.iter().map(|size| size * scale).collect();
+    let mut rects = Vec::with_capacity(areas.len());
+    let mut remaining = bounds;
+    let mut start = 0;
+    while start < areas.len() {
+        let side = remaining.width.min(remaining.height) as f64;
+        // the row grows as long as that makes its worst aspect ratio better
+        let mut end = start + 1;
+        while end < areas.len() && worst_ratio(&areas[start..=end], side) <= worst_ratio(&areas[start..end], side) {
+            end += 1;
+        }
+        let row = &areas[start..end];
+        let row_area : f64 = row.iter().sum();
+        if remaining.width >= remaining.height {
+            let width = if remaining.height > 0.0 { (row_area / remaining.height as f64) as f32 } else { 0.0 };
+            let mut y = remaining.y;
+            for area in row {
+                let height = if width > 0.0 { (area / width as f64) as f32 } else { 0.0 };
+                rects.push(Rectangle::new(Point::new(remaining.x, y), Size::new(width, height)));
+                y += height;
+            }
+            remaining.x += width;
+            remaining.width = (remaining.width - width).max(0.0);
+        } else {
+            let height = if remaining.width > 0.0 { (row_area / remaining.width as f64) as f32 } else { 0.0 };
+            let mut x = remaining.x;
+            for area in row {
+                let width = if height > 0.0 { (area / height as f64) as f32 } else { 0.0 };
+                rects.push(Rectangle::new(Point::new(x, remaining.y), Size::new(width, height)));
+                x += width;
+            }
+            remaining.y += height;
+            remaining.height = (remaining.height - height).max(0.0);
+        }
+        start = end;
+    }
+    rects
+}
+
+fn worst_ratio(row : &[f64], side : f64) -> f64 {
+    let sum : f64 = row.iter().sum();
+    let max = row.iter().cloned().fold(0.0, f64::max);
+    let min = row.iter().cloned().fold(f64::INFINITY, f64::min);
+    if sum <= 0.0 || min <= 0.0 || side <= 0.0 {
+        return f64::INFINITY;
+    }
+    let side = side * side;
+    (side * max / (sum * sum)).max(sum * sum / (side * min))
+}
@@ -1,32 +1,71 @@
-use fuser::BackgroundSession;
 use iced::alignment::Horizontal;
 use iced::futures::SinkExt;
 use iced::widget::text_input::Catalog;
 use iced::{keyboard, Background, Border, Color, Theme};
 use iced::widget::{
-    self, button, center, checkbox, column, container, keyed_column, row, scrollable, text, text_editor, text_input, Column, Container, Text, TextInput
+    self, button, canvas, center, checkbox, column, container, keyed_column, pick_list, progress_bar, row, scrollable, slider, text, text_editor, text_input, Column, Container, Row, Text, TextInput
 };
 use iced::{Center, Element, Fill, Font, Subscription, Task as Command};
 use tokio::sync::Mutex;
+use std::borrow::Cow;
+use std::ffi::OsStr;
 use std::sync::Arc;
 
-use crate::fs::Event;
+use crate::analysis::{self, PathNode};
+use crate::archive::{ArchiveIndex, ArchivePlugin};
+use crate::flamegraph::FlameGraph;
+use crate::fs::{AccessKind, AccessPattern, CaseSensitivity, ChangeEvent, ChangeOperation, Event, EventType, HandleEvent, MetadataOperation, PathFilter, Process, ReadEvent, WriteEvent, XattrEvent, XattrOperation};
+use crate::highlight::{self, HighlightColor, HighlightRule, LogHighlightSettings, LogHighlighter};
+use crate::i18n::{self, tr, Language};
+use crate::external_log::{self, Entry, ExternalLog};
+use crate::extract::{self, ExtractMode};
+use crate::manifest::{Manifest, ManifestReport};
+use crate::script::ScriptPlugin;
+use crate::spill::SpillFile;
+use crate::copy;
+use crate::session::{self, FileComparison, Session};
+use crate::diagnostics::{self, MountProblem, Prerequisite};
+use crate::fs::ScanProgress;
+use crate::privileges;
+use crate::settings::{FilterPreset, Settings};
+use crate::signals;
+use crate::tray::{MonitorTray, TrayAction, TrayIcon};
+use ffam_fs::log;
+use crate::treemap::Treemap;
+use crate::watch::{self, WatchRule};
+use crate::backend::BackendKind;
+use crate::registry::{self, ActiveMount};
+use crate::{html, platform, rules, MountBuilder, MountOptions, MountSession};
+
+static SEARCH_INPUT: std::sync::LazyLock<text_input::Id> = std::sync::LazyLock::new(text_input::Id::unique);
+
+// Hex dumps of very large reads would make the viewer unusable, so only the beginning is shown.
+const HEX_VIEW_LIMIT: usize = 64 * 1024;
+
+// Caps how long a single update takes when the UI fell far behind.
+const MAX_EVENT_BATCH: usize = 10_000;
+/// Rows of the interleaved external log view, the time window narrows down longer logs.
+const MAX_INTERLEAVED_ROWS: usize = 5_000;
+/// Files listed per phase of the startup report.
+const PHASE_TOP_FILES: usize = 10;
+
+// Above this many events per second the live view only shows a line per file and second, it goes back below half of it.
+const DOWNSAMPLE_RATE: u64 = 2_000;
 
 #[derive(Debug)]
 pub struct AccessTrackingFsGui {
     state: State,
     event_sender : tokio::sync::mpsc::Sender<Event>,
     event_receiver : Arc<Mutex<tokio::sync::mpsc::Receiver<Event>>>,
+    log_format : Arc<std::sync::RwLock<LogFormat>>, // shared with the event worker, which formats the lines of new events
+    tray_sender : tokio::sync::mpsc::UnboundedSender<TrayAction>,
+    tray_receiver : Arc<Mutex<tokio::sync::mpsc::UnboundedReceiver<TrayAction>>>,
+    shutdown_receiver : Arc<Mutex<tokio::sync::mpsc::UnboundedReceiver<i32>>>,
 }
 
 impl Default for AccessTrackingFsGui {
     fn default() -> Self {
-        let (sender, receiver) = tokio::sync::mpsc::channel(10000);
-        Self {
-            state: State::default(),
-            event_sender: sender,
-            event_receiver: Arc::new(Mutex::new(receiver))
-        }
+        Self::new(Settings::default())
     }
 }
 
@@ -34,8 +73,8 @@ impl Default for AccessTrackingFsGui {
 pub enum Status {
     Unmounting,
     Unmounted,
-    Mounting,
-    Mounted(BackgroundSession),
+    Mounting(Arc<ScanProgress>, std::sync::mpsc::Receiver<crate::Result<MountSession>>), // the mount runs on its own thread
+    Mounted(MountSession),
 }
 
 #[derive(Debug)]
@@ -45,12 +84,582 @@ struct State {
     pub source_valid: bool,
     pub mountpoint_valid: bool,
     pub status : Status,
-    pub error_text : Option<String>,
+    pub toasts : Vec<Toast>,
     pub event_log : Vec<Event>,
-    pub event_text : String,
-    pub event_log_content: iced::widget::text_editor::Content
+    pub event_lines : Vec<String>, // the log line of every event in event_log, in the current format
+    pub max_log_entries : usize, // older events are moved to the spill file
+    pub max_log_entries_text : String,
+    pub archive_index : String, // path of an `ArchiveIndex` to annotate reads of containers with, if not empty
+    pub events_dropped : u64, // events moved out of event_log, in order, into the spill file
+    pub events_lost : u64, // events the mount couldn't deliver, see `StatsSnapshot::dropped`
+    pub spill : Option<SpillFile>, // created once the first events are dropped
+    pub spilled_page : Option<(usize, String)>, // the page of dropped events being browsed
+    pub log_lines : Vec<LogLine>, // what every line of the log view shows
+    pub group_reads : bool,
+    pub expanded_groups : std::collections::HashSet<usize>, // groups are identified by their first event
+    pub processes : std::collections::BTreeSet<String>, // names of all processes seen so far
+    pub process_filter : Option<String>, // only events of this process are shown
+    pub path_filter : PathFilter, // only hides events in the log, unlike the filter of the mount options
+    pub path_include_text : String,
+    pub path_exclude_text : String,
+    pub filter_presets : Vec<FilterPreset>,
+    pub preset_name : String,
+    pub files : std::collections::BTreeMap<String, FileStats>, // every file read so far
+    pub show_file_list : bool,
+    pub file_filter : Option<String>, // file selected in the file list, only its events are shown
+    file_stats : Option<SelectedFileStats>, // of the selected file, see `view_file_stats`
+    pub watch_rules : Vec<WatchRule>,
+    pub watch_sound : bool,
+    pub watch_last_notified : std::collections::HashMap<usize, std::time::Instant>, // per rule, to not flood the desktop
+    pub watch_last_command : std::collections::HashMap<usize, std::time::Instant>, // per rule
+    pub rule_last_notified : Option<std::time::Instant>, // for notifications of the mount's rules
+    pub watch_hits : Vec<WatchHits>, // one per watch rule
+    pub show_watch_rules : bool,
+    pub columns : Vec<(LogColumn, bool)>, // every column in display order, with whether it's shown
+    pub show_columns : bool,
+    pub show_statistics : bool,
+    pub show_treemap : bool,
+    pub treemap_dir : String, // directory shown by the treemap, relative to the mount root
+    pub show_flame_graph : bool,
+    pub show_access_order : bool,
+    pub show_redundant_reads : bool,
+    pub show_process_usage : bool,
+    pub show_phases : bool,
+    pub phase_bounds : String, // ends of the startup phases in seconds, separated by commas
+    pub phases_from_first_read : bool,
+    pub unused : Option<analysis::UnusedReport>, // computed when opened, the tree has to be walked for it
+    pub flame_graph_root : String, // path the flame graph is zoomed into
+    pub show_search : bool,
+    pub search_query : String,
+    pub search_matches : Vec<usize>, // events matching the query, regardless of the display filters
+    pub search_current : Option<usize>, // index into search_matches
+    pub search_match_hidden : bool, // the current match is hidden by the display filters
+    pub marker_label : String,
+    pub session_name : String,
+    pub copy_target : String, // where the files read so far get copied to
+    pub manifest_path : String,
+    pub manifest_report : Option<ManifestReport>,
+    pub external_log_path : String,
+    pub external_log : Option<ExternalLog>, // shown interleaved with the events while loaded
+    pub external_offset : String, // seconds added to the times of the external log
+    pub external_anchor_marker : Option<String>,
+    pub external_anchor_text : String,
+    pub saved_sessions : Vec<String>,
+    pub active_mounts : Vec<ActiveMount>, // of other running monitors
+    pub stale_mounts : Vec<std::path::PathBuf>, // left behind by crashed monitors, found at startup
+    pub compare_sessions : (Option<String>, Option<String>),
+    pub comparison : Option<(String, String, Vec<FileComparison>)>, // names of both sessions and the result
+    pub timeline_from : f64, // seconds since mount, events before are hidden
+    pub timeline_to : Option<f64>, // seconds since mount, None follows the newest event
+    pub event_log_content: iced::widget::text_editor::Content,
+    pub mount_options : MountOptions,
+    pub prerequisites : Vec<Prerequisite>, // missing for mounting with the options, checked at startup
+    pub prerequisites_dismissed : bool,
+    pub show_advanced_options : bool,
+    pub script_content : iced::widget::text_editor::Content,
+    pub ttl_text : String, // kept separately so it can be invalid while typing
+    pub include_text : String,
+    pub exclude_text : String,
+    pub hex_view : Option<HexView>,
+    pub highlight_rules : Vec<HighlightRule>,
+    pub show_highlight_rules : bool,
+    pub bookmarks : Vec<Event>, // copies, so they survive clearing the log
+    pub details : Vec<(String, String)>, // fields of the selected event
+    pub timestamp_mode : TimestampMode,
+    pub mounted_at : chrono::DateTime<chrono::Utc>,
+    pub events_received : u64, // since mounting, unaffected by clearing the log
+    pub events_at_last_tick : u64,
+    pub event_rate : u64, // events per second
+    pub downsampled : bool, // the live view shows summaries, the rate is too high for a line per event
+    pub pending_summary : Vec<usize>, // visible events of the newest second while downsampled, summarized once it is over
+    pub export_path : String,
+    pub log_exported : bool,
+    pub pending_action : Option<PendingAction>,
+    pub dropped_path : Option<String>, // folder dropped onto the window, waiting for the user to pick a field
+    pub tray_enabled : bool,
+    pub tray : Option<TrayIcon>,
+    pub language : Option<Language>, // only set once the user picked one
+    pub window_size : iced::Size,
+    pub window_position : Option<iced::Point>
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum TimestampMode {
+    Utc,
+    Local,
+    SinceMount
+}
+
+impl TimestampMode {
+    pub const ALL: [TimestampMode; 3] = [Self::Utc, Self::Local, Self::SinceMount];
+}
+
+impl std::fmt::Display for TimestampMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Utc => write!(f, "{}", tr!("timestamp-utc")),
+            Self::Local => write!(f, "{}", tr!("timestamp-local")),
+            Self::SinceMount => write!(f, "{}", tr!("timestamp-since-mount"))
+        }
+    }
+}
+
+/// A field of an event that can be shown as a column of the log.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum LogColumn {
+    Time,
+    Type,
+    Path,
+    Offset,
+    Size,
+    Process,
+    Pid,
+    Latency,
+    Handle,
+    Uid
+}
+
+impl LogColumn {
+    /// Default layout, columns that are off by default come last.
+    pub fn defaults() -> Vec<(LogColumn, bool)> {
+        vec![
+            (Self::Time, true),
+            (Self::Process, true),
+            (Self::Type, true),
+            (Self::Offset, true),
+            (Self::Size, true),
+            (Self::Path, true),
+            (Self::Pid, false),
+            (Self::Latency, false),
+            (Self::Handle, false),
+            (Self::Uid, false)
+        ]
+    }
+
+    /// Adds the columns a layout saved by an older version doesn't know yet, hidden at the end, and drops duplicates.
+    pub fn complete(columns : Vec<(LogColumn, bool)>) -> Vec<(LogColumn, bool)> {
+        let mut complete : Vec<(LogColumn, bool)> = Vec::with_capacity(columns.len());
+        for (column, enabled) in columns {
+            if !complete.iter().any(|(existing, _)| *existing == column) {
+                complete.push((column, enabled));
+            }
+        }
+        for (column, _) in Self::defaults() {
+            if !complete.iter().any(|(existing, _)| *existing == column) {
+                complete.push((column, false));
+            }
+        }
+        complete
+    }
+
+    /// Text width the values are padded to, so the columns line up. The path is usually last and isn't padded.
+    fn width(&self) -> usize {
+        match self {
+            Self::Time => 0,
+            Self::Type => 8,
+            Self::Path => 0,
+            Self::Offset => 12,
+            Self::Size => 10,
+            Self::Process => 16,
+            Self::Pid => 7,
+            Self::Latency => 10,
+            Self::Handle => 8,
+            Self::Uid => 6
+        }
+    }
+}
+
+impl std::fmt::Display for LogColumn {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::Time => tr!("detail-time"),
+            Self::Type => tr!("detail-operation"),
+            Self::Path => tr!("detail-file"),
+            Self::Offset => tr!("detail-offset"),
+            Self::Size => tr!("detail-size"),
+            Self::Process => tr!("detail-process"),
+            Self::Pid => tr!("detail-pid"),
+            Self::Latency => tr!("detail-latency"),
+            Self::Handle => tr!("detail-handle"),
+            Self::Uid => tr!("detail-uid")
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Everything the log line of an event depends on. The event worker formats new events with a copy of it,
+/// so the UI thread gets their lines ready-made.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogFormat {
+    pub columns : Vec<(LogColumn, bool)>,
+    pub timestamp_mode : TimestampMode,
+    pub mounted_at : chrono::DateTime<chrono::Utc>,
+    pub language : Language,
+    pub stable_names : bool // the type as the names of the schema instead of translated labels, for the export
+}
+
+impl LogFormat {
+    pub fn format_time(&self, time : &chrono::DateTime<chrono::Utc>) -> String {
+        let mut text = String::new();
+        self.write_time(&mut text, time);
+        text
+    }
+
+    fn write_time(&self, out : &mut String, time : &chrono::DateTime<chrono::Utc>) {
+        use std::fmt::Write;
+        let _ = match self.timestamp_mode {
+            TimestampMode::Utc => write!(out, "{time}"),
+            TimestampMode::Local => write!(out, "{}", time.with_timezone(&chrono::Local)),
+            TimestampMode::SinceMount => {
+                let since_mount = *time - self.mounted_at;
+                write!(out, "t+{:.2}s", since_mount.num_milliseconds() as f64 / 1000.0)
+            }
+        };
+    }
+
+    /// Log line of an event made of the enabled columns, with the timestamp in the selected format.
+    pub fn format_event(&self, event : &Event) -> String {
+        let mut line = String::new();
+        self.write_event(&mut line, event);
+        line
+    }
+
+    /// Appends the log line of an event to `line`.
+    fn write_event(&self, line : &mut String, event : &Event) {
+        let line_start = line.len();
+        for (column, enabled) in &self.columns {
+            if !enabled {
+                continue;
+            }
+            if line.len() > line_start {
+                line.push_str("  ");
+            }
+            let start = line.len();
+            self.write_column(line, event, *column);
+            let padding = column.width().saturating_sub(line[start..].chars().count());
+            match column {
+                LogColumn::Offset | LogColumn::Size | LogColumn::Pid | LogColumn::Latency | LogColumn::Handle | LogColumn::Uid => line.insert_str(start, &" ".repeat(padding)),
+                _ => line.extend(std::iter::repeat_n(' ', padding))
+            }
+        }
+        line.truncate(line_start + line[line_start..].trim_end().len());
+    }
+
+    fn write_column(&self, line : &mut String, event : &Event, column : LogColumn) {
+        let mut number = itoa::Buffer::new();
+        match (&event.event, column) {
+            (_, LogColumn::Time) => {
+                line.push('[');
+                self.write_time(line, &event.time);
+                line.push(']');
+            }
+            (_, LogColumn::Process) => line.push_str(&event.process.name),
+            (_, LogColumn::Pid) => line.push_str(number.format(event.process.pid)),
+            (_, LogColumn::Uid) => {
+                if let Some(uid) = event.process.uid {
+                    line.push_str(number.format(uid));
+                }
+            }
+            (_, LogColumn::Type) if self.stable_names => line.push_str(&type_name(&event.event)),
+            (EventType::Read(ReadEvent { handle, .. }) | EventType::Write(WriteEvent { handle, .. }), LogColumn::Handle)
+                | (EventType::Open(HandleEvent { handle, .. }) | EventType::Close(HandleEvent { handle, .. }), LogColumn::Handle) => line.push_str(number.format(*handle)),
+            (EventType::Read(_), LogColumn::Type) => line.push_str(&tr!("operation-read")),
+            (EventType::Read(read), LogColumn::Path) => line.push_str(&read.file.to_string_lossy()),
+            (EventType::Read(read), LogColumn::Offset) => line.push_str(number.format(read.offset)),
+            (EventType::Read(read), LogColumn::Size) => line.push_str(number.format(read.size)),
+            (EventType::Read(read), LogColumn::Latency) => {
+                line.push_str(number.format(read.latency.as_micros()));
+                line.push_str(" µs");
+            }
+            (EventType::Open(_), LogColumn::Type) => line.push_str(&tr!("operation-open")),
+            (EventType::Close(_), LogColumn::Type) => line.push_str(&tr!("operation-close")),
+            (EventType::Open(event) | EventType::Close(event), LogColumn::Path) => line.push_str(&event.file.to_string_lossy()),
+            (EventType::Open(_) | EventType::Close(_), _) => (),
+            (EventType::Metadata(metadata), LogColumn::Type) => line.push_str(&metadata_operation(metadata.operation)),
+            (EventType::Metadata(metadata), LogColumn::Path) => {
+                line.push_str(&metadata.file.to_string_lossy());
+                if !metadata.found {
+                    line.push(' ');
+                    line.push_str(&tr!("not-found"));
+                }
+            }
+            (EventType::Metadata(_), _) => (),
+            (EventType::Write(_), LogColumn::Type) => line.push_str(&tr!("operation-write")),
+            (EventType::Write(write), LogColumn::Path) => line.push_str(&write.file.to_string_lossy()),
+            (EventType::Write(write), LogColumn::Offset) => line.push_str(number.format(write.offset)),
+            (EventType::Write(write), LogColumn::Size) => line.push_str(number.format(write.size)),
+            (EventType::Write(_), _) => (),
+            (EventType::Change(change), LogColumn::Type) => line.push_str(&change_operation(&change.operation)),
+            (EventType::Change(change), LogColumn::Path) => {
+                line.push_str(&change.file.to_string_lossy());
+                if let ChangeOperation::Rename { to: target } | ChangeOperation::Symlink { target } = &change.operation {
+                    line.push_str(" → ");
+                    line.push_str(&target.to_string_lossy());
+                }
+            }
+            (EventType::Change(ChangeEvent { operation: ChangeOperation::Truncate { size }, .. }), LogColumn::Size) => line.push_str(number.format(*size)),
+            (EventType::Change(_), _) => (),
+            (EventType::Link(_), LogColumn::Type) => line.push_str(&tr!("operation-readlink")),
+            (EventType::Link(link), LogColumn::Path) => {
+                line.push_str(&link.file.to_string_lossy());
+                line.push_str(" → ");
+                line.push_str(&link.target.to_string_lossy());
+            }
+            (EventType::Link(_), _) => (),
+            (EventType::Xattr(xattr), LogColumn::Type) => line.push_str(&xattr_operation(xattr.operation)),
+            (EventType::Xattr(xattr), LogColumn::Path) => {
+                line.push_str(&xattr.file.to_string_lossy());
+                if let Some(name) = &xattr.name {
+                    line.push_str(" [");
+                    line.push_str(name);
+                    line.push(']');
+                }
+                if xattr.size.is_none() {
+                    line.push(' ');
+                    line.push_str(&tr!("not-found"));
+                }
+            }
+            (EventType::Xattr(XattrEvent { size: Some(size), .. }), LogColumn::Size) => line.push_str(number.format(*size)),
+            (EventType::Xattr(_), _) => (),
+            (EventType::Marker(_), LogColumn::Type) => line.push_str(&tr!("operation-marker")),
+            (EventType::Marker(label), LogColumn::Path) => {
+                line.push_str("--- ");
+                line.push_str(label);
+                line.push_str(" ---");
+            }
+            (EventType::Marker(_), _) => (),
+            (EventType::Derived { plugin, .. }, LogColumn::Type) => line.push_str(plugin),
+            (EventType::Derived { message, .. }, LogColumn::Path) => line.push_str(message),
+            (EventType::Derived { .. }, _) => (),
+            (_, _) => () // added by a newer version of the schema
+        }
+    }
+}
+
+/// The type of an event in the names of the schema, which don't depend on the language, e.g. `Change.Truncate`.
+fn type_name(event : &EventType) -> String {
+    let change = |operation : &ChangeOperation| match operation {
+        ChangeOperation::Create => "Create",
+        ChangeOperation::Truncate { .. } => "Truncate",
+        ChangeOperation::Remove => "Remove",
+        ChangeOperation::Rename { .. } => "Rename",
+        ChangeOperation::CreateDirectory => "CreateDirectory",
+        ChangeOperation::RemoveDirectory => "RemoveDirectory",
+        ChangeOperation::Symlink { .. } => "Symlink",
+        ChangeOperation::Permissions { .. } => "Permissions",
+        ChangeOperation::Owner { .. } => "Owner",
+        ChangeOperation::Times { .. } => "Times",
+        _ => "Unknown"
+    };
+    match event {
+        EventType::Read(_) => String::from("Read"),
+        EventType::Open(_) => String::from("Open"),
+        EventType::Close(_) => String::from("Close"),
+        EventType::Metadata(metadata) => format!("Metadata.{:?}", metadata.operation),
+        EventType::Write(_) => String::from("Write"),
+        EventType::Change(event) => format!("Change.{}", change(&event.operation)),
+        EventType::Link(_) => String::from("Link"),
+        EventType::Xattr(xattr) => format!("Xattr.{:?}", xattr.operation),
+        EventType::Marker(_) => String::from("Marker"),
+        EventType::Derived { plugin, .. } => format!("Derived.{plugin}"),
+        _ => String::from("Unknown")
+    }
+}
+
+fn metadata_operation(operation : MetadataOperation) -> String {
+    match operation {
+        MetadataOperation::Lookup => tr!("operation-lookup"),
+        MetadataOperation::GetAttr => tr!("operation-getattr"),
+        MetadataOperation::ReadDir => tr!("operation-readdir"),
+        _ => format!("{operation:?}")
+    }
+}
+
+fn xattr_operation(operation : XattrOperation) -> String {
+    match operation {
+        XattrOperation::Get => tr!("operation-getxattr"),
+        XattrOperation::List => tr!("operation-listxattr"),
+        XattrOperation::Set => tr!("operation-setxattr"),
+        _ => format!("{operation:?}")
+    }
+}
+
+fn change_operation(operation : &ChangeOperation) -> String {
+    match operation {
+        ChangeOperation::Create => tr!("operation-create"),
+        ChangeOperation::Truncate { .. } => tr!("operation-truncate"),
+        ChangeOperation::Remove => tr!("operation-remove"),
+        ChangeOperation::Rename { .. } => tr!("operation-rename"),
+        ChangeOperation::CreateDirectory => tr!("operation-mkdir"),
+        ChangeOperation::RemoveDirectory => tr!("operation-rmdir"),
+        ChangeOperation::Symlink { .. } => tr!("operation-symlink"),
+        ChangeOperation::Permissions { .. } => tr!("operation-chmod"),
+        ChangeOperation::Owner { .. } => tr!("operation-chown"),
+        ChangeOperation::Times { .. } => tr!("operation-utimens"),
+        _ => format!("{operation:?}")
+    }
+}
+
+/// Events from the mount together with their log lines, formatted by the event worker in `format`.
+#[derive(Debug, Clone)]
+pub struct EventBatch {
+    events : Vec<Event>,
+    lines : Vec<String>,
+    format : LogFormat
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Info,
+    Warning,
+    Error
+}
+
+impl Severity {
+    fn color(&self) -> Color {
+        match self {
+            Self::Info => Color::from_rgb8(0x3a, 0x7b, 0xd5),
+            Self::Warning => Color::from_rgb8(0xd0, 0x8a, 0x10),
+            Self::Error => Color::from_rgb8(0xc0, 0x39, 0x2b)
+        }
+    }
+}
+
+/// Totals of the reads of one file, for the file list.
+#[derive(Debug, Clone, Default)]
+struct FileStats {
+    pub reads : u64,
+    pub bytes : u64,
+    pub pattern : AccessPattern
+}
+
+/// Totals of the reads of the file selected in the file list within the time window. Computed when the log is rebuilt
+/// and updated as reads arrive, rather than going over the whole log on every render.
+#[derive(Debug, Clone, Default)]
+struct SelectedFileStats {
+    stats : FileStats,
+    file_size : u64,
+    ranges : Vec<(u64, u64)>, // read so far, sorted and merged
+    covered : u64
+}
+
+impl SelectedFileStats {
+    fn add(&mut self, read : &ReadEvent) {
+        self.stats.reads += 1;
+        self.stats.bytes += read.size as u64;
+        self.stats.pattern.record(read.offset as u64, read.size as u64);
+        self.file_size = read.file_size;
+        // reads past the end of the file are clamped, like `analysis::file_coverage` does
+        let start = (read.offset as u64).min(read.file_size);
+        let end = (read.offset as u64 + read.size as u64).min(read.file_size);
+        analysis::add_range(&mut self.ranges, start, end);
+        self.covered = self.ranges.iter().map(|(start, end)| end - start).sum();
+    }
+}
+
+/// Accesses to a watched path since it was added or the log was cleared.
+#[derive(Debug, Clone, Default)]
+struct WatchHits {
+    pub count : u64,
+    pub last : Option<chrono::DateTime<chrono::Utc>>
+}
+
+/// A line of the log view.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum LogLine {
+    Event(usize), // index into event_log
+    Group(Vec<usize>), // run of consecutive events on the same file, shown as a single summary line
+    GroupMember(usize), // event of an expanded group, listed below its summary
+    Summary(Vec<usize>) // reads of one file within one second, while the live view is downsampled
+}
+
+/// Action that waits for the user to decide what happens with the unexported log.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PendingAction {
+    Unmount,
+    Exit(iced::window::Id)
+}
+
+/// Dismissible notification shown on top of every view.
+#[derive(Debug)]
+struct Toast {
+    pub severity : Severity,
+    pub text : String
+}
+
+// Older toasts are dropped once there are more than this, so a flood of errors doesn't cover the window.
+const MAX_TOASTS: usize = 5;
+
+#[derive(Debug)]
+struct HexView {
+    pub title : String,
+    pub dump : String
+}
+
+impl HexView {
+    fn new(event : &Event, title : String) -> Self {
+        let dump = match &event.event {
+            EventType::Read(read) => match &read.data {
+                Some(data) => {
+                    let mut dump = hex_dump(&data[0..data.len().min(HEX_VIEW_LIMIT)], read.offset);
+                    if data.len() > HEX_VIEW_LIMIT {
+                        dump.push_str(&format!("{}\n", tr!("hex-more-bytes", count = data.len() - HEX_VIEW_LIMIT)));
+                    }
+                    dump
+                }
+                None => tr!("hex-no-payload")
+            }
+            _ => tr!("hex-no-payload")
+        };
+        Self {
+            title,
+            dump
+        }
+    }
+}
+
+/// Formats data as a classic hex editor view with 16 bytes per line, offsets are relative to the start of the file.
+fn hex_dump(data : &[u8], base_offset : usize) -> String {
+    let mut dump = String::new();
+    for (i, line) in data.chunks(16).enumerate() {
+        dump.push_str(&format!("{:08x}  ", base_offset + i * 16));
+        for j in 0..16 {
+            match line.get(j) {
+                Some(byte) => dump.push_str(&format!("{byte:02x} ")),
+                None => dump.push_str("   ")
+            }
+            if j == 7 {
+                dump.push(' ');
+            }
+        }
+        dump.push_str(" |");
+        for byte in line {
+            if byte.is_ascii_graphic() || *byte == b' ' {
+                dump.push(*byte as char);
+            } else {
+                dump.push('.');
+            }
+        }
+        dump.push_str("|\n");
+    }
+    dump
+}
+
+
+/// Names of the sessions saved in the config directory.
+/// Stale mounts of crashed monitors and the saved mountpoint if it is one, which may predate the registry.
+fn stale_mounts(mountpoint : &str) -> Vec<std::path::PathBuf> {
+    let mut stale : Vec<_> = registry::stale().into_iter().map(|mount| mount.mountpoint).collect();
+    let mountpoint = std::path::PathBuf::from(mountpoint);
+    if !mountpoint.as_os_str().is_empty() && platform::is_stale(&mountpoint) && !stale.contains(&mountpoint) {
+        stale.push(mountpoint);
+    }
+    stale
 }
 
+fn saved_sessions() -> Vec<String> {
+    crate::sessions_dir().map(|dir| Session::list(&dir)).unwrap_or_default()
+}
 
 impl Default for State {
     fn default() -> Self {
@@ -60,10 +669,103 @@ impl Default for State {
             source_valid: false,
             mountpoint_valid: false,
             status: Status::Unmounted,
-            error_text: None,
+            toasts: Vec::new(),
             event_log: Vec::new(),
-            event_text: String::new(),
-            event_log_content: iced::widget::text_editor::Content::new()
+            event_lines: Vec::new(),
+            max_log_entries: 50_000,
+            max_log_entries_text: String::from("50000"),
+            archive_index: String::new(),
+            events_dropped: 0,
+            events_lost: 0,
+            spill: None,
+            spilled_page: None,
+            log_lines: Vec::new(),
+            group_reads: true,
+            expanded_groups: std::collections::HashSet::new(),
+            processes: std::collections::BTreeSet::new(),
+            process_filter: None,
+            path_filter: PathFilter::default(),
+            path_include_text: String::new(),
+            path_exclude_text: String::new(),
+            filter_presets: Vec::new(),
+            preset_name: String::new(),
+            files: std::collections::BTreeMap::new(),
+            show_file_list: false,
+            file_filter: None,
+            file_stats: None,
+            timeline_from: 0.0,
+            timeline_to: None,
+            watch_rules: Vec::new(),
+            watch_sound: false,
+            watch_last_notified: std::collections::HashMap::new(),
+            watch_last_command: std::collections::HashMap::new(),
+            rule_last_notified: None,
+            watch_hits: Vec::new(),
+            show_watch_rules: false,
+            columns: LogColumn::defaults(),
+            show_columns: false,
+            show_statistics: false,
+            show_treemap: false,
+            treemap_dir: String::new(),
+            show_flame_graph: false,
+            show_access_order: false,
+            show_redundant_reads: false,
+            show_process_usage: false,
+            show_phases: false,
+            phase_bounds: String::from("5, 15, 30, 60"),
+            phases_from_first_read: false,
+            unused: None,
+            flame_graph_root: String::new(),
+            show_search: false,
+            search_query: String::new(),
+            search_matches: Vec::new(),
+            search_current: None,
+            search_match_hidden: false,
+            marker_label: String::new(),
+            session_name: String::new(),
+            copy_target: String::new(),
+            manifest_path: String::new(),
+            manifest_report: None,
+            external_log_path: String::new(),
+            external_log: None,
+            external_offset: String::from("0"),
+            external_anchor_marker: None,
+            external_anchor_text: String::new(),
+            saved_sessions: saved_sessions(),
+            active_mounts: registry::active(),
+            stale_mounts: Vec::new(),
+            compare_sessions: (None, None),
+            comparison: None,
+            event_log_content: iced::widget::text_editor::Content::new(),
+            mount_options: MountOptions::default(),
+            prerequisites: Vec::new(),
+            prerequisites_dismissed: false,
+            show_advanced_options: false,
+            script_content: iced::widget::text_editor::Content::new(),
+            ttl_text: String::from("1"),
+            include_text: String::new(),
+            exclude_text: String::new(),
+            hex_view: None,
+            highlight_rules: Vec::new(),
+            show_highlight_rules: false,
+            bookmarks: Vec::new(),
+            details: Vec::new(),
+            timestamp_mode: TimestampMode::Utc,
+            mounted_at: chrono::Utc::now(),
+            events_received: 0,
+            events_at_last_tick: 0,
+            event_rate: 0,
+            downsampled: false,
+            pending_summary: Vec::new(),
+            export_path: String::from("access_log.txt"),
+            log_exported: false,
+            pending_action: None,
+            dropped_path: None,
+            tray_enabled: false,
+            tray: None,
+            language: None,
+            window_size: iced::Size::new(800.0, 600.0),
+            window_position: None
         }
     }
 }
@@ -74,62 +776,552 @@ pub enum Message {
     UpdateMountpoint(String),
     MountPressed,
     UnmountPressed,
-    ReceivedEvent(Event),
-    InitEventCommunication(tokio::sync::mpsc::Sender<Arc<Mutex<tokio::sync::mpsc::Receiver<Event>>>>),
-    LogEdit(iced::widget::text_editor::Action)
+    ReceivedEvents(EventBatch), // everything that arrived since the last batch
+    InitEventCommunication(tokio::sync::mpsc::Sender<(Arc<Mutex<tokio::sync::mpsc::Receiver<Event>>>, Arc<std::sync::RwLock<LogFormat>>)>),
+    LogEdit(iced::widget::text_editor::Action),
+    CapturePayloadsToggled(bool),
+    CloseHexView,
+    ShowSpilledPage(usize),
+    CloseSpilledPage,
+    ToggleHighlightRules,
+    AddHighlightRule,
+    RemoveHighlightRule(usize),
+    UpdateHighlightPattern(usize, String),
+    UpdateHighlightColor(usize, HighlightColor),
+    PinSelectedEvent,
+    Unpin(usize),
+    ClearLog,
+    TimestampModeSelected(TimestampMode),
+    DismissToast(usize),
+    Tick,
+    UpdateExportPath(String),
+    ExportPressed,
+    ExportHtml,
+    CloseRequested(iced::window::Id),
+    ShutdownSignal(i32),
+    ConfirmSave,
+    ConfirmDiscard,
+    ConfirmCancel,
+    FileDropped(std::path::PathBuf),
+    UseDroppedAsSource,
+    UseDroppedAsMountpoint,
+    DismissDropped,
+    LanguageSelected(Language),
+    TrayEnabledToggled(bool),
+    MinimizeToTray,
+    TrayAction(TrayAction),
+    WindowMoved(iced::Point),
+    WindowResized(iced::Size),
+    ToggleAdvancedOptions,
+    ScriptEdit(iced::widget::text_editor::Action),
+    AllowOtherToggled(bool),
+    AutoUnmountToggled(bool),
+    NfsBackendToggled(bool),
+    CheckPrerequisites,
+    RefreshActiveMounts,
+    RecoverStaleMounts,
+    UnmountOther(std::path::PathBuf),
+    DismissPrerequisites,
+    CopyFixCommand(String),
+    ReadOnlyToggled(bool),
+    StatsFileToggled(bool),
+    ControlFileToggled(bool),
+    MetadataEventsToggled(bool),
+    WriteThroughToggled(bool),
+    DirectIoToggled(bool),
+    CaseSensitivitySelected(CaseOption),
+    UpdateTtl(String),
+    UpdateIncludeFilter(String),
+    UpdateExcludeFilter(String),
+    ProcessFilterSelected(ProcessFilter),
+    GroupReadsToggled(bool),
+    FileListToggled(bool),
+    FileSelected(Option<String>),
+    TimelineFromChanged(f64),
+    TimelineToChanged(f64),
+    ResetTimeline,
+    UpdateSessionName(String),
+    SaveSession,
+    LoadSession,
+    UpdateCopyTarget(String),
+    CopyAccessed,
+    ExtractReads(ExtractMode),
+    UpdateManifestPath(String),
+    CompareManifest,
+    CloseManifestReport,
+    UpdateExternalLogPath(String),
+    LoadExternalLog,
+    CloseExternalLog,
+    UpdateExternalOffset(String),
+    ExternalAnchorMarkerSelected(String),
+    UpdateExternalAnchorText(String),
+    AlignExternalLog,
+    CompareFirstSelected(String),
+    CompareSecondSelected(String),
+    CompareSessions,
+    CloseComparison,
+    UpdateMaxLogEntries(String),
+    UpdateArchiveIndex(String),
+    UpdateMarkerLabel(String),
+    UpdatePathInclude(String),
+    UpdatePathExclude(String),
+    PresetSelected(FilterPreset),
+    UpdatePresetName(String),
+    SavePreset,
+    DeletePreset,
+    OpenSearch,
+    CloseSearch,
+    UpdateSearch(String),
+    SearchNext,
+    SearchPrevious,
+    ToggleFlameGraph,
+    ToggleAccessOrder,
+    ToggleRedundantReads,
+    ToggleProcessUsage,
+    TogglePhases,
+    UpdatePhaseBounds(String),
+    PhasesFromFirstReadToggled(bool),
+    ShowUnused,
+    CloseUnused,
+    FlameGraphZoom(String),
+    ToggleWatchRules,
+    AddWatchRule,
+    WatchSelectedFile,
+    RemoveWatchRule(usize),
+    UpdateWatchPattern(usize, String),
+    WatchNotifyToggled(usize, bool),
+    UpdateWatchCommand(usize, String),
+    WatchSoundToggled(bool),
+    ToggleColumns,
+    ColumnToggled(LogColumn, bool),
+    MoveColumn(usize, bool), // index, whether it moves up
+    ToggleStatistics,
+    ToggleTreemap,
+    TreemapOpen(String),
+    TreemapUp,
+    AddMarker,
+    MountProgress,
+    CancelMount
+}
+
+/// Entry of the process filter dropdown.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProcessFilter {
+    All,
+    Process(String)
+}
+
+impl std::fmt::Display for ProcessFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::All => write!(f, "{}", tr!("all-processes")),
+            Self::Process(name) => write!(f, "{name}")
+        }
+    }
+}
+
+/// Entry of the case sensitivity dropdown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CaseOption(pub CaseSensitivity);
+
+impl CaseOption {
+    pub const ALL: [CaseOption; 3] = [
+        Self(CaseSensitivity::InsensitivePreserving),
+        Self(CaseSensitivity::Insensitive),
+        Self(CaseSensitivity::Sensitive)
+    ];
+}
+
+impl std::fmt::Display for CaseOption {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            CaseSensitivity::Sensitive => write!(f, "{}", tr!("case-sensitive")),
+            CaseSensitivity::Insensitive => write!(f, "{}", tr!("case-insensitive")),
+            CaseSensitivity::InsensitivePreserving => write!(f, "{}", tr!("case-insensitive-preserving"))
+        }
+    }
 }
 
 impl AccessTrackingFsGui {
-    /*
-    pub fn new() -> (Self, Command<Message>) {
-        (
-            Self::Loading,
-            Command::perform(SavedState::load(), Message::Loaded),
-        )
+    pub fn new(settings : Settings) -> Self {
+        let (sender, receiver) = tokio::sync::mpsc::channel(10000);
+        let (tray_sender, tray_receiver) = tokio::sync::mpsc::unbounded_channel();
+        let (shutdown_sender, shutdown_receiver) = tokio::sync::mpsc::unbounded_channel();
+        signals::on_shutdown(move |signal| {
+            let _ = shutdown_sender.send(signal);
+        });
+        if let Some(language) = settings.language {
+            i18n::set_language(language);
+        }
+        let state = State {
+            source_valid: std::path::PathBuf::from(&settings.source).is_dir(),
+            mountpoint_valid: std::path::PathBuf::from(&settings.mountpoint).is_dir(),
+            stale_mounts: stale_mounts(&settings.mountpoint),
+            source: settings.source,
+            mountpoint: settings.mountpoint,
+            export_path: settings.export_path,
+            ttl_text: format!("{}", settings.mount_options.ttl.as_secs_f64()),
+            include_text: settings.mount_options.filter.include.join(", "),
+            exclude_text: settings.mount_options.filter.exclude.join(", "),
+            prerequisites: diagnostics::check_prerequisites(&settings.mount_options),
+            mount_options: settings.mount_options,
+            tray_enabled: settings.tray_enabled,
+            language: settings.language,
+            timestamp_mode: settings.timestamp_mode,
+            highlight_rules: settings.highlight_rules,
+            process_filter: settings.process_filter,
+            group_reads: settings.group_reads,
+            max_log_entries: settings.max_log_entries,
+            filter_presets: settings.filter_presets,
+            watch_hits: vec![WatchHits::default(); settings.watch_rules.len()],
+            watch_rules: settings.watch_rules,
+            watch_sound: settings.watch_sound,
+            columns: settings.columns,
+            script_content: iced::widget::text_editor::Content::with_text(&settings.script),
+            max_log_entries_text: format!("{}", settings.max_log_entries),
+            archive_index: settings.archive_index,
+            window_size: iced::Size::new(settings.window_size.0, settings.window_size.1),
+            window_position: settings.window_position.map(|(x, y)| iced::Point::new(x, y)),
+            ..State::default()
+        };
+        let log_format = LogFormat {
+            columns: state.columns.clone(),
+            timestamp_mode: state.timestamp_mode,
+            mounted_at: state.mounted_at,
+            language: i18n::language(),
+            stable_names: false
+        };
+        Self {
+            state,
+            event_sender: sender,
+            event_receiver: Arc::new(Mutex::new(receiver)),
+            log_format: Arc::new(std::sync::RwLock::new(log_format)),
+            tray_sender,
+            tray_receiver: Arc::new(Mutex::new(tray_receiver)),
+            shutdown_receiver: Arc::new(Mutex::new(shutdown_receiver))
+        }
+    }
+
+    /// The part of the state that is remembered across restarts.
+    pub fn settings(&self) -> Settings {
+        Settings {
+            window_size: (self.state.window_size.width, self.state.window_size.height),
+            window_position: self.state.window_position.map(|position| (position.x, position.y)),
+            source: self.state.source.clone(),
+            mountpoint: self.state.mountpoint.clone(),
+            export_path: self.state.export_path.clone(),
+            mount_options: self.state.mount_options.clone(),
+            tray_enabled: self.state.tray_enabled,
+            language: self.state.language,
+            timestamp_mode: self.state.timestamp_mode,
+            highlight_rules: self.state.highlight_rules.clone(),
+            process_filter: self.state.process_filter.clone(),
+            group_reads: self.state.group_reads,
+            max_log_entries: self.state.max_log_entries,
+            filter_presets: self.state.filter_presets.clone(),
+            watch_rules: self.state.watch_rules.clone(),
+            watch_sound: self.state.watch_sound,
+            script: self.state.script_content.text().trim_end().to_string(),
+            archive_index: self.state.archive_index.clone(),
+            columns: self.state.columns.clone()
+        }
     }
-    */
 
     pub fn title(&self) -> String {
-        format!("FUSE File Access Tracker")
+        tr!("window-title")
     }
 
     pub fn update(&mut self, message: Message) -> Command<Message> {
         match message {
             Message::MountPressed => {
+                // a stale mount at the mountpoint is dead anyway, it can't even be checked whether it is a directory
+                if platform::is_stale(self.state.mountpoint.as_ref()) {
+                    registry::recover(self.state.mountpoint.as_ref(), privileges::mounter().as_deref());
+                    self.state.stale_mounts = stale_mounts(&self.state.mountpoint);
+                }
                 self.state.mountpoint_valid = std::path::PathBuf::from(self.state.mountpoint.clone()).is_dir();
                 self.state.source_valid = std::path::PathBuf::from(self.state.source.clone()).is_dir();
-                if self.state.mountpoint_valid && self.state.source_valid {
-                    self.state.status = Status::Mounting;
-                    match super::run_mount(&self.state.source, &self.state.mountpoint, self.event_sender.clone()) {
-                        Ok(process) => {
-                            self.state.status = Status::Mounted(process);
+                if Self::parse_ttl(&self.state.ttl_text).is_none() {
+                    self.push_toast(Severity::Error, tr!("ttl-invalid"));
+                } else if self.state.mountpoint_valid && self.state.source_valid {
+                    let script = self.state.script_content.text();
+                    let script = match script.trim() {
+                        "" => None,
+                        script => match ScriptPlugin::new(script) {
+                            Ok(script) => Some(script),
+                            Err(err) => {
+                                self.push_toast(Severity::Error, err.to_string());
+                                return Command::none();
+                            }
                         }
-                        Err(err) => {
-                            self.state.error_text = Some(format!("{err}"));
-                            self.state.status = Status::Unmounted;
+                    };
+                    let archive_index = match self.state.archive_index.trim() {
+                        "" => None,
+                        path => match ArchiveIndex::load(path.as_ref()) {
+                            Ok(index) => Some(index),
+                            Err(err) => {
+                                self.push_toast(Severity::Error, err.to_string());
+                                return Command::none();
+                            }
                         }
+                    };
+                    // scanning a large source takes a while, so it must not block the UI
+                    let progress = Arc::new(ScanProgress::default());
+                    let (sender, receiver) = std::sync::mpsc::channel();
+                    let mut builder = MountBuilder::new(&self.state.source, &self.state.mountpoint)
+                        .options(self.state.mount_options.clone())
+                        .events(self.event_sender.clone())
+                        .progress(progress.clone());
+                    if let Some(script) = script {
+                        builder = builder.plugin(Box::new(script));
                     }
+                    if let Some(index) = archive_index {
+                        builder = builder.plugin(Box::new(ArchivePlugin::new(index)));
+                    }
+                    if let Some(mounter) = privileges::mounter() {
+                        builder = builder.mounter(mounter);
+                    }
+                    std::thread::spawn(move || {
+                        let _ = sender.send(builder.mount());
+                    });
+                    self.state.status = Status::Mounting(progress, receiver);
                 } else {
                     if !self.state.mountpoint_valid {
-                        self.state.error_text = Some(format!("Mountpoint is not a directory."));
+                        self.push_toast(Severity::Error, tr!("mountpoint-not-directory"));
                     }
                     if !self.state.source_valid {
-                        self.state.error_text = Some(format!("Source is not a directory."));
+                        self.push_toast(Severity::Error, tr!("source-not-directory"));
+                    }
+                }
+            }
+            Message::MountProgress => {
+                let Status::Mounting(progress, receiver) = &self.state.status else {
+                    return Command::none();
+                };
+                match receiver.try_recv() {
+                    Ok(Ok(session)) if progress.is_cancelled() => {
+                        // the scan was already done when cancelling, dropping the session unmounts again
+                        drop(session);
+                        self.state.status = Status::Unmounted;
+                    }
+                    Ok(Ok(session)) => self.mounted(session),
+                    Ok(Err(crate::Error::ScanCancelled)) => {
+                        self.state.status = Status::Unmounted;
+                        self.push_toast(Severity::Info, tr!("mount-cancelled"));
+                    }
+                    Ok(Err(err)) => {
+                        self.state.status = Status::Unmounted;
+                        self.state.active_mounts = registry::active();
+                        let mut text = tr!("mount-failed", error = err.to_string());
+                        for problem in MountProblem::diagnose(&err, &self.state.mountpoint, &self.state.mount_options) {
+                            text.push('\n');
+                            text.push_str(&problem.hint(&self.state.mountpoint));
+                        }
+                        self.push_toast(Severity::Error, text);
                     }
+                    Err(std::sync::mpsc::TryRecvError::Empty) => {}
+                    Err(std::sync::mpsc::TryRecvError::Disconnected) => {
+                        self.state.status = Status::Unmounted;
+                        self.push_toast(Severity::Error, tr!("mount-failed", error = "the mount thread crashed"));
+                    }
+                }
+            }
+            Message::CancelMount => {
+                if let Status::Mounting(progress, _) = &self.state.status {
+                    progress.cancel();
                 }
             }
             Message::UnmountPressed => {
-                let mut status = Status::Unmounting;
-                std::mem::swap(&mut self.state.status, &mut status);
-                match status {
-                    Status::Mounted(process) => {
-                        process.join();
+                if self.has_unexported_log() {
+                    self.state.pending_action = Some(PendingAction::Unmount);
+                } else {
+                    self.unmount();
+                }
+            }
+            Message::UpdateExportPath(path) => {
+                self.state.export_path = path;
+            }
+            Message::ExportPressed => {
+                self.export_log();
+            }
+            Message::ExportHtml => {
+                let path = std::path::Path::new(&self.state.export_path).with_extension("html");
+                let session = self.current_session(self.state.session_name.trim().to_string());
+                let written = session.map_err(std::io::Error::other).and_then(|session| {
+                    let mut file = std::io::BufWriter::new(std::fs::File::create(&path)?);
+                    html::write_report(&mut file, &session)?;
+                    std::io::Write::flush(&mut file)
+                });
+                match written {
+                    Ok(()) => self.push_toast(Severity::Info, tr!("html-exported", path = path.display().to_string())),
+                    Err(err) => self.push_toast(Severity::Error, tr!("html-export-failed", path = path.display().to_string(), error = err.to_string()))
+                }
+            }
+            Message::CloseRequested(id) => {
+                if self.has_unexported_log() {
+                    self.state.pending_action = Some(PendingAction::Exit(id));
+                } else {
+                    return self.close_window(id);
+                }
+            }
+            Message::ShutdownSignal(_) => {
+                // nobody is there to answer the dialog, so the log is exported like after choosing to save it
+                if self.has_unexported_log() {
+                    let path = self.state.export_path.clone();
+                    if let Err(err) = self.write_log(&path) {
+                        eprintln!("Failed to export the log to {path}: {err}");
                     }
-                    _ => {
-                        self.state.error_text = Some(format!("Somehow unmount was pressed, even though nothing was mounted...? Oh well."));
+                }
+                if let Status::Mounted(session) = &self.state.status {
+                    if let Err(err) = session.stop_recording() {
+                        eprintln!("Failed to finish the recording: {err}");
                     }
                 }
-                self.state.status = Status::Unmounted;
+                self.shut_down();
+                return iced::exit();
+            }
+            Message::ConfirmSave => {
+                if self.export_log() {
+                    return self.run_pending_action();
+                }
+                self.state.pending_action = None;
+            }
+            Message::ConfirmDiscard => {
+                return self.run_pending_action();
+            }
+            Message::ConfirmCancel => {
+                self.state.pending_action = None;
+            }
+            Message::FileDropped(path) => {
+                if let Status::Unmounted = self.state.status {
+                    if path.is_dir() {
+                        self.state.dropped_path = Some(path.to_string_lossy().to_string());
+                    } else {
+                        self.push_toast(Severity::Warning, tr!("not-a-folder", path = path.display().to_string()));
+                    }
+                }
+            }
+            Message::UseDroppedAsSource => {
+                if let Some(path) = self.state.dropped_path.take() {
+                    return self.update(Message::UpdateSource(path));
+                }
+            }
+            Message::UseDroppedAsMountpoint => {
+                if let Some(path) = self.state.dropped_path.take() {
+                    return self.update(Message::UpdateMountpoint(path));
+                }
+            }
+            Message::DismissDropped => {
+                self.state.dropped_path = None;
+            }
+            Message::LanguageSelected(language) => {
+                i18n::set_language(language);
+                self.state.language = Some(language);
+                self.update_log_format();
+                self.rebuild_log();
+            }
+            Message::WindowMoved(position) => {
+                self.state.window_position = Some(position);
+            }
+            Message::WindowResized(size) => {
+                self.state.window_size = size;
+            }
+            Message::ToggleAdvancedOptions => {
+                self.state.show_advanced_options = !self.state.show_advanced_options;
+            }
+            Message::ScriptEdit(action) => {
+                self.state.script_content.perform(action);
+            }
+            Message::AllowOtherToggled(allow_other) => {
+                self.state.mount_options.allow_other = allow_other;
+                self.state.prerequisites = diagnostics::check_prerequisites(&self.state.mount_options);
+            }
+            Message::AutoUnmountToggled(auto_unmount) => {
+                self.state.mount_options.auto_unmount = auto_unmount;
+            }
+            Message::NfsBackendToggled(nfs) => {
+                self.state.mount_options.backend = match nfs {
+                    true => BackendKind::Nfs(Default::default()),
+                    false => BackendKind::Fuse
+                };
+                self.state.prerequisites = diagnostics::check_prerequisites(&self.state.mount_options);
+            }
+            Message::CheckPrerequisites => {
+                self.state.prerequisites = diagnostics::check_prerequisites(&self.state.mount_options);
+                self.state.prerequisites_dismissed = false;
+                if self.state.prerequisites.is_empty() {
+                    self.push_toast(Severity::Info, tr!("setup-complete"));
+                }
+            }
+            Message::RecoverStaleMounts => {
+                for mountpoint in std::mem::take(&mut self.state.stale_mounts) {
+                    if !registry::recover(&mountpoint, privileges::mounter().as_deref()) {
+                        self.push_toast(Severity::Error, tr!("stale-mount-not-recovered", mountpoint = mountpoint.to_string_lossy()));
+                    }
+                }
+                self.state.stale_mounts = stale_mounts(&self.state.mountpoint);
+                self.state.mountpoint_valid = std::path::PathBuf::from(&self.state.mountpoint).is_dir();
+            }
+            Message::RefreshActiveMounts => {
+                self.state.active_mounts = registry::active();
+            }
+            Message::UnmountOther(mountpoint) => {
+                if let Err(err) = registry::request_unmount(&mountpoint, privileges::mounter().as_deref()) {
+                    self.push_toast(Severity::Error, tr!("unmount-other-failed", error = err.to_string()));
+                }
+                self.state.active_mounts = registry::active();
+            }
+            Message::DismissPrerequisites => {
+                self.state.prerequisites_dismissed = true;
+            }
+            Message::CopyFixCommand(command) => {
+                return iced::clipboard::write(command);
+            }
+            Message::ReadOnlyToggled(read_only) => {
+                self.state.mount_options.read_only = read_only;
+            }
+            Message::StatsFileToggled(stats_file) => {
+                self.state.mount_options.stats_file = stats_file;
+            }
+            Message::ControlFileToggled(control_file) => {
+                self.state.mount_options.control_file = control_file;
+            }
+            Message::MetadataEventsToggled(metadata_events) => {
+                self.state.mount_options.metadata_events = metadata_events;
+            }
+            Message::WriteThroughToggled(write_through) => {
+                self.state.mount_options.write_through = write_through;
+            }
+            Message::DirectIoToggled(direct_io) => {
+                self.state.mount_options.direct_io = direct_io;
+            }
+            Message::CaseSensitivitySelected(CaseOption(case_sensitivity)) => {
+                self.state.mount_options.case_sensitivity = case_sensitivity;
+            }
+            Message::UpdateTtl(ttl) => {
+                if let Some(ttl) = Self::parse_ttl(&ttl) {
+                    self.state.mount_options.ttl = ttl;
+                }
+                self.state.ttl_text = ttl;
+            }
+            Message::UpdateIncludeFilter(include) => {
+                self.state.mount_options.filter.include = Self::parse_patterns(&include);
+                self.state.include_text = include;
+            }
+            Message::UpdateArchiveIndex(path) => {
+                self.state.archive_index = path;
+            }
+            Message::UpdateMaxLogEntries(text) => {
+                if let Ok(max) = text.trim().parse::<usize>() {
+                    if max > 0 {
+                        self.state.max_log_entries = max;
+                    }
+                }
+                self.state.max_log_entries_text = text;
+            }
+            Message::UpdateExcludeFilter(exclude) => {
+                self.state.mount_options.filter.exclude = Self::parse_patterns(&exclude);
+                self.state.exclude_text = exclude;
             }
             Message::UpdateMountpoint(path) => {
                 self.state.mountpoint_valid = std::path::PathBuf::from(path.clone()).is_dir();
@@ -139,61 +1331,2120 @@ impl AccessTrackingFsGui {
                 self.state.source_valid = std::path::PathBuf::from(path.clone()).is_dir();
                 self.state.source = path;
             }
-            Message::ReceivedEvent(event) => {
-                self.state.event_log.push(event.clone());
-                self.state.event_text.push_str(&format!("{event}\n"));
-                self.state.event_log_content = iced::widget::text_editor::Content::with_text(&self.state.event_text)
+            Message::ReceivedEvents(batch) => {
+                // formatted before a change of the format arrived, rare enough to just format them again
+                let stale = *self.log_format.read().unwrap() != batch.format;
+                for (event, line) in batch.events.into_iter().zip(batch.lines) {
+                    let line = if stale { self.format_event(&event) } else { line };
+                    self.receive_event(event, line);
+                }
             }
-            Message::InitEventCommunication(sender) => {
-                if sender.blocking_send(self.event_receiver.clone()).is_err() {
-                    panic!("Failed to establish event communication! :3");
+            Message::ToggleWatchRules => {
+                self.state.show_watch_rules = !self.state.show_watch_rules;
+            }
+            Message::AddWatchRule => {
+                self.state.watch_rules.push(WatchRule {
+                    pattern: String::new(),
+                    notify: true,
+                    command: String::new()
+                });
+                self.state.watch_hits.push(WatchHits::default());
+            }
+            Message::WatchSelectedFile => {
+                if let Some(file) = self.selected_event().and_then(Self::event_file) {
+                    let pattern = file.to_string();
+                    self.state.watch_rules.push(WatchRule {
+                        pattern,
+                        notify: true,
+                        command: String::new()
+                    });
+                    self.state.watch_hits.push(WatchHits::default());
+                    self.state.show_watch_rules = true;
                 }
             }
-            Message::LogEdit(action) => {
-                //action
-                self.state.event_log_content.perform(action);
+            Message::RemoveWatchRule(index) => {
+                if index < self.state.watch_rules.len() {
+                    self.state.watch_rules.remove(index);
+                    self.state.watch_hits.remove(index);
+                    self.state.watch_last_notified.clear();
+                    self.state.watch_last_command.clear();
+                }
             }
-        }
-        Command::none()
-    }
-
-    fn directory_selector<'a>(placeholder: &str, text : &str, on_input: impl Fn(String) -> Message + 'a) -> TextInput<'a, Message> {
-        text_input(placeholder, text)
-            //.style(|theme, status| iced::widget::text_input::Style::(theme, status))
-            .on_input(on_input)
-            .width(400)
-    }
-
-    fn view_mounted(&self) -> Container<Message> {
-        let centered_container = container(
-            column![
-                button("Unmount").on_press(Message::UnmountPressed),
-                text(format!("{} events logged.", self.state.event_log.len())),
-                scrollable(text_editor(&self.state.event_log_content).on_action(Message::LogEdit)),
-            ]
-        );
-
-        container(centered_container)
-            .width(iced::Fill)
-            .height(iced::Fill)
-            .align_x(Center)
-            .align_y(Center)
-    }
-
-    fn view_unmounted(&self) -> Container<Message> {
-        let centered_container = container(
-            container(column![
-                row![
-                    text("Source Directory:").width(200).align_x(Horizontal::Right),
-                    Self::directory_selector("Source Directory", &self.state.source, Message::UpdateSource).width(400),
-                ].spacing(10).align_y(Center),
+            Message::UpdateWatchPattern(index, pattern) => {
+                if let Some(rule) = self.state.watch_rules.get_mut(index) {
+                    rule.pattern = pattern;
+                    // the hits belonged to the old pattern
+                    self.state.watch_hits[index] = WatchHits::default();
+                }
+            }
+            Message::UpdateWatchCommand(index, command) => {
+                if let Some(rule) = self.state.watch_rules.get_mut(index) {
+                    rule.command = command;
+                }
+            }
+            Message::WatchNotifyToggled(index, notify) => {
+                if let Some(rule) = self.state.watch_rules.get_mut(index) {
+                    rule.notify = notify;
+                }
+            }
+            Message::WatchSoundToggled(sound) => {
+                self.state.watch_sound = sound;
+            }
+            Message::ToggleColumns => {
+                self.state.show_columns = !self.state.show_columns;
+            }
+            Message::ColumnToggled(column, enabled) => {
+                if let Some(entry) = self.state.columns.iter_mut().find(|(existing, _)| *existing == column) {
+                    entry.1 = enabled;
+                }
+                self.update_log_format();
+                self.rebuild_log();
+            }
+            Message::MoveColumn(index, up) => {
+                let other = if up { index.checked_sub(1) } else { Some(index + 1) };
+                if let Some(other) = other.filter(|other| *other < self.state.columns.len()) {
+                    self.state.columns.swap(index, other);
+                    self.update_log_format();
+                    self.rebuild_log();
+                }
+            }
+            Message::ToggleStatistics => {
+                self.state.show_statistics = !self.state.show_statistics;
+            }
+            Message::ToggleTreemap => {
+                self.state.show_treemap = !self.state.show_treemap;
+            }
+            Message::TreemapOpen(dir) => {
+                self.state.treemap_dir = dir;
+            }
+            Message::TreemapUp => {
+                self.state.treemap_dir = match self.state.treemap_dir.rsplit_once('/') {
+                    Some((parent, _)) => parent.to_string(),
+                    None => String::new()
+                };
+            }
+            Message::ToggleFlameGraph => {
+                self.state.show_flame_graph = !self.state.show_flame_graph;
+            }
+            Message::ToggleAccessOrder => {
+                self.state.show_access_order = !self.state.show_access_order;
+            }
+            Message::ToggleRedundantReads => {
+                self.state.show_redundant_reads = !self.state.show_redundant_reads;
+            }
+            Message::ToggleProcessUsage => {
+                self.state.show_process_usage = !self.state.show_process_usage;
+            }
+            Message::TogglePhases => {
+                self.state.show_phases = !self.state.show_phases;
+            }
+            Message::UpdatePhaseBounds(bounds) => {
+                self.state.phase_bounds = bounds;
+            }
+            Message::PhasesFromFirstReadToggled(from_first_read) => {
+                self.state.phases_from_first_read = from_first_read;
+            }
+            Message::ShowUnused => {
+                if let Status::Mounted(session) = &self.state.status {
+                    let tree = session.tree();
+                    let files = tree.iter().filter(|entry| !entry.is_dir && !entry.is_virtual).map(|entry| (entry.path.as_str(), entry.size));
+                    let reads : std::collections::HashMap<&str, u64> = tree.iter().map(|entry| (entry.path.as_str(), entry.reads)).collect();
+                    self.state.unused = Some(analysis::unused_files(files, |file| reads.get(file).is_some_and(|reads| *reads > 0)));
+                }
+            }
+            Message::CloseUnused => {
+                self.state.unused = None;
+            }
+            Message::FlameGraphZoom(path) => {
+                self.state.flame_graph_root = path;
+            }
+            Message::OpenSearch => {
+                self.state.show_search = true;
+                return text_input::focus(SEARCH_INPUT.clone());
+            }
+            Message::CloseSearch => {
+                self.state.show_search = false;
+                self.state.search_query.clear();
+                self.state.search_matches.clear();
+                self.state.search_current = None;
+            }
+            Message::UpdateSearch(query) => {
+                self.state.search_query = query;
+                self.state.search_matches = self.state.event_lines.iter().enumerate().filter(|(_, line)| self.matches_search(line)).map(|(i, _)| i).collect();
+                self.state.search_current = None;
+                self.state.search_match_hidden = false;
+            }
+            Message::SearchNext => {
+                if !self.state.search_matches.is_empty() {
+                    self.state.search_current = Some(match self.state.search_current {
+                        Some(current) => (current + 1) % self.state.search_matches.len(),
+                        None => 0
+                    });
+                    self.show_search_match();
+                }
+            }
+            Message::SearchPrevious => {
+                if !self.state.search_matches.is_empty() {
+                    let count = self.state.search_matches.len();
+                    self.state.search_current = Some(match self.state.search_current {
+                        Some(current) => (current + count - 1) % count,
+                        None => count - 1
+                    });
+                    self.show_search_match();
+                }
+            }
+            Message::UpdatePathInclude(include) => {
+                self.state.path_filter.include = Self::parse_patterns(&include);
+                self.state.path_include_text = include;
+                self.rebuild_log();
+            }
+            Message::UpdatePathExclude(exclude) => {
+                self.state.path_filter.exclude = Self::parse_patterns(&exclude);
+                self.state.path_exclude_text = exclude;
+                self.rebuild_log();
+            }
+            Message::PresetSelected(preset) => {
+                self.state.process_filter = preset.process;
+                self.state.path_include_text = preset.paths.include.join(", ");
+                self.state.path_exclude_text = preset.paths.exclude.join(", ");
+                self.state.path_filter = preset.paths;
+                self.state.preset_name = preset.name;
+                self.rebuild_log();
+            }
+            Message::UpdatePresetName(name) => {
+                self.state.preset_name = name;
+            }
+            Message::SavePreset => {
+                let preset = FilterPreset {
+                    name: self.state.preset_name.trim().to_string(),
+                    process: self.state.process_filter.clone(),
+                    paths: self.state.path_filter.clone()
+                };
+                // saving under an existing name replaces that preset
+                match self.state.filter_presets.iter_mut().find(|existing| existing.name == preset.name) {
+                    Some(existing) => *existing = preset,
+                    None => self.state.filter_presets.push(preset)
+                }
+            }
+            Message::DeletePreset => {
+                let name = self.state.preset_name.trim().to_string();
+                self.state.filter_presets.retain(|preset| preset.name != name);
+            }
+            Message::UpdateMarkerLabel(label) => {
+                self.state.marker_label = label;
+            }
+            Message::AddMarker => {
+                let count = self.state.event_log.iter().filter(|event| matches!(event.event, EventType::Marker(_))).count();
+                let label = match self.state.marker_label.trim() {
+                    "" => tr!("marker-default", number = count + 1),
+                    label => label.to_string()
+                };
+                self.state.marker_label.clear();
+                let event = Event {
+                    time: chrono::Utc::now(),
+                    process: Process {
+                        pid: std::process::id(),
+                        name: Arc::new(String::from("user")),
+                        parent: None,
+                        uid: None,
+                        gid: None
+                    },
+                    event: EventType::Marker(label)
+                };
+                let line = self.format_event(&event);
+                self.log_event(event, line);
+            }
+            Message::InitEventCommunication(sender) => {
+                if sender.blocking_send((self.event_receiver.clone(), self.log_format.clone())).is_err() {
+                    panic!("Failed to establish event communication! :3");
+                }
+            }
+            Message::LogEdit(action) => {
+                //action
+                let double_click = matches!(action, text_editor::Action::SelectWord);
+                let moves_cursor = !matches!(action, text_editor::Action::Scroll { .. });
+                self.state.event_log_content.perform(action);
+                if moves_cursor {
+                    self.state.details = match self.selected_index() {
+                        Some(index) => self.event_details(index),
+                        None => Vec::new()
+                    };
+                }
+                let (line, _) = self.state.event_log_content.cursor_position();
+                if let (true, Some(LogLine::Group(events))) = (double_click, self.state.log_lines.get(line)) {
+                    let first = events[0];
+                    if !self.state.expanded_groups.remove(&first) {
+                        self.state.expanded_groups.insert(first);
+                    }
+                    self.rebuild_log();
+                } else if double_click && self.state.mount_options.capture_payloads {
+                    self.state.hex_view = self.selected_event().map(|event| HexView::new(event, self.format_event(event)));
+                }
+            }
+            Message::CapturePayloadsToggled(capture_payloads) => {
+                self.state.mount_options.capture_payloads = capture_payloads;
+            }
+            Message::CloseHexView => {
+                self.state.hex_view = None;
+            }
+            Message::ShowSpilledPage(page) => {
+                if let Some(spill) = &mut self.state.spill {
+                    let format = self.log_format.read().unwrap();
+                    let page_events = spill.page(page).map(|events| events.iter().map(|event| format.format_event(event) + "\n").collect());
+                    drop(format);
+                    match page_events {
+                        Ok(lines) => self.state.spilled_page = Some((page, lines)),
+                        Err(err) => self.push_toast(Severity::Error, tr!("spilled-page-failed", error = err.to_string()))
+                    }
+                }
+            }
+            Message::CloseSpilledPage => {
+                self.state.spilled_page = None;
+            }
+            Message::ToggleHighlightRules => {
+                self.state.show_highlight_rules = !self.state.show_highlight_rules;
+            }
+            Message::AddHighlightRule => {
+                self.state.highlight_rules.push(HighlightRule {
+                    pattern: String::new(),
+                    color: HighlightColor::Red
+                });
+            }
+            Message::RemoveHighlightRule(index) => {
+                if index < self.state.highlight_rules.len() {
+                    self.state.highlight_rules.remove(index);
+                }
+            }
+            Message::UpdateHighlightPattern(index, pattern) => {
+                if let Some(rule) = self.state.highlight_rules.get_mut(index) {
+                    rule.pattern = pattern;
+                }
+            }
+            Message::UpdateHighlightColor(index, color) => {
+                if let Some(rule) = self.state.highlight_rules.get_mut(index) {
+                    rule.color = color;
+                }
+            }
+            Message::PinSelectedEvent => {
+                if let Some(event) = self.selected_event() {
+                    if !self.state.bookmarks.contains(event) {
+                        self.state.bookmarks.push(event.clone());
+                    }
+                }
+            }
+            Message::Unpin(index) => {
+                if index < self.state.bookmarks.len() {
+                    self.state.bookmarks.remove(index);
+                }
+            }
+            Message::ClearLog => {
+                self.clear_log();
+                self.rebuild_log();
+            }
+            Message::TimestampModeSelected(mode) => {
+                self.state.timestamp_mode = mode;
+                self.update_log_format();
+                self.rebuild_log();
+            }
+            Message::ProcessFilterSelected(filter) => {
+                self.state.process_filter = match filter {
+                    ProcessFilter::All => None,
+                    ProcessFilter::Process(name) => Some(name)
+                };
+                self.rebuild_log();
+            }
+            Message::FileListToggled(show_file_list) => {
+                self.state.show_file_list = show_file_list;
+                if !show_file_list && self.state.file_filter.take().is_some() {
+                    self.rebuild_log();
+                }
+            }
+            Message::FileSelected(file) => {
+                self.state.file_filter = file;
+                self.rebuild_log();
+            }
+            Message::TimelineFromChanged(from) => {
+                self.state.timeline_from = from;
+                if let Some(to) = self.state.timeline_to {
+                    self.state.timeline_to = Some(to.max(from));
+                }
+                self.rebuild_log();
+            }
+            Message::TimelineToChanged(to) => {
+                // dragging to the end follows new events again
+                self.state.timeline_to = if to >= self.session_length() { None } else { Some(to) };
+                self.state.timeline_from = self.state.timeline_from.min(to);
+                self.rebuild_log();
+            }
+            Message::ResetTimeline => {
+                self.state.timeline_from = 0.0;
+                self.state.timeline_to = None;
+                self.rebuild_log();
+            }
+            Message::UpdateSessionName(name) => {
+                self.state.session_name = name;
+            }
+            Message::UpdateCopyTarget(target) => {
+                self.state.copy_target = target;
+            }
+            Message::UpdateManifestPath(path) => {
+                self.state.manifest_path = path;
+            }
+            Message::CompareManifest => {
+                let Status::Mounted(session) = &self.state.status else {
+                    return Command::none();
+                };
+                match Manifest::load(self.state.manifest_path.trim().as_ref()) {
+                    Ok(manifest) => {
+                        let accessed = self.state.files.keys().map(|file| file.as_str());
+                        let report = manifest.compare(accessed, |file| session.entry(file).is_some_and(|entry| !entry.is_dir && !entry.is_virtual));
+                        self.state.manifest_report = Some(report);
+                    }
+                    Err(err) => self.push_toast(Severity::Error, err.to_string())
+                }
+            }
+            Message::UpdateExternalLogPath(path) => {
+                self.state.external_log_path = path;
+            }
+            Message::LoadExternalLog => {
+                match ExternalLog::load(self.state.external_log_path.trim().as_ref(), self.state.mounted_at) {
+                    Ok(log) => self.state.external_log = Some(log),
+                    Err(err) => self.push_toast(Severity::Error, err.to_string())
+                }
+            }
+            Message::CloseExternalLog => {
+                self.state.external_log = None;
+            }
+            Message::UpdateExternalOffset(offset) => {
+                self.state.external_offset = offset;
+            }
+            Message::ExternalAnchorMarkerSelected(marker) => {
+                self.state.external_anchor_marker = Some(marker);
+            }
+            Message::UpdateExternalAnchorText(text) => {
+                self.state.external_anchor_text = text;
+            }
+            Message::AlignExternalLog => {
+                let (Some(log), Some(marker)) = (&self.state.external_log, &self.state.external_anchor_marker) else {
+                    return Command::none();
+                };
+                let time = external_log::marker_time(self.state.event_log.iter(), marker);
+                match time.and_then(|time| log.anchor_offset(&self.state.external_anchor_text, time)) {
+                    Some(offset) => self.state.external_offset = format!("{:.3}", offset.num_milliseconds() as f64 / 1000.0),
+                    None => self.push_toast(Severity::Warning, tr!("external-anchor-not-found", text = self.state.external_anchor_text.as_str()))
+                }
+            }
+            Message::CloseManifestReport => {
+                self.state.manifest_report = None;
+            }
+            Message::CopyAccessed => {
+                let target = self.state.copy_target.trim().to_string();
+                let files = self.state.files.keys().map(|file| file.as_str());
+                match copy::copy_files(self.state.source.as_ref(), files, target.as_ref()) {
+                    Ok((count, bytes)) => self.push_toast(Severity::Info, tr!("files-copied", count = count, size = analysis::format_size(bytes), target = target)),
+                    Err(err) => self.push_toast(Severity::Error, tr!("copy-failed", error = err.to_string()))
+                }
+            }
+            Message::ExtractReads(mode) => {
+                let target = self.state.copy_target.trim().to_string();
+                let extracted = self.spilled_events().and_then(|spilled| {
+                    extract::extract_reads(spilled.iter().chain(&self.state.event_log), self.state.source.as_ref(), target.as_ref(), mode)
+                });
+                match extracted {
+                    Ok((count, bytes)) => self.push_toast(Severity::Info, tr!("reads-extracted", count = count, size = analysis::format_size(bytes), target = target)),
+                    Err(err) => self.push_toast(Severity::Error, tr!("extract-failed", error = err.to_string()))
+                }
+            }
+            Message::SaveSession => {
+                let name = self.state.session_name.trim().to_string();
+                let saved = self.current_session(name.clone()).and_then(|session| session.save(&crate::sessions_dir().ok_or(crate::Error::NoConfigDir)?));
+                match saved {
+                    Ok(()) => {
+                        self.push_toast(Severity::Info, tr!("session-saved", name = name));
+                        self.state.saved_sessions = saved_sessions();
+                    }
+                    Err(err) => self.push_toast(Severity::Error, tr!("session-save-failed", error = err.to_string()))
+                }
+            }
+            Message::LoadSession => {
+                let name = self.state.session_name.trim().to_string();
+                match crate::sessions_dir().ok_or(crate::Error::NoConfigDir).and_then(|dir| Session::load(&dir, &name)) {
+                    Ok(session) => self.show_session(session),
+                    Err(err) => self.push_toast(Severity::Error, tr!("session-load-failed", error = err.to_string()))
+                }
+            }
+            Message::CompareFirstSelected(name) => {
+                self.state.compare_sessions.0 = Some(name);
+            }
+            Message::CompareSecondSelected(name) => {
+                self.state.compare_sessions.1 = Some(name);
+            }
+            Message::CompareSessions => {
+                if let (Some(first), Some(second)) = self.state.compare_sessions.clone() {
+                    let load = |name : &str| Session::load(&crate::sessions_dir().ok_or(crate::Error::NoConfigDir)?, name);
+                    match (load(&first), load(&second)) {
+                        (Ok(a), Ok(b)) => self.state.comparison = Some((first, second, session::compare(&a, &b))),
+                        (Err(err), _) | (_, Err(err)) => self.push_toast(Severity::Error, tr!("session-load-failed", error = err.to_string()))
+                    }
+                }
+            }
+            Message::CloseComparison => {
+                self.state.comparison = None;
+            }
+            Message::GroupReadsToggled(group_reads) => {
+                self.state.group_reads = group_reads;
+                self.rebuild_log();
+            }
+            Message::DismissToast(index) => {
+                if index < self.state.toasts.len() {
+                    self.state.toasts.remove(index);
+                }
+            }
+            Message::Tick => {
+                self.state.event_rate = self.state.events_received - self.state.events_at_last_tick;
+                self.state.events_at_last_tick = self.state.events_received;
+                let downsample = match self.state.downsampled {
+                    false => self.state.event_rate > DOWNSAMPLE_RATE,
+                    true => self.state.event_rate > DOWNSAMPLE_RATE / 2
+                };
+                if downsample != self.state.downsampled {
+                    self.state.downsampled = downsample;
+                    self.rebuild_log();
+                } else if self.state.pending_summary.first().is_some_and(|first| self.state.event_log[*first].time.timestamp() < chrono::Utc::now().timestamp()) {
+                    self.flush_summary();
+                }
+                if let Some(tray) = &self.state.tray {
+                    tray.update(self.state.events_received, self.state.event_rate);
+                }
+                if let Status::Mounted(session) = &self.state.status {
+                    let lost = session.dropped();
+                    if lost > self.state.events_lost {
+                        let count = lost - self.state.events_lost;
+                        self.state.events_lost = lost;
+                        self.push_toast(Severity::Warning, tr!("events-lost", count = count));
+                    }
+                }
+            }
+            Message::TrayEnabledToggled(enabled) => {
+                self.state.tray_enabled = enabled;
+            }
+            Message::MinimizeToTray => {
+                return iced::window::get_oldest().and_then(|id| iced::window::change_mode(id, iced::window::Mode::Hidden));
+            }
+            Message::TrayAction(action) => {
+                let show_window = iced::window::get_oldest().and_then(|id| {
+                    iced::window::change_mode(id, iced::window::Mode::Windowed).chain(iced::window::gain_focus(id))
+                });
+                match action {
+                    TrayAction::Show => {}
+                    TrayAction::Export => {
+                        self.export_log();
+                    }
+                    TrayAction::Unmount => {
+                        // a confirmation might be needed, which has to be visible
+                        return Command::batch([self.update(Message::UnmountPressed), show_window]);
+                    }
+                }
+                return show_window;
+            }
+        }
+        Command::none()
+    }
+
+    fn mounted(&mut self, session : MountSession) {
+        self.state.status = Status::Mounted(session);
+        self.state.mounted_at = chrono::Utc::now();
+        self.update_log_format();
+        self.state.events_received = 0;
+        self.state.events_at_last_tick = 0;
+        self.state.event_rate = 0;
+        self.state.events_lost = 0;
+        self.state.downsampled = false;
+        if self.state.tray_enabled {
+            let tray = MonitorTray::new(format!("{} → {}", self.state.source, self.state.mountpoint), self.tray_sender.clone());
+            match TrayIcon::spawn(tray) {
+                Ok(tray) => self.state.tray = Some(tray),
+                Err(err) => self.push_toast(Severity::Warning, tr!("tray-failed", error = err.to_string()))
+            }
+        }
+    }
+
+    fn unmount(&mut self) {
+        let mut status = Status::Unmounting;
+        std::mem::swap(&mut self.state.status, &mut status);
+        match status {
+            Status::Mounted(process) => {
+                process.join();
+            }
+            _ => {
+                self.push_toast(Severity::Warning, tr!("unmount-without-mount"));
+            }
+        }
+        self.state.tray = None;
+        self.state.status = Status::Unmounted;
+    }
+
+    /// Empties the log, the view still needs to be rebuilt.
+    fn clear_log(&mut self) {
+        self.state.event_log.clear();
+        self.state.event_lines.clear();
+        // dropping the spill file deletes it
+        self.state.spill = None;
+        self.state.spilled_page = None;
+        self.state.events_dropped = 0;
+        self.state.expanded_groups.clear();
+        self.state.search_matches.clear();
+        self.state.search_current = None;
+        self.state.watch_hits.fill(WatchHits::default());
+        self.state.files.clear();
+        self.state.file_filter = None;
+        self.state.timeline_from = 0.0;
+        self.state.timeline_to = None;
+        self.state.hex_view = None;
+        // paths of loaded sessions that are no longer shown
+        crate::schema::forget_unused();
+    }
+
+    /// Replaces the log with the events of a saved session. Its mount time is taken over, so the timestamps and the
+    /// timeline read like when it was recorded. Watch rules aren't run for them, they already happened.
+    fn show_session(&mut self, session : Session) {
+        self.clear_log();
+        self.state.mounted_at = session.mounted_at;
+        self.update_log_format();
+        for event in session.events {
+            self.count_event(&event);
+            let line = self.format_event(&event);
+            if self.matches_search(&line) {
+                self.state.search_matches.push(self.state.event_log.len());
+            }
+            self.state.event_log.push(event);
+            self.state.event_lines.push(line);
+        }
+        // saved already, so leaving doesn't ask to export it
+        self.state.log_exported = true;
+        self.trim_log();
+        self.rebuild_log();
+    }
+
+    fn has_unexported_log(&self) -> bool {
+        !self.state.log_exported && (!self.state.event_log.is_empty() || self.state.events_dropped > 0)
+    }
+
+    /// Drops the oldest events once the log holds more than `max_log_entries`,
+    /// they are appended to the spill file for browsing, the export and saving the session.
+    fn trim_log(&mut self) {
+        let max = self.state.max_log_entries;
+        if self.state.event_log.len() <= max {
+            return;
+        }
+        // evicting a tenth at once, so the log view isn't rebuilt for every new event
+        let count = (self.state.event_log.len() - max + max / 10).min(self.state.event_log.len());
+        let spill = match self.state.spill.take() {
+            Some(spill) => Ok(spill),
+            None => SpillFile::create()
+        };
+        let written = spill.and_then(|mut spill| {
+            let appended = spill.append(&self.state.event_log[..count]);
+            self.state.spill = Some(spill);
+            appended
+        });
+        if let Err(err) = written {
+            self.push_toast(Severity::Warning, tr!("spill-failed", error = err.to_string()));
+        }
+        self.state.event_log.drain(..count);
+        self.state.event_lines.drain(..count);
+        self.state.events_dropped += count as u64;
+        self.state.expanded_groups = self.state.expanded_groups.iter().filter(|first| **first >= count).map(|first| first - count).collect();
+        let dropped_matches = self.state.search_matches.iter().take_while(|index| **index < count).count();
+        self.state.search_matches = self.state.search_matches[dropped_matches..].iter().map(|index| index - count).collect();
+        self.state.search_current = self.state.search_current.and_then(|current| current.checked_sub(dropped_matches));
+        self.rebuild_log();
+    }
+
+    /// Writes all events in the current columns, the spilled ones page by page so they don't all have to be loaded.
+    /// The types are written in the names of the schema, so the export reads the same in every language.
+    fn write_log(&mut self, path : &str) -> std::io::Result<()> {
+        let mut file = std::io::BufWriter::new(std::fs::File::create(path)?);
+        let format = LogFormat { stable_names: true, ..self.log_format.read().unwrap().clone() };
+        if let Some(spill) = &mut self.state.spill {
+            for page in 0..spill.page_count() {
+                for event in spill.page(page)? {
+                    std::io::Write::write_all(&mut file, format.format_event(&event).as_bytes())?;
+                    std::io::Write::write_all(&mut file, b"\n")?;
+                }
+            }
+        }
+        for event in &self.state.event_log {
+            std::io::Write::write_all(&mut file, format.format_event(event).as_bytes())?;
+            std::io::Write::write_all(&mut file, b"\n")?;
+        }
+        std::io::Write::flush(&mut file)
+    }
+
+    /// The events logged so far as a session, including the spilled ones.
+    fn current_session(&mut self, name : String) -> crate::Result<Session> {
+        let mut events = self.spilled_events()?;
+        events.extend(self.state.event_log.iter().cloned());
+        Ok(Session::new(name, self.state.source.clone(), self.state.mountpoint.clone(), self.state.mounted_at, events))
+    }
+
+    /// The events moved to the spill file, oldest first.
+    fn spilled_events(&mut self) -> crate::Result<Vec<Event>> {
+        match &mut self.state.spill {
+            Some(spill) => spill.events().map_err(|err| crate::Error::io("spilled events", err)),
+            None => Ok(Vec::new())
+        }
+    }
+
+    /// Writes the log to the export path, returns whether that succeeded.
+    fn export_log(&mut self) -> bool {
+        // everything is exported, not just what the current filter shows
+        let path = self.state.export_path.clone();
+        match self.write_log(&path) {
+            Ok(()) => {
+                self.state.log_exported = true;
+                let count = self.state.events_dropped + self.state.event_log.len() as u64;
+                self.push_toast(Severity::Info, tr!("exported", count = count, path = self.state.export_path.as_str()));
+                true
+            }
+            Err(err) => {
+                self.push_toast(Severity::Error, tr!("export-failed", path = self.state.export_path.as_str(), error = err.to_string()));
+                false
+            }
+        }
+    }
+
+    fn run_pending_action(&mut self) -> Command<Message> {
+        match self.state.pending_action.take() {
+            Some(PendingAction::Unmount) => {
+                self.unmount();
+                Command::none()
+            }
+            Some(PendingAction::Exit(id)) => self.close_window(id),
+            None => Command::none()
+        }
+    }
+
+    /// TTL in seconds, fractions are allowed.
+    fn parse_ttl(text : &str) -> Option<std::time::Duration> {
+        text.trim().parse::<f64>().ok().and_then(|seconds| std::time::Duration::try_from_secs_f64(seconds).ok())
+    }
+
+    /// Comma separated list of patterns.
+    fn parse_patterns(text : &str) -> Vec<String> {
+        text.split(',').map(|pattern| pattern.trim().to_string()).filter(|pattern| !pattern.is_empty()).collect()
+    }
+
+    /// Saves the settings and unmounts before closing, the process exits with the window and must not leave a dangling mount behind.
+    fn close_window(&mut self, id : iced::window::Id) -> Command<Message> {
+        self.shut_down();
+        iced::window::close(id)
+    }
+
+    /// Saves the settings and unmounts before exiting.
+    fn shut_down(&mut self) {
+        if let Err(err) = self.settings().save() {
+            eprintln!("Failed to save settings: {err}");
+        }
+        match &self.state.status {
+            Status::Mounted(_) => self.unmount(),
+            Status::Mounting(progress, receiver) => {
+                // the result is dropped right away, which unmounts if the mount already went through
+                progress.cancel();
+                drop(receiver.recv());
+                self.state.status = Status::Unmounted;
+            }
+            _ => {}
+        }
+        self.state.spill = None;
+    }
+
+    fn push_toast(&mut self, severity : Severity, text : String) {
+        self.state.toasts.push(Toast { severity, text });
+        if self.state.toasts.len() > MAX_TOASTS {
+            self.state.toasts.remove(0);
+        }
+    }
+
+    fn format_time(&self, time : &chrono::DateTime<chrono::Utc>) -> String {
+        self.log_format.read().unwrap().format_time(time)
+    }
+
+    fn format_event(&self, event : &Event) -> String {
+        self.log_format.read().unwrap().format_event(event)
+    }
+
+    /// Takes over changes of the columns, the timestamp mode, the mount time or the language, and reformats all lines if there are any.
+    /// The log view still needs to be rebuilt.
+    fn update_log_format(&mut self) {
+        let format = LogFormat {
+            columns: self.state.columns.clone(),
+            timestamp_mode: self.state.timestamp_mode,
+            mounted_at: self.state.mounted_at,
+            language: i18n::language(),
+            stable_names: false
+        };
+        if *self.log_format.read().unwrap() == format {
+            return;
+        }
+        self.state.event_lines = self.state.event_log.iter().map(|event| format.format_event(event)).collect();
+        *self.log_format.write().unwrap() = format;
+    }
+
+    /// The event in the line the cursor of the log is placed on.
+    fn selected_event(&self) -> Option<&Event> {
+        self.selected_index().map(|index| &self.state.event_log[index])
+    }
+
+    /// Index of the event in the line the cursor is placed on, the first event for a group.
+    fn selected_index(&self) -> Option<usize> {
+        let (line, _) = self.state.event_log_content.cursor_position();
+        match self.state.log_lines.get(line)? {
+            LogLine::Event(index) | LogLine::GroupMember(index) => Some(*index),
+            LogLine::Group(events) | LogLine::Summary(events) => events.first().copied()
+        }
+    }
+
+    /// Counts accesses to watched paths and notifies about them, at most every few seconds per rule.
+    fn check_watch_rules(&mut self, event : &Event, read : &ReadEvent) {
+        const NOTIFICATION_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+        watch::run_commands(&self.state.watch_rules, &mut self.state.watch_last_command, event, read);
+        for (i, rule) in self.state.watch_rules.iter().enumerate() {
+            if !rule.matches(&read.file.to_string_lossy()) {
+                continue;
+            }
+            let hits = &mut self.state.watch_hits[i];
+            hits.count += 1;
+            hits.last = Some(event.time);
+            if !rule.notify {
+                continue;
+            }
+            let recently_notified = self.state.watch_last_notified.get(&i).is_some_and(|time| time.elapsed() < NOTIFICATION_INTERVAL);
+            if !recently_notified {
+                watch::notify(&rule.pattern, &read.file.to_string_lossy(), &event.process.name, self.state.watch_sound);
+                self.state.watch_last_notified.insert(i, std::time::Instant::now());
+            }
+        }
+    }
+
+    fn receive_event(&mut self, event : Event, line : String) {
+        self.state.events_received += 1;
+        self.count_event(&event);
+        if let EventType::Read(read) = &event.event {
+            self.check_watch_rules(&event, read);
+        }
+        if let EventType::Derived { plugin, message } = &event.event {
+            let recently_notified = self.state.rule_last_notified.is_some_and(|time| time.elapsed() < std::time::Duration::from_secs(5));
+            if plugin == rules::NOTIFY_SOURCE && !recently_notified {
+                watch::show_notification(tr!("rule-notification-summary"), message.clone(), self.state.watch_sound);
+                self.state.rule_last_notified = Some(std::time::Instant::now());
+            }
+        }
+        self.log_event(event, line);
+    }
+
+    fn log_event(&mut self, event : Event, line : String) {
+        self.state.log_exported = false;
+        if self.matches_search(&line) {
+            self.state.search_matches.push(self.state.event_log.len());
+        }
+        let visible = self.is_visible(&event);
+        let selected_file = match &event.event {
+            EventType::Read(read) => self.state.file_filter.as_deref().is_some_and(|file| read.file.to_string_lossy() == file) && self.in_time_window(&event),
+            _ => false
+        };
+        if let (true, Some(stats), EventType::Read(read)) = (selected_file, &mut self.state.file_stats, &event.event) {
+            stats.add(read);
+        }
+        self.state.event_log.push(event);
+        self.state.event_lines.push(line);
+        if visible {
+            self.append_to_log(self.state.event_log.len() - 1);
+        }
+        self.trim_log();
+    }
+
+    /// Adds the process of the event to the filter and a read to the statistics of its file.
+    fn count_event(&mut self, event : &Event) {
+        if !self.state.processes.contains(event.process.name.as_str()) {
+            self.state.processes.insert(event.process.name.to_string());
+        }
+        if let EventType::Read(read) = &event.event {
+            let file = read.file.to_string_lossy();
+            let stats = match self.state.files.get_mut(file.as_ref()) {
+                Some(stats) => stats,
+                None => self.state.files.entry(file.into_owned()).or_default()
+            };
+            stats.reads += 1;
+            stats.bytes += read.size as u64;
+            stats.pattern.record(read.offset as u64, read.size as u64);
+        }
+    }
+
+    fn matches_search(&self, line : &str) -> bool {
+        !self.state.search_query.is_empty() && !highlight::search_matches(line, &self.state.search_query).is_empty()
+    }
+
+    /// Moves the log cursor to the current search match, collapsed groups containing it are expanded.
+    fn show_search_match(&mut self) {
+        let Some(index) = self.state.search_current.and_then(|current| self.state.search_matches.get(current).copied()) else {
+            return;
+        };
+        let collapsed_group = self.state.log_lines.iter().find_map(|line| match line {
+            LogLine::Group(events) if events.contains(&index) && !self.state.expanded_groups.contains(&events[0]) => Some(events[0]),
+            _ => None
+        });
+        if let Some(first) = collapsed_group {
+            self.state.expanded_groups.insert(first);
+            self.rebuild_log();
+        }
+        let line = self.state.log_lines.iter().position(|line| matches!(line, LogLine::Event(i) | LogLine::GroupMember(i) if *i == index));
+        self.state.search_match_hidden = line.is_none();
+        if let Some(line) = line {
+            // the editor can only move line by line
+            self.state.event_log_content.perform(text_editor::Action::Move(text_editor::Motion::DocumentStart));
+            for _ in 0..line {
+                self.state.event_log_content.perform(text_editor::Action::Move(text_editor::Motion::Down));
+            }
+            self.state.event_log_content.perform(text_editor::Action::Select(text_editor::Motion::End));
+        }
+        self.state.details = self.event_details(index);
+    }
+
+    /// The path of an event as shown, names that aren't UTF-8 lossily.
+    fn event_file(event : &Event) -> Option<Cow<'_, str>> {
+        match &event.event {
+            EventType::Read(read) => Some(read.file.to_string_lossy()),
+            EventType::Open(event) | EventType::Close(event) => Some(event.file.to_string_lossy()),
+            EventType::Metadata(event) => Some(event.file.to_string_lossy()),
+            EventType::Write(event) => Some(event.file.to_string_lossy()),
+            EventType::Change(event) => Some(event.file.to_string_lossy()),
+            EventType::Link(event) => Some(event.file.to_string_lossy()),
+            EventType::Xattr(event) => Some(event.file.to_string_lossy()),
+            _ => None
+        }
+    }
+
+    /// The file of a read, only reads are grouped and summarized.
+    fn read_file(event : &Event) -> Option<&OsStr> {
+        match &event.event {
+            EventType::Read(read) => Some(read.file.as_os_str()),
+            _ => None
+        }
+    }
+
+    /// Whether two events can be grouped, markers, opens and closes are never grouped.
+    fn same_file(a : &Event, b : &Event) -> bool {
+        matches!((Self::read_file(a), Self::read_file(b)), (Some(a), Some(b)) if a == b)
+    }
+
+    fn format_line(&self, line : &LogLine) -> String {
+        match line {
+            LogLine::Event(index) => self.state.event_lines[*index].clone(),
+            LogLine::GroupMember(index) => format!("    {}", self.state.event_lines[*index]),
+            LogLine::Group(events) => {
+                let first = &self.state.event_log[events[0]];
+                let bytes = self.bytes_read(events);
+                let marker = if self.state.expanded_groups.contains(&events[0]) { "▾" } else { "▸" };
+                format!("{marker} [{}] [{}] {}", self.format_time(&first.time), first.process, tr!("read-group", count = events.len(), size = analysis::format_size(bytes), file = Self::event_file(first).unwrap_or_default().to_string()))
+            }
+            LogLine::Summary(events) => {
+                let first = &self.state.event_log[events[0]];
+                let bytes = self.bytes_read(events);
+                format!("≡ [{}] {}", self.format_time(&first.time), tr!("read-summary", count = events.len(), size = analysis::format_size(bytes), file = Self::event_file(first).unwrap_or_default().to_string()))
+            }
+        }
+    }
+
+    fn bytes_read(&self, events : &[usize]) -> u64 {
+        events.iter().map(|i| match &self.state.event_log[*i].event {
+            EventType::Read(read) => read.size as u64,
+            _ => 0
+        }).sum()
+    }
+
+    /// One summary line per file and second, in the order the files were first read in that second. Other events keep their own line.
+    fn summary_lines(&self, visible : &[usize]) -> Vec<LogLine> {
+        let mut lines = Vec::new();
+        let mut by_file : std::collections::HashMap<&OsStr, usize> = std::collections::HashMap::new(); // line of each file in the current second
+        let mut second = None;
+        for &index in visible {
+            let event = &self.state.event_log[index];
+            if second != Some(event.time.timestamp()) {
+                second = Some(event.time.timestamp());
+                by_file.clear();
+            }
+            match Self::read_file(event) {
+                Some(file) => match by_file.get(file) {
+                    Some(&line) => if let LogLine::Summary(events) = &mut lines[line] {
+                        events.push(index);
+                    }
+                    None => {
+                        by_file.insert(file, lines.len());
+                        lines.push(LogLine::Summary(vec![index]));
+                    }
+                }
+                None => lines.push(LogLine::Event(index))
+            }
+        }
+        lines
+    }
+
+    /// Appends the summaries of the pending second to the log view.
+    fn flush_summary(&mut self) {
+        let pending = std::mem::take(&mut self.state.pending_summary);
+        let lines = self.summary_lines(&pending);
+        if lines.is_empty() {
+            return;
+        }
+        let text = lines.iter().map(|line| self.format_line(line)).collect::<Vec<_>>().join("\n");
+        self.state.log_lines.extend(lines);
+        self.edit_log_end(false, text);
+    }
+
+    /// Groups runs of visible events on the same file, expanded groups are followed by their events.
+    fn group_lines(&self, visible : Vec<usize>) -> Vec<LogLine> {
+        let mut runs : Vec<Vec<usize>> = Vec::new();
+        for index in visible {
+            match runs.last_mut() {
+                Some(run) if self.state.group_reads && Self::same_file(&self.state.event_log[run[0]], &self.state.event_log[index]) => run.push(index),
+                _ => runs.push(vec![index])
+            }
+        }
+        let mut lines = Vec::new();
+        for run in runs {
+            if run.len() == 1 {
+                lines.push(LogLine::Event(run[0]));
+            } else if self.state.expanded_groups.contains(&run[0]) {
+                lines.push(LogLine::Group(run.clone()));
+                lines.extend(run.into_iter().map(LogLine::GroupMember));
+            } else {
+                lines.push(LogLine::Group(run));
+            }
+        }
+        lines
+    }
+
+    /// Adds a newly received event to the log view, merging it into the last line if it continues a group.
+    fn append_to_log(&mut self, index : usize) {
+        if self.state.downsampled {
+            let second = self.state.event_log[index].time.timestamp();
+            if self.state.pending_summary.first().is_some_and(|first| self.state.event_log[*first].time.timestamp() != second) {
+                self.flush_summary();
+            }
+            self.state.pending_summary.push(index);
+            return;
+        }
+        let continues_group = self.state.group_reads && match self.state.log_lines.last() {
+            Some(LogLine::Event(last)) => Some(*last),
+            Some(LogLine::Group(events)) => events.first().copied(),
+            Some(LogLine::GroupMember(last)) => Some(*last),
+            Some(LogLine::Summary(_)) | None => None
+        }.is_some_and(|last| Self::same_file(&self.state.event_log[last], &self.state.event_log[index]));
+        if !continues_group {
+            let text = self.format_line(&LogLine::Event(index));
+            self.state.log_lines.push(LogLine::Event(index));
+            self.edit_log_end(false, text);
+        } else if let Some(LogLine::Event(last)) = self.state.log_lines.last() {
+            let group = LogLine::Group(vec![*last, index]);
+            self.replace_last_line(group);
+        } else if let Some(LogLine::Group(events)) = self.state.log_lines.last() {
+            let mut events = events.clone();
+            events.push(index);
+            self.replace_last_line(LogLine::Group(events));
+        } else {
+            // the group is expanded, its summary line is further up
+            self.rebuild_log();
+        }
+    }
+
+    fn replace_last_line(&mut self, line : LogLine) {
+        let text = self.format_line(&line);
+        self.edit_log_end(true, text);
+        *self.state.log_lines.last_mut().unwrap() = line;
+    }
+
+    /// Appends a line to the log view or replaces its last line, without rebuilding the whole content.
+    /// The cursor goes back to where it was, the selection is lost.
+    fn edit_log_end(&mut self, replace_last_line : bool, line : String) {
+        let content = &mut self.state.event_log_content;
+        let (cursor_line, cursor_column) = content.cursor_position();
+        // the text ends with a newline, so the end is the start of an empty line
+        content.perform(text_editor::Action::Move(text_editor::Motion::DocumentEnd));
+        if replace_last_line {
+            content.perform(text_editor::Action::Select(text_editor::Motion::Up));
+        }
+        content.perform(text_editor::Action::Edit(text_editor::Edit::Paste(Arc::new(format!("{line}\n")))));
+        // the editor can only move line by line, so the cursor is moved from whichever end is closer
+        let last_line = content.line_count() - 1;
+        if cursor_line < last_line - cursor_line {
+            content.perform(text_editor::Action::Move(text_editor::Motion::DocumentStart));
+            for _ in 0..cursor_line {
+                content.perform(text_editor::Action::Move(text_editor::Motion::Down));
+            }
+        } else {
+            for _ in cursor_line..last_line {
+                content.perform(text_editor::Action::Move(text_editor::Motion::Up));
+            }
+            content.perform(text_editor::Action::Move(text_editor::Motion::Home));
+        }
+        for _ in 0..cursor_column {
+            content.perform(text_editor::Action::Move(text_editor::Motion::Right));
+        }
+    }
+
+    fn is_visible(&self, event : &Event) -> bool {
+        if let EventType::Marker(_) = event.event {
+            // markers annotate whatever is shown, so only the time window applies to them
+            return self.in_time_window(event);
+        }
+        let process_matches = match &self.state.process_filter {
+            Some(name) => event.process.name.as_str() == name,
+            None => true
+        };
+        let file_matches = match &self.state.file_filter {
+            Some(file) => Self::event_file(event).as_deref() == Some(file.as_str()),
+            None => true
+        };
+        let path_matches = Self::event_file(event).is_none_or(|file| self.state.path_filter.matches(&file));
+        process_matches && file_matches && path_matches && self.in_time_window(event)
+    }
+
+    fn in_time_window(&self, event : &Event) -> bool {
+        let time = Self::seconds_since(self.state.mounted_at, event.time);
+        time >= self.state.timeline_from && self.state.timeline_to.is_none_or(|to| time <= to)
+    }
+
+    fn seconds_since(start : chrono::DateTime<chrono::Utc>, time : chrono::DateTime<chrono::Utc>) -> f64 {
+        (time - start).num_milliseconds() as f64 / 1000.0
+    }
+
+    /// Seconds from mounting to the newest event.
+    fn session_length(&self) -> f64 {
+        match self.state.event_log.last() {
+            Some(event) => Self::seconds_since(self.state.mounted_at, event.time).max(0.0),
+            None => 0.0
+        }
+    }
+
+    /// Regenerates the log view from all events, needed whenever the filter or formatting changes.
+    fn rebuild_log(&mut self) {
+        let visible : Vec<usize> = (0..self.state.event_log.len()).filter(|i| self.is_visible(&self.state.event_log[*i])).collect();
+        self.state.pending_summary.clear();
+        self.state.log_lines = if self.state.downsampled {
+            // the newest second may still get events, it is summarized once it is over
+            let newest = visible.last().map(|i| self.state.event_log[*i].time.timestamp());
+            let split = visible.iter().rposition(|i| Some(self.state.event_log[*i].time.timestamp()) != newest).map_or(0, |last| last + 1);
+            self.state.pending_summary = visible[split..].to_vec();
+            self.summary_lines(&visible[..split])
+        } else {
+            self.group_lines(visible)
+        };
+        let text : String = self.state.log_lines.iter().map(|line| format!("{}\n", self.format_line(line))).collect();
+        self.state.event_log_content = iced::widget::text_editor::Content::with_text(&text);
+        self.state.details.clear();
+        self.state.file_stats = self.selected_file_stats();
+    }
+
+    /// Totals of the reads of the selected file in the time window, from the whole log.
+    fn selected_file_stats(&self) -> Option<SelectedFileStats> {
+        let file = self.state.file_filter.as_deref()?;
+        let mut stats = SelectedFileStats::default();
+        for event in self.state.event_log.iter().filter(|event| self.in_time_window(event)) {
+            match &event.event {
+                EventType::Read(read) if read.file.to_string_lossy() == file => stats.add(read),
+                _ => {}
+            }
+        }
+        Some(stats)
+    }
+
+    /// All structured fields of an event, coverage is computed from the events logged up to it.
+    fn event_details(&self, index : usize) -> Vec<(String, String)> {
+        let Some(event) = self.state.event_log.get(index) else {
+            return Vec::new();
+        };
+        let mut details = vec![
+            (tr!("detail-time"), self.format_time(&event.time)),
+            (tr!("detail-process"), format!("{}", event.process.name)),
+            (tr!("detail-pid"), format!("{}", event.process.pid)),
+        ];
+        if let (Some(uid), Some(gid)) = (event.process.uid, event.process.gid) {
+            details.push((tr!("detail-user"), format!("{uid} / {gid}")));
+        }
+        match &event.event {
+            EventType::Read(read) => {
+                let covered = analysis::file_coverage(self.state.event_log[0..=index].iter(), &read.file);
+                let percentage = if read.file_size == 0 { 100.0 } else { covered as f64 * 100.0 / read.file_size as f64 };
+                details.push((tr!("detail-operation"), tr!("operation-read")));
+                details.push((tr!("detail-file"), format!("{}", read.file.display())));
+                details.push((tr!("detail-full-path"), format!("{}", std::path::Path::new(&self.state.source).join(read.file.as_os_str()).display())));
+                details.push((tr!("detail-offset"), format!("{}", read.offset)));
+                details.push((tr!("detail-size"), tr!("bytes", count = read.size)));
+                details.push((tr!("detail-file-size"), tr!("bytes", count = read.file_size)));
+                details.push((tr!("detail-handle"), format!("{}", read.handle)));
+                details.push((tr!("detail-latency"), format!("{} µs", read.latency.as_micros())));
+                details.push((tr!("detail-coverage"), tr!("coverage", covered = covered, total = read.file_size, percentage = format!("{percentage:.1}"))));
+                details.push((tr!("detail-payload"), match &read.data {
+                    Some(data) => tr!("payload-captured", count = data.len()),
+                    None => tr!("payload-not-captured")
+                }));
+            }
+            EventType::Open(open) => details.extend(self.handle_details(tr!("operation-open"), open)),
+            EventType::Close(close) => details.extend(self.handle_details(tr!("operation-close"), close)),
+            EventType::Metadata(metadata) => {
+                details.push((tr!("detail-operation"), metadata_operation(metadata.operation)));
+                details.push((tr!("detail-file"), match metadata.found {
+                    true => format!("{}", metadata.file.display()),
+                    false => format!("{} {}", metadata.file.display(), tr!("not-found"))
+                }));
+                details.push((tr!("detail-full-path"), format!("{}", std::path::Path::new(&self.state.source).join(metadata.file.as_os_str()).display())));
+            }
+            EventType::Write(write) => {
+                details.push((tr!("detail-operation"), tr!("operation-write")));
+                details.push((tr!("detail-file"), format!("{}", write.file.display())));
+                details.push((tr!("detail-full-path"), format!("{}", std::path::Path::new(&self.state.source).join(write.file.as_os_str()).display())));
+                details.push((tr!("detail-offset"), format!("{}", write.offset)));
+                details.push((tr!("detail-size"), tr!("bytes", count = write.size)));
+                details.push((tr!("detail-handle"), format!("{}", write.handle)));
+            }
+            EventType::Change(change) => {
+                details.push((tr!("detail-operation"), change_operation(&change.operation)));
+                details.push((tr!("detail-file"), format!("{}", change.file.display())));
+                details.push((tr!("detail-full-path"), format!("{}", std::path::Path::new(&self.state.source).join(change.file.as_os_str()).display())));
+                match &change.operation {
+                    ChangeOperation::Truncate { size } => details.push((tr!("detail-size"), tr!("bytes", count = *size))),
+                    ChangeOperation::Rename { to } => details.push((tr!("detail-renamed-to"), to.to_string_lossy().into_owned())),
+                    ChangeOperation::Symlink { target } => details.push((tr!("detail-target"), target.to_string_lossy().into_owned())),
+                    ChangeOperation::Permissions { mode } => details.push((tr!("detail-mode"), format!("{mode:04o}"))),
+                    ChangeOperation::Owner { uid, gid } => {
+                        if let Some(uid) = uid {
+                            details.push((tr!("detail-uid"), format!("{uid}")));
+                        }
+                        if let Some(gid) = gid {
+                            details.push((tr!("detail-gid"), format!("{gid}")));
+                        }
+                    }
+                    ChangeOperation::Times { accessed, modified } => {
+                        if let Some(accessed) = accessed {
+                            details.push((tr!("detail-accessed"), self.format_time(accessed)));
+                        }
+                        if let Some(modified) = modified {
+                            details.push((tr!("detail-modified"), self.format_time(modified)));
+                        }
+                    }
+                    _ => ()
+                }
+            }
+            EventType::Link(link) => {
+                details.push((tr!("detail-operation"), tr!("operation-readlink")));
+                details.push((tr!("detail-file"), format!("{}", link.file.display())));
+                details.push((tr!("detail-full-path"), format!("{}", std::path::Path::new(&self.state.source).join(link.file.as_os_str()).display())));
+                details.push((tr!("detail-target"), link.target.to_string_lossy().into_owned()));
+            }
+            EventType::Xattr(xattr) => {
+                details.push((tr!("detail-operation"), xattr_operation(xattr.operation)));
+                details.push((tr!("detail-file"), format!("{}", xattr.file.display())));
+                details.push((tr!("detail-full-path"), format!("{}", std::path::Path::new(&self.state.source).join(xattr.file.as_os_str()).display())));
+                if let Some(name) = &xattr.name {
+                    details.push((tr!("detail-attribute"), name.clone()));
+                }
+                details.push((tr!("detail-size"), match xattr.size {
+                    Some(size) => tr!("bytes", count = size),
+                    None => tr!("not-found")
+                }));
+            }
+            EventType::Marker(label) => {
+                details.push((tr!("detail-operation"), tr!("operation-marker")));
+                details.push((tr!("detail-label"), label.clone()));
+            }
+            EventType::Derived { plugin, message } => {
+                details.push((tr!("detail-operation"), tr!("operation-derived", plugin = plugin.clone())));
+                details.push((tr!("detail-label"), message.clone()));
+            }
+            _ => details.push((tr!("detail-label"), event.event.to_string())) // added by a newer version of the schema
+        }
+        details
+    }
+
+    fn handle_details(&self, operation : String, event : &HandleEvent) -> Vec<(String, String)> {
+        vec![
+            (tr!("detail-operation"), operation),
+            (tr!("detail-file"), format!("{}", event.file.display())),
+            (tr!("detail-full-path"), format!("{}", std::path::Path::new(&self.state.source).join(event.file.as_os_str()).display())),
+            (tr!("detail-handle"), format!("{}", event.handle)),
+        ]
+    }
+
+    fn directory_selector<'a>(placeholder: &str, text : &str, on_input: impl Fn(String) -> Message + 'a) -> TextInput<'a, Message> {
+        text_input(placeholder, text)
+            //.style(|theme, status| iced::widget::text_input::Style::(theme, status))
+            .on_input(on_input)
+            .width(400)
+    }
+
+    fn view_hex(hex_view : &HexView) -> Container<Message> {
+        container(
+            column![
+                button(text(tr!("close"))).on_press(Message::CloseHexView),
+                text(&hex_view.title),
+                scrollable(text(&hex_view.dump).font(Font::MONOSPACE)).width(Fill).height(Fill),
+            ].spacing(10)
+        )
+            .width(iced::Fill)
+            .height(iced::Fill)
+            .padding(10)
+    }
+
+    fn view_spilled_page<'a>(&self, page : usize, lines : &'a str) -> Container<'a, Message> {
+        let pages = self.state.spill.as_ref().map_or(0, |spill| spill.page_count());
+        container(
+            column![
+                row![
+                    button(text(tr!("close"))).on_press(Message::CloseSpilledPage),
+                    button(text(tr!("search-previous"))).on_press_maybe(page.checked_sub(1).map(Message::ShowSpilledPage)),
+                    text(tr!("spilled-page", page = page + 1, pages = pages)),
+                    button(text(tr!("search-next"))).on_press_maybe((page + 1 < pages).then_some(Message::ShowSpilledPage(page + 1))),
+                ].spacing(10).align_y(Center),
+                scrollable(text(lines).font(Font::MONOSPACE)).width(Fill).height(Fill),
+            ].spacing(10)
+        )
+            .width(iced::Fill)
+            .height(iced::Fill)
+            .padding(10)
+    }
+
+    fn view_columns(&self) -> Column<Message> {
+        let mut columns = column![text(tr!("columns-help"))].spacing(5);
+        for (i, (column, enabled)) in self.state.columns.iter().enumerate() {
+            let column = *column;
+            columns = columns.push(row![
+                checkbox(column.to_string(), *enabled).on_toggle(move |enabled| Message::ColumnToggled(column, enabled)).width(200),
+                button(text("↑")).on_press_maybe((i > 0).then_some(Message::MoveColumn(i, true))),
+                button(text("↓")).on_press_maybe((i + 1 < self.state.columns.len()).then_some(Message::MoveColumn(i, false))),
+            ].spacing(10).align_y(Center));
+        }
+        columns
+    }
+
+    fn view_watch_rules(&self) -> Column<Message> {
+        let mut rules = column![text(tr!("watch-rules-help"))].spacing(5);
+        for (i, rule) in self.state.watch_rules.iter().enumerate() {
+            rules = rules.push(row![
+                text_input(&tr!("watch-pattern"), &rule.pattern)
+                    .on_input(move |pattern| Message::UpdateWatchPattern(i, pattern))
+                    .width(300),
+                checkbox(tr!("watch-notify"), rule.notify).on_toggle(move |notify| Message::WatchNotifyToggled(i, notify)),
+                text_input(&tr!("watch-command"), &rule.command)
+                    .on_input(move |command| Message::UpdateWatchCommand(i, command))
+                    .width(250),
+                text(match &self.state.watch_hits[i].last {
+                    Some(last) => tr!("watch-hits", count = self.state.watch_hits[i].count, last = self.format_time(last)),
+                    None => tr!("watch-never")
+                }).width(350),
+                button(text(tr!("remove"))).on_press(Message::RemoveWatchRule(i)),
+            ].spacing(10).align_y(Center));
+        }
+        rules.push(row![
+            button(text(tr!("add-watch"))).on_press(Message::AddWatchRule),
+            button(text(tr!("watch-selected-file"))).on_press(Message::WatchSelectedFile),
+            checkbox(tr!("watch-sound"), self.state.watch_sound).on_toggle(Message::WatchSoundToggled),
+        ].spacing(10).align_y(Center))
+    }
+
+    fn view_highlight_rules(&self) -> Column<Message> {
+        let mut rules = column![text(tr!("highlight-rules-help"))].spacing(5);
+        for (i, rule) in self.state.highlight_rules.iter().enumerate() {
+            rules = rules.push(row![
+                text_input(&tr!("highlight-pattern"), &rule.pattern)
+                    .on_input(move |pattern| Message::UpdateHighlightPattern(i, pattern))
+                    .width(300),
+                pick_list(HighlightColor::ALL, Some(rule.color), move |color| Message::UpdateHighlightColor(i, color)),
+                button(text(tr!("remove"))).on_press(Message::RemoveHighlightRule(i)),
+            ].spacing(10).align_y(Center));
+        }
+        rules.push(button(text(tr!("add-rule"))).on_press(Message::AddHighlightRule))
+    }
+
+    fn view_details(&self) -> Column<Message> {
+        let mut details = column![text(tr!("event-details"))].spacing(5).width(350);
+        for (label, value) in &self.state.details {
+            details = details.push(row![
+                text(label).size(12).width(110),
+                text(value).size(12),
+            ].spacing(5));
+        }
+        details
+    }
+
+    fn view_file_list(&self) -> Column<Message> {
+        let file_button = |label : String, file : Option<String>| {
+            let style = if self.state.file_filter == file { button::primary } else { button::text };
+            button(text(label).size(12)).style(style).width(Fill).on_press(Message::FileSelected(file))
+        };
+        let mut files = column![file_button(tr!("all-files"), None)].spacing(2).width(300);
+        for (file, stats) in &self.state.files {
+            let label = tr!("file-entry", file = file.to_string(), reads = stats.reads, pattern = Self::pattern_label(stats.pattern.kind()));
+            files = files.push(file_button(label, Some(file.to_string())));
+        }
+        files
+    }
+
+    /// Reads, bytes and coverage of the file selected in the file list, within the selected time window.
+    fn view_file_stats(&self, file : &str) -> Text {
+        let none = SelectedFileStats::default();
+        let selected = self.state.file_stats.as_ref().unwrap_or(&none);
+        let (stats, file_size, covered) = (&selected.stats, selected.file_size, selected.covered);
+        let percentage = if file_size == 0 { 100.0 } else { covered as f64 * 100.0 / file_size as f64 };
+        text(tr!("file-stats",
+            file = file.to_string(),
+            reads = stats.reads,
+            bytes = analysis::format_size(stats.bytes),
+            covered = analysis::format_size(covered),
+            total = analysis::format_size(file_size),
+            percentage = format!("{percentage:.1}"),
+            pattern = Self::pattern_label(stats.pattern.kind()),
+            sequential = format!("{:.0}", stats.pattern.sequential_share() * 100.0),
+            seek = analysis::format_size(stats.pattern.mean_seek())
+        ))
+    }
+
+    fn pattern_label(kind : AccessKind) -> String {
+        match kind {
+            AccessKind::Sequential => tr!("pattern-sequential"),
+            AccessKind::Strided => tr!("pattern-strided"),
+            AccessKind::Random => tr!("pattern-random")
+        }
+    }
+
+    /// Two handles over the whole session, the log only shows events between them.
+    fn view_timeline(&self) -> Row<Message> {
+        let length = self.session_length();
+        let to = self.state.timeline_to.unwrap_or(length);
+        row![
+            text(tr!("timeline")),
+            slider(0.0..=length, self.state.timeline_from, Message::TimelineFromChanged).step(0.1),
+            slider(0.0..=length, to, Message::TimelineToChanged).step(0.1),
+            text(format!("t+{:.1}s – t+{:.1}s", self.state.timeline_from, to)),
+            button(text(tr!("timeline-reset"))).on_press(Message::ResetTimeline),
+        ].spacing(10).align_y(Center)
+    }
+
+    fn view_bookmarks(&self) -> Column<Message> {
+        let mut bookmarks = column![text(tr!("pinned-events"))].spacing(5).width(350);
+        for (i, event) in self.state.bookmarks.iter().enumerate() {
+            bookmarks = bookmarks.push(row![
+                button(text(tr!("unpin"))).on_press(Message::Unpin(i)),
+                text(self.format_event(event)).size(12),
+            ].spacing(5).align_y(Center));
+        }
+        bookmarks
+    }
+
+    fn view_confirm(&self, action : PendingAction) -> Container<Message> {
+        let save_label = match action {
+            PendingAction::Unmount => tr!("save-and-unmount"),
+            PendingAction::Exit(_) => tr!("save-and-exit")
+        };
+        let discard_label = match action {
+            PendingAction::Unmount => tr!("unmount-without-saving"),
+            PendingAction::Exit(_) => tr!("exit-without-saving")
+        };
+        container(
+            column![
+                text(tr!("unexported-log", count = self.state.event_log.len())),
+                row![
+                    text(tr!("save-to")),
+                    text_input(&tr!("export-path"), &self.state.export_path).on_input(Message::UpdateExportPath).width(400),
+                ].spacing(10).align_y(Center),
+                row![
+                    button(text(save_label)).on_press(Message::ConfirmSave),
+                    button(text(discard_label)).on_press(Message::ConfirmDiscard),
+                    button(text(tr!("cancel"))).on_press(Message::ConfirmCancel),
+                ].spacing(10),
+            ].spacing(20).align_x(Center)
+        )
+            .width(iced::Fill)
+            .height(iced::Fill)
+            .align_x(Center)
+            .align_y(Center)
+    }
+
+    fn process_filter_options(&self) -> Vec<ProcessFilter> {
+        let mut options = vec![ProcessFilter::All];
+        options.extend(self.state.processes.iter().map(|name| ProcessFilter::Process(name.clone())));
+        options
+    }
+
+    /// The preset with the entered name, if there is one.
+    fn selected_preset(&self) -> Option<FilterPreset> {
+        self.state.filter_presets.iter().find(|preset| preset.name == self.state.preset_name.trim()).cloned()
+    }
+
+    fn selected_process_filter(&self) -> ProcessFilter {
+        match &self.state.process_filter {
+            Some(name) => ProcessFilter::Process(name.clone()),
+            None => ProcessFilter::All
+        }
+    }
+
+    fn view_search(&self) -> Row<Message> {
+        let position = match self.state.search_current {
+            Some(current) => tr!("search-position", current = current + 1, count = self.state.search_matches.len()),
+            None => tr!("search-count", count = self.state.search_matches.len())
+        };
+        let mut search = row![
+            text_input(&tr!("search-placeholder"), &self.state.search_query)
+                .id(SEARCH_INPUT.clone())
+                .on_input(Message::UpdateSearch)
+                .on_submit(Message::SearchNext)
+                .width(400),
+            button(text(tr!("search-previous"))).on_press(Message::SearchPrevious),
+            button(text(tr!("search-next"))).on_press(Message::SearchNext),
+            text(position),
+        ].spacing(10).align_y(Center);
+        if self.state.search_match_hidden {
+            search = search.push(text(tr!("search-match-hidden")));
+        }
+        search.push(button(text(tr!("close"))).on_press(Message::CloseSearch))
+    }
+
+    /// Percentiles and a histogram of one measurement, `format` turns a value into text.
+    fn view_distribution<'a>(title : String, mut values : Vec<u64>, format : impl Fn(u64) -> String) -> Column<'a, Message> {
+        values.sort_unstable();
+        let percentiles = [50.0, 90.0, 99.0].map(|percentage| format!("p{percentage}: {}", format(analysis::percentile(&values, percentage))));
+        let mut distribution = column![
+            text(title).size(18),
+            text(format!("{}   {}   max: {}", percentiles.join("   "), tr!("statistics-samples", count = values.len()), format(values.last().copied().unwrap_or(0)))).size(12),
+        ].spacing(3);
+        let histogram = analysis::log2_histogram(&values);
+        let max_count = histogram.iter().map(|(_, _, count)| *count).max().unwrap_or(0);
+        for (from, to, count) in histogram {
+            distribution = distribution.push(row![
+                text(format!("{} – {}", format(from), format(to))).size(12).width(200),
+                progress_bar(0.0..=max_count as f32, count as f32).width(400).height(12),
+                text(format!("{count}")).size(12),
+            ].spacing(10).align_y(Center));
+        }
+        distribution
+    }
+
+    /// Distributions of the reads within the selected time window.
+    fn view_statistics(&self) -> Container<Message> {
+        let reads : Vec<_> = self.state.event_log.iter()
+            .filter(|event| self.in_time_window(event))
+            .filter_map(|event| match &event.event {
+                EventType::Read(read) => Some(read),
+                _ => None
+            })
+            .collect();
+        let sizes = reads.iter().map(|read| read.size as u64).collect();
+        let latencies = reads.iter().map(|read| read.latency.as_micros() as u64).collect();
+        container(
+            column![
+                button(text(tr!("close"))).on_press(Message::ToggleStatistics),
+                text(tr!("statistics-title")),
+                scrollable(column![
+                    Self::view_distribution(tr!("statistics-read-size"), sizes, analysis::format_size),
+                    Self::view_distribution(tr!("statistics-read-latency"), latencies, |micros| format!("{micros} µs")),
+                ].spacing(30)).width(Fill).height(Fill),
+            ].spacing(10)
+        )
+            .width(iced::Fill)
+            .height(iced::Fill)
+            .padding(10)
+    }
+
+    fn view_treemap(&self) -> Container<Message> {
+        let files = self.state.files.iter().map(|(file, stats)| (file.as_str(), stats.bytes));
+        let entries : Vec<_> = analysis::children_sizes(files, &self.state.treemap_dir).into_iter().filter(|entry| entry.bytes > 0).collect();
+        let total : u64 = entries.iter().map(|entry| entry.bytes).sum();
+        let dir = if self.state.treemap_dir.is_empty() { String::from("/") } else { format!("/{}", self.state.treemap_dir) };
+        container(
+            column![
+                row![
+                    button(text(tr!("close"))).on_press(Message::ToggleTreemap),
+                    button(text(tr!("treemap-up"))).on_press_maybe((!self.state.treemap_dir.is_empty()).then_some(Message::TreemapUp)),
+                    text(tr!("treemap-title", dir = dir, size = analysis::format_size(total))),
+                ].spacing(10).align_y(Center),
+                canvas(Treemap { entries }).width(Fill).height(Fill),
+            ].spacing(10)
+        )
+            .width(iced::Fill)
+            .height(iced::Fill)
+            .padding(10)
+    }
+
+    /// Accesses within the selected time window, aggregated along the path hierarchy.
+    fn view_flame_graph(&self) -> Container<Message> {
+        let files = self.state.event_log.iter()
+            .filter(|event| self.in_time_window(event))
+            .filter_map(Self::event_file)
+            .collect::<Vec<_>>();
+        let tree = PathNode::build(files.iter().map(Cow::as_ref));
+        let root = tree.find(&self.state.flame_graph_root).unwrap_or(&tree);
+        let label = if root.path.is_empty() { String::from("/") } else { root.name.clone() };
+        container(
+            column![
+                row![
+                    button(text(tr!("close"))).on_press(Message::ToggleFlameGraph),
+                    button(text(tr!("flame-graph-reset"))).on_press_maybe((!root.path.is_empty()).then(|| Message::FlameGraphZoom(String::new()))),
+                    text(tr!("flame-graph-title", count = root.count)),
+                ].spacing(10).align_y(Center),
+                canvas(FlameGraph::new(root, label)).width(Fill).height(Fill),
+            ].spacing(10)
+        )
+            .width(iced::Fill)
+            .height(iced::Fill)
+            .padding(10)
+    }
+
+    /// Files in the order they were first read within the selected time window, e.g. everything loaded up to the main menu.
+    fn view_access_order(&self) -> Container<Message> {
+        let accesses = analysis::access_order(self.state.event_log.iter().filter(|event| self.in_time_window(event)));
+        let cell = |value : String| text(value).size(12);
+        let mut table = column![
+            row![
+                cell(tr!("access-order-time")).width(250),
+                cell(tr!("detail-process")).width(150),
+                cell(tr!("detail-file")).width(Fill),
+            ].spacing(10)
+        ].spacing(2);
+        for access in &accesses {
+            table = table.push(row![
+                cell(self.format_time(&access.time)).width(250),
+                cell(access.process.to_string()).width(150),
+                cell(access.file.to_string_lossy().into_owned()).width(Fill),
+            ].spacing(10));
+        }
+        container(
+            column![
+                button(text(tr!("close"))).on_press(Message::ToggleAccessOrder),
+                text(tr!("access-order-title", count = accesses.len())),
+                scrollable(table).width(Fill).height(Fill),
+            ].spacing(10)
+        )
+            .width(iced::Fill)
+            .height(iced::Fill)
+            .padding(10)
+    }
+
+    /// Files read repeatedly at the same ranges within the selected time window, candidates for caching in the program.
+    fn view_redundant_reads(&self) -> Container<Message> {
+        let redundant = analysis::redundant_reads(self.state.event_log.iter().filter(|event| self.in_time_window(event)));
+        let cell = |value : String| text(value).size(12).width(110);
+        let mut table = column![
+            row![
+                text(tr!("detail-file")).size(12).width(Fill),
+                cell(tr!("redundant-bytes")),
+                cell(tr!("redundant-rereads")),
+                cell(tr!("redundant-ranges")),
+                cell(tr!("redundant-max-repeats")),
+                cell(tr!("redundant-per-second")),
+            ].spacing(10)
+        ].spacing(2);
+        for file in &redundant {
+            table = table.push(row![
+                text(file.file.to_string_lossy().into_owned()).size(12).width(Fill),
+                cell(analysis::format_size(file.bytes)),
+                cell(format!("{}", file.rereads)),
+                cell(format!("{}", file.ranges)),
+                cell(format!("{}", file.max_repeats)),
+                cell(format!("{:.2}", file.per_second)),
+            ].spacing(10));
+        }
+        let bytes = redundant.iter().map(|file| file.bytes).sum();
+        container(
+            column![
+                button(text(tr!("close"))).on_press(Message::ToggleRedundantReads),
+                text(tr!("redundant-title", count = redundant.len(), size = analysis::format_size(bytes))),
+                scrollable(table).width(Fill).height(Fill),
+            ].spacing(10)
+        )
+            .width(iced::Fill)
+            .height(iced::Fill)
+            .padding(10)
+    }
+
+    /// The events of the selected time window interleaved with the lines of an external log, moved by the offset.
+    fn view_external_log(&self, log : &ExternalLog) -> Container<Message> {
+        let offset = self.state.external_offset.trim().parse::<f64>().ok();
+        let markers : Vec<String> = self.state.event_log.iter().filter_map(|event| match &event.event {
+            EventType::Marker(label) => Some(label.clone()),
+            _ => None
+        }).collect();
+        let entries = external_log::interleave(
+            self.state.event_log.iter().filter(|event| self.in_time_window(event)),
+            log,
+            chrono::TimeDelta::milliseconds((offset.unwrap_or_default() * 1000.0) as i64)
+        );
+        let mut lines = column![].spacing(2);
+        for entry in entries.iter().take(MAX_INTERLEAVED_ROWS) {
+            lines = lines.push(match entry {
+                Entry::Event(event) => text(self.format_event(event)).size(12),
+                Entry::Line(time, line) => text(format!("{} > {line}", time.with_timezone(&chrono::Local).format("%H:%M:%S%.3f"))).size(12).style(text::primary)
+            });
+        }
+        container(
+            column![
+                button(text(tr!("close"))).on_press(Message::CloseExternalLog),
+                text(tr!("external-log-title", lines = log.lines.len(), shown = entries.len().min(MAX_INTERLEAVED_ROWS), total = entries.len())),
+                row![
+                    text(tr!("external-offset")),
+                    text_input("0", &self.state.external_offset).on_input(Message::UpdateExternalOffset).width(120),
+                    text(tr!("external-anchor")),
+                    pick_list(markers, self.state.external_anchor_marker.clone(), Message::ExternalAnchorMarkerSelected).placeholder(tr!("external-anchor-marker")),
+                    text_input(&tr!("external-anchor-text"), &self.state.external_anchor_text).on_input(Message::UpdateExternalAnchorText).width(250),
+                    button(text(tr!("external-align"))).on_press_maybe(
+                        (self.state.external_anchor_marker.is_some() && !self.state.external_anchor_text.is_empty()).then_some(Message::AlignExternalLog)
+                    ),
+                ].spacing(10).align_y(Center),
+                scrollable(lines).width(Fill).height(Fill),
+            ].spacing(10)
+        )
+            .width(iced::Fill)
+            .height(iced::Fill)
+            .padding(10)
+    }
+
+    /// The files with the most bytes in every phase after mounting or the first read.
+    fn view_phases(&self) -> Container<Message> {
+        let bounds = analysis::parse_phase_bounds(&self.state.phase_bounds);
+        let start = match self.state.phases_from_first_read {
+            true => analysis::first_read_time(self.state.event_log.iter()).unwrap_or(self.state.mounted_at),
+            false => self.state.mounted_at
+        };
+        let phases = analysis::phases(self.state.event_log.iter(), start, bounds.as_deref().unwrap_or(&analysis::DEFAULT_PHASE_BOUNDS));
+        let cell = |value : String| text(value).size(12).width(110);
+        let mut table = column![].spacing(2);
+        for phase in &phases {
+            let title = match phase.to {
+                Some(to) => tr!("phase-title", from = phase.from, to = to, files = phase.files.len(), size = analysis::format_size(phase.bytes)),
+                None => tr!("phase-title-last", from = phase.from, files = phase.files.len(), size = analysis::format_size(phase.bytes))
+            };
+            table = table.push(text(title).size(14));
+            for file in phase.files.iter().take(PHASE_TOP_FILES) {
+                table = table.push(row![
+                    text(file.file.to_string_lossy().into_owned()).size(12).width(Fill),
+                    cell(format!("{}", file.reads)),
+                    cell(analysis::format_size(file.bytes)),
+                ].spacing(10));
+            }
+        }
+        container(
+            column![
+                button(text(tr!("close"))).on_press(Message::TogglePhases),
+                row![
+                    text_input("5, 15, 30, 60", &self.state.phase_bounds).on_input(Message::UpdatePhaseBounds).width(250),
+                    checkbox(tr!("phases-from-first-read"), self.state.phases_from_first_read).on_toggle(Message::PhasesFromFirstReadToggled),
+                ].spacing(10).align_y(Center),
+                text(if bounds.is_some() { tr!("phases-hint") } else { tr!("phases-invalid") }),
+                scrollable(table).width(Fill).height(Fill),
+            ].spacing(10)
+        )
+            .width(iced::Fill)
+            .height(iced::Fill)
+            .padding(10)
+    }
+
+    /// Reads per process within the selected time window, children indented below their parent.
+    fn view_process_usage(&self) -> Container<Message> {
+        let usage = analysis::process_usage(self.state.event_log.iter().filter(|event| self.in_time_window(event)));
+        let cell = |value : String| text(value).size(12).width(110);
+        let mut table = column![
+            row![
+                text(tr!("detail-process")).size(12).width(Fill),
+                cell(tr!("detail-pid")),
+                cell(tr!("process-usage-files")),
+                cell(tr!("compare-reads")),
+                cell(tr!("compare-bytes")),
+                cell(tr!("process-usage-tree-files")),
+                cell(tr!("process-usage-tree-bytes")),
+            ].spacing(10)
+        ].spacing(2);
+        for process in &usage {
+            table = table.push(row![
+                text(format!("{}{}", "    ".repeat(process.depth), process.name)).size(12).width(Fill),
+                cell(format!("{}", process.pid)),
+                cell(format!("{}", process.files)),
+                cell(format!("{}", process.reads)),
+                cell(analysis::format_size(process.bytes)),
+                cell(format!("{}", process.tree_files)),
+                cell(analysis::format_size(process.tree_bytes)),
+            ].spacing(10));
+        }
+        container(
+            column![
+                button(text(tr!("close"))).on_press(Message::ToggleProcessUsage),
+                text(tr!("process-usage-title", count = usage.len())),
+                scrollable(table).width(Fill).height(Fill),
+            ].spacing(10)
+        )
+            .width(iced::Fill)
+            .height(iced::Fill)
+            .padding(10)
+    }
+
+    /// Files of the source that were never read, per directory and then one by one.
+    fn view_unused(report : &analysis::UnusedReport) -> Container<Message> {
+        let cell = |value : String| text(value).size(12).width(120);
+        let mut table = column![
+            row![
+                text(tr!("unused-directory")).size(12).width(Fill),
+                cell(tr!("unused-bytes")),
+                cell(tr!("unused-total-bytes")),
+                cell(tr!("unused-file-count")),
+            ].spacing(10)
+        ].spacing(2);
+        for directory in &report.directories {
+            table = table.push(row![
+                text(format!("{}/", directory.path)).size(12).width(Fill),
+                cell(analysis::format_size(directory.bytes)),
+                cell(analysis::format_size(directory.total_bytes)),
+                cell(format!("{}", directory.files)),
+            ].spacing(10));
+        }
+        table = table.push(iced::widget::Space::new(0, 20)).push(row![
+            text(tr!("detail-file")).size(12).width(Fill),
+            cell(tr!("detail-file-size")),
+        ].spacing(10));
+        for (file, size) in &report.files {
+            table = table.push(row![
+                text(file).size(12).width(Fill),
+                cell(analysis::format_size(*size)),
+            ].spacing(10));
+        }
+        container(
+            column![
+                row![
+                    button(text(tr!("close"))).on_press(Message::CloseUnused),
+                    button(text(tr!("refresh"))).on_press(Message::ShowUnused),
+                ].spacing(10),
+                text(tr!("unused-title", count = report.files.len(), size = analysis::format_size(report.bytes), total = analysis::format_size(report.total_bytes))),
+                scrollable(table).width(Fill).height(Fill),
+            ].spacing(10)
+        )
+            .width(iced::Fill)
+            .height(iced::Fill)
+            .padding(10)
+    }
+
+    fn view_manifest_report(report : &ManifestReport) -> Container<Message> {
+        let mut lists = column![].spacing(2);
+        let sections = [
+            (tr!("manifest-accessed", count = report.accessed.len()), &report.accessed),
+            (tr!("manifest-not-accessed", count = report.not_accessed.len()), &report.not_accessed),
+            (tr!("manifest-missing", count = report.missing.len()), &report.missing),
+            (tr!("manifest-unexpected", count = report.unexpected.len()), &report.unexpected),
+        ];
+        for (heading, files) in sections {
+            lists = lists.push(iced::widget::Space::new(0, 10)).push(text(heading));
+            for file in files {
+                lists = lists.push(text(file).size(12));
+            }
+        }
+        container(
+            column![
                 row![
-                    text("Mountpoint:").width(200).align_x(Horizontal::Right),
-                    Self::directory_selector("Mountpoint", &self.state.mountpoint, Message::UpdateMountpoint).width(400),
+                    button(text(tr!("close"))).on_press(Message::CloseManifestReport),
+                    button(text(tr!("refresh"))).on_press(Message::CompareManifest),
+                ].spacing(10),
+                scrollable(lists).width(Fill).height(Fill),
+            ].spacing(10)
+        )
+            .width(iced::Fill)
+            .height(iced::Fill)
+            .padding(10)
+    }
+
+    fn view_mounted(&self) -> Container<Message> {
+        if let Some(hex_view) = &self.state.hex_view {
+            return Self::view_hex(hex_view);
+        }
+        if let Some((page, lines)) = &self.state.spilled_page {
+            return self.view_spilled_page(*page, lines);
+        }
+        if self.state.show_statistics {
+            return self.view_statistics();
+        }
+        if self.state.show_treemap {
+            return self.view_treemap();
+        }
+        if self.state.show_flame_graph {
+            return self.view_flame_graph();
+        }
+        if self.state.show_access_order {
+            return self.view_access_order();
+        }
+        if self.state.show_redundant_reads {
+            return self.view_redundant_reads();
+        }
+        if self.state.show_process_usage {
+            return self.view_process_usage();
+        }
+        if self.state.show_phases {
+            return self.view_phases();
+        }
+        if let Some(report) = &self.state.unused {
+            return Self::view_unused(report);
+        }
+        if let Some(report) = &self.state.manifest_report {
+            return Self::view_manifest_report(report);
+        }
+        if let Some(log) = &self.state.external_log {
+            return self.view_external_log(log);
+        }
+        let mut content = column![
+            row![
+                button(text(tr!("unmount"))).on_press(Message::UnmountPressed),
+                button(text(tr!("highlight-rules"))).on_press(Message::ToggleHighlightRules),
+                button(text(tr!("watch-rules"))).on_press(Message::ToggleWatchRules),
+                button(text(tr!("columns"))).on_press(Message::ToggleColumns),
+                button(text(tr!("pin-selected-event"))).on_press(Message::PinSelectedEvent),
+                button(text(tr!("clear-log"))).on_press(Message::ClearLog),
+                button(text(tr!("statistics"))).on_press(Message::ToggleStatistics),
+                button(text(tr!("treemap"))).on_press(Message::ToggleTreemap),
+                button(text(tr!("flame-graph"))).on_press(Message::ToggleFlameGraph),
+                button(text(tr!("access-order"))).on_press(Message::ToggleAccessOrder),
+                button(text(tr!("unused-files"))).on_press(Message::ShowUnused),
+                button(text(tr!("redundant-reads"))).on_press(Message::ToggleRedundantReads),
+                button(text(tr!("process-usage"))).on_press(Message::ToggleProcessUsage),
+                button(text(tr!("startup-phases"))).on_press(Message::TogglePhases),
+                pick_list(TimestampMode::ALL, Some(self.state.timestamp_mode), Message::TimestampModeSelected),
+                pick_list(self.process_filter_options(), Some(self.selected_process_filter()), Message::ProcessFilterSelected),
+                checkbox(tr!("group-reads"), self.state.group_reads).on_toggle(Message::GroupReadsToggled),
+                checkbox(tr!("file-list"), self.state.show_file_list).on_toggle(Message::FileListToggled),
+            ].spacing(10),
+            row![
+                text_input(&tr!("export-path"), &self.state.export_path).on_input(Message::UpdateExportPath).width(400),
+                button(text(tr!("export-log"))).on_press(Message::ExportPressed),
+                button(text(tr!("export-html"))).on_press(Message::ExportHtml),
+                button(text(tr!("tray-minimize"))).on_press_maybe(self.state.tray.as_ref().map(|_| Message::MinimizeToTray)),
+            ].spacing(10),
+            row![
+                text_input(&tr!("marker-label"), &self.state.marker_label).on_input(Message::UpdateMarkerLabel).on_submit(Message::AddMarker).width(400),
+                button(text(tr!("add-marker"))).on_press(Message::AddMarker),
+            ].spacing(10),
+            row![
+                text_input(&tr!("session-name"), &self.state.session_name).on_input(Message::UpdateSessionName).width(400),
+                button(text(tr!("save-session"))).on_press_maybe((!self.state.session_name.trim().is_empty()).then_some(Message::SaveSession)),
+                button(text(tr!("load-session"))).on_press_maybe(self.state.saved_sessions.iter().any(|name| name == self.state.session_name.trim()).then_some(Message::LoadSession)),
+            ].spacing(10),
+            row![
+                text_input(&tr!("copy-target"), &self.state.copy_target).on_input(Message::UpdateCopyTarget).width(400),
+                button(text(tr!("copy-accessed"))).on_press_maybe((!self.state.copy_target.trim().is_empty()).then_some(Message::CopyAccessed)),
+                button(text(tr!("extract-sparse"))).on_press_maybe((!self.state.copy_target.trim().is_empty()).then_some(Message::ExtractReads(ExtractMode::Sparse))),
+                button(text(tr!("extract-concatenated"))).on_press_maybe((!self.state.copy_target.trim().is_empty()).then_some(Message::ExtractReads(ExtractMode::Concatenated))),
+            ].spacing(10),
+            row![
+                text_input(&tr!("manifest-path"), &self.state.manifest_path).on_input(Message::UpdateManifestPath).width(400),
+                button(text(tr!("compare-manifest"))).on_press_maybe((!self.state.manifest_path.trim().is_empty()).then_some(Message::CompareManifest)),
+            ].spacing(10),
+            row![
+                text_input(&tr!("external-log-path"), &self.state.external_log_path).on_input(Message::UpdateExternalLogPath).width(400),
+                button(text(tr!("load-external-log"))).on_press_maybe((!self.state.external_log_path.trim().is_empty()).then_some(Message::LoadExternalLog)),
+            ].spacing(10),
+            row![
+                text_input(&tr!("path-include"), &self.state.path_include_text).on_input(Message::UpdatePathInclude).width(250),
+                text_input(&tr!("path-exclude"), &self.state.path_exclude_text).on_input(Message::UpdatePathExclude).width(250),
+                pick_list(self.state.filter_presets.clone(), self.selected_preset(), Message::PresetSelected).placeholder(tr!("filter-presets")),
+                text_input(&tr!("preset-name"), &self.state.preset_name).on_input(Message::UpdatePresetName).on_submit(Message::SavePreset).width(150),
+                button(text(tr!("save-preset"))).on_press_maybe((!self.state.preset_name.trim().is_empty()).then_some(Message::SavePreset)),
+                button(text(tr!("delete-preset"))).on_press_maybe(self.selected_preset().map(|_| Message::DeletePreset)),
+            ].spacing(10).align_y(Center),
+            self.view_timeline(),
+            match &self.state.spill {
+                Some(spill) if self.state.events_dropped > 0 => row![
+                    text(tr!("events-logged-capped", count = self.state.event_log.len(), total = self.state.events_dropped + self.state.event_log.len() as u64)),
+                    button(text(tr!("browse-spilled"))).on_press(Message::ShowSpilledPage(spill.page_count().saturating_sub(1))),
                 ].spacing(10).align_y(Center),
-                iced::widget::Space::new(0, 30),
-                button("Mount").on_press(Message::MountPressed)
-            ].spacing(10).align_x(Center))
+                _ => row![text(tr!("events-logged", count = self.state.event_log.len()))]
+            },
+        ];
+        if self.state.downsampled {
+            content = content.push(text(tr!("live-view-summarized", rate = DOWNSAMPLE_RATE)));
+        }
+        if self.state.show_highlight_rules {
+            content = content.push(self.view_highlight_rules());
+        }
+        if self.state.show_columns {
+            content = content.push(self.view_columns());
+        }
+        if self.state.show_watch_rules {
+            content = content.push(self.view_watch_rules());
+        }
+        if self.state.show_search {
+            content = content.push(self.view_search());
+        }
+        let log = scrollable(
+            text_editor(&self.state.event_log_content)
+                .font(Font::MONOSPACE) // keeps the columns aligned
+                .on_action(Message::LogEdit)
+                .highlight_with::<LogHighlighter>(LogHighlightSettings {
+                    rules: self.state.highlight_rules.clone(),
+                    search: self.state.search_query.clone()
+                }, highlight::to_format)
+        ).width(Fill);
+        let log = match &self.state.file_filter {
+            Some(file) => column![self.view_file_stats(file), log].spacing(5),
+            None => column![log]
+        };
+        let mut panes = row![].spacing(10);
+        if self.state.show_file_list {
+            panes = panes.push(scrollable(self.view_file_list()));
+        }
+        panes = panes.push(log);
+        let mut side_pane = column![].spacing(20);
+        if !self.state.details.is_empty() {
+            side_pane = side_pane.push(self.view_details());
+        }
+        if !self.state.bookmarks.is_empty() {
+            side_pane = side_pane.push(self.view_bookmarks());
+        }
+        if !self.state.details.is_empty() || !self.state.bookmarks.is_empty() {
+            panes = panes.push(scrollable(side_pane));
+        }
+        let centered_container = container(content.push(panes));
+
+        container(centered_container)
+            .width(iced::Fill)
+            .height(iced::Fill)
+            .align_x(Center)
+            .align_y(Center)
+    }
+
+    fn view_advanced_options(&self) -> Column<Message> {
+        let options = &self.state.mount_options;
+        column![
+            checkbox(tr!("allow-other"), options.allow_other).on_toggle(Message::AllowOtherToggled),
+            checkbox(tr!("auto-unmount"), options.auto_unmount && platform::AUTO_UNMOUNT).on_toggle_maybe(platform::AUTO_UNMOUNT.then_some(Message::AutoUnmountToggled)),
+            checkbox(tr!("read-only"), options.read_only).on_toggle(Message::ReadOnlyToggled),
+            checkbox(tr!("stats-file"), options.stats_file).on_toggle(Message::StatsFileToggled),
+            checkbox(tr!("control-file"), options.control_file).on_toggle(Message::ControlFileToggled),
+            checkbox(tr!("metadata-events"), options.metadata_events).on_toggle(Message::MetadataEventsToggled),
+            checkbox(tr!("direct-io"), options.direct_io).on_toggle(Message::DirectIoToggled),
+            checkbox(tr!("write-through"), options.write_through && !options.read_only).on_toggle_maybe((!options.read_only).then_some(Message::WriteThroughToggled)),
+            checkbox(tr!("nfs-backend"), matches!(options.backend, BackendKind::Nfs(_))).on_toggle(Message::NfsBackendToggled),
+            row![
+                text(tr!("case-sensitivity-label")).width(200).align_x(Horizontal::Right),
+                pick_list(CaseOption::ALL, Some(CaseOption(options.case_sensitivity)), Message::CaseSensitivitySelected),
+            ].spacing(10).align_y(Center),
+            row![
+                text(tr!("ttl-label")).width(200).align_x(Horizontal::Right),
+                // with direct io nothing is cached
+                text_input("1", &self.state.ttl_text).on_input_maybe((!options.direct_io).then_some(Message::UpdateTtl)).width(100),
+            ].spacing(10).align_y(Center),
+            row![
+                text(tr!("include-label")).width(200).align_x(Horizontal::Right),
+                text_input(&tr!("filter-placeholder"), &self.state.include_text).on_input(Message::UpdateIncludeFilter).width(400),
+            ].spacing(10).align_y(Center),
+            row![
+                text(tr!("exclude-label")).width(200).align_x(Horizontal::Right),
+                text_input(&tr!("filter-placeholder"), &self.state.exclude_text).on_input(Message::UpdateExcludeFilter).width(400),
+            ].spacing(10).align_y(Center),
+            row![
+                text(tr!("max-log-entries-label")).width(200).align_x(Horizontal::Right),
+                text_input("50000", &self.state.max_log_entries_text).on_input(Message::UpdateMaxLogEntries).width(100),
+            ].spacing(10).align_y(Center),
+            row![
+                text(tr!("archive-index-label")).width(200).align_x(Horizontal::Right),
+                text_input(&tr!("archive-index-placeholder"), &self.state.archive_index).on_input(Message::UpdateArchiveIndex).width(400),
+            ].spacing(10).align_y(Center),
+            text(tr!("script-help")).size(12),
+            text_editor(&self.state.script_content)
+                .placeholder(tr!("script-placeholder"))
+                .font(Font::MONOSPACE)
+                .height(150)
+                .on_action(Message::ScriptEdit),
+        ].spacing(10).width(610)
+    }
+
+    fn view_comparison(&self) -> Container<Message> {
+        let Some((first, second, files)) = &self.state.comparison else {
+            return container(column![]);
+        };
+        let cell = |value : String| text(value).size(12).width(120);
+        let mut table = column![
+            row![
+                text(tr!("detail-file")).size(12).width(Fill),
+                cell(format!("{first} ({})", tr!("compare-reads"))),
+                cell(format!("{second} ({})", tr!("compare-reads"))),
+                cell(format!("{first} ({})", tr!("compare-bytes"))),
+                cell(format!("{second} ({})", tr!("compare-bytes"))),
+            ].spacing(10)
+        ].spacing(2);
+        for file in files {
+            table = table.push(row![
+                text(&file.file).size(12).width(Fill),
+                cell(format!("{}", file.reads.0)),
+                cell(format!("{}", file.reads.1)),
+                cell(analysis::format_size(file.bytes.0)),
+                cell(analysis::format_size(file.bytes.1)),
+            ].spacing(10));
+        }
+        container(
+            column![
+                button(text(tr!("close"))).on_press(Message::CloseComparison),
+                text(tr!("comparison-title", first = first.as_str(), second = second.as_str())),
+                scrollable(table).width(Fill).height(Fill),
+            ].spacing(10)
+        )
+            .width(iced::Fill)
+            .height(iced::Fill)
+            .padding(10)
+    }
+
+    fn view_unmounted(&self) -> Container<Message> {
+        let mut form = column![
+            row![
+                text(tr!("source-directory-label")).width(200).align_x(Horizontal::Right),
+                Self::directory_selector(&tr!("source-directory"), &self.state.source, Message::UpdateSource).width(400),
+            ].spacing(10).align_y(Center),
+            row![
+                text(tr!("mountpoint-label")).width(200).align_x(Horizontal::Right),
+                Self::directory_selector(&tr!("mountpoint"), &self.state.mountpoint, Message::UpdateMountpoint).width(400),
+            ].spacing(10).align_y(Center),
+            text(tr!("drop-tip")).size(12),
+            checkbox(tr!("capture-payloads"), self.state.mount_options.capture_payloads).on_toggle(Message::CapturePayloadsToggled),
+            checkbox(tr!("tray-enabled"), self.state.tray_enabled).on_toggle(Message::TrayEnabledToggled),
+            row![
+                text(tr!("language")),
+                pick_list(Language::ALL, Some(i18n::language()), Message::LanguageSelected),
+            ].spacing(10).align_y(Center),
+        ].spacing(10).align_x(Center);
+        form = form.push(button(text(tr!("advanced-options"))).on_press(Message::ToggleAdvancedOptions));
+        if !self.state.prerequisites.is_empty() && !self.state.prerequisites_dismissed {
+            form = form.push(self.view_prerequisites());
+        }
+        if !self.state.stale_mounts.is_empty() {
+            form = form.push(self.view_stale_mounts());
+        }
+        if !self.state.active_mounts.is_empty() {
+            form = form.push(self.view_active_mounts());
+        }
+        if self.state.saved_sessions.len() >= 2 {
+            let (first, second) = &self.state.compare_sessions;
+            form = form.push(row![
+                text(tr!("compare-sessions")),
+                pick_list(self.state.saved_sessions.clone(), first.clone(), Message::CompareFirstSelected),
+                pick_list(self.state.saved_sessions.clone(), second.clone(), Message::CompareSecondSelected),
+                button(text(tr!("compare"))).on_press_maybe(first.as_ref().and(second.as_ref()).map(|_| Message::CompareSessions)),
+            ].spacing(10).align_y(Center));
+        }
+        if self.state.show_advanced_options {
+            form = form.push(self.view_advanced_options());
+        }
+        if let Some(path) = &self.state.dropped_path {
+            form = form.push(row![
+                text(tr!("use-dropped-as", path = path.as_str())),
+                button(text(tr!("source-directory"))).on_press(Message::UseDroppedAsSource),
+                button(text(tr!("mountpoint"))).on_press(Message::UseDroppedAsMountpoint),
+                button(text(tr!("cancel"))).on_press(Message::DismissDropped),
+            ].spacing(10).align_y(Center));
+        }
+        form = form.push(iced::widget::Space::new(0, 30)).push(button(text(tr!("mount"))).on_press(Message::MountPressed));
+        let centered_container = container(
+            container(form)
                 .padding(10)
                 .center(800)
                 .align_x(Center)
@@ -201,34 +3452,165 @@ impl AccessTrackingFsGui {
         centered_container
     }
 
-    pub fn view_loading(&self, display_text : &'static str) -> Container<Message> {
+    fn view_stale_mounts(&self) -> Container<Message> {
+        let mut list = column![text(tr!("stale-mounts"))].spacing(5);
+        for mountpoint in &self.state.stale_mounts {
+            list = list.push(text(mountpoint.to_string_lossy()).font(Font::MONOSPACE));
+        }
+        list = list.push(button(text(tr!("recover-stale-mounts"))).on_press(Message::RecoverStaleMounts));
+        container(list).padding(10).width(700).style(container::rounded_box)
+    }
+
+    /// Mounts of other monitors, which can't be mounted over but unmounted from here.
+    fn view_active_mounts(&self) -> Column<Message> {
+        let mut list = column![
+            row![
+                text(tr!("active-mounts")).width(Fill),
+                button(text(tr!("refresh"))).on_press(Message::RefreshActiveMounts),
+            ].spacing(10).align_y(Center)
+        ].spacing(5).width(700);
+        for mount in &self.state.active_mounts {
+            list = list.push(row![
+                text(tr!("active-mount", mountpoint = mount.mountpoint.to_string_lossy(), source = mount.source.as_str(), pid = mount.pid)).width(Fill),
+                button(text(tr!("unmount"))).on_press(Message::UnmountOther(mount.mountpoint.clone())),
+            ].spacing(10).align_y(Center));
+        }
+        list
+    }
+
+    /// What has to be set up before mounting, with the commands that do it.
+    fn view_prerequisites(&self) -> Container<Message> {
+        let mut list = column![text(tr!("setup-title")).size(18)].spacing(10);
+        for prerequisite in &self.state.prerequisites {
+            let mut entry = column![text(prerequisite.hint())].spacing(5);
+            if let Some(command) = prerequisite.fix() {
+                entry = entry.push(row![
+                    text(command.clone()).font(Font::MONOSPACE).width(Fill),
+                    button(text(tr!("setup-copy-command"))).on_press(Message::CopyFixCommand(command)),
+                ].spacing(10).align_y(Center));
+            }
+            list = list.push(entry);
+        }
+        let mut buttons = row![
+            button(text(tr!("setup-check-again"))).on_press(Message::CheckPrerequisites),
+            button(text(tr!("dismiss"))).on_press(Message::DismissPrerequisites),
+        ].spacing(10);
+        if self.state.prerequisites.iter().any(Prerequisite::nfs_avoids) {
+            buttons = buttons.push(button(text(tr!("setup-use-nfs"))).on_press(Message::NfsBackendToggled(true)));
+        }
+        container(list.push(buttons)).padding(10).width(700).style(container::rounded_box)
+    }
+
+    fn view_mounting(&self, progress : &ScanProgress) -> Container<Message> {
+        let directories = progress.directories.load(std::sync::atomic::Ordering::Relaxed);
+        let files = progress.files.load(std::sync::atomic::Ordering::Relaxed);
+        container(
+            column![
+                text(tr!("mounting")),
+                text(tr!("scan-progress", directories = directories, files = files)),
+                button(text(tr!("cancel"))).on_press_maybe((!progress.is_cancelled()).then_some(Message::CancelMount)),
+            ].spacing(10).align_x(Center)
+        ).width(iced::Fill).height(iced::Fill).align_x(Center).align_y(Center)
+    }
+
+    pub fn view_loading(&self, display_text : String) -> Container<Message> {
         container(
             text(display_text).align_x(Center).align_y(Center)
         ).width(iced::Fill).height(iced::Fill).align_x(Center).align_y(Center)
     }
 
+    fn view_toasts(&self) -> Column<Message> {
+        let mut toasts = column![].spacing(5).padding(5);
+        for (i, toast) in self.state.toasts.iter().enumerate() {
+            let background = toast.severity.color();
+            toasts = toasts.push(
+                container(row![
+                    text(&toast.text).width(Fill),
+                    button(text(tr!("dismiss"))).on_press(Message::DismissToast(i)),
+                ].spacing(10).align_y(Center))
+                    .padding(5)
+                    .width(Fill)
+                    .style(move |_theme| container::Style {
+                        background: Some(Background::Color(background)),
+                        text_color: Some(Color::WHITE),
+                        border: Border { radius: 4.0.into(), ..Border::default() },
+                        ..container::Style::default()
+                    })
+            );
+        }
+        toasts
+    }
+
+    fn view_status_bar(&self) -> Container<Message> {
+        let uptime = (chrono::Utc::now() - self.state.mounted_at).num_seconds();
+        // events the mount sent that the log didn't take yet, the mount waits while the queue is full
+        let sink = match self.event_sender.is_closed() {
+            true => tr!("sink-closed"),
+            false => {
+                let capacity = self.event_sender.max_capacity();
+                tr!("sink-queue", queued = capacity - self.event_sender.capacity(), capacity = capacity)
+            }
+        };
+        container(
+            row![
+                text(format!("{} → {}", self.state.source, self.state.mountpoint)).width(Fill),
+                text(tr!("uptime", time = format!("{:02}:{:02}:{:02}", uptime / 3600, uptime / 60 % 60, uptime % 60))),
+                text(tr!("event-count", count = self.state.events_received)),
+                text(tr!("event-rate", rate = self.state.event_rate)),
+                text(sink),
+                text(tr!("events-dropped", count = self.state.events_lost)),
+            ].spacing(20)
+        )
+            .padding(5)
+            .width(Fill)
+            .style(container::rounded_box)
+    }
+
     pub fn view(&self) -> Container<Message> {
-        match self.state.status {
+        let view = match self.state.status {
+            _ if self.state.pending_action.is_some() => self.view_confirm(self.state.pending_action.unwrap()),
+            _ if self.state.comparison.is_some() => self.view_comparison(),
             Status::Unmounted => self.view_unmounted(),
-            Status::Mounting => self.view_loading("Mounting..."),
-            Status::Unmounting => self.view_loading("Unmounting..."),
+            Status::Mounting(ref progress, _) => self.view_mounting(progress),
+            Status::Unmounting => self.view_loading(tr!("unmounting")),
             Status::Mounted(_) => self.view_mounted()
             
+        };
+        let view = if self.state.toasts.is_empty() {
+            view
+        } else {
+            container(column![self.view_toasts(), view])
+        };
+        match self.state.status {
+            Status::Mounted(_) => container(column![view.height(Fill), self.view_status_bar()]),
+            _ => view
         }
     }
 
     fn some_worker() -> impl iced::futures::Stream<Item = Message> {
-        iced::stream::channel(100, |mut output| async move {
+        // only one batch waits for the UI at a time, everything arriving meanwhile goes into the next one,
+        // so thousands of reads per second still mean one update per frame
+        iced::stream::channel(1, |mut output| async move {
             let (sender, mut receiver) = tokio::sync::mpsc::channel(5);
             output.send(Message::InitEventCommunication(sender)).await;
             match receiver.recv().await {
-                Some(receiver) => {
-                    println!("Established event communication.");
+                Some((receiver, format)) => {
+                    log!(debug, "Established event communication");
                     let mut receiver = receiver.lock().await;
                     loop {
                         match receiver.recv().await {
                             Some(event) => {
-                                output.send(Message::ReceivedEvent(event)).await;
+                                let mut events = vec![event];
+                                while events.len() < MAX_EVENT_BATCH {
+                                    match receiver.try_recv() {
+                                        Ok(event) => events.push(event),
+                                        Err(_) => break
+                                    }
+                                }
+                                // formatted here, so the UI thread only has to lay the lines out
+                                let format = format.read().unwrap().clone();
+                                let lines = events.iter().map(|event| format.format_event(event)).collect();
+                                output.send(Message::ReceivedEvents(EventBatch { events, lines, format })).await;
                             }
                             None => {
                                 break;
@@ -243,7 +3625,90 @@ impl AccessTrackingFsGui {
         })
     }
 
+    /// Produces a `Tick` every second, used to refresh the status bar.
+    fn ticker() -> impl iced::futures::Stream<Item = Message> {
+        iced::stream::channel(1, |mut output| async move {
+            // there is no timer without an async runtime, so a thread does the waiting
+            std::thread::spawn(move || loop {
+                std::thread::sleep(std::time::Duration::from_secs(1));
+                if let Err(err) = output.try_send(Message::Tick) {
+                    if err.is_disconnected() {
+                        break;
+                    }
+                }
+            });
+            std::future::pending::<()>().await;
+        })
+    }
+
+    /// Polls the mount thread while mounting, often enough for the progress to look live.
+    fn mount_ticker() -> impl iced::futures::Stream<Item = Message> {
+        iced::stream::channel(1, |mut output| async move {
+            std::thread::spawn(move || loop {
+                std::thread::sleep(std::time::Duration::from_millis(100));
+                if let Err(err) = output.try_send(Message::MountProgress) {
+                    if err.is_disconnected() {
+                        break;
+                    }
+                }
+            });
+            std::future::pending::<()>().await;
+        })
+    }
+
+    fn key_press(key : keyboard::Key, modifiers : keyboard::Modifiers) -> Option<Message> {
+        match key.as_ref() {
+            keyboard::Key::Character("m") if modifiers.command() => Some(Message::AddMarker),
+            keyboard::Key::Character("f") if modifiers.command() => Some(Message::OpenSearch),
+            keyboard::Key::Named(keyboard::key::Named::F3) if modifiers.shift() => Some(Message::SearchPrevious),
+            keyboard::Key::Named(keyboard::key::Named::F3) => Some(Message::SearchNext),
+            _ => None
+        }
+    }
+
+    fn window_event(event : iced::Event, _status : iced::event::Status, _window : iced::window::Id) -> Option<Message> {
+        match event {
+            iced::Event::Window(iced::window::Event::FileDropped(path)) => Some(Message::FileDropped(path)),
+            iced::Event::Window(iced::window::Event::Moved(position)) => Some(Message::WindowMoved(position)),
+            iced::Event::Window(iced::window::Event::Resized(size)) => Some(Message::WindowResized(size)),
+            _ => None
+        }
+    }
+
+    fn tray_actions(receiver : Arc<Mutex<tokio::sync::mpsc::UnboundedReceiver<TrayAction>>>) -> impl iced::futures::Stream<Item = Message> {
+        iced::stream::channel(10, |mut output| async move {
+            let mut receiver = receiver.lock().await;
+            while let Some(action) = receiver.recv().await {
+                let _ = output.send(Message::TrayAction(action)).await;
+            }
+        })
+    }
+
+    fn shutdown_signals(receiver : Arc<Mutex<tokio::sync::mpsc::UnboundedReceiver<i32>>>) -> impl iced::futures::Stream<Item = Message> {
+        iced::stream::channel(1, |mut output| async move {
+            let mut receiver = receiver.lock().await;
+            while let Some(signal) = receiver.recv().await {
+                let _ = output.send(Message::ShutdownSignal(signal)).await;
+            }
+        })
+    }
+
     pub fn subscription(&self) -> Subscription<Message> {
-        Subscription::run(Self::some_worker)
+        let mut subscriptions = vec![
+            Subscription::run(Self::some_worker),
+            Subscription::run_with_id("shutdown-signals", Self::shutdown_signals(self.shutdown_receiver.clone())),
+            Subscription::run_with_id("tray-actions", Self::tray_actions(self.tray_receiver.clone())),
+            iced::window::close_requests().map(Message::CloseRequested),
+            iced::event::listen_with(Self::window_event)
+        ];
+        match self.state.status {
+            Status::Mounted(_) => {
+                subscriptions.push(Subscription::run(Self::ticker));
+                subscriptions.push(keyboard::on_key_press(Self::key_press));
+            }
+            Status::Mounting(..) => subscriptions.push(Subscription::run(Self::mount_ticker)),
+            _ => {}
+        }
+        Subscription::batch(subscriptions)
     }
 }
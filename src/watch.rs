@@ -0,0 +1,99 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use crate::fs::{Event, ReadEvent};
+use crate::i18n::tr;
+
+/// A path that should be noticed as soon as it is accessed. Matches anywhere in the path, ignoring case.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WatchRule {
+    pub pattern : String,
+    pub notify : bool, // show a desktop notification on access
+    #[serde(default)]
+    pub command : String // run on access if not empty, see `run_command`
+}
+
+impl WatchRule {
+    pub fn matches(&self, path : &str) -> bool {
+        !self.pattern.is_empty() && path.to_lowercase().contains(&self.pattern.to_lowercase())
+    }
+}
+
+/// Shows a desktop notification about an access to a watched path.
+pub fn notify(pattern : &str, file : &str, process : &str, sound : bool) {
+    let summary = tr!("watch-notification-summary", pattern = pattern);
+    let body = tr!("watch-notification-body", file = file, process = process);
+    show_notification(summary, body, sound);
+}
+
+/// Shows a desktop notification on its own thread, since it talks to D-Bus.
+pub fn show_notification(summary : String, body : String, sound : bool) {
+    std::thread::spawn(move || {
+        let mut notification = notify_rust::Notification::new();
+        notification.appname(&tr!("window-title")).summary(&summary).body(&body).icon("dialog-information");
+        if sound {
+            // a name from the freedesktop sound theme, played by the notification daemon
+            notification.sound_name("message-new-instant");
+        }
+        if let Err(err) = notification.show() {
+            eprintln!("Failed to show notification: {err}");
+        }
+    });
+}
+
+/// The command of a rule is started at most this often, a game reading a file in many small reads must not start it
+/// for each of them.
+const COMMAND_INTERVAL : Duration = Duration::from_secs(1);
+
+/// Runs the commands of the rules matching a read, for the window and the command line alike. `last_run` is when the
+/// command of each rule was last started, by the index of the rule.
+pub fn run_commands(rules : &[WatchRule], last_run : &mut HashMap<usize, Instant>, event : &Event, read : &ReadEvent) {
+    let file = read.file.to_string_lossy();
+    for (i, rule) in rules.iter().enumerate() {
+        if rule.command.trim().is_empty() || !rule.matches(&file) {
+            continue;
+        }
+        if last_run.get(&i).is_some_and(|time| time.elapsed() < COMMAND_INTERVAL) {
+            continue;
+        }
+        run_command(&rule.command, event, read);
+        last_run.insert(i, Instant::now());
+    }
+}
+
+/// Runs the command of a watch rule through `sh -c` without waiting for it, e.g. to dump the memory of the game
+/// the moment it reads a save file.
+///
+/// The read is passed as the arguments `$1` (file), `$2` (process), `$3` (pid), `$4` (offset) and `$5` (size)
+/// and as the environment variables `FFAM_FILE`, `FFAM_PROCESS`, `FFAM_PID`, `FFAM_OFFSET`, `FFAM_SIZE` and `FFAM_TIME`.
+pub fn run_command(command : &str, event : &Event, read : &ReadEvent) {
+    let fields = [
+        ("FFAM_FILE", read.file.to_string_lossy().into_owned()),
+        ("FFAM_PROCESS", event.process.name.to_string()),
+        ("FFAM_PID", event.process.pid.to_string()),
+        ("FFAM_OFFSET", read.offset.to_string()),
+        ("FFAM_SIZE", read.size.to_string())
+    ];
+    let child = std::process::Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .arg("ffam") // $0
+        .args(fields.iter().map(|(_, value)| value))
+        .envs(fields.iter().map(|(name, value)| (name, value)))
+        .env("FFAM_TIME", event.time.to_rfc3339())
+        .stdin(std::process::Stdio::null())
+        .spawn();
+    match child {
+        // waited for on its own thread, so it doesn't stay a zombie
+        Ok(mut child) => {
+            let command = command.to_string();
+            std::thread::spawn(move || match child.wait() {
+                Ok(status) if !status.success() => eprintln!("Watch command `{command}` failed with {status}"),
+                Ok(_) => {}
+                Err(err) => eprintln!("Failed to wait for watch command `{command}`: {err}")
+            });
+        }
+        Err(err) => eprintln!("Failed to run watch command `{command}`: {err}")
+    }
+}